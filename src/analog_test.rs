@@ -0,0 +1,102 @@
+//! Analog test output (`debug-unlock` feature)
+//!
+//! For lab characterization, GCONF test_mode routes an internal analog signal to pin REFR2, selected by SLAVEADDR.
+//! REFR2 is then no longer a reference switch input and the UART address is changed: this is not for use in a
+//! product, hence the [`DangerZone`] acknowledgment.
+//!
+//! SLAVECONF being write only, both helpers take its actual value: the selection overwrites SLAVEADDR, which
+//! [`Tmc5072::disable_analog_test_output`] restores.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::gconf::GConfError;
+use crate::registers::{general_configuration_register::SlaveConf, DangerZone};
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Signal output on REFR2
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AnalogTestSignal {
+    /// T120: temperature comparator reference
+    T120 = 0,
+    /// DAC1: current DAC of motor 0
+    Dac1 = 1,
+    /// VDDH1: high side supply of motor 0
+    Vddh1 = 2,
+    /// DAC2: current DAC of motor 1
+    Dac2 = 3,
+    /// VDDH2: high side supply of motor 1
+    Vddh2 = 4,
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Selects `signal` with SLAVEADDR, then sets GCONF test_mode
+    pub fn enable_analog_test_output_unchecked<SPI: Transfer<u8>>(
+        &mut self,
+        signal: AnalogTestSignal,
+        slave_conf: SlaveConf,
+        _acknowledgment: DangerZone,
+        spi: &mut SPI,
+    ) -> Result<(), GConfError<SPI::Error, CS::Error>> {
+        self.write_register(
+            SlaveConf {
+                slave_addr: signal as u8,
+                ..slave_conf
+            },
+            spi,
+        )?;
+        self.update_gconf(|g_conf| g_conf.test_mode = true, spi)?;
+        Ok(())
+    }
+
+    /// Clears GCONF test_mode, then restores SLAVECONF
+    pub fn disable_analog_test_output<SPI: Transfer<u8>>(
+        &mut self,
+        slave_conf: SlaveConf,
+        spi: &mut SPI,
+    ) -> Result<(), GConfError<SPI::Error, CS::Error>> {
+        self.update_gconf(|g_conf| g_conf.test_mode = false, spi)?;
+        self.write_register(slave_conf, spi)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{general_configuration_register::GConf, Register};
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn analog_test_output() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let slave_conf = SlaveConf {
+            slave_addr: 7,
+            send_delay: 2,
+        };
+        tmc5072
+            .enable_analog_test_output_unchecked(
+                AnalogTestSignal::Dac2,
+                slave_conf,
+                DangerZone::acknowledge(),
+                &mut sim,
+            )
+            .unwrap();
+        assert!(GConf::from(sim.get(GConf::addr())).test_mode);
+        assert_eq!(
+            SlaveConf::from(sim.get(SlaveConf::addr())),
+            SlaveConf {
+                slave_addr: 3,
+                send_delay: 2,
+            }
+        );
+        tmc5072
+            .disable_analog_test_output(slave_conf, &mut sim)
+            .unwrap();
+        assert!(!GConf::from(sim.get(GConf::addr())).test_mode);
+        assert_eq!(SlaveConf::from(sim.get(SlaveConf::addr())), slave_conf);
+    }
+}
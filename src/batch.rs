@@ -0,0 +1,129 @@
+//! Batched register writes
+//!
+//! [`Tmc5072::write_batch`] does not stop at the first failed write: every write is attempted and, if any failed,
+//! a [`BatchError`] tells which ones went through. The caller can then decide whether the chip is usable in this
+//! partial state, retry the failed writes only, or re-apply everything.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::spi::SpiError;
+use crate::Tmc5072;
+
+/// Outcome of a write of a batch
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct BatchWrite<SPI, CS> {
+    /// Register address
+    pub addr: u8,
+    /// Write result
+    pub result: Result<(), SpiError<SPI, CS>>,
+}
+
+/// Outcome of each write of a batch with at least one failed write
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BatchError<SPI, CS, const N: usize> {
+    /// Writes, in order
+    pub writes: [BatchWrite<SPI, CS>; N],
+}
+
+impl<SPI, CS, const N: usize> BatchError<SPI, CS, N> {
+    /// Addresses of the successful writes
+    pub fn succeeded(&self) -> impl Iterator<Item = u8> + '_ {
+        self.writes
+            .iter()
+            .filter(|write| write.result.is_ok())
+            .map(|write| write.addr)
+    }
+
+    /// Addresses and errors of the failed writes
+    pub fn failed(&self) -> impl Iterator<Item = (u8, &SpiError<SPI, CS>)> + '_ {
+        self.writes
+            .iter()
+            .filter_map(|write| write.result.as_ref().err().map(|e| (write.addr, e)))
+    }
+
+    /// Number of failed writes
+    pub fn failures(&self) -> usize {
+        self.failed().count()
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Writes every (address, value) of `writes` in order, going on after a failed write
+    pub fn write_batch<const N: usize, SPI: Transfer<u8>>(
+        &mut self,
+        writes: &[(u8, u32); N],
+        spi: &mut SPI,
+    ) -> Result<(), BatchError<SPI::Error, CS::Error, N>> {
+        let mut failed = false;
+        let results = writes.map(|(addr, value)| {
+            let result = self.write_raw(addr, value, spi).map(|_| ());
+            failed |= result.is_err();
+            BatchWrite { addr, result }
+        });
+        if failed {
+            Err(BatchError { writes: results })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        ramp_generator_register::{AMax, VMax},
+        Register,
+    };
+    use crate::sim::{CsPin, Simulator};
+
+    /// Simulator whose bus fails one transfer
+    struct Failing {
+        sim: Simulator,
+        count: usize,
+        fail: usize,
+    }
+
+    impl Transfer<u8> for Failing {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+            self.count += 1;
+            if self.count == self.fail {
+                return Err(());
+            }
+            Ok(self.sim.transfer(words).unwrap())
+        }
+    }
+
+    #[test]
+    fn partial() {
+        let mut spi = Failing {
+            sim: Simulator::new(),
+            count: 0,
+            fail: 0,
+        };
+        let mut tmc5072 = Tmc5072::new(&mut spi, CsPin).unwrap();
+        let writes = [
+            (VMax::<0>::addr(), 1000),
+            (AMax::<0>::addr(), 200),
+            (VMax::<1>::addr(), 3000),
+        ];
+        assert_eq!(tmc5072.write_batch(&writes, &mut spi), Ok(()));
+        spi.fail = spi.count + 2;
+        let error = tmc5072
+            .write_batch(&writes.map(|(addr, value)| (addr, value + 1)), &mut spi)
+            .unwrap_err();
+        assert_eq!(error.failures(), 1);
+        let mut succeeded = error.succeeded();
+        assert_eq!(succeeded.next(), Some(VMax::<0>::addr()));
+        assert_eq!(succeeded.next(), Some(VMax::<1>::addr()));
+        assert_eq!(
+            error.failed().next(),
+            Some((AMax::<0>::addr(), &SpiError::SpiError(())))
+        );
+        assert_eq!(spi.sim.get(VMax::<1>::addr()), 3001);
+        assert_eq!(spi.sim.get(AMax::<0>::addr()), 200);
+    }
+}
@@ -0,0 +1,235 @@
+//! Mechanical brake coordination
+//!
+//! A vertical axis falls as soon as its holding current drops. [`BrakedAxis`] drives an external brake output
+//! so that the brake is engaged before IHOLD is dropped when parking, and only released once the current is
+//! established again when unparking, each transition waiting for the brake to act.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{ramp_generator_driver_feature_control_register::IHoldIRun, Register};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Brake output settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BrakeConfig {
+    /// The brake is engaged with the output high (most brakes are engaged when unpowered, i.e. low)
+    pub engaged_high: bool,
+    /// Time for the brake to engage, in the unit of the `now` argument of [`BrakedAxis::poll`]
+    pub engage_time: u32,
+    /// Time for the brake to release
+    pub release_time: u32,
+}
+
+/// State of a braked axis
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AxisState {
+    /// Current on, brake released, the axis may move
+    Enabled,
+    /// Brake engaging, current still on
+    Engaging,
+    /// Brake engaged, IHOLD dropped
+    Parked,
+    /// Current on, brake releasing
+    Releasing,
+}
+
+/// Wrapper for brake operation Result
+pub type BrakeResult<T, SPI, CS, PIN> = Result<T, BrakeError<SPI, CS, PIN>>;
+
+/// Brake error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BrakeError<SPI, CS, PIN> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Brake output error
+    PinError(PIN),
+}
+
+impl<SPI, CS, PIN> From<SpiError<SPI, CS>> for BrakeError<SPI, CS, PIN> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        BrakeError::SpiError(e)
+    }
+}
+
+/// Motor `M` with an external brake on pin `B`
+pub struct BrakedAxis<const M: u8, B> {
+    brake: B,
+    config: BrakeConfig,
+    current: IHoldIRun<M>,
+    state: AxisState,
+    since: u32,
+}
+
+impl<const M: u8, B: OutputPin> BrakedAxis<M, B>
+where
+    IHoldIRun<M>: Register,
+{
+    /// Creates a braked axis, `current` being the IHOLD_IRUN of the enabled axis
+    ///
+    /// The axis is assumed to be parked, [`BrakedAxis::unpark`] it before moving.
+    pub fn new(brake: B, current: IHoldIRun<M>, config: BrakeConfig) -> Self {
+        Self {
+            brake,
+            config,
+            current,
+            state: AxisState::Parked,
+            since: 0,
+        }
+    }
+
+    /// Actual state
+    pub fn state(&self) -> AxisState {
+        self.state
+    }
+
+    /// Whether the axis may move
+    pub fn is_enabled(&self) -> bool {
+        self.state == AxisState::Enabled
+    }
+
+    /// Releases the brake pin
+    pub fn free(self) -> B {
+        self.brake
+    }
+
+    /// Engages the brake, IHOLD is dropped by [`BrakedAxis::poll`] once the brake acted
+    ///
+    /// The motor must be at standstill.
+    pub fn park(&mut self, now: u32) -> Result<(), B::Error> {
+        self.set_brake(true)?;
+        if self.state != AxisState::Parked {
+            self.state = AxisState::Engaging;
+            self.since = now;
+        }
+        Ok(())
+    }
+
+    /// Establishes the current, the brake is released by [`BrakedAxis::poll`] afterwards
+    pub fn unpark<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> BrakeResult<(), SPI::Error, CS::Error, B::Error> {
+        if self.state == AxisState::Enabled {
+            return Ok(());
+        }
+        tmc5072.write_register(self.current, spi)?;
+        self.state = AxisState::Releasing;
+        self.since = now;
+        Ok(())
+    }
+
+    /// Completes the pending transition once the brake had time to act
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> BrakeResult<AxisState, SPI::Error, CS::Error, B::Error> {
+        let elapsed = now.wrapping_sub(self.since);
+        match self.state {
+            AxisState::Engaging if elapsed >= self.config.engage_time => {
+                tmc5072.write_register(
+                    IHoldIRun::<M> {
+                        i_hold: 0,
+                        ..self.current
+                    },
+                    spi,
+                )?;
+                self.state = AxisState::Parked;
+            }
+            AxisState::Releasing if elapsed >= self.config.release_time => {
+                self.set_brake(false).map_err(BrakeError::PinError)?;
+                self.state = AxisState::Enabled;
+            }
+            _ => {}
+        }
+        Ok(self.state)
+    }
+
+    fn set_brake(&mut self, engaged: bool) -> Result<(), B::Error> {
+        if engaged == self.config.engaged_high {
+            self.brake.set_high()
+        } else {
+            self.brake.set_low()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+    use core::convert::Infallible;
+
+    struct Pin(bool);
+
+    impl OutputPin for &mut Pin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0 = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0 = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn park_unpark() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut pin = Pin(false);
+        let current = IHoldIRun::<1> {
+            i_hold: 8,
+            i_run: 20,
+            i_hold_delay: 4,
+        };
+        let config = BrakeConfig {
+            engaged_high: false,
+            engage_time: 50,
+            release_time: 30,
+        };
+        let mut axis = BrakedAxis::new(&mut pin, current, config);
+        axis.unpark(&mut tmc5072, &mut sim, 0).unwrap();
+        assert_eq!(
+            IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())),
+            current
+        );
+        assert_eq!(
+            axis.poll(&mut tmc5072, &mut sim, 20).unwrap(),
+            AxisState::Releasing
+        );
+        assert_eq!(
+            axis.poll(&mut tmc5072, &mut sim, 30).unwrap(),
+            AxisState::Enabled
+        );
+        axis.park(100).unwrap();
+        assert_eq!(
+            axis.poll(&mut tmc5072, &mut sim, 120).unwrap(),
+            AxisState::Engaging
+        );
+        // brake engaged, current still on
+        assert_eq!(sim.get(IHoldIRun::<1>::addr()), u32::from(current));
+        assert_eq!(
+            axis.poll(&mut tmc5072, &mut sim, 150).unwrap(),
+            AxisState::Parked
+        );
+        assert_eq!(
+            IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())).i_hold,
+            0
+        );
+        assert!(!axis.free().0);
+    }
+}
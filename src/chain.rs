@@ -0,0 +1,234 @@
+//! SPI daisy chain
+//!
+//! Several TMC5072 can share one chip select, SDO of each chip feeding SDI of the next one: every access then shifts
+//! one 40 bit datagram per chip in a single transaction. Chip 0 is the one wired to the MOSI line of the master, the
+//! last chip drives MISO. [`Tmc5072Chain`] addresses one chip of the chain by sending a read of GCONF, which has no
+//! side effect, to the other chips, and returns the SPI status of every chip.
+//!
+//! As with a single chip, read data comes with the reply to the next datagram: reads take two transactions.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    general_configuration_register::GConf, ReadableRegister, Register, WritableRegister,
+};
+use crate::spi::{self, SpiError};
+use crate::status::SpiStatus;
+
+/// Data of a chained access with the SPI status of every chip of the chain
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ChainOk<T, const N: usize> {
+    /// SPI status of each chip
+    pub status: [SpiStatus; N],
+    /// Actual transfer data
+    pub data: T,
+}
+
+/// Wrapper for chained SPI communication Result
+pub type ChainResult<T, const N: usize, SPI, CS> = Result<ChainOk<T, N>, SpiError<SPI, CS>>;
+
+/// Chain of `N` TMC5072 sharing the chip select pin `CS`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Tmc5072Chain<CS, const N: usize> {
+    cs: CS,
+}
+
+impl<CS: OutputPin, const N: usize> Tmc5072Chain<CS, N> {
+    /// Creates a chain driver, the SPI bus is passed to every access
+    pub fn new(cs: CS) -> Self {
+        Self { cs }
+    }
+
+    /// Releases the Chip Select pin
+    pub fn release(self) -> CS {
+        self.cs
+    }
+
+    /// Shifts `frames[k]` into chip `k` in a single transaction, returns the reply data of each chip
+    pub fn transfer<SPI: Transfer<u8>>(
+        &mut self,
+        frames: [[u8; 5]; N],
+        spi: &mut SPI,
+    ) -> ChainResult<[u32; N], N, SPI::Error, CS::Error> {
+        let mut replies = ChainOk {
+            status: [SpiStatus::default(); N],
+            data: [0; N],
+        };
+        self.cs.set_low().map_err(SpiError::CSError)?;
+        // the first datagram shifted in ends up in the last chip, whose reply comes out first
+        for k in (0..N).rev() {
+            let mut buffer = frames[k];
+            spi.transfer(&mut buffer).map_err(SpiError::SpiError)?;
+            let reply = spi::parse_frame(&buffer);
+            replies.status[k] = reply.status;
+            replies.data[k] = reply.data;
+        }
+        self.cs.set_high().map_err(SpiError::CSError)?;
+        Ok(replies)
+    }
+
+    /// Read a raw register of chip `chip`
+    ///
+    /// Panics if `chip` is not below `N`.
+    pub fn read_raw<SPI: Transfer<u8>>(
+        &mut self,
+        chip: usize,
+        addr: u8,
+        spi: &mut SPI,
+    ) -> ChainResult<u32, N, SPI::Error, CS::Error> {
+        self.transfer(Self::frames(chip, spi::read_frame(addr)), spi)?;
+        let replies = self.transfer([Self::padding(); N], spi)?;
+        Ok(ChainOk {
+            status: replies.status,
+            data: replies.data[chip],
+        })
+    }
+
+    /// Write a raw register of chip `chip`
+    ///
+    /// Panics if `chip` is not below `N`.
+    pub fn write_raw<SPI: Transfer<u8>>(
+        &mut self,
+        chip: usize,
+        addr: u8,
+        data: u32,
+        spi: &mut SPI,
+    ) -> ChainResult<(), N, SPI::Error, CS::Error> {
+        let replies = self.transfer(Self::frames(chip, spi::write_frame(addr, data)), spi)?;
+        Ok(ChainOk {
+            status: replies.status,
+            data: (),
+        })
+    }
+
+    /// Read a typed register of chip `chip`
+    pub fn read_register<R, SPI: Transfer<u8>>(
+        &mut self,
+        chip: usize,
+        spi: &mut SPI,
+    ) -> ChainResult<R, N, SPI::Error, CS::Error>
+    where
+        R: Register + ReadableRegister,
+        u32: From<R>,
+    {
+        let ok = self.read_raw(chip, R::addr(), spi)?;
+        Ok(ChainOk {
+            status: ok.status,
+            data: R::from(ok.data),
+        })
+    }
+
+    /// Write a typed register of chip `chip`
+    pub fn write_register<R, SPI: Transfer<u8>>(
+        &mut self,
+        chip: usize,
+        r: R,
+        spi: &mut SPI,
+    ) -> ChainResult<(), N, SPI::Error, CS::Error>
+    where
+        R: Register + WritableRegister,
+        u32: From<R>,
+    {
+        self.write_raw(chip, R::addr(), u32::from(r), spi)
+    }
+
+    /// Datagram without side effect sent to the chips not addressed
+    fn padding() -> [u8; 5] {
+        spi::read_frame(GConf::addr())
+    }
+
+    fn frames(chip: usize, frame: [u8; 5]) -> [[u8; 5]; N] {
+        assert!(chip < N, "chip index out of the chain");
+        let mut frames = [Self::padding(); N];
+        frames[chip] = frame;
+        frames
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        general_configuration_register::GStat, ramp_generator_register::XTarget,
+    };
+    use crate::sim::{CsPin, Simulator};
+    use core::convert::Infallible;
+
+    /// Chain of simulators, executing the shifted datagrams once `N` of them went through
+    struct Chain<const N: usize> {
+        chips: [Simulator; N],
+        shift: [[u8; 5]; N],
+        shifted: usize,
+    }
+
+    impl<const N: usize> Chain<N> {
+        fn new() -> Self {
+            Self {
+                chips: core::array::from_fn(|_| Simulator::new()),
+                shift: [[0; 5]; N],
+                shifted: 0,
+            }
+        }
+    }
+
+    impl<const N: usize> Transfer<u8> for Chain<N> {
+        type Error = Infallible;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+            if self.shifted == 0 {
+                // load the replies to the previous transaction
+                for (chip, shift) in self.chips.iter_mut().zip(self.shift.iter_mut()) {
+                    shift[0] = u8::from(chip.spi_status());
+                }
+            }
+            let out = self.shift[N - 1];
+            self.shift.copy_within(0..N - 1, 1);
+            self.shift[0].copy_from_slice(words);
+            words.copy_from_slice(&out);
+            self.shifted += 1;
+            if self.shifted == N {
+                self.shifted = 0;
+                for (chip, shift) in self.chips.iter_mut().zip(self.shift.iter_mut()) {
+                    chip.transfer(shift).unwrap();
+                    // the simulator replies to the previous datagram, get the reply to this one
+                    *shift = spi::read_frame(GConf::addr());
+                    chip.transfer(shift).unwrap();
+                }
+            }
+            Ok(words)
+        }
+    }
+
+    #[test]
+    fn chain() {
+        let mut spi = Chain::<3>::new();
+        let mut chain = Tmc5072Chain::<_, 3>::new(CsPin);
+        let reset = u32::from(GStat {
+            reset: true,
+            ..Default::default()
+        });
+        for chip in spi.chips.iter_mut() {
+            chip.set(GStat::addr(), reset);
+        }
+        let ok = chain
+            .write_register(1, XTarget::<0> { x_target: -5 }, &mut spi)
+            .unwrap();
+        // all chips report their reset
+        assert!(ok.status.iter().all(|status| status.reset_flag));
+        assert_eq!(spi.chips[1].get(XTarget::<0>::addr()) as i32, -5);
+        assert_eq!(spi.chips[0].get(XTarget::<0>::addr()), 0);
+        assert_eq!(spi.chips[2].get(XTarget::<0>::addr()), 0);
+        spi.chips[2].set(XTarget::<0>::addr(), 42);
+        let ok = chain.read_register::<XTarget<0>, _>(2, &mut spi).unwrap();
+        assert_eq!(ok.data, XTarget::<0> { x_target: 42 });
+        let ok = chain.read_register::<XTarget<0>, _>(1, &mut spi).unwrap();
+        assert_eq!(ok.data, XTarget::<0> { x_target: -5 });
+        // reading GSTAT of chip 0 clears its reset flag only
+        chain.read_register::<GStat, _>(0, &mut spi).unwrap();
+        let ok = chain.write_raw(0, 0x2D, 0, &mut spi).unwrap();
+        assert_eq!(
+            ok.status.map(|status| status.reset_flag),
+            [false, true, true]
+        );
+    }
+}
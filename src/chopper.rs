@@ -0,0 +1,187 @@
+//! Chopper presets
+//!
+//! CHOPCONF holds many interdependent chopper parameters. The presets below start from the datasheet
+//! recommendations (TOFF=5, HSTRT=4, HEND=1, TBL=2 i.e. 36 clocks). The other bits keep their reset value:
+//! set vsense and the microstep resolution on top of the preset.
+//! - [`ChopConf::spreadcycle_default`]: spreadCycle, the datasheet starting point
+//! - [`ChopConf::quiet_standstill`]: shorter off time, a higher chopper frequency out of the audible range,
+//!   with random off time modulation, suited to stealthChop and low velocities
+//! - [`ChopConf::high_velocity`]: spreadCycle switching to constant off time and fullstep above VHIGH
+//!
+//! [`ChopperTuning`] exposes the spreadCycle parameters in physical units and checks their datasheet constraints.
+
+use crate::registers::motor_driver_register::ChopConf;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+impl<const M: u8> ChopConf<M> {
+    /// spreadCycle with TOFF=5, HSTRT=4, HEND=1, TBL=2
+    pub fn spreadcycle_default() -> Self {
+        Self {
+            toff: 5,
+            hstrt: 4,
+            hend: 1,
+            tbl: 2,
+            ..Default::default()
+        }
+    }
+
+    /// spreadCycle with TOFF=3 and random off time, for a quiet standstill and slow motion
+    pub fn quiet_standstill() -> Self {
+        Self {
+            toff: 3,
+            rndtf: true,
+            ..Self::spreadcycle_default()
+        }
+    }
+
+    /// spreadCycle switching to constant off time (vhighchm) and fullstep (vhighfs) above VHIGH
+    pub fn high_velocity() -> Self {
+        Self {
+            vhighchm: true,
+            vhighfs: true,
+            ..Self::spreadcycle_default()
+        }
+    }
+}
+
+/// spreadCycle parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChopperTuning {
+    /// TOFF: slow decay time of 12 + 32*TOFF clocks, 1..15
+    pub toff: u8,
+    /// TBL: comparator blank time of 16, 24, 36 or 54 clocks, 0..3
+    pub tbl: u8,
+    /// Hysteresis start offset added to the hysteresis end, 1..8
+    pub hysteresis_start: u8,
+    /// Hysteresis end, -3..12
+    pub hysteresis_end: i8,
+    /// rndtf: random off time modulation
+    pub random_off_time: bool,
+}
+
+impl Default for ChopperTuning {
+    fn default() -> Self {
+        Self::of(&ChopConf::<0>::spreadcycle_default())
+    }
+}
+
+impl ChopperTuning {
+    /// spreadCycle parameters of a CHOPCONF
+    pub fn of<const M: u8>(chop_conf: &ChopConf<M>) -> Self {
+        Self {
+            toff: chop_conf.toff,
+            tbl: chop_conf.tbl,
+            hysteresis_start: chop_conf.hstrt + 1,
+            hysteresis_end: chop_conf.hend as i8 - 3,
+            random_off_time: chop_conf.rndtf,
+        }
+    }
+
+    /// Checks the datasheet constraints
+    pub fn validate(&self) -> Result<(), ChopperTuningError> {
+        if self.toff > 15
+            || self.tbl > 3
+            || !(1..=8).contains(&self.hysteresis_start)
+            || !(-3..=12).contains(&self.hysteresis_end)
+        {
+            Err(ChopperTuningError::OutOfRange)
+        } else if self.toff == 0 {
+            Err(ChopperTuningError::DriverDisabled)
+        } else if self.toff == 1 && self.tbl < 2 {
+            Err(ChopperTuningError::OffTimeBlankTime)
+        } else if self.hysteresis_start as i8 + self.hysteresis_end > 16 {
+            Err(ChopperTuningError::Hysteresis)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes the parameters into `chop_conf`, in spreadCycle mode (chm=0)
+    pub fn apply<const M: u8>(
+        &self,
+        chop_conf: ChopConf<M>,
+    ) -> Result<ChopConf<M>, ChopperTuningError> {
+        self.validate()?;
+        Ok(ChopConf {
+            toff: self.toff,
+            tbl: self.tbl,
+            hstrt: self.hysteresis_start - 1,
+            hend: (self.hysteresis_end + 3) as u8,
+            rndtf: self.random_off_time,
+            chm: false,
+            ..chop_conf
+        })
+    }
+}
+
+/// Datasheet constraint violated by chopper parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChopperTuningError {
+    /// A value does not fit its field
+    OutOfRange,
+    /// TOFF is 0, which disables the driver
+    DriverDisabled,
+    /// TOFF=1 requires TBL ≥ 2 (36 clocks)
+    OffTimeBlankTime,
+    /// Hysteresis start plus end exceeds 16
+    Hysteresis,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        assert_eq!(u32::from(ChopConf::<0>::spreadcycle_default()), 0x0001_00C5);
+        assert_eq!(u32::from(ChopConf::<1>::quiet_standstill()), 0x0001_20C3);
+        assert_eq!(u32::from(ChopConf::<0>::high_velocity()), 0x000D_00C5);
+    }
+
+    #[test]
+    fn tuning() {
+        let tuning = ChopperTuning::default();
+        assert_eq!(tuning.hysteresis_start, 5);
+        assert_eq!(tuning.hysteresis_end, -2);
+        let base = ChopConf::<1>::high_velocity();
+        assert_eq!(tuning.apply(base), Ok(base));
+        let tuned = ChopperTuning {
+            hysteresis_end: 2,
+            ..tuning
+        }
+        .apply(base)
+        .unwrap();
+        assert_eq!(tuned.hend, 5);
+        assert!(tuned.vhighchm);
+        assert_eq!(
+            ChopperTuning {
+                hysteresis_start: 8,
+                hysteresis_end: 9,
+                ..tuning
+            }
+            .validate(),
+            Err(ChopperTuningError::Hysteresis)
+        );
+        assert_eq!(
+            ChopperTuning {
+                toff: 1,
+                tbl: 1,
+                ..tuning
+            }
+            .validate(),
+            Err(ChopperTuningError::OffTimeBlankTime)
+        );
+        assert_eq!(
+            ChopperTuning { toff: 0, ..tuning }.validate(),
+            Err(ChopperTuningError::DriverDisabled)
+        );
+        assert_eq!(
+            ChopperTuning { tbl: 4, ..tuning }.validate(),
+            Err(ChopperTuningError::OutOfRange)
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Encoder based step loss recovery
+//!
+//! [`ClosedLoopCorrector`] compares X_ENC to XACTUAL while the motor stands still and, when they drift apart,
+//! shifts XACTUAL towards the encoder so that the ramp generator moves the mechanism back to XTARGET.
+//! Corrections are bounded and rate limited, large deviations are compensated over several corrections.
+//! This is not a servo loop.
+//!
+//! [`StepLossDetector`] checks periodically, moving or not: it latches X_ENC and XACTUAL together
+//! ([`encoder_deviation`]) and flags a step loss when they differ by more than a threshold. Optionally, it moves
+//! XTARGET by the lost distance so that the mechanism still reaches the intended position.
+//!
+//! The encoder must be scaled (ENC_CONST) to count microsteps, in the same direction as XACTUAL
+//! ([`set_encoder_resolution`](crate::encoder::set_encoder_resolution)).
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::encoder::encoder_deviation;
+use crate::registers::{
+    encoder_registers::{EncLatch, EncMode, XEnc},
+    ramp_generator_driver_feature_control_register::{RampStat, XLatch},
+    ramp_generator_register::{RampMode, RampModeValue, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Corrector parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CorrectorConfig {
+    /// Largest tolerated |X_ENC - XACTUAL| (microsteps)
+    pub threshold: u32,
+    /// Largest distance of a single corrective move (microsteps)
+    pub max_correction: u32,
+    /// Minimum time between two corrections, in the unit of the `now` argument of [`ClosedLoopCorrector::poll`]
+    pub min_interval: u32,
+}
+
+/// Corrector outcome
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CorrectionStatus {
+    /// The motor is moving, nothing was checked
+    Moving,
+    /// Encoder and XACTUAL agree
+    InSync,
+    /// A corrective move of `correction` microsteps was started to compensate `deviation` (X_ENC - XACTUAL)
+    Corrected {
+        /// Measured deviation
+        deviation: i32,
+        /// Distance of the corrective move
+        correction: i32,
+    },
+    /// A correction is needed but the previous one is too recent
+    RateLimited {
+        /// Measured deviation
+        deviation: i32,
+    },
+}
+
+/// Step loss corrector of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ClosedLoopCorrector<const M: u8> {
+    config: CorrectorConfig,
+    last_correction: Option<u32>,
+    corrections: u32,
+}
+
+impl<const M: u8> ClosedLoopCorrector<M>
+where
+    RampMode<M>: Register,
+    RampStat<M>: Register,
+    XActual<M>: Register,
+    XEnc<M>: Register,
+{
+    /// Creates a corrector
+    pub fn new(config: CorrectorConfig) -> Self {
+        Self {
+            config,
+            last_correction: None,
+            corrections: 0,
+        }
+    }
+
+    /// Number of corrective moves issued
+    pub fn corrections(&self) -> u32 {
+        self.corrections
+    }
+
+    /// Checks the deviation at standstill and starts a corrective move if needed
+    ///
+    /// `now` is a monotonic (wrapping) time used for rate limiting.
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<CorrectionStatus, SpiError<SPI::Error, CS::Error>> {
+        let ramp_stat = tmc5072.read_register::<RampStat<M>, _>(spi)?.data;
+        if !(ramp_stat.position_reached && ramp_stat.vzero) {
+            return Ok(CorrectionStatus::Moving);
+        }
+        let x_actual = tmc5072.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+        let x_enc = tmc5072.read_register::<XEnc<M>, _>(spi)?.data.x_enc;
+        let deviation = x_enc.wrapping_sub(x_actual);
+        if deviation.unsigned_abs() <= self.config.threshold {
+            return Ok(CorrectionStatus::InSync);
+        }
+        if let Some(last) = self.last_correction {
+            if now.wrapping_sub(last) < self.config.min_interval {
+                return Ok(CorrectionStatus::RateLimited { deviation });
+            }
+        }
+        let max = self.config.max_correction.min(i32::MAX as u32) as i32;
+        let correction = deviation.wrapping_neg().clamp(-max, max);
+        // hold mode, so that redefining XACTUAL does not start a move before the switch back
+        tmc5072.write_register(
+            RampMode::<M> {
+                ramp_mode: RampModeValue::Hold,
+            },
+            spi,
+        )?;
+        // shift XACTUAL towards the encoder, the ramp generator then moves back to the unchanged XTARGET
+        tmc5072.write_register(
+            XActual::<M> {
+                x_actual: x_actual.wrapping_sub(correction),
+            },
+            spi,
+        )?;
+        tmc5072.write_register(
+            RampMode::<M> {
+                ramp_mode: RampModeValue::Positioning,
+            },
+            spi,
+        )?;
+        self.last_correction = Some(now);
+        self.corrections = self.corrections.saturating_add(1);
+        Ok(CorrectionStatus::Corrected {
+            deviation,
+            correction,
+        })
+    }
+}
+
+/// Step loss detector parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StepLossConfig {
+    /// Largest tolerated |X_ENC - XACTUAL| not yet compensated (microsteps)
+    pub threshold: u32,
+    /// Time between two checks, in the unit of the `now` argument of [`StepLossDetector::poll`]
+    pub interval: u32,
+    /// Moves XTARGET by the lost distance on a step loss
+    pub retarget: bool,
+}
+
+/// Step loss detector outcome
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StepLossStatus {
+    /// The interval did not elapse since the last check
+    NotDue,
+    /// The deviation (X_ENC - XACTUAL) is compensated or within the threshold
+    InSync {
+        /// Measured deviation
+        deviation: i32,
+    },
+    /// Steps were lost, XTARGET is unchanged
+    StepLoss {
+        /// Measured deviation
+        deviation: i32,
+    },
+    /// Steps were lost, XTARGET was moved to `x_target`
+    Retargeted {
+        /// Measured deviation
+        deviation: i32,
+        /// New target
+        x_target: i32,
+    },
+}
+
+/// Periodic step loss detector of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct StepLossDetector<const M: u8> {
+    config: StepLossConfig,
+    last: Option<u32>,
+    compensated: i32,
+}
+
+impl<const M: u8> StepLossDetector<M>
+where
+    EncMode<M>: Register,
+    EncLatch<M>: Register,
+    XLatch<M>: Register,
+    XTarget<M>: Register,
+{
+    /// Creates a detector
+    pub fn new(config: StepLossConfig) -> Self {
+        Self {
+            config,
+            last: None,
+            compensated: 0,
+        }
+    }
+
+    /// Deviation already compensated by moving XTARGET
+    pub fn compensated(&self) -> i32 {
+        self.compensated
+    }
+
+    /// Forgets the compensated deviation, e.g. after XACTUAL was set from the encoder
+    pub fn reset(&mut self) {
+        self.compensated = 0;
+    }
+
+    /// Measures the deviation if the interval elapsed, moves XTARGET on a step loss if enabled
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<StepLossStatus, SpiError<SPI::Error, CS::Error>> {
+        if let Some(last) = self.last {
+            if now.wrapping_sub(last) < self.config.interval {
+                return Ok(StepLossStatus::NotDue);
+            }
+        }
+        self.last = Some(now);
+        let deviation = encoder_deviation(&mut tmc5072.motor::<M, _>(spi))?;
+        let lost = deviation.wrapping_sub(self.compensated);
+        if lost.unsigned_abs() <= self.config.threshold {
+            return Ok(StepLossStatus::InSync { deviation });
+        }
+        if !self.config.retarget {
+            return Ok(StepLossStatus::StepLoss { deviation });
+        }
+        // the mechanism lags XACTUAL by the lost distance, so does the intended position
+        let x_target = tmc5072
+            .read_register::<XTarget<M>, _>(spi)?
+            .data
+            .x_target
+            .wrapping_sub(lost);
+        tmc5072.write_register(XTarget::<M> { x_target }, spi)?;
+        self.compensated = deviation;
+        Ok(StepLossStatus::Retargeted {
+            deviation,
+            x_target,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn correct() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_encoder_slip(0, -30);
+        tmc5072
+            .write_register(XTarget::<0> { x_target: 1000 }, &mut sim)
+            .unwrap();
+        sim.set_encoder_slip(0, 0);
+        let mut corrector = ClosedLoopCorrector::<0>::new(CorrectorConfig {
+            threshold: 5,
+            max_correction: 20,
+            min_interval: 100,
+        });
+        assert_eq!(
+            corrector.poll(&mut tmc5072, &mut sim, 0).unwrap(),
+            CorrectionStatus::Corrected {
+                deviation: -30,
+                correction: 20,
+            }
+        );
+        assert_eq!(sim.get(XActual::<0>::addr()), 1000);
+        assert_eq!(sim.get(XEnc::<0>::addr()), 990);
+        assert_eq!(
+            corrector.poll(&mut tmc5072, &mut sim, 50).unwrap(),
+            CorrectionStatus::RateLimited { deviation: -10 }
+        );
+        assert_eq!(
+            corrector.poll(&mut tmc5072, &mut sim, 150).unwrap(),
+            CorrectionStatus::Corrected {
+                deviation: -10,
+                correction: 10,
+            }
+        );
+        assert_eq!(
+            corrector.poll(&mut tmc5072, &mut sim, 300).unwrap(),
+            CorrectionStatus::InSync
+        );
+        assert_eq!(sim.get(XActual::<0>::addr()), 1000);
+        assert_eq!(corrector.corrections(), 2);
+    }
+
+    #[test]
+    fn step_loss() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_encoder_slip(1, -40);
+        tmc5072
+            .write_register(XTarget::<1> { x_target: 1000 }, &mut sim)
+            .unwrap();
+        sim.set_encoder_slip(1, 0);
+        let config = StepLossConfig {
+            threshold: 10,
+            interval: 100,
+            retarget: false,
+        };
+        let mut detector = StepLossDetector::<1>::new(config);
+        assert_eq!(
+            detector.poll(&mut tmc5072, &mut sim, 0),
+            Ok(StepLossStatus::StepLoss { deviation: -40 })
+        );
+        assert_eq!(
+            detector.poll(&mut tmc5072, &mut sim, 50),
+            Ok(StepLossStatus::NotDue)
+        );
+        let mut detector = StepLossDetector::<1>::new(StepLossConfig {
+            retarget: true,
+            ..config
+        });
+        assert_eq!(
+            detector.poll(&mut tmc5072, &mut sim, 0),
+            Ok(StepLossStatus::Retargeted {
+                deviation: -40,
+                x_target: 1040,
+            })
+        );
+        assert_eq!(sim.get(XEnc::<1>::addr()), 1000);
+        assert_eq!(
+            detector.poll(&mut tmc5072, &mut sim, 100),
+            Ok(StepLossStatus::InSync { deviation: -40 })
+        );
+        assert_eq!(detector.compensated(), -40);
+    }
+}
@@ -0,0 +1,802 @@
+//! Device configuration
+//!
+//! [`Config`] gathers the writable configuration registers of the TMC5072.
+//! Motion commands (RAMPMODE, XACTUAL, XTARGET) and counters (X_ENC) are not part of it.
+//!
+//! Configurations may come from untrusted sources (serde, non volatile memory),
+//! use [`validate_config`] to reject them or [`Config::sanitize`] to fix them before applying them.
+//!
+//! [`Config::frames`] encodes a configuration into the SPI datagrams writing it, in the order they must be sent
+//! (global registers first, then motor 0 and motor 1). Bootloaders or supervisors can replay this blob as is,
+//! the bytes being independent of the host endianness.
+//!
+//! [`Config::default`] holds the registers as the chip comes out of reset, with a sine wave microstep table and a
+//! stealthChop configuration, all other registers cleared. [`Config::non_default_registers`] lists what a
+//! configuration changes from the reset state, for concise startup logs and support dumps.
+//!
+//! Most configuration registers are write only: the driver keeps a shadow [`Config`] of the values written through
+//! it ([`Tmc5072::config`]). [`Tmc5072::dump_config`] completes it with the readable registers for persistence, and
+//! [`Tmc5072::apply_config`] writes a configuration back, e.g. after a power cycle or a reset reported by GSTAT.
+
+use crate::batch::BatchError;
+use crate::current::PowerStageConfig;
+use crate::registers::{
+    encoder_registers::{EncConst, EncMode},
+    general_configuration_register::{GConf, Output, SlaveConf, XCompare},
+    microstep_table_register::{
+        MsLut0, MsLut1, MsLut2, MsLut3, MsLut4, MsLut5, MsLut6, MsLut7, MsLutSel, MsLutStart,
+    },
+    motor_driver_register::{ChopConf, CoolConf, DcCtrl},
+    ramp_generator_driver_feature_control_register::{IHoldIRun, SwMode, VCoolThrs, VDcMin, VHigh},
+    ramp_generator_register::{AMax, DMax, TZeroWait, VMax, VStart, VStop, A1, D1, V1},
+    voltage_pwm_mode_stealth_chop::PwmConf,
+    Register,
+};
+use crate::spi::{write_frame, SpiError};
+use crate::Tmc5072;
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of register writes of a motor configuration
+pub const MOTOR_CONFIG_WRITES: usize = 20;
+
+/// Number of register writes of a configuration
+pub const CONFIG_WRITES: usize = 14 + 2 * MOTOR_CONFIG_WRITES;
+
+/// Address and value of a register write
+fn write<R: Register>(r: R) -> (u8, u32)
+where
+    u32: From<R>,
+{
+    (R::addr(), u32::from(r))
+}
+
+/// Updates `r` if it is the register at `addr`
+fn observe<R: Register>(r: &mut R, addr: u8, value: u32) -> bool
+where
+    u32: From<R>,
+{
+    let matches = R::addr() == addr;
+    if matches {
+        *r = R::from(value);
+    }
+    matches
+}
+
+/// Configuration registers of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MotorConfig<const M: u8> {
+    /// IHOLD_IRUN: Driver current control
+    pub i_hold_i_run: IHoldIRun<M>,
+    /// VCOOLTHRS: coolStep and stop on stall lower velocity threshold
+    pub v_cool_thrs: VCoolThrs<M>,
+    /// VHIGH: High velocity threshold
+    pub v_high: VHigh<M>,
+    /// VDCMIN: dcStep minimum velocity
+    pub v_dc_min: VDcMin<M>,
+    /// SW_MODE: Reference switch and stallGuard2 event configuration
+    pub sw_mode: SwMode<M>,
+    /// VSTART: Motor start velocity
+    pub v_start: VStart<M>,
+    /// A1: First acceleration between VSTART and V1
+    pub a1: A1<M>,
+    /// V1: First acceleration / deceleration phase threshold velocity
+    pub v1: V1<M>,
+    /// AMAX: Second acceleration between V1 and VMAX
+    pub a_max: AMax<M>,
+    /// VMAX: Motion ramp target velocity
+    pub v_max: VMax<M>,
+    /// DMAX: Deceleration between VMAX and V1
+    pub d_max: DMax<M>,
+    /// D1: Deceleration between V1 and VSTOP
+    pub d1: D1<M>,
+    /// VSTOP: Motor stop velocity
+    pub v_stop: VStop<M>,
+    /// TZEROWAIT: Waiting time after ramping down to zero velocity
+    pub t_zero_wait: TZeroWait<M>,
+    /// CHOPCONF: Chopper and driver configuration
+    pub chop_conf: ChopConf<M>,
+    /// COOLCONF: coolStep smart current control and stallGuard2 configuration
+    pub cool_conf: CoolConf<M>,
+    /// DCCTRL: dcStep automatic commutation configuration
+    pub dc_ctrl: DcCtrl<M>,
+    /// PWMCONF: Voltage PWM mode chopper configuration
+    pub pwm_conf: PwmConf<M>,
+    /// ENCMODE: Encoder configuration
+    pub enc_mode: EncMode<M>,
+    /// ENC_CONST: Encoder accumulation constant
+    pub enc_const: EncConst<M>,
+}
+
+/// Configuration registers of the TMC5072
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Config {
+    /// GCONF: Global configuration flags
+    pub g_conf: GConf,
+    /// SLAVECONF: UART slave configuration
+    pub slave_conf: SlaveConf,
+    /// OUTPUT: IO output pin polarity and data direction
+    pub output: Output,
+    /// X_COMPARE: Position comparison register
+    pub x_compare: XCompare,
+    /// MSLUT\[0\]: Microstep table entries 0..31
+    pub ms_lut0: MsLut0,
+    /// MSLUT\[1\]: Microstep table entries 32..63
+    pub ms_lut1: MsLut1,
+    /// MSLUT\[2\]: Microstep table entries 64..95
+    pub ms_lut2: MsLut2,
+    /// MSLUT\[3\]: Microstep table entries 96..127
+    pub ms_lut3: MsLut3,
+    /// MSLUT\[4\]: Microstep table entries 128..159
+    pub ms_lut4: MsLut4,
+    /// MSLUT\[5\]: Microstep table entries 160..191
+    pub ms_lut5: MsLut5,
+    /// MSLUT\[6\]: Microstep table entries 192..223
+    pub ms_lut6: MsLut6,
+    /// MSLUT\[7\]: Microstep table entries 224..255
+    pub ms_lut7: MsLut7,
+    /// MSLUTSEL: Microstep table segmentation
+    pub ms_lut_sel: MsLutSel,
+    /// MSLUTSTART: Microstep table start values
+    pub ms_lut_start: MsLutStart,
+    /// Motor 1 configuration
+    pub motor0: MotorConfig<0>,
+    /// Motor 2 configuration
+    pub motor1: MotorConfig<1>,
+}
+
+/// Changes made by [`Config::sanitize`]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SanitizeReport {
+    /// GCONF test_mode was cleared
+    pub test_mode_cleared: bool,
+    /// Short to GND protection was enabled again, per motor
+    pub short_protection_enabled: [bool; 2],
+    /// IRUN or IHOLD were reduced to keep the current within the driver limit, per motor
+    pub current_clamped: [bool; 2],
+    /// TBL was raised to 36 clocks because TOFF=1, per motor
+    pub blank_time_raised: [bool; 2],
+}
+
+impl SanitizeReport {
+    /// Whether the configuration was left untouched
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl<const M: u8> MotorConfig<M>
+where
+    IHoldIRun<M>: Register,
+    VCoolThrs<M>: Register,
+    VHigh<M>: Register,
+    VDcMin<M>: Register,
+    SwMode<M>: Register,
+    VStart<M>: Register,
+    A1<M>: Register,
+    V1<M>: Register,
+    AMax<M>: Register,
+    VMax<M>: Register,
+    DMax<M>: Register,
+    D1<M>: Register,
+    VStop<M>: Register,
+    TZeroWait<M>: Register,
+    ChopConf<M>: Register,
+    CoolConf<M>: Register,
+    DcCtrl<M>: Register,
+    PwmConf<M>: Register,
+    EncMode<M>: Register,
+    EncConst<M>: Register,
+{
+    /// Register writes (address, value) applying the motor configuration
+    ///
+    /// The driver is configured before the ramp generator, VMAX being written last.
+    pub fn writes(&self) -> [(u8, u32); MOTOR_CONFIG_WRITES] {
+        [
+            write(self.chop_conf),
+            write(self.cool_conf),
+            write(self.dc_ctrl),
+            write(self.pwm_conf),
+            write(self.i_hold_i_run),
+            write(self.v_cool_thrs),
+            write(self.v_high),
+            write(self.v_dc_min),
+            write(self.sw_mode),
+            write(self.enc_mode),
+            write(self.enc_const),
+            write(self.v_start),
+            write(self.a1),
+            write(self.v1),
+            write(self.a_max),
+            write(self.d_max),
+            write(self.d1),
+            write(self.v_stop),
+            write(self.t_zero_wait),
+            write(self.v_max),
+        ]
+    }
+
+    /// Updates the register written at `addr`, returns whether it is part of the motor configuration
+    fn observe_write(&mut self, addr: u8, value: u32) -> bool {
+        observe(&mut self.chop_conf, addr, value)
+            || observe(&mut self.cool_conf, addr, value)
+            || observe(&mut self.dc_ctrl, addr, value)
+            || observe(&mut self.pwm_conf, addr, value)
+            || observe(&mut self.i_hold_i_run, addr, value)
+            || observe(&mut self.v_cool_thrs, addr, value)
+            || observe(&mut self.v_high, addr, value)
+            || observe(&mut self.v_dc_min, addr, value)
+            || observe(&mut self.sw_mode, addr, value)
+            || observe(&mut self.enc_mode, addr, value)
+            || observe(&mut self.enc_const, addr, value)
+            || observe(&mut self.v_start, addr, value)
+            || observe(&mut self.a1, addr, value)
+            || observe(&mut self.v1, addr, value)
+            || observe(&mut self.a_max, addr, value)
+            || observe(&mut self.d_max, addr, value)
+            || observe(&mut self.d1, addr, value)
+            || observe(&mut self.v_stop, addr, value)
+            || observe(&mut self.t_zero_wait, addr, value)
+            || observe(&mut self.v_max, addr, value)
+    }
+}
+
+impl<const M: u8> MotorConfig<M> {
+    fn sanitize(&mut self, power_stage: Option<PowerStageConfig>, report: &mut SanitizeReport) {
+        let m = M as usize;
+        if self.chop_conf.diss2g.is_disabled() {
+            self.chop_conf.enable_short_protection();
+            report.short_protection_enabled[m] = true;
+        }
+        if let Some(power_stage) = power_stage {
+            let max = PowerStageConfig {
+                vsense: self.chop_conf.vsense,
+                ..power_stage
+            }
+            .max_current_scale();
+            if self.i_hold_i_run.i_run > max || self.i_hold_i_run.i_hold > max {
+                self.i_hold_i_run.i_run = self.i_hold_i_run.i_run.min(max);
+                self.i_hold_i_run.i_hold = self.i_hold_i_run.i_hold.min(max);
+                report.current_clamped[m] = true;
+            }
+        }
+        if OffTimeBlankTimeRule::violated(self) {
+            self.chop_conf.tbl = 2;
+            report.blank_time_raised[m] = true;
+        }
+    }
+}
+
+impl Config {
+    /// Configuration of the chip after a reset, same as [`Config::default`]
+    pub fn reset_defaults() -> Self {
+        Self::default()
+    }
+
+    /// Register writes (address, value) of the registers differing from [the reset state](Config::reset_defaults)
+    pub fn non_default_registers(&self) -> impl Iterator<Item = (u8, u32)> {
+        self.writes()
+            .into_iter()
+            .zip(Self::reset_defaults().writes())
+            .filter(|(write, reset)| write.1 != reset.1)
+            .map(|(write, _)| write)
+    }
+
+    /// Register writes (address, value) applying the configuration, in order
+    pub fn writes(&self) -> [(u8, u32); CONFIG_WRITES] {
+        let global = [
+            write(self.slave_conf),
+            write(self.g_conf),
+            write(self.output),
+            write(self.x_compare),
+            write(self.ms_lut0),
+            write(self.ms_lut1),
+            write(self.ms_lut2),
+            write(self.ms_lut3),
+            write(self.ms_lut4),
+            write(self.ms_lut5),
+            write(self.ms_lut6),
+            write(self.ms_lut7),
+            write(self.ms_lut_sel),
+            write(self.ms_lut_start),
+        ];
+        let mut writes = [(0, 0); CONFIG_WRITES];
+        let all = global
+            .into_iter()
+            .chain(self.motor0.writes())
+            .chain(self.motor1.writes());
+        for (w, a) in writes.iter_mut().zip(all) {
+            *w = a;
+        }
+        writes
+    }
+
+    /// Updates the register written at `addr`, returns whether it is part of the configuration
+    pub(crate) fn observe_write(&mut self, addr: u8, value: u32) -> bool {
+        observe(&mut self.slave_conf, addr, value)
+            || observe(&mut self.g_conf, addr, value)
+            || observe(&mut self.output, addr, value)
+            || observe(&mut self.x_compare, addr, value)
+            || observe(&mut self.ms_lut0, addr, value)
+            || observe(&mut self.ms_lut1, addr, value)
+            || observe(&mut self.ms_lut2, addr, value)
+            || observe(&mut self.ms_lut3, addr, value)
+            || observe(&mut self.ms_lut4, addr, value)
+            || observe(&mut self.ms_lut5, addr, value)
+            || observe(&mut self.ms_lut6, addr, value)
+            || observe(&mut self.ms_lut7, addr, value)
+            || observe(&mut self.ms_lut_sel, addr, value)
+            || observe(&mut self.ms_lut_start, addr, value)
+            || self.motor0.observe_write(addr, value)
+            || self.motor1.observe_write(addr, value)
+    }
+
+    /// SPI datagrams applying the configuration, in order
+    pub fn frames(&self) -> [[u8; 5]; CONFIG_WRITES] {
+        self.writes().map(|(addr, data)| write_frame(addr, data))
+    }
+
+    /// Clamps dangerous values of a configuration coming from an untrusted source
+    ///
+    /// - clears GCONF test_mode
+    /// - enables the short to GND protection
+    /// - limits IRUN and IHOLD to the driver maximum current when the power stage is known (its vsense being taken
+    ///   from CHOPCONF)
+    /// - raises TBL to 36 clocks when TOFF=1
+    pub fn sanitize(&mut self, power_stage: Option<PowerStageConfig>) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+        if self.g_conf.test_mode {
+            self.g_conf.test_mode = false;
+            report.test_mode_cleared = true;
+        }
+        self.motor0.sanitize(power_stage, &mut report);
+        self.motor1.sanitize(power_stage, &mut report);
+        report
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Shadow of the configuration registers written through the driver
+    ///
+    /// Registers not written since the driver was created hold their default value.
+    pub fn config(&self) -> &Config {
+        &self.shadow
+    }
+
+    /// Reads the readable configuration registers (GCONF, CHOPCONF, SW_MODE, ENCMODE) into the shadow configuration,
+    /// returns the whole configuration
+    pub fn dump_config<SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Config, SpiError<SPI::Error, CS::Error>> {
+        let values = self
+            .read_many(
+                &[
+                    GConf::addr(),
+                    ChopConf::<0>::addr(),
+                    SwMode::<0>::addr(),
+                    EncMode::<0>::addr(),
+                    ChopConf::<1>::addr(),
+                    SwMode::<1>::addr(),
+                    EncMode::<1>::addr(),
+                ],
+                spi,
+            )?
+            .data;
+        let [g_conf, chop_conf0, sw_mode0, enc_mode0, chop_conf1, sw_mode1, enc_mode1] = values;
+        self.shadow.g_conf = g_conf.into();
+        self.shadow.motor0.chop_conf = chop_conf0.into();
+        self.shadow.motor0.sw_mode = sw_mode0.into();
+        self.shadow.motor0.enc_mode = enc_mode0.into();
+        self.shadow.motor1.chop_conf = chop_conf1.into();
+        self.shadow.motor1.sw_mode = sw_mode1.into();
+        self.shadow.motor1.enc_mode = enc_mode1.into();
+        Ok(self.shadow)
+    }
+
+    /// Writes every register of `config`, in the order of [`Config::writes`]
+    ///
+    /// All writes are attempted even if some fail, the error telling which ones did.
+    pub fn apply_config<SPI: Transfer<u8>>(
+        &mut self,
+        config: &Config,
+        spi: &mut SPI,
+    ) -> Result<(), BatchError<SPI::Error, CS::Error, CONFIG_WRITES>> {
+        self.write_batch(&config.writes(), spi)
+    }
+}
+
+/// Configuration validation error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// Short to GND protection is disabled (CHOPCONF diss2g) on motor (index)
+    ShortProtectionDisabled(u8),
+    /// TOFF=1 is used with a blank time (TBL) below 36 clocks on motor (index)
+    OffTimeBlankTime(u8),
+    /// Effective HEND+HSTRT exceeds 16 in spreadCycle mode on motor (index)
+    HysteresisTooHigh(u8),
+    /// dcStep is enabled with VDCMIN below 256 on motor (index)
+    DcStepMinVelocityTooLow(u8),
+    /// IHOLD=0 (freewheeling or no holding torque) on position critical motor (index)
+    ZeroHoldCurrent(u8),
+    /// GCONF test_mode is set
+    TestModeEnabled,
+}
+
+impl ConfigError {
+    /// Diagnostic code identifying the violated rule
+    pub fn code(&self) -> u16 {
+        match self {
+            ConfigError::ShortProtectionDisabled(_) => 1,
+            ConfigError::OffTimeBlankTime(_) => 2,
+            ConfigError::HysteresisTooHigh(_) => 3,
+            ConfigError::DcStepMinVelocityTooLow(_) => 4,
+            ConfigError::ZeroHoldCurrent(_) => 5,
+            ConfigError::TestModeEnabled => 6,
+        }
+    }
+
+    /// Motor concerned by the violation, `None` for global registers
+    pub fn motor(&self) -> Option<u8> {
+        match *self {
+            ConfigError::ShortProtectionDisabled(m)
+            | ConfigError::OffTimeBlankTime(m)
+            | ConfigError::HysteresisTooHigh(m)
+            | ConfigError::DcStepMinVelocityTooLow(m)
+            | ConfigError::ZeroHoldCurrent(m) => Some(m),
+            ConfigError::TestModeEnabled => None,
+        }
+    }
+}
+
+/// A configuration check evaluated by [`check_config`]
+pub trait ConfigRule {
+    /// Calls `report` for each violation found in `config`
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError));
+}
+
+/// GCONF test_mode must never be set in production
+pub struct TestModeRule;
+
+impl ConfigRule for TestModeRule {
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError)) {
+        if config.g_conf.test_mode {
+            report(ConfigError::TestModeEnabled);
+        }
+    }
+}
+
+/// Short to GND protection must stay enabled
+pub struct ShortProtectionRule;
+
+impl ConfigRule for ShortProtectionRule {
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError)) {
+        if config.motor0.chop_conf.diss2g.is_disabled() {
+            report(ConfigError::ShortProtectionDisabled(0));
+        }
+        if config.motor1.chop_conf.diss2g.is_disabled() {
+            report(ConfigError::ShortProtectionDisabled(1));
+        }
+    }
+}
+
+/// TOFF=1 must only be used with TBL ≥ 36 clocks
+pub struct OffTimeBlankTimeRule;
+
+impl OffTimeBlankTimeRule {
+    fn violated<const M: u8>(motor: &MotorConfig<M>) -> bool {
+        motor.chop_conf.toff == 1 && motor.chop_conf.tbl < 2
+    }
+}
+
+impl ConfigRule for OffTimeBlankTimeRule {
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError)) {
+        if Self::violated(&config.motor0) {
+            report(ConfigError::OffTimeBlankTime(0));
+        }
+        if Self::violated(&config.motor1) {
+            report(ConfigError::OffTimeBlankTime(1));
+        }
+    }
+}
+
+/// Effective HEND+HSTRT must not exceed 16 in spreadCycle mode (chm=0)
+pub struct HysteresisRule;
+
+impl HysteresisRule {
+    fn violated<const M: u8>(motor: &MotorConfig<M>) -> bool {
+        // HSTRT adds 1..8, HEND is -3..12
+        let chop_conf = &motor.chop_conf;
+        !chop_conf.chm && (chop_conf.hstrt as i16 + 1) + (chop_conf.hend as i16 - 3) > 16
+    }
+}
+
+impl ConfigRule for HysteresisRule {
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError)) {
+        if Self::violated(&config.motor0) {
+            report(ConfigError::HysteresisTooHigh(0));
+        }
+        if Self::violated(&config.motor1) {
+            report(ConfigError::HysteresisTooHigh(1));
+        }
+    }
+}
+
+/// VDCMIN must be at least 256 when dcStep is enabled (VDCMIN ≠ 0)
+pub struct DcStepMinVelocityRule;
+
+impl DcStepMinVelocityRule {
+    fn violated<const M: u8>(motor: &MotorConfig<M>) -> bool {
+        motor.v_dc_min.v_dc_min != 0 && motor.v_dc_min.v_dc_min < 256
+    }
+}
+
+impl ConfigRule for DcStepMinVelocityRule {
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError)) {
+        if Self::violated(&config.motor0) {
+            report(ConfigError::DcStepMinVelocityTooLow(0));
+        }
+        if Self::violated(&config.motor1) {
+            report(ConfigError::DcStepMinVelocityTooLow(1));
+        }
+    }
+}
+
+/// IHOLD must not be 0 on position critical motors, which would lose their position under load at standstill
+///
+/// Not part of [`DEFAULT_RULES`], the critical motors depend on the application.
+pub struct PositionCriticalRule {
+    /// Position critical motors
+    pub motors: [bool; 2],
+}
+
+impl ConfigRule for PositionCriticalRule {
+    fn check(&self, config: &Config, report: &mut dyn FnMut(ConfigError)) {
+        if self.motors[0] && config.motor0.i_hold_i_run.i_hold == 0 {
+            report(ConfigError::ZeroHoldCurrent(0));
+        }
+        if self.motors[1] && config.motor1.i_hold_i_run.i_hold == 0 {
+            report(ConfigError::ZeroHoldCurrent(1));
+        }
+    }
+}
+
+/// Rules evaluated by [`validate_config`]
+pub const DEFAULT_RULES: &[&dyn ConfigRule] = &[
+    &TestModeRule,
+    &ShortProtectionRule,
+    &OffTimeBlankTimeRule,
+    &HysteresisRule,
+    &DcStepMinVelocityRule,
+];
+
+/// Evaluates `rules` on a configuration, calling `report` for every violation
+pub fn check_config(
+    config: &Config,
+    rules: &[&dyn ConfigRule],
+    report: &mut dyn FnMut(ConfigError),
+) {
+    for rule in rules {
+        rule.check(config, report);
+    }
+}
+
+/// Checks a configuration against [`DEFAULT_RULES`], returns the first violation
+pub fn validate_config(config: &Config) -> Result<(), ConfigError> {
+    let mut error = None;
+    check_config(config, DEFAULT_RULES, &mut |e| {
+        error.get_or_insert(e);
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::DangerZone;
+
+    #[test]
+    fn frames() {
+        let mut config = Config::default();
+        config.g_conf.poscmp_enable = true;
+        config.motor1.v_max.v_max = 0x123456;
+        let frames = config.frames();
+        // SLAVECONF, then GCONF
+        assert_eq!(frames[0], [0x83, 0, 0, 0, 0]);
+        assert_eq!(frames[1], [0x80, 0, 0, 0, 0x08]);
+        assert_eq!(frames[CONFIG_WRITES - 1], [0xC7, 0x00, 0x12, 0x34, 0x56]);
+        let mut addrs = config.writes().map(|(addr, _)| addr);
+        addrs.sort_unstable();
+        assert!(addrs.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn non_default_registers() {
+        let mut config = Config::reset_defaults();
+        assert_eq!(config.non_default_registers().count(), 0);
+        config.motor1.v_max.v_max = 5000;
+        config.ms_lut_start.start_sin90 = 0xf0;
+        let mut changed = config.non_default_registers();
+        assert_eq!(changed.next(), Some((MsLutStart::addr(), 0x00F0_0000)));
+        assert_eq!(changed.next(), Some((VMax::<1>::addr(), 5000)));
+        assert_eq!(changed.next(), None);
+        assert_eq!(Config::reset_defaults(), Config::default());
+        // all cleared
+        let cleared = Config {
+            ms_lut_sel: MsLutSel::from(0),
+            motor0: MotorConfig {
+                pwm_conf: PwmConf::from(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(cleared.non_default_registers().count(), 2);
+    }
+
+    #[test]
+    fn snapshot() {
+        use crate::sim::{CsPin, Simulator};
+
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut config = Config::default();
+        config.motor1.v_max.v_max = 5000;
+        config.motor0.i_hold_i_run.i_run = 20;
+        config.motor0.chop_conf.toff = 3;
+        tmc5072.apply_config(&config, &mut sim).unwrap();
+        assert_eq!(tmc5072.config(), &config);
+        assert_eq!(sim.get(VMax::<1>::addr()), 5000);
+        tmc5072
+            .write_register(
+                IHoldIRun::<1> {
+                    i_run: 10,
+                    ..Default::default()
+                },
+                &mut sim,
+            )
+            .unwrap();
+        // changed behind the driver back
+        sim.set(
+            ChopConf::<0>::addr(),
+            u32::from(ChopConf::<0> {
+                toff: 5,
+                ..Default::default()
+            }),
+        );
+        let dump = tmc5072.dump_config(&mut sim).unwrap();
+        assert_eq!(dump.motor0.chop_conf.toff, 5);
+        assert_eq!(dump.motor1.i_hold_i_run.i_run, 10);
+        assert_eq!(dump.motor1.v_max.v_max, 5000);
+        // power cycle
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072.apply_config(&dump, &mut sim).unwrap();
+        assert_eq!(tmc5072.dump_config(&mut sim).unwrap(), dump);
+        assert_eq!(
+            sim.get(IHoldIRun::<1>::addr()),
+            u32::from(dump.motor1.i_hold_i_run)
+        );
+    }
+
+    #[test]
+    fn default_is_valid() {
+        assert_eq!(validate_config(&Config::default()), Ok(()));
+    }
+
+    #[test]
+    fn sanitize() {
+        let mut config = Config::default();
+        assert!(config.sanitize(Some(PowerStageConfig::new(150))).is_clean());
+        config.g_conf.test_mode = true;
+        config
+            .motor0
+            .chop_conf
+            .disable_short_protection_unchecked(DangerZone::acknowledge());
+        config.motor0.i_hold_i_run.i_run = 31;
+        config.motor0.i_hold_i_run.i_hold = 10;
+        config.motor1.chop_conf.toff = 1;
+        config.motor1.chop_conf.tbl = 1;
+        let report = config.sanitize(Some(PowerStageConfig::new(150)));
+        assert_eq!(
+            report,
+            SanitizeReport {
+                test_mode_cleared: true,
+                short_protection_enabled: [true, false],
+                current_clamped: [true, false],
+                blank_time_raised: [false, true],
+            }
+        );
+        assert!(!config.g_conf.test_mode);
+        assert_eq!(validate_config(&config), Ok(()));
+        assert_eq!(config.motor0.i_hold_i_run.i_run, 25);
+        assert_eq!(config.motor0.i_hold_i_run.i_hold, 10);
+        assert_eq!(config.motor1.chop_conf.tbl, 2);
+        assert!(config.sanitize(Some(PowerStageConfig::new(150))).is_clean());
+    }
+
+    #[test]
+    fn short_protection_disabled() {
+        let mut config = Config::default();
+        config
+            .motor1
+            .chop_conf
+            .disable_short_protection_unchecked(DangerZone::acknowledge());
+        assert_eq!(
+            validate_config(&config),
+            Err(ConfigError::ShortProtectionDisabled(1))
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let mut config = Config::default();
+        config.motor0.chop_conf.toff = 1;
+        config.motor0.chop_conf.tbl = 2;
+        config.motor0.chop_conf.hstrt = 7;
+        config.motor0.chop_conf.hend = 11;
+        config.motor1.chop_conf.toff = 1;
+        config.motor1.chop_conf.tbl = 1;
+        config.motor1.chop_conf.hstrt = 7;
+        config.motor1.chop_conf.hend = 12;
+        config.motor1.v_dc_min.v_dc_min = 255;
+        let mut errors = [None; 4];
+        let mut count = 0;
+        check_config(&config, DEFAULT_RULES, &mut |e| {
+            errors[count] = Some(e);
+            count += 1;
+        });
+        assert_eq!(
+            errors,
+            [
+                Some(ConfigError::OffTimeBlankTime(1)),
+                Some(ConfigError::HysteresisTooHigh(1)),
+                Some(ConfigError::DcStepMinVelocityTooLow(1)),
+                None,
+            ]
+        );
+        assert_eq!(errors[1].unwrap().code(), 3);
+        assert_eq!(errors[1].unwrap().motor(), Some(1));
+        // chm=1 reuses HSTRT and HEND for fast decay time and offset
+        config.motor1.chop_conf.chm = true;
+        config.motor1.chop_conf.tbl = 2;
+        config.motor1.v_dc_min.v_dc_min = 256;
+        assert_eq!(validate_config(&config), Ok(()));
+    }
+
+    #[test]
+    fn position_critical() {
+        let mut config = Config::default();
+        config.motor0.i_hold_i_run.i_hold = 4;
+        let rule = PositionCriticalRule {
+            motors: [true, true],
+        };
+        let mut errors = [None; 2];
+        let mut count = 0;
+        check_config(&config, &[&rule], &mut |e| {
+            errors[count] = Some(e);
+            count += 1;
+        });
+        assert_eq!(errors, [Some(ConfigError::ZeroHoldCurrent(1)), None]);
+        assert_eq!(errors[0].unwrap().code(), 5);
+    }
+
+    #[test]
+    fn test_mode() {
+        let mut config = Config::default();
+        config.g_conf.test_mode = true;
+        assert_eq!(validate_config(&config), Err(ConfigError::TestModeEnabled));
+        assert_eq!(ConfigError::TestModeEnabled.code(), 6);
+        assert_eq!(ConfigError::TestModeEnabled.motor(), None);
+        assert!(config.sanitize(None).test_mode_cleared);
+        assert_eq!(validate_config(&config), Ok(()));
+    }
+}
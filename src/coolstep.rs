@@ -0,0 +1,334 @@
+//! coolStep configuration
+//!
+//! coolStep adapts the motor current to the load measured by stallGuard2: the current is increased when SG_RESULT
+//! falls below SEMIN*32 and decreased when it rises above (SEMIN+SEMAX+1)*32, down to 1/2 or 1/4 of IRUN.
+//! It is only active between VCOOLTHRS and VHIGH, and only with SEMIN ≠ 0.
+//!
+//! [`Tmc5072::apply_cool_step`] validates and writes COOLCONF, VCOOLTHRS and VHIGH together,
+//! [`Tmc5072::cool_step_scaling`] reads back the resulting current scale (CS_ACTUAL).
+//!
+//! The stallGuard2 threshold rarely suits the whole velocity envelope: a [`SgtTable`] assigns one to each velocity
+//! range, [`TelemetryPoller`](crate::telemetry::TelemetryPoller) rewriting COOLCONF as VACTUAL crosses ranges.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    motor_driver_register::{CoolConf, CurrentDownStep, DrvStatus},
+    ramp_generator_driver_feature_control_register::{VCoolThrs, VHigh},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// coolStep settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CoolStepConfig {
+    /// Lower SG_RESULT threshold (in 32), 1..15
+    pub semin: u8,
+    /// Hysteresis of the upper SG_RESULT threshold (in 32), 0..15
+    pub semax: u8,
+    /// Current increment steps per measurement: 0..3 for 1, 2, 4, 8
+    pub seup: u8,
+    /// Current decrement speed
+    pub sedn: CurrentDownStep,
+    /// Minimum current 1/4 of IRUN instead of 1/2
+    pub seimin: bool,
+    /// stallGuard2 threshold, sharing COOLCONF
+    pub sgt: i8,
+    /// stallGuard2 filter, sharing COOLCONF
+    pub sfilt: bool,
+    /// VCOOLTHRS: coolStep is active above this velocity
+    pub v_cool_thrs: u32,
+    /// VHIGH: coolStep is active below this velocity
+    pub v_high: u32,
+}
+
+impl CoolStepConfig {
+    /// Checks the settings
+    pub fn validate(&self) -> Result<(), CoolStepConfigError> {
+        if self.semin == 0 {
+            Err(CoolStepConfigError::Disabled)
+        } else if self.semin > 15
+            || self.semax > 15
+            || self.seup > 3
+            || !(-64..=63).contains(&self.sgt)
+            || self.v_cool_thrs >= 1 << 23
+            || self.v_high >= 1 << 23
+        {
+            Err(CoolStepConfigError::OutOfRange)
+        } else if self.v_cool_thrs >= self.v_high {
+            Err(CoolStepConfigError::EmptyVelocityRange)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// COOLCONF of motor `M`
+    pub fn cool_conf<const M: u8>(&self) -> CoolConf<M> {
+        CoolConf {
+            semin: self.semin,
+            seup: self.seup,
+            semax: self.semax,
+            sedn: self.sedn,
+            seimin: self.seimin,
+            sgt: self.sgt,
+            sfilt: self.sfilt,
+        }
+    }
+}
+
+/// Invalid coolStep settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CoolStepConfigError {
+    /// SEMIN is 0, which turns coolStep off
+    Disabled,
+    /// A value does not fit its field
+    OutOfRange,
+    /// VCOOLTHRS is not below VHIGH, coolStep would never be active
+    EmptyVelocityRange,
+}
+
+/// coolStep error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CoolStepError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Invalid settings, nothing was written
+    Invalid(CoolStepConfigError),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for CoolStepError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        CoolStepError::SpiError(e)
+    }
+}
+
+/// Number of velocity ranges of a [`SgtTable`]
+pub const SGT_RANGES: usize = 4;
+
+/// stallGuard2 threshold of a velocity range
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SgtRange {
+    /// Upper velocity of the range
+    pub v_max: u32,
+    /// stallGuard2 threshold, -64..63
+    pub sgt: i8,
+}
+
+/// stallGuard2 thresholds per velocity range
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SgtTable {
+    /// Ranges by increasing upper velocity, the last one also applying above its upper velocity
+    pub ranges: [SgtRange; SGT_RANGES],
+    /// Velocity to cross past a range boundary before changing range, COOLCONF not being rewritten on each cycle
+    /// at a boundary
+    pub hysteresis: u32,
+}
+
+impl SgtTable {
+    /// Checks the thresholds and the order of the ranges
+    pub fn validate(&self) -> Result<(), SgtTableError> {
+        if self
+            .ranges
+            .iter()
+            .any(|range| !(-64..=63).contains(&range.sgt))
+        {
+            Err(SgtTableError::OutOfRange)
+        } else if self.ranges.windows(2).any(|r| r[0].v_max > r[1].v_max) {
+            Err(SgtTableError::Unsorted)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Range of velocity `v_actual` when in range `current`
+    pub fn range(&self, current: Option<usize>, v_actual: i32) -> usize {
+        let v = v_actual.unsigned_abs();
+        let range_of = |v: u32| {
+            self.ranges
+                .iter()
+                .position(|range| v <= range.v_max)
+                .unwrap_or(SGT_RANGES - 1)
+        };
+        match current {
+            None => range_of(v),
+            Some(current) => {
+                let up = range_of(v.saturating_sub(self.hysteresis));
+                let down = range_of(v.saturating_add(self.hysteresis));
+                if up > current {
+                    up
+                } else if down < current {
+                    down
+                } else {
+                    current
+                }
+            }
+        }
+    }
+}
+
+/// Invalid stallGuard2 threshold table
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SgtTableError {
+    /// A threshold does not fit SGT
+    OutOfRange,
+    /// The upper velocities are not increasing
+    Unsorted,
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Validates and writes COOLCONF, VCOOLTHRS and VHIGH of motor `M`
+    pub fn apply_cool_step<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        config: &CoolStepConfig,
+        spi: &mut SPI,
+    ) -> Result<(), CoolStepError<SPI::Error, CS::Error>>
+    where
+        CoolConf<M>: Register,
+        VCoolThrs<M>: Register,
+        VHigh<M>: Register,
+    {
+        config.validate().map_err(CoolStepError::Invalid)?;
+        self.write_register(
+            VCoolThrs::<M> {
+                v_cool_thrs: config.v_cool_thrs,
+            },
+            spi,
+        )?;
+        self.write_register(
+            VHigh::<M> {
+                v_high: config.v_high,
+            },
+            spi,
+        )?;
+        self.write_register(config.cool_conf::<M>(), spi)?;
+        Ok(())
+    }
+
+    /// Actual current scale CS_ACTUAL of motor `M`, in ‰ of the run current `i_run` (IHOLD_IRUN being write only)
+    pub fn cool_step_scaling<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        i_run: u8,
+        spi: &mut SPI,
+    ) -> Result<u16, SpiError<SPI::Error, CS::Error>>
+    where
+        DrvStatus<M>: Register,
+    {
+        let cs_actual = self.read_register::<DrvStatus<M>, _>(spi)?.data.cs_actual;
+        Ok(scaling(cs_actual, i_run))
+    }
+}
+
+/// (CS_ACTUAL+1) / (IRUN+1) in ‰
+fn scaling(cs_actual: u8, i_run: u8) -> u16 {
+    ((cs_actual as u32 + 1) * 1000 / (i_run as u32 + 1)) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    const CONFIG: CoolStepConfig = CoolStepConfig {
+        semin: 5,
+        semax: 2,
+        seup: 1,
+        sedn: CurrentDownStep::Per32,
+        seimin: false,
+        sgt: -3,
+        sfilt: false,
+        v_cool_thrs: 5000,
+        v_high: 200_000,
+    };
+
+    #[test]
+    fn validate() {
+        assert_eq!(CONFIG.validate(), Ok(()));
+        assert_eq!(
+            CoolStepConfig { semin: 0, ..CONFIG }.validate(),
+            Err(CoolStepConfigError::Disabled)
+        );
+        assert_eq!(
+            CoolStepConfig { seup: 4, ..CONFIG }.validate(),
+            Err(CoolStepConfigError::OutOfRange)
+        );
+        assert_eq!(
+            CoolStepConfig {
+                v_high: 5000,
+                ..CONFIG
+            }
+            .validate(),
+            Err(CoolStepConfigError::EmptyVelocityRange)
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072.apply_cool_step::<0, _>(&CONFIG, &mut sim).unwrap();
+        assert_eq!(
+            CoolConf::<0>::from(sim.get(CoolConf::<0>::addr())),
+            CONFIG.cool_conf()
+        );
+        assert_eq!(sim.get(VCoolThrs::<0>::addr()), 5000);
+        assert_eq!(sim.get(VHigh::<0>::addr()), 200_000);
+        sim.set(
+            DrvStatus::<0>::addr(),
+            u32::from(DrvStatus::<0> {
+                cs_actual: 15,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(
+            tmc5072.cool_step_scaling::<0, _>(31, &mut sim).unwrap(),
+            500
+        );
+    }
+
+    #[test]
+    fn sgt_table() {
+        let table = SgtTable {
+            ranges: [
+                SgtRange {
+                    v_max: 10_000,
+                    sgt: 2,
+                },
+                SgtRange {
+                    v_max: 50_000,
+                    sgt: 5,
+                },
+                SgtRange {
+                    v_max: 200_000,
+                    sgt: 10,
+                },
+                SgtRange {
+                    v_max: 200_000,
+                    sgt: 10,
+                },
+            ],
+            hysteresis: 1000,
+        };
+        assert_eq!(table.validate(), Ok(()));
+        assert_eq!(table.range(None, -10_500), 1);
+        assert_eq!(table.range(Some(0), 10_500), 0);
+        assert_eq!(table.range(Some(0), -11_500), 1);
+        assert_eq!(table.range(Some(1), 9_500), 1);
+        assert_eq!(table.range(Some(1), 8_500), 0);
+        assert_eq!(table.range(Some(0), 300_000), 3);
+        let mut unsorted = table;
+        unsorted.ranges[1].v_max = 5000;
+        assert_eq!(unsorted.validate(), Err(SgtTableError::Unsorted));
+    }
+}
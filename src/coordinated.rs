@@ -0,0 +1,139 @@
+//! Coordinated moves of both motors
+//!
+//! [`Tmc5072::move_xy`] moves both motors along a straight line (axis 0 = motor 0, axis 1 = motor 1) in a single
+//! positioning move: the axis with the longest distance leads, the other one follows with the ramp profile of the
+//! leader scaled by the ratio of their distances, so that both ramps have the same shape and duration and both
+//! motors arrive together. The profile of the leader is first lowered where the follower could not keep up,
+//! so that no motor exceeds its shadow ramp parameters.
+//!
+//! The ramp parameters of both motors may be lowered: apply their own profiles again ([`Tmc5072::apply_ramp`])
+//! for single axis moves.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::plan::MovePlan;
+use crate::ramp::{RampError, RampProfile};
+use crate::registers::{ramp_generator_register::XActual, Register};
+use crate::Tmc5072;
+
+impl RampProfile {
+    /// Scales velocities and accelerations by `num / den` (at most 1), keeping them valid
+    fn scaled(&self, num: u32, den: u32) -> Self {
+        let scale = |value: u32| (value as u64 * num as u64 / den as u64) as u32;
+        let scale_a = |value: u16| (scale(value as u32) as u16).max(1);
+        let v_start = scale(self.v_start);
+        Self {
+            v_start,
+            a1: scale_a(self.a1),
+            v1: scale(self.v1),
+            a_max: scale_a(self.a_max),
+            v_max: scale(self.v_max).max(1),
+            d_max: scale_a(self.d_max),
+            d1: scale_a(self.d1),
+            v_stop: scale(self.v_stop).max(v_start).max(1),
+            t_zero_wait: self.t_zero_wait,
+        }
+    }
+
+    /// Lowers velocities and accelerations to those of `other` scaled by `num / den` (at least 1)
+    fn limited(&self, other: &Self, num: u32, den: u32) -> Self {
+        let limit = |value: u32, other: u32| {
+            (other as u64 * num as u64 / den as u64).min(value as u64) as u32
+        };
+        let limit_a = |value: u16, other: u16| limit(value as u32, other as u32) as u16;
+        let v_start = limit(self.v_start, other.v_start);
+        Self {
+            v_start,
+            a1: limit_a(self.a1, other.a1),
+            v1: limit(self.v1, other.v1),
+            a_max: limit_a(self.a_max, other.a_max),
+            v_max: limit(self.v_max, other.v_max),
+            d_max: limit_a(self.d_max, other.d_max),
+            d1: limit_a(self.d1, other.d1),
+            v_stop: limit(self.v_stop, other.v_stop).max(v_start),
+            t_zero_wait: self.t_zero_wait.max(other.t_zero_wait),
+        }
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Moves motor 0 to `target0` and motor 1 to `target1` along a straight line, both motors arriving together
+    ///
+    /// Returns the plan of the leading axis. Nothing is written if a shadow ramp profile is invalid.
+    pub fn move_xy<SPI: Transfer<u8>>(
+        &mut self,
+        target0: i32,
+        target1: i32,
+        spi: &mut SPI,
+    ) -> Result<MovePlan, RampError<SPI::Error, CS::Error>> {
+        let x_actual = self
+            .read_many(&[XActual::<0>::addr(), XActual::<1>::addr()], spi)?
+            .data;
+        let distances = [
+            target0.wrapping_sub(x_actual[0] as i32).unsigned_abs(),
+            target1.wrapping_sub(x_actual[1] as i32).unsigned_abs(),
+        ];
+        let profiles = [
+            RampProfile::of(&self.shadow.motor0),
+            RampProfile::of(&self.shadow.motor1),
+        ];
+        profiles[0].validate().map_err(RampError::Invalid)?;
+        profiles[1].validate().map_err(RampError::Invalid)?;
+        let lead = if distances[1] > distances[0] { 1 } else { 0 };
+        let follower = 1 - lead;
+        let (d_lead, d_follower) = (distances[lead], distances[follower]);
+        let mut profile = profiles;
+        if d_follower > 0 {
+            profile[lead] = profiles[lead].limited(&profiles[follower], d_lead, d_follower);
+            profile[follower] = profile[lead].scaled(d_follower, d_lead);
+        }
+        let plan = profile[lead].plan(d_lead).map_err(RampError::Invalid)?;
+        let moves = self.arm_moves(target0, target1, spi)?;
+        if d_follower > 0 {
+            self.apply_ramp::<0, _>(&profile[0], spi)?;
+            self.apply_ramp::<1, _>(&profile[1], spi)?;
+        }
+        self.trigger(moves, spi)?;
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::ramp_generator_register::{AMax, RampMode, VMax};
+    use crate::sim::{CsPin, Simulator, Timing};
+
+    #[test]
+    fn move_xy() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        let fast = RampProfile::trapezoidal(100_000, 1000);
+        let slow = RampProfile::trapezoidal(20_000, 1000);
+        tmc5072.apply_ramp::<0, _>(&fast, &mut sim).unwrap();
+        tmc5072.apply_ramp::<1, _>(&slow, &mut sim).unwrap();
+        // motor 0 leads, limited by motor 1 to 4 times its velocity
+        let plan = tmc5072.move_xy(-40_000, 10_000, &mut sim).unwrap();
+        let lead = RampProfile {
+            v_max: 80_000,
+            ..fast
+        };
+        assert_eq!(Ok(plan), lead.plan(40_000));
+        assert_eq!(sim.get(RampMode::<0>::addr()), 0);
+        assert_eq!(sim.get(VMax::<0>::addr()), 80_000);
+        assert_eq!(sim.get(AMax::<0>::addr()), 1000);
+        assert_eq!(sim.get(VMax::<1>::addr()), 20_000);
+        assert_eq!(sim.get(AMax::<1>::addr()), 250);
+        let mut polls = 0;
+        while sim.get(XActual::<1>::addr()) as i32 != 10_000 {
+            sim.advance(10_000);
+            let x0 = sim.get(XActual::<0>::addr()) as i32;
+            let x1 = sim.get(XActual::<1>::addr()) as i32;
+            assert!((x0 + 4 * x1).abs() <= 8);
+            polls += 1;
+            assert!(polls < 1000);
+        }
+        assert_eq!(sim.get(XActual::<0>::addr()) as i32, -40_000);
+    }
+}
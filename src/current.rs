@@ -0,0 +1,485 @@
+//! Motor current helpers
+//!
+//! Changing IRUN abruptly while the motor turns makes it jerk.
+//! [`RunCurrentChange`] stages the change in small IRUN steps, optionally only while the motor is slow,
+//! and applies it at once when the motor stands still.
+//!
+//! [`CurrentConfig::from_milliamps`] computes IRUN and IHOLD from RMS coil currents with the datasheet formula
+//! I_RMS = (CS+1)/32 * VFS/(RSENSE+20mΩ) * 1/√2, VFS being 180mV with vsense and 320mV without. The sense resistor,
+//! vsense and the single driver mode are described once by a [`PowerStageConfig`], written by
+//! [`Tmc5072::apply_power_stage`]. Current scales are rounded to the nearest step: the achievable currents are
+//! returned along them.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::gconf::GConfError;
+use crate::registers::{
+    motor_driver_register::ChopConf, ramp_generator_driver_feature_control_register::IHoldIRun,
+    ramp_generator_register::VActual, Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Run current change parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SmoothCurrentConfig {
+    /// Largest IRUN change applied by a single update (at least 1)
+    pub max_step: u8,
+    /// IRUN is only changed while |VACTUAL| is at most this velocity, `None` to change it at any velocity
+    pub v_window: Option<u32>,
+}
+
+impl Default for SmoothCurrentConfig {
+    fn default() -> Self {
+        Self {
+            max_step: 2,
+            v_window: None,
+        }
+    }
+}
+
+/// Poll driven IRUN change of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct RunCurrentChange<const M: u8> {
+    current: IHoldIRun<M>,
+    i_run: u8,
+    config: SmoothCurrentConfig,
+}
+
+impl<const M: u8> RunCurrentChange<M>
+where
+    IHoldIRun<M>: Register,
+    VActual<M>: Register,
+{
+    /// Creates a change of IRUN to `i_run`, IHOLD_IRUN being write only `current` must hold its actual value
+    pub fn new(current: IHoldIRun<M>, i_run: u8, config: SmoothCurrentConfig) -> Self {
+        Self {
+            current,
+            i_run: i_run.min(31),
+            config,
+        }
+    }
+
+    /// Value of IHOLD_IRUN last written
+    pub fn current(&self) -> IHoldIRun<M> {
+        self.current
+    }
+
+    /// Whether IRUN reached its target
+    pub fn is_done(&self) -> bool {
+        self.current.i_run == self.i_run
+    }
+
+    /// Applies the next step of the change, returns whether it is over
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<bool, SpiError<SPI::Error, CS::Error>> {
+        if self.is_done() {
+            return Ok(true);
+        }
+        let velocity = tmc5072
+            .read_register::<VActual<M>, _>(spi)?
+            .data
+            .v_actual
+            .unsigned_abs();
+        let i_run = if velocity == 0 {
+            // standstill, no disturbance
+            self.i_run
+        } else if self
+            .config
+            .v_window
+            .is_some_and(|v_window| velocity > v_window)
+        {
+            return Ok(false);
+        } else {
+            let step = self.config.max_step.max(1);
+            if self.i_run > self.current.i_run {
+                self.i_run.min(self.current.i_run.saturating_add(step))
+            } else {
+                self.i_run.max(self.current.i_run.saturating_sub(step))
+            }
+        };
+        let current = IHoldIRun {
+            i_run,
+            ..self.current
+        };
+        tmc5072.write_register(current, spi)?;
+        self.current = current;
+        Ok(self.is_done())
+    }
+}
+
+/// Maximum RMS coil current of the TMC5072 drivers (mA)
+const MAX_RMS_CURRENT_MA: u64 = 1100;
+
+/// Sense resistor and full scale current of the power stage of a motor
+///
+/// Single source of the current math: the current scales of IHOLD_IRUN only mean a current together with the sense
+/// resistor, CHOPCONF vsense and GCONF single_driver.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PowerStageConfig {
+    /// Sense resistor (mΩ)
+    pub r_sense_mohm: u32,
+    /// CHOPCONF vsense: high sensitivity, low full scale sense voltage
+    pub vsense: bool,
+    /// GCONF single_driver: both drivers in parallel on motor 0, each carrying half of the coil current
+    pub single_driver: bool,
+}
+
+impl PowerStageConfig {
+    /// Power stage with `r_sense_mohm` sense resistors, low sensitivity and one driver per motor
+    pub fn new(r_sense_mohm: u32) -> Self {
+        Self {
+            r_sense_mohm,
+            vsense: false,
+            single_driver: false,
+        }
+    }
+
+    /// Selects high sensitivity when `run_ma` RMS milliamps are achievable with it, for a finer resolution and a
+    /// lower sense resistor dissipation
+    pub fn with_vsense_for(self, run_ma: u32) -> Self {
+        let high = Self {
+            vsense: true,
+            ..self
+        };
+        Self {
+            vsense: high.current_steps(run_ma) <= 32,
+            ..self
+        }
+    }
+
+    /// Sense resistor full scale voltage (mV)
+    pub fn full_scale_voltage_mv(&self) -> u64 {
+        if self.vsense {
+            180
+        } else {
+            320
+        }
+    }
+
+    /// Drivers carrying the coil current
+    fn drivers(&self) -> u64 {
+        if self.single_driver {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Current scale CS+1 (1..=32 when achievable) of `ma` RMS milliamps, rounded
+    fn current_steps(&self, ma: u32) -> u64 {
+        let vfs = self.full_scale_voltage_mv() * 1_000_000 * self.drivers();
+        (ma as u64 * 32 * (self.r_sense_mohm as u64 + 20) * 1414 + vfs / 2) / vfs
+    }
+
+    /// Current scale (IRUN, IHOLD) of `ma` RMS milliamps, rounded and clamped to the scale range
+    pub fn current_scale(&self, ma: u32) -> u8 {
+        (self.current_steps(ma).clamp(1, 32) - 1) as u8
+    }
+
+    /// RMS milliamps of current scale `cs`, rounded
+    ///
+    /// I_RMS = (CS+1)/32 * VFS/(RSENSE+20mΩ) * 1/√2, times 2 with both drivers in parallel
+    pub fn current_ma(&self, cs: u8) -> u32 {
+        let divisor = 32 * (self.r_sense_mohm as u64 + 20) * 1414;
+        let vfs = self.full_scale_voltage_mv() * 1_000_000 * self.drivers();
+        (((cs as u64 + 1) * vfs + divisor / 2) / divisor) as u32
+    }
+
+    /// Largest current scale that keeps the current of each driver within its limit
+    pub fn max_current_scale(&self) -> u8 {
+        let max = MAX_RMS_CURRENT_MA * 32 * (self.r_sense_mohm as u64 + 20) * 1414
+            / (self.full_scale_voltage_mv() * 1_000_000);
+        max.clamp(1, 32) as u8 - 1
+    }
+}
+
+/// Motor current settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CurrentConfig {
+    /// IRUN: run current scale
+    pub i_run: u8,
+    /// IHOLD: standstill current scale
+    pub i_hold: u8,
+    /// CHOPCONF vsense: high sensitivity, low full scale sense voltage
+    pub vsense: bool,
+    /// Achievable RMS run current (mA)
+    pub run_ma: u32,
+    /// Achievable RMS standstill current (mA)
+    pub hold_ma: u32,
+}
+
+impl CurrentConfig {
+    /// Current scales of `run_ma` and `hold_ma` RMS milliamps with `power_stage`
+    ///
+    /// Currents out of reach are clamped to the nearest scale.
+    pub fn from_milliamps(run_ma: u32, hold_ma: u32, power_stage: &PowerStageConfig) -> Self {
+        let (i_run, i_hold) = (
+            power_stage.current_scale(run_ma),
+            power_stage.current_scale(hold_ma),
+        );
+        Self {
+            i_run,
+            i_hold,
+            vsense: power_stage.vsense,
+            run_ma: power_stage.current_ma(i_run),
+            hold_ma: power_stage.current_ma(i_hold),
+        }
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Starts a smooth change of the run current of motor `M` to `i_run`
+    ///
+    /// The first step is applied immediately, poll the returned change until it is over.
+    pub fn set_run_current_smooth<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        current: IHoldIRun<M>,
+        i_run: u8,
+        config: SmoothCurrentConfig,
+        spi: &mut SPI,
+    ) -> Result<RunCurrentChange<M>, SpiError<SPI::Error, CS::Error>>
+    where
+        IHoldIRun<M>: Register,
+        VActual<M>: Register,
+    {
+        let mut change = RunCurrentChange::new(current, i_run, config);
+        change.poll(self, spi)?;
+        Ok(change)
+    }
+
+    /// Writes the power stage of motor `M`: CHOPCONF vsense and, for motor 0, GCONF single_driver
+    ///
+    /// The other CHOPCONF fields are read back. GCONF is left as is for motor 1, whose driver is not used in single
+    /// driver mode.
+    pub fn apply_power_stage<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        power_stage: &PowerStageConfig,
+        spi: &mut SPI,
+    ) -> Result<(), GConfError<SPI::Error, CS::Error>>
+    where
+        ChopConf<M>: Register,
+    {
+        let chop_conf = self.read_register::<ChopConf<M>, _>(spi)?.data;
+        self.write_register(
+            ChopConf::<M> {
+                vsense: power_stage.vsense,
+                ..chop_conf
+            },
+            spi,
+        )?;
+        if M == 0 {
+            self.update_gconf(
+                |g_conf| g_conf.single_diver = power_stage.single_driver,
+                spi,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the current scales (IHOLD_IRUN) and CHOPCONF vsense of motor `M`
+    ///
+    /// IHOLDDELAY is kept from the shadow configuration, the other CHOPCONF fields are read back. When the full scale
+    /// voltage rises, IHOLD_IRUN is written first so that the current never overshoots in between.
+    pub fn apply_current<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        current: &CurrentConfig,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        IHoldIRun<M>: Register,
+        ChopConf<M>: Register,
+    {
+        let i_hold_i_run = match M {
+            0 => u32::from(self.config().motor0.i_hold_i_run),
+            _ => u32::from(self.config().motor1.i_hold_i_run),
+        };
+        let i_hold_i_run = IHoldIRun::<M> {
+            i_run: current.i_run,
+            i_hold: current.i_hold,
+            ..IHoldIRun::from(i_hold_i_run)
+        };
+        let chop_conf = ChopConf::<M> {
+            vsense: current.vsense,
+            ..self.read_register::<ChopConf<M>, _>(spi)?.data
+        };
+        if current.vsense {
+            self.write_register(chop_conf, spi)?;
+            self.write_register(i_hold_i_run, spi)?;
+        } else {
+            self.write_register(i_hold_i_run, spi)?;
+            self.write_register(chop_conf, spi)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::general_configuration_register::GConf;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn standstill() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let current = IHoldIRun::<0> {
+            i_hold: 5,
+            i_run: 10,
+            i_hold_delay: 6,
+        };
+        let change = tmc5072
+            .set_run_current_smooth(current, 20, SmoothCurrentConfig::default(), &mut sim)
+            .unwrap();
+        assert!(change.is_done());
+        assert_eq!(
+            IHoldIRun::<0>::from(sim.get(IHoldIRun::<0>::addr())),
+            IHoldIRun::<0> {
+                i_hold: 5,
+                i_run: 20,
+                i_hold_delay: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn moving() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(VActual::<1>::addr(), -5000i32 as u32);
+        let config = SmoothCurrentConfig {
+            max_step: 4,
+            v_window: Some(1000),
+        };
+        let mut change = RunCurrentChange::new(IHoldIRun::<1>::default(), 31, config);
+        // too fast, waiting
+        assert!(!change.poll(&mut tmc5072, &mut sim).unwrap());
+        assert_eq!(change.current().i_run, 0);
+        sim.set(VActual::<1>::addr(), 800);
+        let mut steps = 0;
+        while !change.poll(&mut tmc5072, &mut sim).unwrap() {
+            steps += 1;
+            assert_eq!(
+                IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())).i_run,
+                4 * steps
+            );
+        }
+        assert_eq!(steps, 7);
+        assert_eq!(change.current().i_run, 31);
+    }
+
+    #[test]
+    fn from_milliamps() {
+        // 500mA: 21.4 steps with vsense and 150mΩ
+        let stage = PowerStageConfig::new(150);
+        assert_eq!(
+            CurrentConfig::from_milliamps(500, 250, &stage.with_vsense_for(500)),
+            CurrentConfig {
+                i_run: 20,
+                i_hold: 10,
+                vsense: true,
+                run_ma: 491,
+                hold_ma: 257,
+            }
+        );
+        // 1A: 42.7 steps with vsense, 24.0 without
+        assert_eq!(
+            CurrentConfig::from_milliamps(1000, 500, &stage.with_vsense_for(1000)),
+            CurrentConfig {
+                i_run: 23,
+                i_hold: 11,
+                vsense: false,
+                run_ma: 998,
+                hold_ma: 499,
+            }
+        );
+        let current = CurrentConfig::from_milliamps(1500, 0, &PowerStageConfig::new(100));
+        assert_eq!((current.i_run, current.run_ma), (24, 1473));
+        assert_eq!(current.i_hold, 0);
+        // both drivers in parallel: half the scale
+        let single = PowerStageConfig {
+            single_driver: true,
+            ..PowerStageConfig::new(100)
+        };
+        let current = CurrentConfig::from_milliamps(1500, 0, &single);
+        assert_eq!((current.i_run, current.run_ma), (12, 1532));
+        // out of reach
+        let high = PowerStageConfig {
+            vsense: true,
+            ..stage
+        };
+        assert_eq!(CurrentConfig::from_milliamps(5000, 0, &high).i_run, 31);
+    }
+
+    #[test]
+    fn apply_power_stage() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let stage = PowerStageConfig {
+            vsense: true,
+            single_driver: true,
+            ..PowerStageConfig::new(100)
+        };
+        tmc5072.apply_power_stage::<0, _>(&stage, &mut sim).unwrap();
+        assert!(ChopConf::<0>::from(sim.get(ChopConf::<0>::addr())).vsense);
+        assert!(GConf::from(sim.get(GConf::addr())).single_diver);
+    }
+
+    #[test]
+    fn max_current_scale() {
+        let scale = |r_sense_mohm, vsense| {
+            PowerStageConfig {
+                vsense,
+                ..PowerStageConfig::new(r_sense_mohm)
+            }
+            .max_current_scale()
+        };
+        assert_eq!(scale(150, false), 25);
+        assert_eq!(scale(220, false), 31);
+        assert_eq!(scale(50, true), 18);
+        assert_eq!(scale(0, false), 2);
+    }
+
+    #[test]
+    fn apply_current() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let i_hold_i_run = IHoldIRun::<1> {
+            i_hold_delay: 7,
+            ..Default::default()
+        };
+        tmc5072.write_register(i_hold_i_run, &mut sim).unwrap();
+        let chop_conf = ChopConf::<1> {
+            toff: 3,
+            ..Default::default()
+        };
+        tmc5072.write_register(chop_conf, &mut sim).unwrap();
+        let stage = PowerStageConfig::new(150).with_vsense_for(500);
+        let current = CurrentConfig::from_milliamps(500, 250, &stage);
+        tmc5072.apply_current::<1, _>(&current, &mut sim).unwrap();
+        assert_eq!(
+            IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())),
+            IHoldIRun::<1> {
+                i_hold: current.i_hold,
+                i_run: current.i_run,
+                i_hold_delay: 7,
+            }
+        );
+        assert_eq!(
+            ChopConf::<1>::from(sim.get(ChopConf::<1>::addr())),
+            ChopConf::<1> {
+                vsense: true,
+                ..chop_conf
+            }
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! dcStep setup
+//!
+//! dcStep commutates the motor from its back EMF above VDCMIN: under load the motor slows down instead of stalling,
+//! down to VDCMIN. [`apply`] writes the dcStep registers in the order recommended by the datasheet, VDCMIN last as
+//! it switches dcStep on:
+//! - DCCTRL: DC_TIME slightly above the blank time, DC_SG slightly above DC_TIME/16
+//! - CHOPCONF vhighfs and vhighchm: fullstep and constant off time chopper above VDCMIN
+//! - SW_MODE sg_stop: optionally stops the ramp on a dcStep stall
+//! - VDCMIN
+//!
+//! Below VDCMIN the motor is driven in microstep mode again and can stall: [`lost_steps_possible`] reports the
+//! dcStep stall flag.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::motor::Motor;
+use crate::registers::{
+    motor_driver_register::{ChopConf, DcCtrl, DrvStatus},
+    ramp_generator_driver_feature_control_register::{SwMode, VDcMin},
+    Register,
+};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Comparator blank time (clocks) of each TBL setting
+const BLANK_TIME: [u8; 4] = [16, 24, 36, 54];
+
+/// dcStep settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DcStepConfig {
+    /// VDCMIN: dcStep is active above this velocity, at least 256
+    pub v_dc_min: u32,
+    /// DC_TIME: upper PWM on time limit for commutation (clocks), above the blank time
+    pub dc_time: u8,
+    /// DC_SG: PWM on time limit for step loss detection (16 clocks), above DC_TIME/16, 0 disables it
+    pub dc_sg: u8,
+    /// Stops the ramp on a dcStep stall (SW_MODE sg_stop)
+    pub stop_on_stall: bool,
+}
+
+impl DcStepConfig {
+    /// Checks the settings against the blank time TBL of CHOPCONF
+    pub fn validate(&self, tbl: u8) -> Result<(), DcStepConfigError> {
+        if self.v_dc_min == 0 {
+            Err(DcStepConfigError::Disabled)
+        } else if self.v_dc_min >= 1 << 23 || tbl > 3 {
+            Err(DcStepConfigError::OutOfRange)
+        } else if self.v_dc_min < 256 {
+            Err(DcStepConfigError::MinVelocityTooLow)
+        } else if self.dc_time <= BLANK_TIME[tbl as usize] {
+            Err(DcStepConfigError::DcTimeBelowBlankTime)
+        } else if self.dc_sg != 0 && self.dc_sg as u16 * 16 <= self.dc_time as u16 {
+            Err(DcStepConfigError::DcSgBelowDcTime)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// DCCTRL of motor `M`
+    pub fn dc_ctrl<const M: u8>(&self) -> DcCtrl<M> {
+        DcCtrl {
+            dc_time: self.dc_time,
+            dc_sg: self.dc_sg,
+        }
+    }
+}
+
+/// Invalid dcStep settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DcStepConfigError {
+    /// VDCMIN is 0, which turns dcStep off
+    Disabled,
+    /// A value does not fit its field
+    OutOfRange,
+    /// VDCMIN is below 256
+    MinVelocityTooLow,
+    /// DC_TIME is not above the blank time
+    DcTimeBelowBlankTime,
+    /// DC_SG is not above DC_TIME/16
+    DcSgBelowDcTime,
+}
+
+/// dcStep error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DcStepError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Invalid settings, nothing was written
+    Invalid(DcStepConfigError),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for DcStepError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        DcStepError::SpiError(e)
+    }
+}
+
+/// Validates the settings against the actual CHOPCONF, then writes DCCTRL, CHOPCONF, SW_MODE and VDCMIN of the motor
+pub fn apply<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    config: DcStepConfig,
+) -> Result<(), DcStepError<SPI::Error, CS::Error>>
+where
+    ChopConf<M>: Register,
+    DcCtrl<M>: Register,
+    SwMode<M>: Register,
+    VDcMin<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let chop_conf = tmc5072.read_register::<ChopConf<M>, _>(spi)?.data;
+    config
+        .validate(chop_conf.tbl)
+        .map_err(DcStepError::Invalid)?;
+    let sw_mode = tmc5072.read_register::<SwMode<M>, _>(spi)?.data;
+    tmc5072.write_register(config.dc_ctrl::<M>(), spi)?;
+    tmc5072.write_register(
+        ChopConf {
+            vhighfs: true,
+            vhighchm: true,
+            ..chop_conf
+        },
+        spi,
+    )?;
+    tmc5072.write_register(
+        SwMode {
+            sg_stop: config.stop_on_stall,
+            ..sw_mode
+        },
+        spi,
+    )?;
+    tmc5072.write_register(
+        VDcMin::<M> {
+            v_dc_min: config.v_dc_min,
+        },
+        spi,
+    )?;
+    Ok(())
+}
+
+/// Whether the motor may have lost steps: dcStep stall flag of DRV_STATUS
+///
+/// RAMP_STAT is not read, its events are left for the application.
+pub fn lost_steps_possible<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+) -> Result<bool, SpiError<SPI::Error, CS::Error>>
+where
+    DrvStatus<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    Ok(tmc5072
+        .read_register::<DrvStatus<M>, _>(spi)?
+        .data
+        .stall_guard)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+
+    const CONFIG: DcStepConfig = DcStepConfig {
+        v_dc_min: 20_000,
+        dc_time: 40,
+        dc_sg: 3,
+        stop_on_stall: true,
+    };
+
+    #[test]
+    fn validate() {
+        assert_eq!(CONFIG.validate(2), Ok(()));
+        assert_eq!(
+            CONFIG.validate(3),
+            Err(DcStepConfigError::DcTimeBelowBlankTime)
+        );
+        assert_eq!(
+            DcStepConfig { dc_sg: 2, ..CONFIG }.validate(2),
+            Err(DcStepConfigError::DcSgBelowDcTime)
+        );
+        assert_eq!(
+            DcStepConfig {
+                v_dc_min: 100,
+                ..CONFIG
+            }
+            .validate(2),
+            Err(DcStepConfigError::MinVelocityTooLow)
+        );
+        assert_eq!(
+            DcStepConfig {
+                v_dc_min: 0,
+                ..CONFIG
+            }
+            .validate(2),
+            Err(DcStepConfigError::Disabled)
+        );
+    }
+
+    #[test]
+    fn apply_and_diagnose() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            ChopConf::<1>::addr(),
+            u32::from(ChopConf::<1> {
+                toff: 3,
+                tbl: 1,
+                ..Default::default()
+            }),
+        );
+        apply(&mut tmc5072.motor::<1, _>(&mut sim), CONFIG).unwrap();
+        let chop_conf = ChopConf::<1>::from(sim.get(ChopConf::<1>::addr()));
+        assert!(chop_conf.vhighfs && chop_conf.vhighchm);
+        assert_eq!(chop_conf.toff, 3);
+        assert!(SwMode::<1>::from(sim.get(SwMode::<1>::addr())).sg_stop);
+        assert_eq!(
+            DcCtrl::<1>::from(sim.get(DcCtrl::<1>::addr())),
+            CONFIG.dc_ctrl()
+        );
+        assert_eq!(sim.get(VDcMin::<1>::addr()), 20_000);
+        assert!(!lost_steps_possible(&mut tmc5072.motor::<1, _>(&mut sim)).unwrap());
+        sim.set(
+            DrvStatus::<1>::addr(),
+            u32::from(DrvStatus::<1> {
+                stall_guard: true,
+                ..Default::default()
+            }),
+        );
+        assert!(lost_steps_possible(&mut tmc5072.motor::<1, _>(&mut sim)).unwrap());
+    }
+
+    #[test]
+    fn rejected() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            ChopConf::<0>::addr(),
+            u32::from(ChopConf::<0> {
+                tbl: 3,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(
+            apply(&mut tmc5072.motor::<0, _>(&mut sim), CONFIG),
+            Err(DcStepError::Invalid(
+                DcStepConfigError::DcTimeBelowBlankTime
+            ))
+        );
+        assert_eq!(sim.get(VDcMin::<0>::addr()), 0);
+    }
+}
@@ -0,0 +1,209 @@
+//! Motion direction semantics
+//!
+//! The direction of a motor depends on its wiring, the direction of an encoder on its mounting.
+//! [`AxisDirection`] maps logical [`Direction`]s and physical [`Rotation`]s onto the sign conventions
+//! of the chip (GCONF shaft bits, XTARGET offsets, ENC_CONST sign), so that moving forward always
+//! increases XACTUAL and X_ENC.
+
+use crate::registers::{
+    encoder_registers::EncConst, general_configuration_register::GConf, MotorIndexCheck,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Logical direction of an axis
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    /// Increasing positions
+    Forward,
+    /// Decreasing positions
+    Reverse,
+}
+
+impl Direction {
+    /// The other direction
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Forward => Direction::Reverse,
+            Direction::Reverse => Direction::Forward,
+        }
+    }
+
+    /// Sign of position offsets in this direction
+    pub fn sign(self) -> i32 {
+        match self {
+            Direction::Forward => 1,
+            Direction::Reverse => -1,
+        }
+    }
+
+    /// Direction of a position offset, `None` for a null offset
+    pub fn of(offset: i32) -> Option<Self> {
+        match offset {
+            0 => None,
+            o if o > 0 => Some(Direction::Forward),
+            _ => Some(Direction::Reverse),
+        }
+    }
+}
+
+/// Physical rotation, seen from the front of the motor shaft
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rotation {
+    /// Clockwise
+    Cw,
+    /// Counterclockwise
+    Ccw,
+}
+
+impl Rotation {
+    /// The other rotation
+    pub fn opposite(self) -> Self {
+        match self {
+            Rotation::Cw => Rotation::Ccw,
+            Rotation::Ccw => Rotation::Cw,
+        }
+    }
+}
+
+/// Direction conventions of the axis driven by motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AxisDirection<const M: u8> {
+    /// Rotation of the motor when XACTUAL increases with the shaft bit cleared (depends on the wiring)
+    pub native: Rotation,
+    /// Rotation considered forward by the application
+    pub forward: Rotation,
+    /// Rotation making the encoder count up with a positive ENC_CONST (depends on the mounting)
+    pub encoder: Rotation,
+}
+
+impl<const M: u8> Default for AxisDirection<M> {
+    fn default() -> Self {
+        Self {
+            native: Rotation::Cw,
+            forward: Rotation::Cw,
+            encoder: Rotation::Cw,
+        }
+    }
+}
+
+impl<const M: u8> AxisDirection<M> {
+    /// Value of the GCONF shaft bit of motor `M`
+    pub fn shaft(&self) -> bool {
+        self.native != self.forward
+    }
+
+    /// Whether ENC_CONST must be negative
+    pub fn encoder_inverted(&self) -> bool {
+        self.encoder != self.forward
+    }
+
+    /// Physical rotation of a logical direction
+    pub fn rotation(&self, direction: Direction) -> Rotation {
+        match direction {
+            Direction::Forward => self.forward,
+            Direction::Reverse => self.forward.opposite(),
+        }
+    }
+
+    /// Logical direction of a physical rotation
+    pub fn direction(&self, rotation: Rotation) -> Direction {
+        if rotation == self.forward {
+            Direction::Forward
+        } else {
+            Direction::Reverse
+        }
+    }
+
+    /// Position offset of a move of `distance` microsteps in `direction`, to be added to XTARGET
+    pub fn offset(&self, direction: Direction, distance: u32) -> i32 {
+        (distance as i32).wrapping_mul(direction.sign())
+    }
+
+    /// Sets the shaft bit of motor `M`
+    pub fn apply_gconf(&self, g_conf: &mut GConf) {
+        let () = MotorIndexCheck::<M>::OK;
+        match M {
+            0 => g_conf.shaft1 = self.shaft(),
+            1 => g_conf.shaft2 = self.shaft(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Gives ENC_CONST the sign matching the encoder direction, whatever its sign was
+    pub fn apply_enc_const(&self, enc_const: &mut EncConst<M>, enc_sel_decimal: bool) {
+        if (enc_const.enc_const_int < 0) != self.encoder_inverted() {
+            *enc_const = negate(*enc_const, enc_sel_decimal);
+        }
+    }
+}
+
+/// Negates a fixed point ENC_CONST, the fractional part is always positive
+fn negate<const M: u8>(enc_const: EncConst<M>, enc_sel_decimal: bool) -> EncConst<M> {
+    if enc_sel_decimal {
+        if enc_const.enc_const_frac == 0 {
+            EncConst {
+                enc_const_int: enc_const.enc_const_int.wrapping_neg(),
+                enc_const_frac: 0,
+            }
+        } else {
+            EncConst {
+                enc_const_int: enc_const.enc_const_int.wrapping_neg().wrapping_sub(1),
+                enc_const_frac: 10000 - enc_const.enc_const_frac,
+            }
+        }
+    } else {
+        EncConst::from(u32::from(enc_const).wrapping_neg())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shaft() {
+        let axis = AxisDirection::<1> {
+            native: Rotation::Cw,
+            forward: Rotation::Ccw,
+            encoder: Rotation::Ccw,
+        };
+        let mut g_conf = GConf::default();
+        axis.apply_gconf(&mut g_conf);
+        assert!(!g_conf.shaft1);
+        assert!(g_conf.shaft2);
+        assert!(!axis.encoder_inverted());
+        assert_eq!(axis.rotation(Direction::Reverse), Rotation::Cw);
+        assert_eq!(axis.direction(Rotation::Ccw), Direction::Forward);
+        assert_eq!(axis.offset(Direction::Reverse, 200), -200);
+        assert_eq!(Direction::of(-3), Some(Direction::Reverse));
+    }
+
+    #[test]
+    fn enc_const() {
+        let axis = AxisDirection::<0> {
+            encoder: Rotation::Ccw,
+            ..Default::default()
+        };
+        let mut enc_const = EncConst::<0> {
+            enc_const_int: 1,
+            enc_const_frac: 0x4000,
+        };
+        axis.apply_enc_const(&mut enc_const, false);
+        assert_eq!(enc_const.enc_const(false), -1.25);
+        // already negative, unchanged
+        axis.apply_enc_const(&mut enc_const, false);
+        assert_eq!(enc_const.enc_const(false), -1.25);
+        let mut enc_const = EncConst::<0> {
+            enc_const_int: 2,
+            enc_const_frac: 5000,
+        };
+        axis.apply_enc_const(&mut enc_const, true);
+        assert_eq!(enc_const.enc_const(true), -2.5);
+        AxisDirection::<0>::default().apply_enc_const(&mut enc_const, true);
+        assert_eq!(enc_const.enc_const(true), 2.5);
+    }
+}
@@ -0,0 +1,68 @@
+//! Register dump
+//!
+//! [`Tmc5072::dump_all_readable`] reads every readable register in a single pipelined batch, for bug reports and
+//! host side diagnostic tools. Together with the shadow configuration ([`Tmc5072::config`]) holding the write only
+//! registers, it captures the whole chip state.
+//!
+//! Reading GSTAT, RAMP_STAT and ENC_STATUS clears their flags: the dump returns them, but they are no longer
+//! pending afterwards.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::address::{RegisterAddress, READABLE_COUNT};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+
+/// (address, value) of every readable register, in ascending address order
+pub type RegisterDump = [(RegisterAddress, u32); READABLE_COUNT];
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Reads all readable registers
+    pub fn dump_all_readable<SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<RegisterDump, SpiError<SPI::Error, CS::Error>> {
+        let mut registers = [RegisterAddress::GConf; READABLE_COUNT];
+        for (slot, r) in registers.iter_mut().zip(RegisterAddress::readable()) {
+            *slot = r;
+        }
+        let data = self
+            .read_many(&registers.map(RegisterAddress::addr), spi)?
+            .data;
+        Ok(core::array::from_fn(|i| (registers[i], data[i])))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        general_configuration_register::Input, motor_driver_register::ChopConf,
+    };
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn dump() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072
+            .write_register(
+                ChopConf::<1> {
+                    toff: 4,
+                    ..Default::default()
+                },
+                &mut sim,
+            )
+            .unwrap();
+        let dump = tmc5072.dump_all_readable(&mut sim).unwrap();
+        assert!(dump.iter().all(|(r, _)| r.access().is_readable()));
+        assert_eq!(dump[0], (RegisterAddress::GConf, 0));
+        let value = |r| dump.iter().find(|(a, _)| *a == r).unwrap().1;
+        assert_eq!(value(RegisterAddress::ChopConf1), 4);
+        assert_eq!(
+            Input::from(value(RegisterAddress::InputOutput)).version,
+            0x10
+        );
+        assert_eq!(value(RegisterAddress::DrvStatus0), sim.get(0x6F));
+    }
+}
@@ -0,0 +1,223 @@
+//! Encoder feedback
+//!
+//! ENC_CONST scales the encoder counts into X_ENC: every count adds ENC_CONST to X_ENC, its fractional part counting
+//! in 1/65536 (binary mode) or 1/10000 (decimal mode, ENCMODE enc_sel_decimal). [`set_encoder_resolution`] computes it
+//! so that X_ENC counts microsteps like XACTUAL, picking the mode representing the ratio exactly (e.g. decimal for
+//! 51200 microsteps and a 1000 lines encoder, 4000 counts per revolution). A negative encoder resolution reverses the
+//! counting direction.
+//!
+//! [`encoder_deviation`] then latches X_ENC and XACTUAL at the same clock (ENCMODE latch_now with latch_x_act) and
+//! returns their difference, free of the skew between two separate reads of a moving motor.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::motor::Motor;
+use crate::registers::{
+    encoder_registers::{EncConst, EncLatch, EncMode},
+    ramp_generator_driver_feature_control_register::XLatch,
+    Register,
+};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Encoder resolution that cannot be represented by ENC_CONST
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EncoderResolutionError {
+    /// The encoder resolution is 0
+    ZeroCounts,
+    /// The ratio is 0 or its integer part does not fit 16 bits
+    OutOfRange,
+}
+
+/// Encoder resolution error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EncoderError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Invalid resolution, nothing was written
+    Invalid(EncoderResolutionError),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for EncoderError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        EncoderError::SpiError(e)
+    }
+}
+
+/// ENC_CONST value and prescaler mode of an encoder resolution
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EncoderScaling {
+    /// Integer part of ENC_CONST
+    pub enc_const_int: i16,
+    /// Fractional part of ENC_CONST
+    pub enc_const_frac: u16,
+    /// Decimal prescaler (ENCMODE enc_sel_decimal)
+    pub decimal: bool,
+}
+
+impl EncoderScaling {
+    /// Scaling of `counts_per_rev` encoder counts to `usteps_per_rev` microsteps, rounded when not exact
+    pub fn new(counts_per_rev: i32, usteps_per_rev: u32) -> Result<Self, EncoderResolutionError> {
+        if counts_per_rev == 0 {
+            return Err(EncoderResolutionError::ZeroCounts);
+        }
+        let counts = counts_per_rev.unsigned_abs() as i64;
+        let exact = |scale: i64| (usteps_per_rev as i64 * scale) % counts == 0;
+        let decimal = !exact(65536) && exact(10000);
+        let scale = if decimal { 10000 } else { 65536 };
+        let mut total = (usteps_per_rev as i64 * scale * 2 + counts) / (counts * 2);
+        if counts_per_rev < 0 {
+            total = -total;
+        }
+        let int = total.div_euclid(scale);
+        if total == 0 || int < i16::MIN as i64 || int > i16::MAX as i64 {
+            return Err(EncoderResolutionError::OutOfRange);
+        }
+        Ok(Self {
+            enc_const_int: int as i16,
+            enc_const_frac: total.rem_euclid(scale) as u16,
+            decimal,
+        })
+    }
+}
+
+/// Writes ENC_CONST and ENCMODE enc_sel_decimal so that X_ENC counts microsteps
+///
+/// The other ENCMODE settings are read back and kept.
+pub fn set_encoder_resolution<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    counts_per_rev: i32,
+    usteps_per_rev: u32,
+) -> Result<EncoderScaling, EncoderError<SPI::Error, CS::Error>>
+where
+    EncMode<M>: Register,
+    EncConst<M>: Register,
+{
+    let scaling =
+        EncoderScaling::new(counts_per_rev, usteps_per_rev).map_err(EncoderError::Invalid)?;
+    let (tmc5072, spi) = motor.parts();
+    let mut enc_mode = tmc5072.read_register::<EncMode<M>, _>(spi)?.data;
+    enc_mode.enc_sel_decimal = scaling.decimal;
+    enc_mode.latch_now = false;
+    tmc5072.write_register(enc_mode, spi)?;
+    tmc5072.write_register(
+        EncConst::<M> {
+            enc_const_int: scaling.enc_const_int,
+            enc_const_frac: scaling.enc_const_frac,
+        },
+        spi,
+    )?;
+    Ok(scaling)
+}
+
+/// Latches X_ENC and XACTUAL together, returns X_ENC - XACTUAL in microsteps
+///
+/// ENCMODE is restored afterwards. ENC_LATCH and XLATCH are overwritten.
+pub fn encoder_deviation<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+) -> Result<i32, SpiError<SPI::Error, CS::Error>>
+where
+    EncMode<M>: Register,
+    EncLatch<M>: Register,
+    XLatch<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let enc_mode = tmc5072.read_register::<EncMode<M>, _>(spi)?.data;
+    tmc5072.write_register(
+        EncMode::<M> {
+            latch_x_act: true,
+            latch_now: true,
+            ..enc_mode
+        },
+        spi,
+    )?;
+    let [enc_latch, x_latch] = tmc5072
+        .read_many(&[EncLatch::<M>::addr(), XLatch::<M>::addr()], spi)?
+        .data;
+    tmc5072.write_register(
+        EncMode::<M> {
+            latch_now: false,
+            ..enc_mode
+        },
+        spi,
+    )?;
+    Ok((enc_latch as i32).wrapping_sub(x_latch as i32))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{encoder_registers::XEnc, ramp_generator_register::XTarget};
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+
+    #[test]
+    fn scaling() {
+        let scaling = |counts, usteps| EncoderScaling::new(counts, usteps).unwrap();
+        assert_eq!(
+            scaling(4000, 51200),
+            EncoderScaling {
+                enc_const_int: 12,
+                enc_const_frac: 8000,
+                decimal: true,
+            }
+        );
+        assert_eq!(
+            scaling(4096, 51200),
+            EncoderScaling {
+                enc_const_int: 12,
+                enc_const_frac: 32768,
+                decimal: false,
+            }
+        );
+        // -12.5 = -13 + 0.5
+        assert_eq!(
+            scaling(-4096, 51200),
+            EncoderScaling {
+                enc_const_int: -13,
+                enc_const_frac: 32768,
+                decimal: false,
+            }
+        );
+        assert_eq!(
+            EncoderScaling::new(0, 51200),
+            Err(EncoderResolutionError::ZeroCounts)
+        );
+        assert_eq!(
+            EncoderScaling::new(1, 1 << 16),
+            Err(EncoderResolutionError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn deviation() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_encoder_slip(1, 25);
+        tmc5072
+            .write_register(XTarget::<1> { x_target: 1000 }, &mut sim)
+            .unwrap();
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        let scaling = set_encoder_resolution(&mut motor, 4000, 51200).unwrap();
+        assert!(scaling.decimal);
+        assert_eq!(encoder_deviation(&mut motor), Ok(25));
+        assert_eq!(
+            EncMode::<1>::from(sim.get(EncMode::<1>::addr())),
+            EncMode::<1> {
+                enc_sel_decimal: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(sim.get(XEnc::<1>::addr()), 1025);
+        assert_eq!(
+            EncConst::<1>::from(sim.get(EncConst::<1>::addr())).enc_const(true),
+            12.8
+        );
+    }
+}
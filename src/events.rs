@@ -0,0 +1,341 @@
+//! Motor events
+//!
+//! The INT output of the TMC5072 is the OR of the RAMP_STAT events (stop switches, stallGuard2 stop, position
+//! reached) and of the encoder N event of ENC_STATUS. [`poll_events`] reads and clears these registers for a motor
+//! and returns all its pending events as a single [`MotorEvents`] set, e.g. from an INT pin handler.
+//!
+//! The events of interest passed to [`poll_events`] select which registers are read: reading a register clears all its
+//! events, so every event read is returned, of interest or not.
+//!
+//! [`MotorEvents`] are also what [`isr`](crate::isr) publishes from interrupt context.
+//!
+//! GSTAT, RAMP_STAT and ENC_STATUS are [`ClearOnRead`]: a read returns their latched flags once and clears them.
+//! [`Tmc5072::read_and_clear`], [`Tmc5072::read_events`] and [`poll_events`] make this explicit at the call site and
+//! are `#[must_use]`, so discarding the result of such a read (e.g. a debug read of RAMP_STAT losing a stall event)
+//! is warned about.
+
+use core::ops::{BitAnd, BitOr, BitOrAssign};
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::motor::Motor;
+use crate::registers::{
+    encoder_registers::EncStatus, ramp_generator_driver_feature_control_register::RampStat,
+    ClearOnRead, Register,
+};
+use crate::spi::SpiError;
+use crate::status::SpiStatus;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Events of a motor: the RAMP_STAT events and the encoder N event of ENC_STATUS
+///
+/// Also used as the set of events of interest of [`poll_events`].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MotorEvents {
+    /// Motor stopped by stallGuard2 (RAMP_STAT event_stop_sg)
+    pub stall: bool,
+    /// Target position reached (RAMP_STAT event_pos_reached)
+    pub position_reached: bool,
+    /// Motor stopped by the left reference switch (RAMP_STAT event_stop_l)
+    pub stop_l: bool,
+    /// Motor stopped by the right reference switch (RAMP_STAT event_stop_r)
+    pub stop_r: bool,
+    /// Encoder N event (ENC_STATUS n_event)
+    pub n_event: bool,
+}
+
+impl MotorEvents {
+    const STALL: u8 = 1 << 0;
+    const POSITION_REACHED: u8 = 1 << 1;
+    const STOP_L: u8 = 1 << 2;
+    const STOP_R: u8 = 1 << 3;
+    const N_EVENT: u8 = 1 << 4;
+
+    /// No event
+    pub const NONE: Self = Self {
+        stall: false,
+        position_reached: false,
+        stop_l: false,
+        stop_r: false,
+        n_event: false,
+    };
+    /// Events of RAMP_STAT
+    pub const RAMP_STAT: Self = Self {
+        stall: true,
+        position_reached: true,
+        stop_l: true,
+        stop_r: true,
+        n_event: false,
+    };
+    /// Events of ENC_STATUS
+    pub const ENC_STATUS: Self = Self {
+        n_event: true,
+        ..Self::NONE
+    };
+    /// All events
+    pub const ALL: Self = Self {
+        n_event: true,
+        ..Self::RAMP_STAT
+    };
+
+    /// Whether no event is set
+    pub fn is_empty(&self) -> bool {
+        self.bits() == 0
+    }
+
+    /// Whether all events of `other` are set
+    pub fn contains(&self, other: Self) -> bool {
+        self.bits() & other.bits() == other.bits()
+    }
+
+    /// Whether any event of `other` is set
+    pub fn intersects(&self, other: Self) -> bool {
+        self.bits() & other.bits() != 0
+    }
+
+    pub(crate) fn bits(&self) -> u8 {
+        let mut bits = 0;
+        if self.stall {
+            bits |= Self::STALL;
+        }
+        if self.position_reached {
+            bits |= Self::POSITION_REACHED;
+        }
+        if self.stop_l {
+            bits |= Self::STOP_L;
+        }
+        if self.stop_r {
+            bits |= Self::STOP_R;
+        }
+        if self.n_event {
+            bits |= Self::N_EVENT;
+        }
+        bits
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Self {
+        Self {
+            stall: bits & Self::STALL != 0,
+            position_reached: bits & Self::POSITION_REACHED != 0,
+            stop_l: bits & Self::STOP_L != 0,
+            stop_r: bits & Self::STOP_R != 0,
+            n_event: bits & Self::N_EVENT != 0,
+        }
+    }
+}
+
+impl BitOr for MotorEvents {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self::from_bits(self.bits() | rhs.bits())
+    }
+}
+
+impl BitOrAssign for MotorEvents {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitAnd for MotorEvents {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self::from_bits(self.bits() & rhs.bits())
+    }
+}
+
+impl<const M: u8> From<RampStat<M>> for MotorEvents {
+    fn from(ramp_stat: RampStat<M>) -> Self {
+        Self {
+            stall: ramp_stat.event_stop_sg,
+            position_reached: ramp_stat.event_pos_reached,
+            stop_l: ramp_stat.event_stop_l,
+            stop_r: ramp_stat.event_stop_r,
+            n_event: false,
+        }
+    }
+}
+
+impl<const M: u8> From<EncStatus<M>> for MotorEvents {
+    fn from(enc_status: EncStatus<M>) -> Self {
+        Self {
+            n_event: enc_status.enc_status,
+            ..Self::NONE
+        }
+    }
+}
+
+/// Value of a [`ClearOnRead`] register, its flags being cleared on the chip by the read
+#[must_use = "the flags were cleared on the chip by the read"]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cleared<R> {
+    /// SPI status of the read
+    pub status: SpiStatus,
+    /// Register value, the last one holding these flags
+    pub value: R,
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Reads the [`ClearOnRead`] register `R`, e.g. GSTAT, clearing its flags
+    pub fn read_and_clear<R, SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Cleared<R>, SpiError<SPI::Error, CS::Error>>
+    where
+        R: Register + ClearOnRead,
+        u32: From<R>,
+    {
+        let ok = self.read_register::<R, _>(spi)?;
+        Ok(Cleared {
+            status: ok.status,
+            value: ok.data,
+        })
+    }
+
+    /// Reads and clears the events of the event register `R` (RAMP_STAT or ENC_STATUS of a motor)
+    ///
+    /// The status bits of RAMP_STAT (switch states, position_reached...) are not cleared by the read and are left out:
+    /// read them with [`Tmc5072::read_and_clear`] when the events are consumed at the same place.
+    #[must_use = "the events were cleared on the chip by the read"]
+    pub fn read_events<R, SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<MotorEvents, SpiError<SPI::Error, CS::Error>>
+    where
+        R: Register + ClearOnRead,
+        u32: From<R>,
+        MotorEvents: From<R>,
+    {
+        Ok(self.read_register::<R, _>(spi)?.data.into())
+    }
+}
+
+/// Reads and clears the registers holding the events of `mask` (RAMP_STAT, ENC_STATUS), returns their events
+#[must_use = "the events were cleared on the chip by the read"]
+pub fn poll_events<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    mask: MotorEvents,
+) -> Result<MotorEvents, SpiError<SPI::Error, CS::Error>>
+where
+    RampStat<M>: Register,
+    EncStatus<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let ramp = mask.intersects(MotorEvents::RAMP_STAT);
+    let encoder = mask.intersects(MotorEvents::ENC_STATUS);
+    Ok(match (ramp, encoder) {
+        (true, true) => {
+            let [ramp_stat, enc_status] = tmc5072
+                .read_many(&[RampStat::<M>::addr(), EncStatus::<M>::addr()], spi)?
+                .data;
+            MotorEvents::from(RampStat::<M>::from(ramp_stat))
+                | MotorEvents::from(EncStatus::<M>::from(enc_status))
+        }
+        (true, false) => tmc5072.read_register::<RampStat<M>, _>(spi)?.data.into(),
+        (false, true) => tmc5072.read_register::<EncStatus<M>, _>(spi)?.data.into(),
+        (false, false) => MotorEvents::NONE,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::general_configuration_register::GStat;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn events() {
+        let stop_l = MotorEvents {
+            stop_l: true,
+            ..Default::default()
+        };
+        let stop_r = MotorEvents {
+            stop_r: true,
+            ..Default::default()
+        };
+        let events = stop_l | MotorEvents::ENC_STATUS;
+        assert!(events.contains(MotorEvents::ENC_STATUS));
+        assert!(!events.contains(stop_l | stop_r));
+        assert!(events.intersects(MotorEvents::RAMP_STAT));
+        assert_eq!(events & MotorEvents::RAMP_STAT, stop_l);
+        assert_eq!(MotorEvents::from_bits(0xff), MotorEvents::ALL);
+    }
+
+    #[test]
+    fn poll() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            RampStat::<1>::addr(),
+            u32::from(RampStat::<1> {
+                event_stop_sg: true,
+                event_pos_reached: true,
+                ..Default::default()
+            }),
+        );
+        sim.set(EncStatus::<1>::addr(), 1);
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        assert_eq!(
+            poll_events(&mut motor, MotorEvents::ENC_STATUS).unwrap(),
+            MotorEvents::ENC_STATUS
+        );
+        assert_eq!(
+            poll_events(&mut motor, MotorEvents::ALL).unwrap(),
+            MotorEvents {
+                stall: true,
+                position_reached: true,
+                ..Default::default()
+            }
+        );
+        assert!(poll_events(&mut motor, MotorEvents::ALL)
+            .unwrap()
+            .is_empty());
+        assert_eq!(sim.get(EncStatus::<1>::addr()), 0);
+    }
+
+    #[test]
+    fn clear_on_read() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            RampStat::<0>::addr(),
+            u32::from(RampStat::<0> {
+                event_stop_sg: true,
+                status_sg: true,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(
+            tmc5072.read_events::<RampStat<0>, _>(&mut sim).unwrap(),
+            MotorEvents {
+                stall: true,
+                ..Default::default()
+            }
+        );
+        assert!(tmc5072
+            .read_events::<RampStat<0>, _>(&mut sim)
+            .unwrap()
+            .is_empty());
+        sim.set(
+            GStat::addr(),
+            u32::from(GStat {
+                uv_cp: true,
+                ..Default::default()
+            }),
+        );
+        let gstat = tmc5072.read_and_clear::<GStat, _>(&mut sim).unwrap();
+        assert!(gstat.value.uv_cp);
+        assert!(
+            !tmc5072
+                .read_and_clear::<GStat, _>(&mut sim)
+                .unwrap()
+                .value
+                .uv_cp
+        );
+    }
+}
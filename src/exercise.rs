@@ -0,0 +1,381 @@
+//! Deterministic motion exercise patterns
+//!
+//! Canned move sequences for burn-in and vibration testing.
+//! An [`Exercise`] is advanced by calling [`Exercise::poll`] until it reports a result;
+//! the run fails on driver error flags or when the encoder deviates too much from XACTUAL.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    encoder_registers::XEnc,
+    motor_driver_register::DrvStatus,
+    ramp_generator_driver_feature_control_register::RampStat,
+    ramp_generator_register::{RampMode, VMax, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Exercise pattern
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Pattern {
+    /// Back-and-forth moves between `from` and `to`, `cycles` times
+    Sweep {
+        /// First position (microsteps)
+        from: i32,
+        /// Second position (microsteps)
+        to: i32,
+        /// Number of round trips
+        cycles: u16,
+    },
+    /// `count` moves of `step` microsteps from `start`, then back to `start`
+    Staircase {
+        /// Start position (microsteps)
+        start: i32,
+        /// Distance of each step (microsteps)
+        step: i32,
+        /// Number of steps
+        count: u16,
+    },
+    /// Back-and-forth moves between `from` and `to` with VMAX linearly stepped from `v_start` to `v_end`
+    SpeedSweep {
+        /// First position (microsteps)
+        from: i32,
+        /// Second position (microsteps)
+        to: i32,
+        /// VMAX of the first round trip
+        v_start: u32,
+        /// VMAX of the last round trip
+        v_end: u32,
+        /// Number of round trips
+        count: u16,
+    },
+}
+
+/// A single move of a pattern
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Move {
+    /// Target position (microsteps)
+    pub x_target: i32,
+    /// Velocity of the move, `None` to use the exercise VMAX
+    pub v_max: Option<u32>,
+}
+
+impl Pattern {
+    /// Number of moves of the pattern
+    pub fn len(&self) -> usize {
+        match *self {
+            Pattern::Sweep { cycles, .. } => 2 * cycles as usize,
+            Pattern::Staircase { count, .. } => count as usize + 1,
+            Pattern::SpeedSweep { count, .. } => 2 * count as usize,
+        }
+    }
+
+    /// Whether the pattern has no move
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Move number `index` of the pattern
+    // is_multiple_of needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn get(&self, index: usize) -> Option<Move> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(match *self {
+            Pattern::Sweep { from, to, .. } => Move {
+                x_target: if index % 2 == 0 { to } else { from },
+                v_max: None,
+            },
+            Pattern::Staircase { start, step, count } => Move {
+                x_target: if index < count as usize {
+                    start.wrapping_add(step.wrapping_mul(index as i32 + 1))
+                } else {
+                    start
+                },
+                v_max: None,
+            },
+            Pattern::SpeedSweep {
+                from,
+                to,
+                v_start,
+                v_end,
+                count,
+            } => {
+                let trip = (index / 2) as i64;
+                let v_max = if count > 1 {
+                    v_start as i64 + (v_end as i64 - v_start as i64) * trip / (count as i64 - 1)
+                } else {
+                    v_start as i64
+                };
+                Move {
+                    x_target: if index % 2 == 0 { to } else { from },
+                    v_max: Some(v_max as u32),
+                }
+            }
+        })
+    }
+
+    /// Iterates over the moves of the pattern
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        (0..self.len()).filter_map(move |index| self.get(index))
+    }
+}
+
+/// Exercise parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExerciseConfig {
+    /// Move sequence
+    pub pattern: Pattern,
+    /// VMAX used by moves that don't specify one
+    pub v_max: u32,
+    /// Maximum allowed |X_ENC - XACTUAL| after each move, `None` when no encoder is fitted
+    pub max_deviation: Option<u32>,
+}
+
+/// Exercise outcome
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExerciseReport {
+    /// Number of completed moves
+    pub moves: usize,
+    /// Largest encoder deviation seen after a move
+    pub max_deviation: u32,
+    /// Driver error flags (ot, s2ga, s2gb) were raised
+    pub fault: bool,
+    /// All moves were completed without fault and within the allowed deviation
+    pub passed: bool,
+}
+
+/// Exercise progress
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ExerciseStatus {
+    /// A move is in progress
+    Running,
+    /// The exercise is over
+    Done(ExerciseReport),
+}
+
+/// Poll driven exercise runner for motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Exercise<const M: u8> {
+    config: ExerciseConfig,
+    index: usize,
+    moving: bool,
+    report: ExerciseReport,
+}
+
+impl<const M: u8> Exercise<M>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XTarget<M>: Register,
+    XActual<M>: Register,
+    XEnc<M>: Register,
+    RampStat<M>: Register,
+    DrvStatus<M>: Register,
+{
+    /// Creates an exercise, nothing happens until the first poll
+    pub fn new(config: ExerciseConfig) -> Self {
+        Self {
+            config,
+            index: 0,
+            moving: false,
+            report: ExerciseReport::default(),
+        }
+    }
+
+    /// Advances the exercise: starts the next move once the previous one has reached its target
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<ExerciseStatus, SpiError<SPI::Error, CS::Error>> {
+        if self.moving {
+            let ramp_stat = tmc5072.read_register::<RampStat<M>, _>(spi)?.data;
+            if !ramp_stat.position_reached {
+                return Ok(ExerciseStatus::Running);
+            }
+            self.moving = false;
+            self.report.moves += 1;
+            if !self.check(tmc5072, spi)? {
+                return Ok(ExerciseStatus::Done(self.report));
+            }
+        }
+        match self.config.pattern.get(self.index) {
+            Some(step) => {
+                tmc5072.move_to::<M, _>(
+                    step.x_target,
+                    step.v_max.unwrap_or(self.config.v_max),
+                    spi,
+                )?;
+                self.index += 1;
+                self.moving = true;
+                Ok(ExerciseStatus::Running)
+            }
+            None => {
+                self.report.passed = true;
+                Ok(ExerciseStatus::Done(self.report))
+            }
+        }
+    }
+
+    /// Checks fault flags and encoder deviation, returns whether the exercise may go on
+    fn check<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<bool, SpiError<SPI::Error, CS::Error>> {
+        let drv_status = tmc5072.read_register::<DrvStatus<M>, _>(spi)?.data;
+        if drv_status.ot || drv_status.s2ga || drv_status.s2gb {
+            self.report.fault = true;
+            return Ok(false);
+        }
+        if let Some(max_deviation) = self.config.max_deviation {
+            let x_actual = tmc5072.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+            let x_enc = tmc5072.read_register::<XEnc<M>, _>(spi)?.data.x_enc;
+            let deviation = x_enc.wrapping_sub(x_actual).unsigned_abs();
+            self.report.max_deviation = self.report.max_deviation.max(deviation);
+            if deviation > max_deviation {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    fn run<const M: u8>(
+        exercise: &mut Exercise<M>,
+        tmc5072: &mut Tmc5072<CsPin>,
+        sim: &mut Simulator,
+    ) -> ExerciseReport
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+        XTarget<M>: Register,
+        XActual<M>: Register,
+        XEnc<M>: Register,
+        RampStat<M>: Register,
+        DrvStatus<M>: Register,
+    {
+        loop {
+            if let ExerciseStatus::Done(report) = exercise.poll(tmc5072, sim).unwrap() {
+                return report;
+            }
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        let sweep = Pattern::Sweep {
+            from: -10,
+            to: 10,
+            cycles: 2,
+        };
+        assert!(sweep.moves().map(|m| m.x_target).eq([10, -10, 10, -10]));
+        let staircase = Pattern::Staircase {
+            start: 100,
+            step: 50,
+            count: 3,
+        };
+        assert!(staircase
+            .moves()
+            .map(|m| m.x_target)
+            .eq([150, 200, 250, 100]));
+        let speed_sweep = Pattern::SpeedSweep {
+            from: 0,
+            to: 1000,
+            v_start: 1000,
+            v_end: 4000,
+            count: 4,
+        };
+        assert!(speed_sweep
+            .moves()
+            .map(|m| m.v_max.unwrap())
+            .eq([1000, 1000, 2000, 2000, 3000, 3000, 4000, 4000]));
+    }
+
+    #[test]
+    fn passed() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut exercise = Exercise::<1>::new(ExerciseConfig {
+            pattern: Pattern::Staircase {
+                start: 0,
+                step: 100,
+                count: 3,
+            },
+            v_max: 5000,
+            max_deviation: Some(0),
+        });
+        let report = run(&mut exercise, &mut tmc5072, &mut sim);
+        assert_eq!(
+            report,
+            ExerciseReport {
+                moves: 4,
+                max_deviation: 0,
+                fault: false,
+                passed: true,
+            }
+        );
+        assert_eq!(tmc5072.stats().motor::<1>().moves, 4);
+    }
+
+    #[test]
+    fn encoder_deviation() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_encoder_slip(0, 3);
+        let mut exercise = Exercise::<0>::new(ExerciseConfig {
+            pattern: Pattern::Sweep {
+                from: 0,
+                to: 1000,
+                cycles: 5,
+            },
+            v_max: 5000,
+            max_deviation: Some(5),
+        });
+        let report = run(&mut exercise, &mut tmc5072, &mut sim);
+        assert_eq!(report.moves, 2);
+        assert_eq!(report.max_deviation, 6);
+        assert!(!report.passed);
+    }
+
+    #[test]
+    fn fault() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            DrvStatus::<0>::addr(),
+            u32::from(DrvStatus::<0> {
+                s2ga: true,
+                ..Default::default()
+            }),
+        );
+        let mut exercise = Exercise::<0>::new(ExerciseConfig {
+            pattern: Pattern::Sweep {
+                from: 0,
+                to: 1000,
+                cycles: 5,
+            },
+            v_max: 5000,
+            max_deviation: None,
+        });
+        let report = run(&mut exercise, &mut tmc5072, &mut sim);
+        assert_eq!(report.moves, 1);
+        assert!(report.fault);
+        assert!(!report.passed);
+    }
+}
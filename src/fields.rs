@@ -0,0 +1,889 @@
+//! Register fields by identifier
+//!
+//! Trinamic's TMC-API accesses the chip through (register, field) pairs, e.g. `TMC5072_IRUN_MASK` and
+//! `TMC5072_IRUN_SHIFT` of `TMC5072_IHOLD_IRUN(motor)`. [`Field::of`] maps a [`RegisterAddress`] and a [`FieldId`] to
+//! the same bit position and mask, taken from the `<FIELD>_SHIFT` and `<FIELD>_MASK` constants of the register types,
+//! so firmware ported from C can keep its field accesses while moving to the typed registers step by step.
+//!
+//! [`Tmc5072::read_field`] and [`Tmc5072::write_field`] work like TMC-API's `field_read` and `field_write`: write only
+//! registers are read from the shadow configuration ([`Tmc5072::config`]), the other fields of the register are kept.
+//! Fields are raw bits, signed fields are not sign extended.
+//!
+//! ```
+//! # use tmc5072::fields::{Field, FieldId};
+//! # use tmc5072::registers::address::RegisterAddress;
+//! let i_run = Field::of(RegisterAddress::IHoldIRun1, FieldId::IRun).unwrap();
+//! assert_eq!(i_run.set(0x0006_1F0A, 16), 0x0006_100A);
+//! assert_eq!(i_run.get(0x0006_100A), 16);
+//! ```
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    address::{Access, RegisterAddress},
+    encoder_registers::{EncConst, EncLatch, EncMode, EncStatus, XEnc},
+    general_configuration_register::{GConf, GStat, IfCnt, Input, Output, SlaveConf, XCompare},
+    microstep_table_register::{
+        MsLut0, MsLut1, MsLut2, MsLut3, MsLut4, MsLut5, MsLut6, MsLut7, MsLutSel, MsLutStart,
+    },
+    motor_driver_register::{ChopConf, CoolConf, DcCtrl, DrvStatus, MsCnt, MsCurAct},
+    ramp_generator_driver_feature_control_register::{
+        IHoldIRun, RampStat, SwMode, VCoolThrs, VDcMin, VHigh, XLatch,
+    },
+    ramp_generator_register::{
+        AMax, DMax, RampMode, TZeroWait, VActual, VMax, VStart, VStop, XActual, XTarget, A1, D1, V1,
+    },
+    voltage_pwm_mode_stealth_chop::{PwmConf, PwmStatus},
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Field of a register, named after the datasheet
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FieldId {
+    /// POL_A of ENCMODE
+    PolA,
+    /// POL_B of ENCMODE
+    PolB,
+    /// POL_N of ENCMODE
+    PolN,
+    /// IGNORE_AB of ENCMODE
+    IgnoreAb,
+    /// CLR_CONT of ENCMODE
+    ClrCont,
+    /// CLR_ONCE of ENCMODE
+    ClrOnce,
+    /// POS_EDGE of ENCMODE
+    PosEdge,
+    /// NEG_EDGE of ENCMODE
+    NegEdge,
+    /// CLR_ENC_X of ENCMODE
+    ClrEncX,
+    /// LATCH_X_ACT of ENCMODE
+    LatchXAct,
+    /// ENC_SEL_DECIMAL of ENCMODE
+    EncSelDecimal,
+    /// LATCH_NOW of ENCMODE
+    LatchNow,
+    /// X_ENC of X_ENC
+    XEnc,
+    /// ENC_CONST_FRAC of ENC_CONST
+    EncConstFrac,
+    /// ENC_CONST_INT of ENC_CONST
+    EncConstInt,
+    /// ENC_STATUS of ENC_STATUS
+    EncStatus,
+    /// ENC_LATCH of ENC_LATCH
+    EncLatch,
+    /// SINGLE_DRIVER of GCONF
+    SingleDriver,
+    /// STEPDIR1_ENABLE of GCONF
+    Stepdir1Enable,
+    /// STEPDIR2_ENABLE of GCONF
+    Stepdir2Enable,
+    /// POSCMP_ENABLE of GCONF
+    PoscmpEnable,
+    /// ENC1_REFSEL of GCONF
+    Enc1Refsel,
+    /// ENC2_ENABLE of GCONF
+    Enc2Enable,
+    /// ENC2_REFSEL of GCONF
+    Enc2Refsel,
+    /// TEST_MODE of GCONF
+    TestMode,
+    /// SHAFT1 of GCONF
+    Shaft1,
+    /// SHAFT2 of GCONF
+    Shaft2,
+    /// LOCK_GCONF of GCONF
+    LockGconf,
+    /// DC_SYNC of GCONF
+    DcSync,
+    /// RESET of GSTAT
+    Reset,
+    /// DRV_ERR1 of GSTAT
+    DrvErr1,
+    /// DRV_ERR2 of GSTAT
+    DrvErr2,
+    /// UV_CP of GSTAT
+    UvCp,
+    /// IF_CNT of IFCNT
+    IfCnt,
+    /// SLAVE_ADDR of SLAVECONF
+    SlaveAddr,
+    /// SEND_DELAY of SLAVECONF
+    SendDelay,
+    /// IO0 of INPUT/OUTPUT
+    Io0,
+    /// IO1 of INPUT/OUTPUT
+    Io1,
+    /// IO2 of INPUT/OUTPUT
+    Io2,
+    /// IO3 of INPUT/OUTPUT
+    Io3,
+    /// IOP of INPUT/OUTPUT
+    Iop,
+    /// ION of INPUT/OUTPUT
+    Ion,
+    /// NEXT_ADDR of INPUT/OUTPUT
+    NextAddr,
+    /// DRV_ENN of INPUT/OUTPUT
+    DrvEnn,
+    /// SW_COMP of INPUT/OUTPUT
+    SwComp,
+    /// VERSION of INPUT/OUTPUT
+    Version,
+    /// IO_DDR0 of INPUT/OUTPUT
+    IoDdr0,
+    /// IO_DDR1 of INPUT/OUTPUT
+    IoDdr1,
+    /// IO_DDR2 of INPUT/OUTPUT
+    IoDdr2,
+    /// X_COMPARE of X_COMPARE
+    XCompare,
+    /// MS_LUT0 of MSLUT\[0\]
+    MsLut0,
+    /// MS_LUT1 of MSLUT\[1\]
+    MsLut1,
+    /// MS_LUT2 of MSLUT\[2\]
+    MsLut2,
+    /// MS_LUT3 of MSLUT\[3\]
+    MsLut3,
+    /// MS_LUT4 of MSLUT\[4\]
+    MsLut4,
+    /// MS_LUT5 of MSLUT\[5\]
+    MsLut5,
+    /// MS_LUT6 of MSLUT\[6\]
+    MsLut6,
+    /// MS_LUT7 of MSLUT\[7\]
+    MsLut7,
+    /// W0 of MSLUTSEL
+    W0,
+    /// W1 of MSLUTSEL
+    W1,
+    /// W2 of MSLUTSEL
+    W2,
+    /// W3 of MSLUTSEL
+    W3,
+    /// X1 of MSLUTSEL
+    X1,
+    /// X2 of MSLUTSEL
+    X2,
+    /// X3 of MSLUTSEL
+    X3,
+    /// START_SIN of MSLUTSTART
+    StartSin,
+    /// START_SIN90 of MSLUTSTART
+    StartSin90,
+    /// MS_CNT of MSCNT
+    MsCnt,
+    /// CUR_A of MSCURACT
+    CurA,
+    /// CUR_B of MSCURACT
+    CurB,
+    /// TOFF of CHOPCONF
+    Toff,
+    /// HSTRT of CHOPCONF
+    Hstrt,
+    /// HEND of CHOPCONF
+    Hend,
+    /// FD3 of CHOPCONF
+    Fd3,
+    /// DISFDCC of CHOPCONF
+    Disfdcc,
+    /// RNDTF of CHOPCONF
+    Rndtf,
+    /// CHM of CHOPCONF
+    Chm,
+    /// TBL of CHOPCONF
+    Tbl,
+    /// VSENSE of CHOPCONF
+    Vsense,
+    /// VHIGHFS of CHOPCONF
+    Vhighfs,
+    /// VHIGHCHM of CHOPCONF
+    Vhighchm,
+    /// MRES of CHOPCONF
+    Mres,
+    /// INTPOL16 of CHOPCONF
+    Intpol16,
+    /// DEDGE of CHOPCONF
+    Dedge,
+    /// DISS2G of CHOPCONF
+    Diss2g,
+    /// SEMIN of COOLCONF
+    Semin,
+    /// SEUP of COOLCONF
+    Seup,
+    /// SEMAX of COOLCONF
+    Semax,
+    /// SEDN of COOLCONF
+    Sedn,
+    /// SEIMIN of COOLCONF
+    Seimin,
+    /// SGT of COOLCONF
+    Sgt,
+    /// SFILT of COOLCONF
+    Sfilt,
+    /// DC_TIME of DCCTRL
+    DcTime,
+    /// DC_SG of DCCTRL
+    DcSg,
+    /// SG_RESULT of DRV_STATUS
+    SgResult,
+    /// FSACTIVE of DRV_STATUS
+    Fsactive,
+    /// CS_ACTUAL of DRV_STATUS
+    CsActual,
+    /// STALL_GUARD of DRV_STATUS
+    StallGuard,
+    /// OT of DRV_STATUS
+    Ot,
+    /// OTPW of DRV_STATUS
+    Otpw,
+    /// S2GA of DRV_STATUS
+    S2ga,
+    /// S2GB of DRV_STATUS
+    S2gb,
+    /// OLA of DRV_STATUS
+    Ola,
+    /// OLB of DRV_STATUS
+    Olb,
+    /// STST of DRV_STATUS
+    Stst,
+    /// I_HOLD of IHOLD_IRUN
+    IHold,
+    /// I_RUN of IHOLD_IRUN
+    IRun,
+    /// I_HOLD_DELAY of IHOLD_IRUN
+    IHoldDelay,
+    /// V_COOL_THRS of VCOOLTHRS
+    VCoolThrs,
+    /// V_HIGH of VHIGH
+    VHigh,
+    /// V_DC_MIN of VDCMIN
+    VDcMin,
+    /// STOP_L_ENABLE of SW_MODE
+    StopLEnable,
+    /// STOP_R_ENABLE of SW_MODE
+    StopREnable,
+    /// POL_STOP_L of SW_MODE
+    PolStopL,
+    /// POL_STOP_R of SW_MODE
+    PolStopR,
+    /// SWAP_LR of SW_MODE
+    SwapLr,
+    /// LATCH_L_ACTIVE of SW_MODE
+    LatchLActive,
+    /// LATCH_L_INACTIVE of SW_MODE
+    LatchLInactive,
+    /// LATCH_R_ACTIVE of SW_MODE
+    LatchRActive,
+    /// LATCH_R_INACTIVE of SW_MODE
+    LatchRInactive,
+    /// EN_LATCH_ENCODER of SW_MODE
+    EnLatchEncoder,
+    /// SG_STOP of SW_MODE
+    SgStop,
+    /// EN_SOFTSTOP of SW_MODE
+    EnSoftstop,
+    /// STATUS_STOP_L of RAMP_STAT
+    StatusStopL,
+    /// STATUS_STOP_R of RAMP_STAT
+    StatusStopR,
+    /// STATUS_LATCH_L of RAMP_STAT
+    StatusLatchL,
+    /// STATUS_LATCH_R of RAMP_STAT
+    StatusLatchR,
+    /// EVENT_STOP_L of RAMP_STAT
+    EventStopL,
+    /// EVENT_STOP_R of RAMP_STAT
+    EventStopR,
+    /// EVENT_STOP_SG of RAMP_STAT
+    EventStopSg,
+    /// EVENT_POS_REACHED of RAMP_STAT
+    EventPosReached,
+    /// VELOCITY_REACHED of RAMP_STAT
+    VelocityReached,
+    /// POSITION_REACHED of RAMP_STAT
+    PositionReached,
+    /// VZERO of RAMP_STAT
+    Vzero,
+    /// T_ZEROWAIT_ACTIVE of RAMP_STAT
+    TZerowaitActive,
+    /// SECOND_MOVE of RAMP_STAT
+    SecondMove,
+    /// STATUS_SG of RAMP_STAT
+    StatusSg,
+    /// X_LATCH of XLATCH
+    XLatch,
+    /// RAMP_MODE of RAMPMODE
+    RampMode,
+    /// X_ACTUAL of XACTUAL
+    XActual,
+    /// V_ACTUAL of VACTUAL
+    VActual,
+    /// V_START of VSTART
+    VStart,
+    /// A1 of A1
+    A1,
+    /// V1 of V1
+    V1,
+    /// A_MAX of AMAX
+    AMax,
+    /// V_MAX of VMAX
+    VMax,
+    /// D_MAX of DMAX
+    DMax,
+    /// D1 of D1
+    D1,
+    /// V_STOP of VSTOP
+    VStop,
+    /// T_ZERO_WAIT of TZEROWAIT
+    TZeroWait,
+    /// X_TARGET of XTARGET
+    XTarget,
+    /// PWM_AMPL of PWMCONF
+    PwmAmpl,
+    /// PWM_GRAD of PWMCONF
+    PwmGrad,
+    /// PWM_FREQ of PWMCONF
+    PwmFreq,
+    /// PWM_AUTOSCALE of PWMCONF
+    PwmAutoscale,
+    /// FREEWHEEL of PWMCONF
+    Freewheel,
+    /// PWM_STATUS of PWM_STATUS
+    PwmStatus,
+}
+
+const ENC_MODE: &[RegisterAddress] = &[RegisterAddress::EncMode0, RegisterAddress::EncMode1];
+const XENC: &[RegisterAddress] = &[RegisterAddress::XEnc0, RegisterAddress::XEnc1];
+const ENC_CONST: &[RegisterAddress] = &[RegisterAddress::EncConst0, RegisterAddress::EncConst1];
+const ENC_STATUS: &[RegisterAddress] = &[RegisterAddress::EncStatus0, RegisterAddress::EncStatus1];
+const ENC_LATCH: &[RegisterAddress] = &[RegisterAddress::EncLatch0, RegisterAddress::EncLatch1];
+const GCONF: &[RegisterAddress] = &[RegisterAddress::GConf];
+const GSTAT: &[RegisterAddress] = &[RegisterAddress::GStat];
+const IF_CNT: &[RegisterAddress] = &[RegisterAddress::IfCnt];
+const SLAVE_CONF: &[RegisterAddress] = &[RegisterAddress::SlaveConf];
+const INPUT_OUTPUT: &[RegisterAddress] = &[RegisterAddress::InputOutput];
+const XCOMPARE: &[RegisterAddress] = &[RegisterAddress::XCompare];
+const MS_LUT0: &[RegisterAddress] = &[RegisterAddress::MsLut0];
+const MS_LUT1: &[RegisterAddress] = &[RegisterAddress::MsLut1];
+const MS_LUT2: &[RegisterAddress] = &[RegisterAddress::MsLut2];
+const MS_LUT3: &[RegisterAddress] = &[RegisterAddress::MsLut3];
+const MS_LUT4: &[RegisterAddress] = &[RegisterAddress::MsLut4];
+const MS_LUT5: &[RegisterAddress] = &[RegisterAddress::MsLut5];
+const MS_LUT6: &[RegisterAddress] = &[RegisterAddress::MsLut6];
+const MS_LUT7: &[RegisterAddress] = &[RegisterAddress::MsLut7];
+const MS_LUT_SEL: &[RegisterAddress] = &[RegisterAddress::MsLutSel];
+const MS_LUT_START: &[RegisterAddress] = &[RegisterAddress::MsLutStart];
+const MS_CNT: &[RegisterAddress] = &[RegisterAddress::MsCnt0, RegisterAddress::MsCnt1];
+const MS_CUR_ACT: &[RegisterAddress] = &[RegisterAddress::MsCurAct0, RegisterAddress::MsCurAct1];
+const CHOP_CONF: &[RegisterAddress] = &[RegisterAddress::ChopConf0, RegisterAddress::ChopConf1];
+const COOL_CONF: &[RegisterAddress] = &[RegisterAddress::CoolConf0, RegisterAddress::CoolConf1];
+const DC_CTRL: &[RegisterAddress] = &[RegisterAddress::DcCtrl0, RegisterAddress::DcCtrl1];
+const DRV_STATUS: &[RegisterAddress] = &[RegisterAddress::DrvStatus0, RegisterAddress::DrvStatus1];
+const IHOLD_IRUN: &[RegisterAddress] = &[RegisterAddress::IHoldIRun0, RegisterAddress::IHoldIRun1];
+const VCOOL_THRS: &[RegisterAddress] = &[RegisterAddress::VCoolThrs0, RegisterAddress::VCoolThrs1];
+const VHIGH: &[RegisterAddress] = &[RegisterAddress::VHigh0, RegisterAddress::VHigh1];
+const VDC_MIN: &[RegisterAddress] = &[RegisterAddress::VDcMin0, RegisterAddress::VDcMin1];
+const SW_MODE: &[RegisterAddress] = &[RegisterAddress::SwMode0, RegisterAddress::SwMode1];
+const RAMP_STAT: &[RegisterAddress] = &[RegisterAddress::RampStat0, RegisterAddress::RampStat1];
+const XLATCH: &[RegisterAddress] = &[RegisterAddress::XLatch0, RegisterAddress::XLatch1];
+const RAMP_MODE: &[RegisterAddress] = &[RegisterAddress::RampMode0, RegisterAddress::RampMode1];
+const XACTUAL: &[RegisterAddress] = &[RegisterAddress::XActual0, RegisterAddress::XActual1];
+const VACTUAL: &[RegisterAddress] = &[RegisterAddress::VActual0, RegisterAddress::VActual1];
+const VSTART: &[RegisterAddress] = &[RegisterAddress::VStart0, RegisterAddress::VStart1];
+const A1: &[RegisterAddress] = &[RegisterAddress::A10, RegisterAddress::A11];
+const V1: &[RegisterAddress] = &[RegisterAddress::V10, RegisterAddress::V11];
+const AMAX: &[RegisterAddress] = &[RegisterAddress::AMax0, RegisterAddress::AMax1];
+const VMAX: &[RegisterAddress] = &[RegisterAddress::VMax0, RegisterAddress::VMax1];
+const DMAX: &[RegisterAddress] = &[RegisterAddress::DMax0, RegisterAddress::DMax1];
+const D1: &[RegisterAddress] = &[RegisterAddress::D10, RegisterAddress::D11];
+const VSTOP: &[RegisterAddress] = &[RegisterAddress::VStop0, RegisterAddress::VStop1];
+const TZERO_WAIT: &[RegisterAddress] = &[RegisterAddress::TZeroWait0, RegisterAddress::TZeroWait1];
+const XTARGET: &[RegisterAddress] = &[RegisterAddress::XTarget0, RegisterAddress::XTarget1];
+const PWM_CONF: &[RegisterAddress] = &[RegisterAddress::PwmConf0, RegisterAddress::PwmConf1];
+const PWM_STATUS: &[RegisterAddress] = &[RegisterAddress::PwmStatus0, RegisterAddress::PwmStatus1];
+
+/// Registers, field and bits of the field in the register value
+const FIELDS: &[(&[RegisterAddress], FieldId, u32)] = &[
+    (ENC_MODE, FieldId::PolA, EncMode::<0>::POL_A_MASK),
+    (ENC_MODE, FieldId::PolB, EncMode::<0>::POL_B_MASK),
+    (ENC_MODE, FieldId::PolN, EncMode::<0>::POL_N_MASK),
+    (ENC_MODE, FieldId::IgnoreAb, EncMode::<0>::IGNORE_AB_MASK),
+    (ENC_MODE, FieldId::ClrCont, EncMode::<0>::CLR_CONT_MASK),
+    (ENC_MODE, FieldId::ClrOnce, EncMode::<0>::CLR_ONCE_MASK),
+    (ENC_MODE, FieldId::PosEdge, EncMode::<0>::POS_EDGE_MASK),
+    (ENC_MODE, FieldId::NegEdge, EncMode::<0>::NEG_EDGE_MASK),
+    (ENC_MODE, FieldId::ClrEncX, EncMode::<0>::CLR_ENC_X_MASK),
+    (ENC_MODE, FieldId::LatchXAct, EncMode::<0>::LATCH_X_ACT_MASK),
+    (
+        ENC_MODE,
+        FieldId::EncSelDecimal,
+        EncMode::<0>::ENC_SEL_DECIMAL_MASK,
+    ),
+    (ENC_MODE, FieldId::LatchNow, EncMode::<0>::LATCH_NOW_MASK),
+    (XENC, FieldId::XEnc, XEnc::<0>::X_ENC_MASK),
+    (
+        ENC_CONST,
+        FieldId::EncConstFrac,
+        EncConst::<0>::ENC_CONST_FRAC_MASK,
+    ),
+    (
+        ENC_CONST,
+        FieldId::EncConstInt,
+        EncConst::<0>::ENC_CONST_INT_MASK,
+    ),
+    (
+        ENC_STATUS,
+        FieldId::EncStatus,
+        EncStatus::<0>::ENC_STATUS_MASK,
+    ),
+    (ENC_LATCH, FieldId::EncLatch, EncLatch::<0>::ENC_LATCH_MASK),
+    (GCONF, FieldId::SingleDriver, GConf::SINGLE_DIVER_MASK),
+    (GCONF, FieldId::Stepdir1Enable, GConf::STEPDIR1_ENABLE_MASK),
+    (GCONF, FieldId::Stepdir2Enable, GConf::STEPDIR2_ENABLE_MASK),
+    (GCONF, FieldId::PoscmpEnable, GConf::POSCMP_ENABLE_MASK),
+    (GCONF, FieldId::Enc1Refsel, GConf::ENC1_REFSEL_MASK),
+    (GCONF, FieldId::Enc2Enable, GConf::ENC2_ENABLE_MASK),
+    (GCONF, FieldId::Enc2Refsel, GConf::ENC2_REFSEL_MASK),
+    (GCONF, FieldId::TestMode, GConf::TEST_MODE_MASK),
+    (GCONF, FieldId::Shaft1, GConf::SHAFT1_MASK),
+    (GCONF, FieldId::Shaft2, GConf::SHAFT2_MASK),
+    (GCONF, FieldId::LockGconf, GConf::LOCK_GCONF_MASK),
+    (GCONF, FieldId::DcSync, GConf::DC_SYNC_MASK),
+    (GSTAT, FieldId::Reset, GStat::RESET_MASK),
+    (GSTAT, FieldId::DrvErr1, GStat::DRV_ERR1_MASK),
+    (GSTAT, FieldId::DrvErr2, GStat::DRV_ERR2_MASK),
+    (GSTAT, FieldId::UvCp, GStat::UV_CP_MASK),
+    (IF_CNT, FieldId::IfCnt, IfCnt::IF_CNT_MASK),
+    (SLAVE_CONF, FieldId::SlaveAddr, SlaveConf::SLAVE_ADDR_MASK),
+    (SLAVE_CONF, FieldId::SendDelay, SlaveConf::SEND_DELAY_MASK),
+    (INPUT_OUTPUT, FieldId::Io0, Input::IO0_MASK),
+    (INPUT_OUTPUT, FieldId::Io1, Input::IO1_MASK),
+    (INPUT_OUTPUT, FieldId::Io2, Input::IO2_MASK),
+    (INPUT_OUTPUT, FieldId::Io3, Input::IO3_MASK),
+    (INPUT_OUTPUT, FieldId::Iop, Input::IOP_MASK),
+    (INPUT_OUTPUT, FieldId::Ion, Input::ION_MASK),
+    (INPUT_OUTPUT, FieldId::NextAddr, Input::NEXT_ADDR_MASK),
+    (INPUT_OUTPUT, FieldId::DrvEnn, Input::DRV_ENN_MASK),
+    (INPUT_OUTPUT, FieldId::SwComp, Input::SW_COMP_MASK),
+    (INPUT_OUTPUT, FieldId::Version, Input::VERSION_MASK),
+    (INPUT_OUTPUT, FieldId::IoDdr0, Output::IO_DDR0_MASK),
+    (INPUT_OUTPUT, FieldId::IoDdr1, Output::IO_DDR1_MASK),
+    (INPUT_OUTPUT, FieldId::IoDdr2, Output::IO_DDR2_MASK),
+    (XCOMPARE, FieldId::XCompare, XCompare::X_COMPARE_MASK),
+    (MS_LUT0, FieldId::MsLut0, MsLut0::MS_LUT0_MASK),
+    (MS_LUT1, FieldId::MsLut1, MsLut1::MS_LUT1_MASK),
+    (MS_LUT2, FieldId::MsLut2, MsLut2::MS_LUT2_MASK),
+    (MS_LUT3, FieldId::MsLut3, MsLut3::MS_LUT3_MASK),
+    (MS_LUT4, FieldId::MsLut4, MsLut4::MS_LUT4_MASK),
+    (MS_LUT5, FieldId::MsLut5, MsLut5::MS_LUT5_MASK),
+    (MS_LUT6, FieldId::MsLut6, MsLut6::MS_LUT6_MASK),
+    (MS_LUT7, FieldId::MsLut7, MsLut7::MS_LUT7_MASK),
+    (MS_LUT_SEL, FieldId::W0, MsLutSel::W0_MASK),
+    (MS_LUT_SEL, FieldId::W1, MsLutSel::W1_MASK),
+    (MS_LUT_SEL, FieldId::W2, MsLutSel::W2_MASK),
+    (MS_LUT_SEL, FieldId::W3, MsLutSel::W3_MASK),
+    (MS_LUT_SEL, FieldId::X1, MsLutSel::X1_MASK),
+    (MS_LUT_SEL, FieldId::X2, MsLutSel::X2_MASK),
+    (MS_LUT_SEL, FieldId::X3, MsLutSel::X3_MASK),
+    (MS_LUT_START, FieldId::StartSin, MsLutStart::START_SIN_MASK),
+    (
+        MS_LUT_START,
+        FieldId::StartSin90,
+        MsLutStart::START_SIN90_MASK,
+    ),
+    (MS_CNT, FieldId::MsCnt, MsCnt::<0>::MS_CNT_MASK),
+    (MS_CUR_ACT, FieldId::CurA, MsCurAct::<0>::CUR_A_MASK),
+    (MS_CUR_ACT, FieldId::CurB, MsCurAct::<0>::CUR_B_MASK),
+    (CHOP_CONF, FieldId::Toff, ChopConf::<0>::TOFF_MASK),
+    (CHOP_CONF, FieldId::Hstrt, ChopConf::<0>::HSTRT_MASK),
+    (CHOP_CONF, FieldId::Hend, ChopConf::<0>::HEND_MASK),
+    (CHOP_CONF, FieldId::Fd3, ChopConf::<0>::FD3_MASK),
+    (CHOP_CONF, FieldId::Disfdcc, ChopConf::<0>::DISFDCC_MASK),
+    (CHOP_CONF, FieldId::Rndtf, ChopConf::<0>::RNDTF_MASK),
+    (CHOP_CONF, FieldId::Chm, ChopConf::<0>::CHM_MASK),
+    (CHOP_CONF, FieldId::Tbl, ChopConf::<0>::TBL_MASK),
+    (CHOP_CONF, FieldId::Vsense, ChopConf::<0>::VSENSE_MASK),
+    (CHOP_CONF, FieldId::Vhighfs, ChopConf::<0>::VHIGHFS_MASK),
+    (CHOP_CONF, FieldId::Vhighchm, ChopConf::<0>::VHIGHCHM_MASK),
+    (CHOP_CONF, FieldId::Mres, ChopConf::<0>::MRES_MASK),
+    (CHOP_CONF, FieldId::Intpol16, ChopConf::<0>::INTPOL16_MASK),
+    (CHOP_CONF, FieldId::Dedge, ChopConf::<0>::DEDGE_MASK),
+    (CHOP_CONF, FieldId::Diss2g, ChopConf::<0>::DISS2G_MASK),
+    (COOL_CONF, FieldId::Semin, CoolConf::<0>::SEMIN_MASK),
+    (COOL_CONF, FieldId::Seup, CoolConf::<0>::SEUP_MASK),
+    (COOL_CONF, FieldId::Semax, CoolConf::<0>::SEMAX_MASK),
+    (COOL_CONF, FieldId::Sedn, CoolConf::<0>::SEDN_MASK),
+    (COOL_CONF, FieldId::Seimin, CoolConf::<0>::SEIMIN_MASK),
+    (COOL_CONF, FieldId::Sgt, CoolConf::<0>::SGT_MASK),
+    (COOL_CONF, FieldId::Sfilt, CoolConf::<0>::SFILT_MASK),
+    (DC_CTRL, FieldId::DcTime, DcCtrl::<0>::DC_TIME_MASK),
+    (DC_CTRL, FieldId::DcSg, DcCtrl::<0>::DC_SG_MASK),
+    (
+        DRV_STATUS,
+        FieldId::SgResult,
+        DrvStatus::<0>::SG_RESULT_MASK,
+    ),
+    (DRV_STATUS, FieldId::Fsactive, DrvStatus::<0>::FSACTIVE_MASK),
+    (
+        DRV_STATUS,
+        FieldId::CsActual,
+        DrvStatus::<0>::CS_ACTUAL_MASK,
+    ),
+    (
+        DRV_STATUS,
+        FieldId::StallGuard,
+        DrvStatus::<0>::STALL_GUARD_MASK,
+    ),
+    (DRV_STATUS, FieldId::Ot, DrvStatus::<0>::OT_MASK),
+    (DRV_STATUS, FieldId::Otpw, DrvStatus::<0>::OTPW_MASK),
+    (DRV_STATUS, FieldId::S2ga, DrvStatus::<0>::S2GA_MASK),
+    (DRV_STATUS, FieldId::S2gb, DrvStatus::<0>::S2GB_MASK),
+    (DRV_STATUS, FieldId::Ola, DrvStatus::<0>::OLA_MASK),
+    (DRV_STATUS, FieldId::Olb, DrvStatus::<0>::OLB_MASK),
+    (DRV_STATUS, FieldId::Stst, DrvStatus::<0>::STST_MASK),
+    (IHOLD_IRUN, FieldId::IHold, IHoldIRun::<0>::I_HOLD_MASK),
+    (IHOLD_IRUN, FieldId::IRun, IHoldIRun::<0>::I_RUN_MASK),
+    (
+        IHOLD_IRUN,
+        FieldId::IHoldDelay,
+        IHoldIRun::<0>::I_HOLD_DELAY_MASK,
+    ),
+    (
+        VCOOL_THRS,
+        FieldId::VCoolThrs,
+        VCoolThrs::<0>::V_COOL_THRS_MASK,
+    ),
+    (VHIGH, FieldId::VHigh, VHigh::<0>::V_HIGH_MASK),
+    (VDC_MIN, FieldId::VDcMin, VDcMin::<0>::V_DC_MIN_MASK),
+    (
+        SW_MODE,
+        FieldId::StopLEnable,
+        SwMode::<0>::STOP_L_ENABLE_MASK,
+    ),
+    (
+        SW_MODE,
+        FieldId::StopREnable,
+        SwMode::<0>::STOP_R_ENABLE_MASK,
+    ),
+    (SW_MODE, FieldId::PolStopL, SwMode::<0>::POL_STOP_L_MASK),
+    (SW_MODE, FieldId::PolStopR, SwMode::<0>::POL_STOP_R_MASK),
+    (SW_MODE, FieldId::SwapLr, SwMode::<0>::SWAP_LR_MASK),
+    (
+        SW_MODE,
+        FieldId::LatchLActive,
+        SwMode::<0>::LATCH_L_ACTIVE_MASK,
+    ),
+    (
+        SW_MODE,
+        FieldId::LatchLInactive,
+        SwMode::<0>::LATCH_L_INACTIVE_MASK,
+    ),
+    (
+        SW_MODE,
+        FieldId::LatchRActive,
+        SwMode::<0>::LATCH_R_ACTIVE_MASK,
+    ),
+    (
+        SW_MODE,
+        FieldId::LatchRInactive,
+        SwMode::<0>::LATCH_R_INACTIVE_MASK,
+    ),
+    (
+        SW_MODE,
+        FieldId::EnLatchEncoder,
+        SwMode::<0>::EN_LATCH_ENCODER_MASK,
+    ),
+    (SW_MODE, FieldId::SgStop, SwMode::<0>::SG_STOP_MASK),
+    (SW_MODE, FieldId::EnSoftstop, SwMode::<0>::EN_SOFTSTOP_MASK),
+    (
+        RAMP_STAT,
+        FieldId::StatusStopL,
+        RampStat::<0>::STATUS_STOP_L_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::StatusStopR,
+        RampStat::<0>::STATUS_STOP_R_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::StatusLatchL,
+        RampStat::<0>::STATUS_LATCH_L_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::StatusLatchR,
+        RampStat::<0>::STATUS_LATCH_R_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::EventStopL,
+        RampStat::<0>::EVENT_STOP_L_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::EventStopR,
+        RampStat::<0>::EVENT_STOP_R_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::EventStopSg,
+        RampStat::<0>::EVENT_STOP_SG_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::EventPosReached,
+        RampStat::<0>::EVENT_POS_REACHED_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::VelocityReached,
+        RampStat::<0>::VELOCITY_REACHED_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::PositionReached,
+        RampStat::<0>::POSITION_REACHED_MASK,
+    ),
+    (RAMP_STAT, FieldId::Vzero, RampStat::<0>::VZERO_MASK),
+    (
+        RAMP_STAT,
+        FieldId::TZerowaitActive,
+        RampStat::<0>::T_ZEROWAIT_ACTIVE_MASK,
+    ),
+    (
+        RAMP_STAT,
+        FieldId::SecondMove,
+        RampStat::<0>::SECOND_MOVE_MASK,
+    ),
+    (RAMP_STAT, FieldId::StatusSg, RampStat::<0>::STATUS_SG_MASK),
+    (XLATCH, FieldId::XLatch, XLatch::<0>::X_LATCH_MASK),
+    (RAMP_MODE, FieldId::RampMode, RampMode::<0>::RAMP_MODE_MASK),
+    (XACTUAL, FieldId::XActual, XActual::<0>::X_ACTUAL_MASK),
+    (VACTUAL, FieldId::VActual, VActual::<0>::V_ACTUAL_MASK),
+    (VSTART, FieldId::VStart, VStart::<0>::V_START_MASK),
+    (A1, FieldId::A1, A1::<0>::A1_MASK),
+    (V1, FieldId::V1, V1::<0>::V1_MASK),
+    (AMAX, FieldId::AMax, AMax::<0>::A_MAX_MASK),
+    (VMAX, FieldId::VMax, VMax::<0>::V_MAX_MASK),
+    (DMAX, FieldId::DMax, DMax::<0>::D_MAX_MASK),
+    (D1, FieldId::D1, D1::<0>::D1_MASK),
+    (VSTOP, FieldId::VStop, VStop::<0>::V_STOP_MASK),
+    (
+        TZERO_WAIT,
+        FieldId::TZeroWait,
+        TZeroWait::<0>::T_ZERO_WAIT_MASK,
+    ),
+    (XTARGET, FieldId::XTarget, XTarget::<0>::X_TARGET_MASK),
+    (PWM_CONF, FieldId::PwmAmpl, PwmConf::<0>::PWM_AMPL_MASK),
+    (PWM_CONF, FieldId::PwmGrad, PwmConf::<0>::PWM_GRAD_MASK),
+    (PWM_CONF, FieldId::PwmFreq, PwmConf::<0>::PWM_FREQ_MASK),
+    (
+        PWM_CONF,
+        FieldId::PwmAutoscale,
+        PwmConf::<0>::PWM_AUTOSCALE_MASK,
+    ),
+    (PWM_CONF, FieldId::Freewheel, PwmConf::<0>::FREEWHEEL_MASK),
+    (
+        PWM_STATUS,
+        FieldId::PwmStatus,
+        PwmStatus::<0>::PWM_STATUS_MASK,
+    ),
+];
+
+/// Bits of a field in a register
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Field {
+    /// Register holding the field
+    pub addr: RegisterAddress,
+    /// Bits of the field in the register value
+    pub mask: u32,
+    /// Bit offset of the field
+    pub shift: u32,
+}
+
+impl Field {
+    const fn new(addr: RegisterAddress, mask: u32) -> Self {
+        Self {
+            addr,
+            mask,
+            shift: mask.trailing_zeros(),
+        }
+    }
+
+    /// Field `id` of the register at `addr`, `None` if the register has no such field
+    pub fn of(addr: RegisterAddress, id: FieldId) -> Option<Self> {
+        FIELDS
+            .iter()
+            .find(|(addrs, field, _)| *field == id && addrs.contains(&addr))
+            .map(|&(_, _, mask)| Self::new(addr, mask))
+    }
+
+    /// Field value in the register value
+    pub fn get(&self, value: u32) -> u32 {
+        (value & self.mask) >> self.shift
+    }
+
+    /// Register value with the field set to `field`, truncated to the field bits
+    pub fn set(&self, value: u32, field: u32) -> u32 {
+        (value & !self.mask) | ((field << self.shift) & self.mask)
+    }
+}
+
+/// Fields of the register at `addr`
+pub fn fields(addr: RegisterAddress) -> impl Iterator<Item = (FieldId, Field)> {
+    FIELDS
+        .iter()
+        .filter(move |(addrs, _, _)| addrs.contains(&addr))
+        .map(move |&(_, id, mask)| (id, Field::new(addr, mask)))
+}
+
+/// Field access error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FieldError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// The register has no such field
+    NoField(RegisterAddress, FieldId),
+    /// The register is read only, nothing was written
+    ReadOnly(RegisterAddress),
+    /// The write only register is not part of the shadow configuration
+    NoShadow(RegisterAddress),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for FieldError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        FieldError::SpiError(e)
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Reads field `id` of the register at `addr`
+    ///
+    /// Write only registers are read from the shadow configuration. Reading a clear on read register clears its
+    /// flags, the other fields included.
+    pub fn read_field<SPI: Transfer<u8>>(
+        &mut self,
+        addr: RegisterAddress,
+        id: FieldId,
+        spi: &mut SPI,
+    ) -> Result<u32, FieldError<SPI::Error, CS::Error>> {
+        let field = Field::of(addr, id).ok_or(FieldError::NoField(addr, id))?;
+        let value = self.field_register(addr, spi)?;
+        Ok(field.get(value))
+    }
+
+    /// Writes field `id` of the register at `addr` to `value`, keeping the other fields
+    ///
+    /// The register is read back first, or taken from the shadow configuration if write only. Only the field bits
+    /// are written to clear on read registers, where writing 1 clears a flag.
+    pub fn write_field<SPI: Transfer<u8>>(
+        &mut self,
+        addr: RegisterAddress,
+        id: FieldId,
+        value: u32,
+        spi: &mut SPI,
+    ) -> Result<(), FieldError<SPI::Error, CS::Error>> {
+        let field = Field::of(addr, id).ok_or(FieldError::NoField(addr, id))?;
+        let register = match addr.access() {
+            Access::Read => return Err(FieldError::ReadOnly(addr)),
+            Access::ReadClear => 0,
+            Access::Write | Access::ReadWrite => self.field_register(addr, spi)?,
+        };
+        self.write_raw(addr.addr(), field.set(register, value), spi)?;
+        Ok(())
+    }
+
+    /// Register value from the chip, or from the shadow configuration if write only
+    fn field_register<SPI: Transfer<u8>>(
+        &mut self,
+        addr: RegisterAddress,
+        spi: &mut SPI,
+    ) -> Result<u32, FieldError<SPI::Error, CS::Error>> {
+        if addr.access().is_readable() {
+            return Ok(self.read_raw(addr.addr(), spi)?.data);
+        }
+        self.config()
+            .writes()
+            .into_iter()
+            .find(|&(a, _)| a == addr.addr())
+            .map(|(_, value)| value)
+            .ok_or(FieldError::NoShadow(addr))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::Register;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn table() {
+        let field = Field::of(RegisterAddress::ChopConf1, FieldId::Mres).unwrap();
+        assert_eq!(
+            (field.mask, field.shift),
+            (ChopConf::<1>::MRES_MASK, ChopConf::<1>::MRES_SHIFT)
+        );
+        assert_eq!(Field::of(RegisterAddress::VMax0, FieldId::Mres), None);
+        for addr in RegisterAddress::ALL {
+            let mask = fields(addr).fold(0, |mask, (_, field)| {
+                // fields do not overlap, but INPUT and OUTPUT share their address
+                if addr != RegisterAddress::InputOutput {
+                    assert_eq!(mask & field.mask, 0, "{:?}", addr);
+                }
+                mask | field.mask
+            });
+            assert_ne!(mask, 0, "{:?}", addr);
+        }
+        assert_eq!(
+            fields(RegisterAddress::PwmConf0).fold(0, |mask, (_, field)| mask | field.mask),
+            PwmConf::<0>::MASK
+        );
+    }
+
+    #[test]
+    fn read_write() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let addr = RegisterAddress::IHoldIRun0;
+        tmc5072
+            .write_field(addr, FieldId::IRun, 20, &mut sim)
+            .unwrap();
+        tmc5072
+            .write_field(addr, FieldId::IHold, 5, &mut sim)
+            .unwrap();
+        let i_hold_i_run = IHoldIRun::<0>::from(sim.get(IHoldIRun::<0>::addr()));
+        assert_eq!((i_hold_i_run.i_run, i_hold_i_run.i_hold), (20, 5));
+        // write only: from the shadow
+        assert_eq!(tmc5072.read_field(addr, FieldId::IRun, &mut sim), Ok(20));
+
+        sim.set(ChopConf::<0>::addr(), 0x0003_0005);
+        tmc5072
+            .write_field(RegisterAddress::ChopConf0, FieldId::Toff, 3, &mut sim)
+            .unwrap();
+        assert_eq!(sim.get(ChopConf::<0>::addr()), 0x0003_0003);
+
+        assert_eq!(
+            tmc5072.write_field(RegisterAddress::VActual0, FieldId::VActual, 1, &mut sim),
+            Err(FieldError::ReadOnly(RegisterAddress::VActual0))
+        );
+        assert_eq!(
+            tmc5072.read_field(RegisterAddress::VMax0, FieldId::IRun, &mut sim),
+            Err(FieldError::NoField(RegisterAddress::VMax0, FieldId::IRun))
+        );
+    }
+}
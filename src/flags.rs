@@ -0,0 +1,90 @@
+//! Compact formatting of status flags
+//!
+//! The status registers display the names of their set flags, as in the datasheet, e.g. `[otpw ola stst]`.
+
+use core::fmt;
+
+/// Writes the names of the set `flags` between brackets
+pub(crate) fn fmt_flags(f: &mut fmt::Formatter<'_>, flags: &[(&str, bool)]) -> fmt::Result {
+    f.write_str("[")?;
+    let mut first = true;
+    for (name, _) in flags.iter().filter(|(_, set)| *set) {
+        if !first {
+            f.write_str(" ")?;
+        }
+        f.write_str(name)?;
+        first = false;
+    }
+    f.write_str("]")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        general_configuration_register::GStat, motor_driver_register::DrvStatus,
+        ramp_generator_driver_feature_control_register::RampStat,
+    };
+    use crate::status::SpiStatus;
+    use core::fmt::Write;
+
+    /// Fixed size text buffer
+    #[derive(Default)]
+    struct Buffer {
+        bytes: [u8; 32],
+        len: usize,
+    }
+
+    impl Buffer {
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    fn display(value: impl fmt::Display) -> Buffer {
+        let mut buffer = Buffer::default();
+        write!(buffer, "{}", value).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn display_flags() {
+        let drv_status = DrvStatus::<0> {
+            otpw: true,
+            ola: true,
+            stst: true,
+            cs_actual: 31,
+            ..Default::default()
+        };
+        assert_eq!(display(drv_status).as_str(), "[otpw ola stst]");
+        assert_eq!(display(DrvStatus::<1>::default()).as_str(), "[]");
+        let ramp_stat = RampStat::<1> {
+            event_stop_sg: true,
+            vzero: true,
+            ..Default::default()
+        };
+        assert_eq!(display(ramp_stat).as_str(), "[event_stop_sg vzero]");
+        let g_stat = GStat {
+            reset: true,
+            uv_cp: true,
+            ..Default::default()
+        };
+        assert_eq!(display(g_stat).as_str(), "[reset uv_cp]");
+        assert_eq!(
+            display(SpiStatus::from(0x03)).as_str(),
+            "[reset_flag driver_error1]"
+        );
+    }
+}
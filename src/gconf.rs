@@ -0,0 +1,110 @@
+//! GCONF helpers
+//!
+//! Once lock_gconf is set (possibly by a bootloader), GCONF silently ignores writes until the next reset.
+//! These helpers read GCONF back after writing it and report a [`GConfError::GconfLocked`] error
+//! when the write did not take effect because lock_gconf is set, or a [`GConfError::Mismatch`] error
+//! when the read back value differs for another reason.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::general_configuration_register::GConf;
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// GCONF write error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GConfError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// GCONF is locked, it still holds the read back value
+    GconfLocked(GConf),
+    /// GCONF is not locked but reads back a different value than written
+    Mismatch(GConf),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for GConfError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        GConfError::SpiError(e)
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Writes GCONF and checks it was applied
+    pub fn write_gconf<SPI: Transfer<u8>>(
+        &mut self,
+        g_conf: GConf,
+        spi: &mut SPI,
+    ) -> Result<(), GConfError<SPI::Error, CS::Error>> {
+        self.write_register(g_conf, spi)?;
+        let read_back = self.read_register::<GConf, _>(spi)?.data;
+        if u32::from(read_back) != u32::from(g_conf) {
+            return Err(if read_back.lock_gconf {
+                GConfError::GconfLocked(read_back)
+            } else {
+                GConfError::Mismatch(read_back)
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads GCONF, modifies it with `f` and writes it back, checking it was applied
+    ///
+    /// Returns the written value.
+    pub fn update_gconf<SPI: Transfer<u8>, F: FnOnce(&mut GConf)>(
+        &mut self,
+        f: F,
+        spi: &mut SPI,
+    ) -> Result<GConf, GConfError<SPI::Error, CS::Error>> {
+        let mut g_conf = self.read_register::<GConf, _>(spi)?.data;
+        f(&mut g_conf);
+        self.write_gconf(g_conf, spi)?;
+        Ok(g_conf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::Register;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn update_gconf() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let g_conf = tmc5072.update_gconf(|g| g.shaft2 = true, &mut sim).unwrap();
+        assert!(g_conf.shaft2);
+        assert!(GConf::from(sim.get(GConf::addr())).shaft2);
+    }
+
+    #[test]
+    fn locked() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let locked = GConf {
+            lock_gconf: true,
+            ..Default::default()
+        };
+        tmc5072.write_gconf(locked, &mut sim).unwrap();
+        assert_eq!(
+            tmc5072.update_gconf(|g| g.shaft1 = true, &mut sim),
+            Err(GConfError::GconfLocked(locked))
+        );
+    }
+
+    #[cfg(not(feature = "debug-unlock"))]
+    #[test]
+    fn unencoded_test_mode() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let g_conf = GConf {
+            test_mode: true,
+            ..Default::default()
+        };
+        assert_eq!(tmc5072.write_gconf(g_conf, &mut sim), Ok(()));
+    }
+}
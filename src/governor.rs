@@ -0,0 +1,205 @@
+//! Velocity and acceleration governor
+//!
+//! Ramp limits configured once at init are applied by [`Tmc5072::write_raw`](crate::Tmc5072::write_raw) to every
+//! write of a ramp register (VSTART, V1, VMAX, VSTOP, A1, AMAX, DMAX, D1), whatever layer issues it.
+//! Locking the limits prevents them from being raised later on.
+//!
+//! Motors marked position critical never get IHOLD=0 (freewheeling or no holding torque at standstill):
+//! an IHOLD_IRUN write with IHOLD=0 keeps the last IHOLD written, or IRUN if there was none.
+
+use crate::registers::{
+    ramp_generator_driver_feature_control_register::IHoldIRun,
+    ramp_generator_register::{AMax, DMax, VMax, VStart, VStop, A1, D1, V1},
+    Register,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Ramp limits of a motor
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RampLimits {
+    /// Highest velocity (VSTART, V1, VMAX, VSTOP), `None` for no limit
+    pub v_max: Option<u32>,
+    /// Highest acceleration and deceleration (A1, AMAX, DMAX, D1), `None` for no limit
+    pub a_max: Option<u16>,
+}
+
+/// The ramp limits are locked
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RampLimitsLocked;
+
+/// Ramp limits and holding current guard of both motors
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) struct Governor {
+    pub(crate) limits: [RampLimits; 2],
+    pub(crate) locked: bool,
+    pub(crate) position_critical: [bool; 2],
+    /// Last non zero IHOLD written
+    hold: [u8; 2],
+}
+
+impl Governor {
+    /// Clamps the data written to a ramp register, guards IHOLD of position critical motors
+    pub(crate) fn limit(&mut self, addr: u8, data: u32) -> u32 {
+        if addr == IHoldIRun::<0>::addr() {
+            return u32::from(self.guard_hold::<0>(IHoldIRun::from(data)));
+        } else if addr == IHoldIRun::<1>::addr() {
+            return u32::from(self.guard_hold::<1>(IHoldIRun::from(data)));
+        }
+        match Self::motor(addr) {
+            Some(m) => {
+                let limits = &self.limits[m];
+                if Self::is_velocity(addr) {
+                    limits.v_max.map_or(data, |v_max| data.min(v_max))
+                } else {
+                    limits.a_max.map_or(data, |a_max| data.min(a_max as u32))
+                }
+            }
+            None => data,
+        }
+    }
+
+    fn guard_hold<const M: u8>(&mut self, mut current: IHoldIRun<M>) -> IHoldIRun<M> {
+        let m = M as usize;
+        if current.i_hold != 0 {
+            self.hold[m] = current.i_hold;
+        } else if self.position_critical[m] {
+            current.i_hold = if self.hold[m] != 0 {
+                self.hold[m]
+            } else {
+                current.i_run
+            };
+        }
+        current
+    }
+
+    /// Motor of a ramp register
+    fn motor(addr: u8) -> Option<usize> {
+        let ramp_registers = |addrs: [u8; 8]| addrs.contains(&addr);
+        if ramp_registers([
+            VStart::<0>::addr(),
+            V1::<0>::addr(),
+            VMax::<0>::addr(),
+            VStop::<0>::addr(),
+            A1::<0>::addr(),
+            AMax::<0>::addr(),
+            DMax::<0>::addr(),
+            D1::<0>::addr(),
+        ]) {
+            Some(0)
+        } else if ramp_registers([
+            VStart::<1>::addr(),
+            V1::<1>::addr(),
+            VMax::<1>::addr(),
+            VStop::<1>::addr(),
+            A1::<1>::addr(),
+            AMax::<1>::addr(),
+            DMax::<1>::addr(),
+            D1::<1>::addr(),
+        ]) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    fn is_velocity(addr: u8) -> bool {
+        [
+            VStart::<0>::addr(),
+            V1::<0>::addr(),
+            VMax::<0>::addr(),
+            VStop::<0>::addr(),
+            VStart::<1>::addr(),
+            V1::<1>::addr(),
+            VMax::<1>::addr(),
+            VStop::<1>::addr(),
+        ]
+        .contains(&addr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+
+    #[test]
+    fn clamp() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072
+            .set_ramp_limits::<1>(RampLimits {
+                v_max: Some(10000),
+                a_max: Some(500),
+            })
+            .unwrap();
+        tmc5072
+            .write_register(VMax::<1> { v_max: 50000 }, &mut sim)
+            .unwrap();
+        tmc5072
+            .write_raw(AMax::<1>::addr(), 1000, &mut sim)
+            .unwrap();
+        tmc5072
+            .write_register(D1::<1> { d1: 100 }, &mut sim)
+            .unwrap();
+        tmc5072
+            .write_register(VMax::<0> { v_max: 50000 }, &mut sim)
+            .unwrap();
+        assert_eq!(sim.get(VMax::<1>::addr()), 10000);
+        assert_eq!(sim.get(AMax::<1>::addr()), 500);
+        assert_eq!(sim.get(D1::<1>::addr()), 100);
+        assert_eq!(sim.get(VMax::<0>::addr()), 50000);
+    }
+
+    #[test]
+    fn position_critical() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let current = IHoldIRun::<0> {
+            i_hold: 0,
+            i_run: 16,
+            i_hold_delay: 2,
+        };
+        tmc5072.write_register(current, &mut sim).unwrap();
+        assert_eq!(sim.get(IHoldIRun::<0>::addr()), u32::from(current));
+        tmc5072.set_position_critical::<0>(true);
+        tmc5072.write_register(current, &mut sim).unwrap();
+        assert_eq!(
+            IHoldIRun::<0>::from(sim.get(IHoldIRun::<0>::addr())).i_hold,
+            16
+        );
+        tmc5072
+            .write_register(
+                IHoldIRun::<0> {
+                    i_hold: 6,
+                    ..current
+                },
+                &mut sim,
+            )
+            .unwrap();
+        tmc5072.write_register(current, &mut sim).unwrap();
+        assert_eq!(
+            IHoldIRun::<0>::from(sim.get(IHoldIRun::<0>::addr())).i_hold,
+            6
+        );
+        // motor 1 is not critical
+        tmc5072
+            .write_register(IHoldIRun::<1>::default(), &mut sim)
+            .unwrap();
+        assert_eq!(sim.get(IHoldIRun::<1>::addr()), 0);
+    }
+
+    #[test]
+    fn lock() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072.lock_ramp_limits();
+        assert_eq!(
+            tmc5072.set_ramp_limits::<0>(RampLimits::default()),
+            Err(RampLimitsLocked)
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Link supervision
+//!
+//! A wiring fault does not always show up as a bus error: with MISO stuck or the chip unpowered, SPI transfers
+//! succeed but return constant bytes. A [`Heartbeat`] periodically reads the IC version from INPUT and counts the
+//! consecutive reads failing or returning a wrong version, telling supervisory layers a link fault ([`LinkHealth`])
+//! apart from a motion fault.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{general_configuration_register::Input, IC_VERSION};
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Heartbeat settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeartbeatConfig {
+    /// Time between two reads, in the unit of the `now` argument of [`Heartbeat::poll`]
+    pub interval: u32,
+    /// Consecutive failures for the link to be degraded
+    pub degraded_after: u8,
+    /// Consecutive failures for the link to be down
+    pub down_after: u8,
+}
+
+/// Link health
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LinkHealth {
+    /// The last read succeeded
+    Up,
+    /// Some consecutive reads failed
+    Degraded,
+    /// Too many consecutive reads failed
+    Down,
+}
+
+/// Periodic link check
+#[derive(Debug)]
+pub struct Heartbeat {
+    config: HeartbeatConfig,
+    last: Option<u32>,
+    failures: u8,
+}
+
+impl Heartbeat {
+    /// Creates a heartbeat, the link being up until a read fails
+    pub fn new(config: HeartbeatConfig) -> Self {
+        Self {
+            config,
+            last: None,
+            failures: 0,
+        }
+    }
+
+    /// Consecutive failed reads
+    pub fn failures(&self) -> u8 {
+        self.failures
+    }
+
+    /// Link health after the last read
+    pub fn health(&self) -> LinkHealth {
+        if self.failures >= self.config.down_after {
+            LinkHealth::Down
+        } else if self.failures >= self.config.degraded_after {
+            LinkHealth::Degraded
+        } else {
+            LinkHealth::Up
+        }
+    }
+
+    /// Reads INPUT if the interval elapsed since the last read, returns the link health
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> LinkHealth {
+        if let Some(last) = self.last {
+            if now.wrapping_sub(last) < self.config.interval {
+                return self.health();
+            }
+        }
+        self.last = Some(now);
+        let ok = matches!(
+            tmc5072.read_register::<Input, _>(spi),
+            Ok(input) if input.data.version == IC_VERSION
+        );
+        self.failures = if ok {
+            0
+        } else {
+            self.failures.saturating_add(1)
+        };
+        self.health()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    /// Simulator behind a MISO line that can be stuck low
+    struct Link {
+        sim: Simulator,
+        connected: bool,
+    }
+
+    impl Transfer<u8> for Link {
+        type Error = core::convert::Infallible;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+            self.sim.transfer(words)?;
+            if !self.connected {
+                words.fill(0);
+            }
+            Ok(words)
+        }
+    }
+
+    #[test]
+    fn health() {
+        let mut link = Link {
+            sim: Simulator::new(),
+            connected: true,
+        };
+        let mut tmc5072 = Tmc5072::new(&mut link, CsPin).unwrap();
+        let mut heartbeat = Heartbeat::new(HeartbeatConfig {
+            interval: 100,
+            degraded_after: 1,
+            down_after: 3,
+        });
+        assert_eq!(heartbeat.poll(&mut tmc5072, &mut link, 0), LinkHealth::Up);
+        link.connected = false;
+        // not due yet
+        assert_eq!(heartbeat.poll(&mut tmc5072, &mut link, 50), LinkHealth::Up);
+        assert_eq!(
+            heartbeat.poll(&mut tmc5072, &mut link, 100),
+            LinkHealth::Degraded
+        );
+        heartbeat.poll(&mut tmc5072, &mut link, 200);
+        assert_eq!(
+            heartbeat.poll(&mut tmc5072, &mut link, 300),
+            LinkHealth::Down
+        );
+        assert_eq!(heartbeat.failures(), 3);
+        link.connected = true;
+        assert_eq!(heartbeat.poll(&mut tmc5072, &mut link, 400), LinkHealth::Up);
+    }
+}
@@ -0,0 +1,609 @@
+//! Homing routines
+//!
+//! Switch homing ([`switch_home`]) follows the datasheet flow: the motor moves towards the reference switch with
+//! stop and latch on the active going edge enabled (SW_MODE), stops on the switch, XLATCH becomes XACTUAL=0 and the
+//! motor backs off the switch.
+//!
+//! Sensorless homing ([`sensorless_home`]) drives the motor in velocity mode against a mechanical stop with
+//! stop on stall enabled (SW_MODE sg_stop): the ramp generator stops the motor as soon as stallGuard detects the stall.
+//! stallGuard is only evaluated above VCOOLTHRS, which blanks out the acceleration phase.
+//! Once stopped, the stall position becomes XACTUAL=0 and the motor is left in positioning mode at its target.
+//!
+//! Both report a [`HomingResult`] for automated acceptance checks: reference position, approach velocity, and for
+//! switch homing the overtravel past the latched position, the latched encoder position and, with repeat approaches,
+//! the spread of the reference.
+//!
+//! stallGuard requires spreadCycle (no stealthChop at the homing velocity) and an SGT (COOLCONF) tuned for the
+//! homing velocity and load.
+//!
+//! ```
+//! # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+//! # use tmc5072::homing::{sensorless_home, HomingStatus, SensorlessHomingConfig};
+//! # use tmc5072::Tmc5072;
+//! # fn example<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI) {
+//! let config = SensorlessHomingConfig {
+//!     velocity: -20_000,
+//!     v_cool_thrs: 10_000,
+//!     v_cool_thrs_after: 0,
+//!     max_travel: 200 * 256 * 10,
+//! };
+//! let mut motor = tmc5072.motor::<0, _>(spi);
+//! if let Ok(mut homing) = sensorless_home(&mut motor, config) {
+//!     while let Ok(HomingStatus::Homing) = homing.poll(&mut motor) {}
+//! }
+//! # }
+//! ```
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::motor::Motor;
+use crate::registers::{
+    encoder_registers::EncLatch,
+    ramp_generator_driver_feature_control_register::{RampStat, SwMode, VCoolThrs, XLatch},
+    ramp_generator_register::{RampMode, RampModeValue, VActual, VMax, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Reference switch input
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ReferenceSwitch {
+    /// REFL, reached moving in negative direction
+    Left,
+    /// REFR, reached moving in positive direction
+    Right,
+}
+
+/// Switch homing parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HomingConfig {
+    /// Switch to home on
+    pub switch: ReferenceSwitch,
+    /// The switch input is high active (pol_stop_l/pol_stop_r cleared)
+    pub active_high: bool,
+    /// Decelerate using DMAX on the switch instead of a hard stop
+    pub soft_stop: bool,
+    /// Homing velocity (VMAX), towards the switch then backing off
+    pub velocity: u32,
+    /// Distance (microsteps) from the switch position to the final position, away from the switch
+    pub back_off: u32,
+    /// Travel (microsteps) after which homing is given up
+    pub max_travel: u32,
+    /// Latches the encoder position with XLATCH (SW_MODE en_latch_encoder) and reads it (ENC_LATCH)
+    pub encoder: bool,
+    /// Additional approaches after the first one, each defining the origin again, to estimate the repeatability
+    pub repeat: u8,
+}
+
+/// Sensorless homing parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SensorlessHomingConfig {
+    /// Homing velocity (VMAX), its sign gives the direction
+    pub velocity: i32,
+    /// VCOOLTHRS while homing, stop on stall is enabled above it: keep it below |velocity|
+    pub v_cool_thrs: u32,
+    /// VCOOLTHRS restored after homing, VCOOLTHRS being write only this must be its actual value
+    pub v_cool_thrs_after: u32,
+    /// Travel (microsteps) after which homing is given up
+    pub max_travel: u32,
+}
+
+/// Outcome of a homing, for acceptance checks
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HomingResult {
+    /// XACTUAL of the reference before homing, which is now 0
+    pub position: i32,
+    /// VACTUAL last seen while approaching the reference
+    pub approach_velocity: i32,
+    /// Distance (microsteps) from the latched switch position to the stop position, `None` without switch
+    pub overtravel: Option<i32>,
+    /// X_ENC latched on the switch, with [`HomingConfig::encoder`]
+    pub enc_latch: Option<i32>,
+    /// Largest deviation (microsteps) of the reference over the repeat approaches, `None` without repeat
+    pub repeatability: Option<u32>,
+}
+
+/// Homing progress
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HomingStatus {
+    /// The motor is moving towards the reference
+    Homing,
+    /// The reference was found
+    Homed(HomingResult),
+    /// No reference found within the maximum travel, the motor was stopped
+    NotFound,
+}
+
+/// Switch homing of motor `M` in progress, started by [`switch_home`]
+pub struct SwitchHoming<const M: u8> {
+    config: HomingConfig,
+    sw_mode: SwMode<M>,
+    start: i32,
+    /// XLATCH once the motor stopped on the switch
+    latch: Option<i32>,
+    /// Completed approaches
+    pass: u8,
+    result: HomingResult,
+    status: HomingStatus,
+}
+
+/// Starts switch homing of the motor
+///
+/// SW_MODE is saved and restored once the motor backed off after the last approach.
+/// Each repeat approach starts from the back off position and defines the origin again.
+pub fn switch_home<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    config: HomingConfig,
+) -> Result<SwitchHoming<M>, SpiError<SPI::Error, CS::Error>>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XActual<M>: Register,
+    SwMode<M>: Register,
+    RampStat<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let sw_mode = tmc5072.read_register::<SwMode<M>, _>(spi)?.data;
+    let start = tmc5072.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+    let left = config.switch == ReferenceSwitch::Left;
+    let homing_mode = SwMode::<M> {
+        stop_l_enable: left,
+        stop_r_enable: !left,
+        latch_l_active: left,
+        latch_r_active: !left,
+        latch_l_inactive: false,
+        latch_r_inactive: false,
+        en_softstop: config.soft_stop,
+        en_latch_encoder: config.encoder,
+        sg_stop: false,
+        ..sw_mode
+    };
+    let homing_mode = if left {
+        SwMode {
+            pol_stop_l: !config.active_high,
+            ..homing_mode
+        }
+    } else {
+        SwMode {
+            pol_stop_r: !config.active_high,
+            ..homing_mode
+        }
+    };
+    tmc5072.write_register(homing_mode, spi)?;
+    // clear a stale latch event
+    tmc5072.read_register::<RampStat<M>, _>(spi)?;
+    let velocity = config.velocity.min(i32::MAX as u32) as i32;
+    tmc5072.rotate::<M, _>(if left { -velocity } else { velocity }, spi)?;
+    Ok(SwitchHoming {
+        config,
+        sw_mode,
+        start,
+        latch: None,
+        pass: 0,
+        result: HomingResult {
+            position: 0,
+            approach_velocity: 0,
+            overtravel: None,
+            enc_latch: None,
+            repeatability: None,
+        },
+        status: HomingStatus::Homing,
+    })
+}
+
+impl<const M: u8> SwitchHoming<M>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XActual<M>: Register,
+    XTarget<M>: Register,
+    XLatch<M>: Register,
+    EncLatch<M>: Register,
+    SwMode<M>: Register,
+    RampStat<M>: Register,
+    VActual<M>: Register,
+{
+    /// Actual status
+    pub fn status(&self) -> HomingStatus {
+        self.status
+    }
+
+    /// Completed approaches
+    pub fn pass(&self) -> u8 {
+        self.pass
+    }
+
+    /// Checks for the switch stop, then for the end of the back off move
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        motor: &mut Motor<'_, CS, SPI, M>,
+    ) -> Result<HomingStatus, SpiError<SPI::Error, CS::Error>> {
+        if self.status != HomingStatus::Homing {
+            return Ok(self.status);
+        }
+        let left = self.config.switch == ReferenceSwitch::Left;
+        let (tmc5072, spi) = motor.parts();
+        let [ramp_stat, x_actual, v_actual] = tmc5072
+            .read_many(
+                &[
+                    RampStat::<M>::addr(),
+                    XActual::<M>::addr(),
+                    VActual::<M>::addr(),
+                ],
+                spi,
+            )?
+            .data;
+        let ramp_stat = RampStat::<M>::from(ramp_stat);
+        let x_actual = x_actual as i32;
+        let v_actual = VActual::<M>::from(v_actual).v_actual;
+        match self.latch {
+            None => {
+                let stopped = if left {
+                    ramp_stat.event_stop_l
+                } else {
+                    ramp_stat.event_stop_r
+                };
+                if v_actual != 0 {
+                    self.result.approach_velocity = v_actual;
+                }
+                if stopped && ramp_stat.vzero {
+                    let latch = tmc5072.read_register::<XLatch<M>, _>(spi)?.data.x_latch as i32;
+                    if self.config.encoder {
+                        let enc_latch = tmc5072.read_register::<EncLatch<M>, _>(spi)?.data;
+                        self.result.enc_latch = Some(enc_latch.enc_latch);
+                    }
+                    self.result.overtravel = Some(x_actual.wrapping_sub(latch));
+                    self.result.position = self.result.position.wrapping_add(latch);
+                    if self.pass > 0 {
+                        // the previous approach defined the origin: the latch is the deviation
+                        let deviation = latch.unsigned_abs();
+                        self.result.repeatability =
+                            Some(self.result.repeatability.unwrap_or(0).max(deviation));
+                    }
+                    // hold mode while the latch becomes the origin
+                    tmc5072.write_register(
+                        RampMode::<M> {
+                            ramp_mode: RampModeValue::Hold,
+                        },
+                        spi,
+                    )?;
+                    tmc5072.write_register(
+                        XActual::<M> {
+                            x_actual: x_actual.wrapping_sub(latch),
+                        },
+                        spi,
+                    )?;
+                    let back_off = self.config.back_off.min(i32::MAX as u32) as i32;
+                    let x_target = if left { back_off } else { -back_off };
+                    tmc5072.write_register(XTarget::<M> { x_target }, spi)?;
+                    tmc5072.move_to::<M, _>(x_target, self.config.velocity, spi)?;
+                    self.latch = Some(latch);
+                } else if x_actual.wrapping_sub(self.start).unsigned_abs() > self.config.max_travel
+                {
+                    tmc5072.stop::<M, _>(spi)?;
+                    tmc5072.write_register(self.sw_mode, spi)?;
+                    self.status = HomingStatus::NotFound;
+                }
+            }
+            Some(_) => {
+                if ramp_stat.position_reached {
+                    self.pass += 1;
+                    if self.pass <= self.config.repeat {
+                        // approach again, SW_MODE still stops and latches on the switch
+                        tmc5072.read_register::<RampStat<M>, _>(spi)?;
+                        let velocity = self.config.velocity.min(i32::MAX as u32) as i32;
+                        tmc5072.rotate::<M, _>(if left { -velocity } else { velocity }, spi)?;
+                        self.start = x_actual;
+                        self.latch = None;
+                    } else {
+                        tmc5072.write_register(self.sw_mode, spi)?;
+                        self.status = HomingStatus::Homed(self.result);
+                    }
+                }
+            }
+        }
+        Ok(self.status)
+    }
+}
+
+/// Sensorless homing of motor `M` in progress, started by [`sensorless_home`]
+pub struct SensorlessHoming<const M: u8> {
+    config: SensorlessHomingConfig,
+    sw_mode: SwMode<M>,
+    start: i32,
+    /// VACTUAL last seen while moving
+    approach_velocity: i32,
+    status: HomingStatus,
+}
+
+/// Starts sensorless homing of the motor
+///
+/// SW_MODE is saved and restored afterwards, VMAX keeps the homing velocity.
+pub fn sensorless_home<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    config: SensorlessHomingConfig,
+) -> Result<SensorlessHoming<M>, SpiError<SPI::Error, CS::Error>>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XActual<M>: Register,
+    SwMode<M>: Register,
+    VCoolThrs<M>: Register,
+    RampStat<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let sw_mode = tmc5072.read_register::<SwMode<M>, _>(spi)?.data;
+    let start = tmc5072.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+    tmc5072.write_register(
+        VCoolThrs::<M> {
+            v_cool_thrs: config.v_cool_thrs,
+        },
+        spi,
+    )?;
+    // clear a stale stall event before enabling the stop
+    tmc5072.read_register::<RampStat<M>, _>(spi)?;
+    tmc5072.write_register(
+        SwMode::<M> {
+            sg_stop: true,
+            ..sw_mode
+        },
+        spi,
+    )?;
+    tmc5072.rotate::<M, _>(config.velocity, spi)?;
+    Ok(SensorlessHoming {
+        config,
+        sw_mode,
+        start,
+        approach_velocity: 0,
+        status: HomingStatus::Homing,
+    })
+}
+
+impl<const M: u8> SensorlessHoming<M>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XActual<M>: Register,
+    XTarget<M>: Register,
+    SwMode<M>: Register,
+    VCoolThrs<M>: Register,
+    RampStat<M>: Register,
+    VActual<M>: Register,
+{
+    /// Actual status
+    pub fn status(&self) -> HomingStatus {
+        self.status
+    }
+
+    /// Checks for the stall, completes the homing once the motor stopped
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        motor: &mut Motor<'_, CS, SPI, M>,
+    ) -> Result<HomingStatus, SpiError<SPI::Error, CS::Error>> {
+        if self.status != HomingStatus::Homing {
+            return Ok(self.status);
+        }
+        let (tmc5072, spi) = motor.parts();
+        let [ramp_stat, x_actual, v_actual] = tmc5072
+            .read_many(
+                &[
+                    RampStat::<M>::addr(),
+                    XActual::<M>::addr(),
+                    VActual::<M>::addr(),
+                ],
+                spi,
+            )?
+            .data;
+        let x_actual = x_actual as i32;
+        let v_actual = VActual::<M>::from(v_actual).v_actual;
+        if v_actual != 0 {
+            self.approach_velocity = v_actual;
+        }
+        if RampStat::<M>::from(ramp_stat).event_stop_sg {
+            // hold mode while XACTUAL and XTARGET are reset
+            tmc5072.write_register(
+                RampMode::<M> {
+                    ramp_mode: RampModeValue::Hold,
+                },
+                spi,
+            )?;
+            tmc5072.write_register(XActual::<M> { x_actual: 0 }, spi)?;
+            tmc5072.write_register(XTarget::<M> { x_target: 0 }, spi)?;
+            self.restore(motor)?;
+            let (tmc5072, spi) = motor.parts();
+            tmc5072.write_register(
+                RampMode::<M> {
+                    ramp_mode: RampModeValue::Positioning,
+                },
+                spi,
+            )?;
+            self.status = HomingStatus::Homed(HomingResult {
+                position: x_actual,
+                approach_velocity: self.approach_velocity,
+                overtravel: None,
+                enc_latch: None,
+                repeatability: None,
+            });
+        } else if x_actual.wrapping_sub(self.start).unsigned_abs() > self.config.max_travel {
+            tmc5072.stop::<M, _>(spi)?;
+            self.restore(motor)?;
+            self.status = HomingStatus::NotFound;
+        }
+        Ok(self.status)
+    }
+
+    fn restore<CS: OutputPin, SPI: Transfer<u8>>(
+        &self,
+        motor: &mut Motor<'_, CS, SPI, M>,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        let (tmc5072, spi) = motor.parts();
+        tmc5072.write_register(self.sw_mode, spi)?;
+        tmc5072.write_register(
+            VCoolThrs::<M> {
+                v_cool_thrs: self.config.v_cool_thrs_after,
+            },
+            spi,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator, Timing};
+    use crate::Tmc5072;
+
+    #[test]
+    fn switch() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        sim.set_switches(0, None, Some(2500));
+        let config = HomingConfig {
+            switch: ReferenceSwitch::Right,
+            active_high: false,
+            soft_stop: false,
+            velocity: 20_000,
+            back_off: 100,
+            max_travel: 10_000,
+            encoder: true,
+            repeat: 0,
+        };
+        let mut homing = switch_home(&mut tmc5072.motor::<0, _>(&mut sim), config).unwrap();
+        let sw_mode = SwMode::<0>::from(sim.get(SwMode::<0>::addr()));
+        assert!(sw_mode.stop_r_enable && sw_mode.latch_r_active && sw_mode.pol_stop_r);
+        let status = loop {
+            sim.advance(1000);
+            match homing.poll(&mut tmc5072.motor::<0, _>(&mut sim)).unwrap() {
+                HomingStatus::Homing => {}
+                status => break status,
+            }
+        };
+        assert_eq!(
+            status,
+            HomingStatus::Homed(HomingResult {
+                position: 2500,
+                approach_velocity: 20_000,
+                overtravel: Some(0),
+                enc_latch: Some(2500),
+                repeatability: None,
+            })
+        );
+        assert_eq!(sim.get(XActual::<0>::addr()) as i32, -100);
+        assert_eq!(sim.get(SwMode::<0>::addr()), 0);
+    }
+
+    #[test]
+    fn repeat() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        sim.set_switches(1, Some(-2000), None);
+        let config = HomingConfig {
+            switch: ReferenceSwitch::Left,
+            active_high: true,
+            soft_stop: false,
+            velocity: 20_000,
+            back_off: 200,
+            max_travel: 10_000,
+            encoder: false,
+            repeat: 2,
+        };
+        let mut homing = switch_home(&mut tmc5072.motor::<1, _>(&mut sim), config).unwrap();
+        // the switch triggers 3 then -2 microsteps off the origin on the repeat approaches
+        let mut switches = [Some(3), Some(-2)].into_iter();
+        let mut pass = 0;
+        let result = loop {
+            sim.advance(1000);
+            match homing.poll(&mut tmc5072.motor::<1, _>(&mut sim)).unwrap() {
+                HomingStatus::Homing => {}
+                HomingStatus::Homed(result) => break result,
+                HomingStatus::NotFound => panic!("switch not found"),
+            }
+            if homing.pass() != pass {
+                pass = homing.pass();
+                sim.set_switches(1, switches.next().unwrap(), None);
+            }
+        };
+        assert_eq!(homing.pass(), 3);
+        assert_eq!(result.position, -2000 + 3 - 2);
+        assert_eq!(result.repeatability, Some(3));
+        assert_eq!(result.enc_latch, None);
+        assert_eq!(result.approach_velocity, -20_000);
+        assert_eq!(sim.get(XActual::<1>::addr()), 200);
+    }
+
+    const CONFIG: SensorlessHomingConfig = SensorlessHomingConfig {
+        velocity: -20_000,
+        v_cool_thrs: 10_000,
+        v_cool_thrs_after: 500,
+        max_travel: 5000,
+    };
+
+    #[test]
+    fn sensorless() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        sim.set_stalls(1, Some(-1200), None);
+        sim.set(XActual::<1>::addr(), 300);
+        let sw_mode = SwMode::<1> {
+            stop_l_enable: true,
+            ..Default::default()
+        };
+        sim.set(SwMode::<1>::addr(), u32::from(sw_mode));
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        let mut homing = sensorless_home(&mut motor, CONFIG).unwrap();
+        assert_eq!(homing.poll(&mut motor).unwrap(), HomingStatus::Homing);
+        let status = loop {
+            sim.advance(1000);
+            let mut motor = tmc5072.motor::<1, _>(&mut sim);
+            match homing.poll(&mut motor).unwrap() {
+                HomingStatus::Homing => {}
+                status => break status,
+            }
+        };
+        assert_eq!(
+            status,
+            HomingStatus::Homed(HomingResult {
+                position: -1200,
+                approach_velocity: -20_000,
+                overtravel: None,
+                enc_latch: None,
+                repeatability: None,
+            })
+        );
+        assert_eq!(sim.get(XActual::<1>::addr()), 0);
+        assert_eq!(sim.get(XTarget::<1>::addr()), 0);
+        assert_eq!(sim.get(RampMode::<1>::addr()), 0);
+        assert_eq!(sim.get(SwMode::<1>::addr()), u32::from(sw_mode));
+        assert_eq!(sim.get(VCoolThrs::<1>::addr()), 500);
+    }
+
+    #[test]
+    fn not_found() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        let mut homing = sensorless_home(&mut tmc5072.motor::<0, _>(&mut sim), CONFIG).unwrap();
+        let status = loop {
+            sim.advance(1000);
+            match homing.poll(&mut tmc5072.motor::<0, _>(&mut sim)).unwrap() {
+                HomingStatus::Homing => {}
+                status => break status,
+            }
+        };
+        assert_eq!(status, HomingStatus::NotFound);
+        assert_eq!(homing.status(), HomingStatus::NotFound);
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+        assert!(!SwMode::<0>::from(sim.get(SwMode::<0>::addr())).sg_stop);
+    }
+}
@@ -0,0 +1,250 @@
+//! Motor identification (experimental)
+//!
+//! In standstill, SG_RESULT holds the chopper on-time of coil A, which depends on the coil resistance and inductance.
+//! [`MotorIdentifier`] applies a few standstill current levels (IHOLD), averages the on-time at each level
+//! and matches the resulting signature against stored [`MotorProfile`]s,
+//! so that multi-SKU hardware can pick a preset matching the fitted motor.
+//!
+//! Signatures depend on the supply voltage, sense resistors, chopper settings and temperature:
+//! record the profiles on the target hardware with the same settings.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    motor_driver_register::DrvStatus, ramp_generator_driver_feature_control_register::IHoldIRun,
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of current levels of a probe
+pub const PROBE_LEVELS: usize = 3;
+
+/// Chopper on-time signature of a motor, one value per probe level
+pub type Signature = [u16; PROBE_LEVELS];
+
+/// Stored motor profile
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MotorProfile {
+    /// Application defined identifier
+    pub id: u16,
+    /// Expected signature
+    pub signature: Signature,
+    /// Largest accepted difference at each level
+    pub tolerance: u16,
+}
+
+impl MotorProfile {
+    /// Sum of the differences with `signature`, `None` if a level is out of tolerance
+    pub fn distance(&self, signature: &Signature) -> Option<u32> {
+        self.signature
+            .iter()
+            .zip(signature)
+            .try_fold(0u32, |sum, (&expected, &measured)| {
+                let difference = expected.abs_diff(measured);
+                (difference <= self.tolerance).then_some(sum + difference as u32)
+            })
+    }
+}
+
+/// Probe parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProbeConfig<const M: u8> {
+    /// IHOLD of each probe level
+    pub levels: [u8; PROBE_LEVELS],
+    /// Polls ignored after a level change, to let the current settle
+    pub settle_polls: u8,
+    /// Number of SG_RESULT samples averaged at each level (at least 1)
+    pub samples: u8,
+    /// IHOLD_IRUN restored once done
+    pub restore: IHoldIRun<M>,
+}
+
+/// Identification result
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Identification {
+    /// Measured signature
+    pub signature: Signature,
+    /// Average CS_ACTUAL over the probe, 0 hints at a disconnected or shorted motor
+    pub cs_actual: u8,
+    /// Index of the closest matching profile
+    pub profile: Option<usize>,
+}
+
+/// Identification progress
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IdentifyStatus {
+    /// Probe in progress
+    Probing,
+    /// Probe complete, standstill current restored
+    Done(Identification),
+}
+
+/// Poll driven identification of motor `M`, which must stand still during the probe
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct MotorIdentifier<'a, const M: u8> {
+    config: ProbeConfig<M>,
+    profiles: &'a [MotorProfile],
+    level: usize,
+    polls: u16,
+    sums: [u32; PROBE_LEVELS],
+    cs_actual_sum: u32,
+}
+
+impl<'a, const M: u8> MotorIdentifier<'a, M>
+where
+    IHoldIRun<M>: Register,
+    DrvStatus<M>: Register,
+{
+    /// Creates an identifier matching against `profiles`
+    pub fn new(config: ProbeConfig<M>, profiles: &'a [MotorProfile]) -> Self {
+        Self {
+            config,
+            profiles,
+            level: 0,
+            polls: 0,
+            sums: [0; PROBE_LEVELS],
+            cs_actual_sum: 0,
+        }
+    }
+
+    /// Advances the probe by one sample
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<IdentifyStatus, SpiError<SPI::Error, CS::Error>> {
+        let settle = self.config.settle_polls as u16;
+        let samples = self.config.samples.max(1) as u16;
+        if self.polls == 0 {
+            tmc5072.write_register(
+                IHoldIRun::<M> {
+                    i_hold: self.config.levels[self.level],
+                    i_hold_delay: 0,
+                    ..self.config.restore
+                },
+                spi,
+            )?;
+        }
+        self.polls += 1;
+        if self.polls <= settle {
+            return Ok(IdentifyStatus::Probing);
+        }
+        let drv_status = tmc5072.read_register::<DrvStatus<M>, _>(spi)?.data;
+        self.sums[self.level] += drv_status.sg_result as u32;
+        self.cs_actual_sum += drv_status.cs_actual as u32;
+        if self.polls < settle + samples {
+            return Ok(IdentifyStatus::Probing);
+        }
+        self.level += 1;
+        self.polls = 0;
+        if self.level < PROBE_LEVELS {
+            return Ok(IdentifyStatus::Probing);
+        }
+        tmc5072.write_register(self.config.restore, spi)?;
+        let signature = self.sums.map(|sum| (sum / samples as u32) as u16);
+        let profile = self
+            .profiles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, profile)| profile.distance(&signature).map(|d| (d, index)))
+            .min()
+            .map(|(_, index)| index);
+        Ok(IdentifyStatus::Done(Identification {
+            signature,
+            cs_actual: (self.cs_actual_sum / (samples as u32 * PROBE_LEVELS as u32)) as u8,
+            profile,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn distance() {
+        let profile = MotorProfile {
+            id: 1,
+            signature: [100, 200, 300],
+            tolerance: 10,
+        };
+        assert_eq!(profile.distance(&[95, 205, 300]), Some(10));
+        assert_eq!(profile.distance(&[100, 200, 311]), None);
+    }
+
+    #[test]
+    fn identify() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let profiles = [
+            MotorProfile {
+                id: 17,
+                signature: [120, 120, 120],
+                tolerance: 30,
+            },
+            MotorProfile {
+                id: 23,
+                signature: [150, 150, 150],
+                tolerance: 30,
+            },
+        ];
+        let restore = IHoldIRun::<1> {
+            i_hold: 8,
+            i_run: 20,
+            i_hold_delay: 4,
+        };
+        let mut identifier = MotorIdentifier::new(
+            ProbeConfig {
+                levels: [8, 16, 24],
+                settle_polls: 1,
+                samples: 2,
+                restore,
+            },
+            &profiles,
+        );
+        sim.set(
+            DrvStatus::<1>::addr(),
+            u32::from(DrvStatus::<1> {
+                sg_result: 140,
+                cs_actual: 16,
+                ..Default::default()
+            }),
+        );
+        let mut polls = 0;
+        let identification = loop {
+            polls += 1;
+            if let IdentifyStatus::Done(identification) =
+                identifier.poll(&mut tmc5072, &mut sim).unwrap()
+            {
+                break identification;
+            }
+            if polls == 1 {
+                assert_eq!(
+                    IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())).i_hold,
+                    8
+                );
+            }
+        };
+        assert_eq!(polls, 9);
+        assert_eq!(
+            identification,
+            Identification {
+                signature: [140, 140, 140],
+                cs_actual: 16,
+                profile: Some(1),
+            }
+        );
+        assert_eq!(
+            IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())),
+            restore
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Configuration integrity checks (`shadow-crc` feature)
+//!
+//! For high reliability applications, the driver keeps a CRC-32 over its shadow configuration ([`Tmc5072::config`]),
+//! updated on every configuration register write. An [`IntegrityMonitor`] then detects single event upsets:
+//! - in the MCU memory, the shadow configuration no longer matching its CRC
+//! - in the chip, a readable configuration register (GCONF, CHOPCONF, SW_MODE, ENCMODE) no longer matching the
+//!   shadow configuration
+//!
+//! Each [`IntegrityMonitor::poll`] reads a single register, bounding the bus load: the whole set is verified every
+//! [`CHECKED_REGISTERS`] polls. On a reported corruption, the application refreshes the chip configuration, e.g. with
+//! [`Tmc5072::apply_config`] from a trusted copy.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::config::Config;
+use crate::registers::{
+    encoder_registers::EncMode, general_configuration_register::GConf,
+    motor_driver_register::ChopConf, ramp_generator_driver_feature_control_register::SwMode,
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of registers verified by an [`IntegrityMonitor`]
+pub const CHECKED_REGISTERS: usize = 7;
+
+/// CRC-32 (IEEE 802.3) of the configuration register values, in the order of [`Config::writes`]
+pub fn config_crc(config: &Config) -> u32 {
+    let mut crc = !0u32;
+    for (_, value) in config.writes() {
+        for byte in value.to_le_bytes() {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+    }
+    !crc
+}
+
+/// Result of an integrity check
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Integrity {
+    /// No corruption detected
+    Intact,
+    /// The shadow configuration does not match its CRC, it cannot be trusted anymore
+    ShadowCorrupted,
+    /// A chip register does not match the shadow configuration
+    RegisterMismatch {
+        /// Register address
+        addr: u8,
+        /// Shadow value
+        expected: u32,
+        /// Chip value
+        actual: u32,
+    },
+}
+
+/// Round robin verification of the chip configuration
+#[derive(Debug, Default)]
+pub struct IntegrityMonitor {
+    next: usize,
+}
+
+impl IntegrityMonitor {
+    /// Creates a monitor, starting with GCONF
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the shadow configuration CRC, then reads and verifies the next register
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<Integrity, SpiError<SPI::Error, CS::Error>> {
+        if !tmc5072.shadow_intact() {
+            return Ok(Integrity::ShadowCorrupted);
+        }
+        let shadow = &tmc5072.shadow;
+        let (addr, expected) = match self.next {
+            0 => (GConf::addr(), u32::from(shadow.g_conf)),
+            1 => (ChopConf::<0>::addr(), u32::from(shadow.motor0.chop_conf)),
+            2 => (SwMode::<0>::addr(), u32::from(shadow.motor0.sw_mode)),
+            3 => (EncMode::<0>::addr(), u32::from(shadow.motor0.enc_mode)),
+            4 => (ChopConf::<1>::addr(), u32::from(shadow.motor1.chop_conf)),
+            5 => (SwMode::<1>::addr(), u32::from(shadow.motor1.sw_mode)),
+            _ => (EncMode::<1>::addr(), u32::from(shadow.motor1.enc_mode)),
+        };
+        self.next = (self.next + 1) % CHECKED_REGISTERS;
+        let actual = tmc5072.read_raw(addr, spi)?.data;
+        Ok(if actual == expected {
+            Integrity::Intact
+        } else {
+            Integrity::RegisterMismatch {
+                addr,
+                expected,
+                actual,
+            }
+        })
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Whether the shadow configuration matches its CRC
+    pub fn shadow_intact(&self) -> bool {
+        config_crc(&self.shadow) == self.shadow_crc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn crc() {
+        let mut config = Config::default();
+        let crc = config_crc(&config);
+        config.motor1.v_max.v_max = 1;
+        assert_ne!(config_crc(&config), crc);
+    }
+
+    #[test]
+    fn register_mismatch() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let chop_conf = ChopConf::<1> {
+            toff: 3,
+            ..Default::default()
+        };
+        tmc5072.write_register(chop_conf, &mut sim).unwrap();
+        let mut monitor = IntegrityMonitor::new();
+        for _ in 0..CHECKED_REGISTERS {
+            assert_eq!(monitor.poll(&mut tmc5072, &mut sim), Ok(Integrity::Intact));
+        }
+        // bit flip in the chip
+        sim.set(ChopConf::<1>::addr(), u32::from(chop_conf) ^ 0x100);
+        let mut found = None;
+        for _ in 0..CHECKED_REGISTERS {
+            match monitor.poll(&mut tmc5072, &mut sim).unwrap() {
+                Integrity::Intact => {}
+                mismatch => found = Some(mismatch),
+            }
+        }
+        assert_eq!(
+            found,
+            Some(Integrity::RegisterMismatch {
+                addr: ChopConf::<1>::addr(),
+                expected: u32::from(chop_conf),
+                actual: u32::from(chop_conf) ^ 0x100,
+            })
+        );
+    }
+
+    #[test]
+    fn shadow_corrupted() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        assert!(tmc5072.shadow_intact());
+        // bit flip in the MCU memory
+        tmc5072.shadow.motor0.v_max.v_max ^= 0x40;
+        assert_eq!(
+            IntegrityMonitor::new().poll(&mut tmc5072, &mut sim),
+            Ok(Integrity::ShadowCorrupted)
+        );
+    }
+}
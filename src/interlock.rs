@@ -0,0 +1,184 @@
+//! Motion interlock
+//!
+//! Motion commands issued through an [`Interlock`] require a [`MotionPermit`] of the axis.
+//! Revoking the permissions of an axis (e.g. when a door switch opens) soft stops the motor
+//! and invalidates all permits issued so far, new ones can only be obtained once the axis is released again.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    ramp_generator_register::{RampMode, VMax, XTarget},
+    MotorIndexCheck, Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Interlock error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterlockError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Motion of the axis is not permitted
+    Revoked,
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for InterlockError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        InterlockError::SpiError(e)
+    }
+}
+
+/// Permission to move motor `M`, valid until the axis is revoked
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct MotionPermit<const M: u8> {
+    generation: u32,
+}
+
+/// Motion permissions of both axes
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Interlock {
+    revoked: [bool; 2],
+    generation: [u32; 2],
+}
+
+impl Interlock {
+    /// Creates an interlock with both axes permitted
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issues a permit for motor `M`, `None` while the axis is revoked
+    pub fn permit<const M: u8>(&self) -> Option<MotionPermit<M>> {
+        let () = MotorIndexCheck::<M>::OK;
+        if self.revoked[M as usize] {
+            None
+        } else {
+            Some(MotionPermit {
+                generation: self.generation[M as usize],
+            })
+        }
+    }
+
+    /// Whether `permit` is still valid
+    pub fn is_valid<const M: u8>(&self, permit: &MotionPermit<M>) -> bool {
+        let () = MotorIndexCheck::<M>::OK;
+        !self.revoked[M as usize] && permit.generation == self.generation[M as usize]
+    }
+
+    /// Whether motion of motor `M` is revoked
+    pub fn is_revoked<const M: u8>(&self) -> bool {
+        let () = MotorIndexCheck::<M>::OK;
+        self.revoked[M as usize]
+    }
+
+    /// Revokes the permits of motor `M` and soft stops it
+    ///
+    /// The axis stays revoked even if the stop could not be sent.
+    pub fn revoke<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+    {
+        self.revoked[M as usize] = true;
+        self.generation[M as usize] = self.generation[M as usize].wrapping_add(1);
+        tmc5072.stop::<M, _>(spi)
+    }
+
+    /// Allows motor `M` to move again, permits revoked before stay invalid
+    pub fn release<const M: u8>(&mut self) {
+        let () = MotorIndexCheck::<M>::OK;
+        self.revoked[M as usize] = false;
+    }
+
+    /// Moves motor `M` to `x_target` in positioning mode at `v_max`
+    pub fn move_to<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &self,
+        permit: &MotionPermit<M>,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        x_target: i32,
+        v_max: u32,
+    ) -> Result<(), InterlockError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+        XTarget<M>: Register,
+    {
+        self.check(permit)?;
+        Ok(tmc5072.move_to::<M, _>(x_target, v_max, spi)?)
+    }
+
+    /// Rotates motor `M` at `velocity` (signed) in velocity mode
+    pub fn rotate<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &self,
+        permit: &MotionPermit<M>,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        velocity: i32,
+    ) -> Result<(), InterlockError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+    {
+        self.check(permit)?;
+        Ok(tmc5072.rotate::<M, _>(velocity, spi)?)
+    }
+
+    fn check<const M: u8, SPI, CS>(
+        &self,
+        permit: &MotionPermit<M>,
+    ) -> Result<(), InterlockError<SPI, CS>> {
+        if self.is_valid(permit) {
+            Ok(())
+        } else {
+            Err(InterlockError::Revoked)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::ramp_generator_register::XActual;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn revoke() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut interlock = Interlock::new();
+        let permit = interlock.permit::<1>().unwrap();
+        interlock
+            .move_to(&permit, &mut tmc5072, &mut sim, 100, 1000)
+            .unwrap();
+        assert_eq!(sim.get(XActual::<1>::addr()), 100);
+        interlock.revoke::<1, _, _>(&mut tmc5072, &mut sim).unwrap();
+        assert_eq!(sim.get(RampMode::<1>::addr()), 1);
+        assert_eq!(sim.get(VMax::<1>::addr()), 0);
+        assert!(interlock.permit::<1>().is_none());
+        assert!(interlock.permit::<0>().is_some());
+        assert_eq!(
+            interlock.rotate(&permit, &mut tmc5072, &mut sim, 1000),
+            Err(InterlockError::Revoked)
+        );
+        // old permits stay invalid once released
+        interlock.release::<1>();
+        assert_eq!(
+            interlock.move_to(&permit, &mut tmc5072, &mut sim, 200, 1000),
+            Err(InterlockError::Revoked)
+        );
+        let permit = interlock.permit::<1>().unwrap();
+        interlock
+            .move_to(&permit, &mut tmc5072, &mut sim, 200, 1000)
+            .unwrap();
+        assert_eq!(sim.get(XActual::<1>::addr()), 200);
+    }
+}
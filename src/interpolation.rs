@@ -0,0 +1,397 @@
+//! Two axis interpolation
+//!
+//! Turns the two ramp generators into a minimal 2 axis motion controller: straight lines and arcs
+//! (axis 0 = motor 0, axis 1 = motor 1) are split into short segments, each segment being a positioning move of both motors
+//! with VMAX (and AMAX/DMAX) scaled by the share of each axis, so that both motors arrive together.
+//!
+//! The motors stop at the end of each segment, shorter segments follow the path more closely but move slower.
+//! V1 should be 0 (trapezoidal ramps) for the scaled accelerations to keep both axes in sync.
+//!
+//! Requires the `interpolation` feature.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    ramp_generator_register::{AMax, DMax, RampMode, VMax, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Largest angle of an arc segment (rad)
+const MAX_ARC_STEP: f64 = 0.1;
+
+/// Path followed by both axes from their current position
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Path {
+    /// Straight line to a position
+    Line {
+        /// End position of both axes
+        to: [i32; 2],
+    },
+    /// Arc around a center
+    Arc {
+        /// Center position
+        center: [i32; 2],
+        /// Swept angle in millidegrees, positive from axis 0 towards axis 1
+        sweep: i32,
+    },
+}
+
+/// Interpolation parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterpolationConfig {
+    /// Velocity along the path (VMAX units)
+    pub feed: u32,
+    /// Acceleration along the path (AMAX units), `None` to keep AMAX and DMAX
+    pub acceleration: Option<u16>,
+    /// Longest segment (microsteps)
+    pub max_segment: u32,
+}
+
+/// Synchronized move of both axes
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Segment {
+    /// XTARGET of each axis
+    pub targets: [i32; 2],
+    /// VMAX of each axis
+    pub v_max: [u32; 2],
+    /// AMAX and DMAX of each axis, if the acceleration is scaled
+    pub a_max: Option<[u16; 2]>,
+}
+
+/// Segments of a path, in order
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Interpolation {
+    config: InterpolationConfig,
+    start: [i32; 2],
+    path: Path,
+    segments: u32,
+    segment: u32,
+    position: [i32; 2],
+    /// Arc radius vector and rotation of a segment
+    radius: [f64; 2],
+    rotation: [f64; 2],
+}
+
+impl Interpolation {
+    /// Splits `path` starting at `start`
+    pub fn new(start: [i32; 2], path: Path, config: InterpolationConfig) -> Self {
+        let max_segment = config.max_segment.max(1) as u64;
+        let mut radius = [0.0; 2];
+        let mut rotation = [1.0, 0.0];
+        let segments = match path {
+            Path::Line { to } => {
+                let length = distance(start, to);
+                length.div_ceil(max_segment).max(1)
+            }
+            Path::Arc { center, sweep } => {
+                radius = [
+                    start[0] as f64 - center[0] as f64,
+                    start[1] as f64 - center[1] as f64,
+                ];
+                let angle = (sweep as f64 / 1000.0).to_radians();
+                let length = (distance(start, center) as f64 * angle.abs()) as u64;
+                let segments = length
+                    .div_ceil(max_segment)
+                    .max((angle.abs() / MAX_ARC_STEP) as u64 + 1);
+                let step = angle / segments as f64;
+                rotation = [cos(step), sin(step)];
+                segments
+            }
+        };
+        Self {
+            config,
+            start,
+            path,
+            segments: segments.min(u32::MAX as u64) as u32,
+            segment: 0,
+            position: start,
+            radius,
+            rotation,
+        }
+    }
+
+    /// Number of segments of the path
+    pub fn segments(&self) -> u32 {
+        self.segments
+    }
+
+    /// Number of segments already returned
+    pub fn segment(&self) -> u32 {
+        self.segment
+    }
+
+    fn next_point(&mut self) -> [i32; 2] {
+        match self.path {
+            Path::Line { to } => {
+                let point = |axis: usize| {
+                    let delta = to[axis] as i64 - self.start[axis] as i64;
+                    (self.start[axis] as i64 + delta * self.segment as i64 / self.segments as i64)
+                        as i32
+                };
+                [point(0), point(1)]
+            }
+            Path::Arc { center, .. } => {
+                let [x, y] = self.radius;
+                let [cos, sin] = self.rotation;
+                self.radius = [x * cos - y * sin, x * sin + y * cos];
+                [
+                    (center[0] as f64 + round(self.radius[0])) as i32,
+                    (center[1] as f64 + round(self.radius[1])) as i32,
+                ]
+            }
+        }
+    }
+}
+
+impl Iterator for Interpolation {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Segment> {
+        if self.segment >= self.segments {
+            return None;
+        }
+        self.segment += 1;
+        let targets = self.next_point();
+        let deltas = [
+            (targets[0] as i64 - self.position[0] as i64).unsigned_abs(),
+            (targets[1] as i64 - self.position[1] as i64).unsigned_abs(),
+        ];
+        self.position = targets;
+        let length = isqrt(deltas[0] * deltas[0] + deltas[1] * deltas[1]).max(1);
+        // share of the path velocity of each axis, a moving axis never gets 0
+        let share = |value: u64, delta: u64| {
+            if delta == 0 {
+                0
+            } else {
+                (value * delta / length).max(1)
+            }
+        };
+        let v_max = [
+            share(self.config.feed as u64, deltas[0]) as u32,
+            share(self.config.feed as u64, deltas[1]) as u32,
+        ];
+        let a_max = self.config.acceleration.map(|a| {
+            [
+                share(a as u64, deltas[0]) as u16,
+                share(a as u64, deltas[1]) as u16,
+            ]
+        });
+        Some(Segment {
+            targets,
+            v_max,
+            a_max,
+        })
+    }
+}
+
+/// Interpolation progress
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InterpolationStatus {
+    /// Moving along segment `segment` of `segments` (starting at 1)
+    Moving {
+        /// Current segment
+        segment: u32,
+        /// Number of segments
+        segments: u32,
+    },
+    /// The end of the path is reached
+    Done,
+}
+
+/// Poll driven interpolated move of both motors
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Interpolator {
+    interpolation: Interpolation,
+    targets: Option<[i32; 2]>,
+}
+
+impl Interpolator {
+    /// Creates an interpolated move along `path` from `start`, the actual position of both motors
+    pub fn new(start: [i32; 2], path: Path, config: InterpolationConfig) -> Self {
+        Self {
+            interpolation: Interpolation::new(start, path, config),
+            targets: None,
+        }
+    }
+
+    /// Starts the next segment once both motors reached the current one
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<InterpolationStatus, SpiError<SPI::Error, CS::Error>> {
+        if let Some(targets) = self.targets {
+            let x_actual = tmc5072
+                .read_many(&[XActual::<0>::addr(), XActual::<1>::addr()], spi)?
+                .data
+                .map(|x| x as i32);
+            if x_actual != targets {
+                return Ok(self.status());
+            }
+        }
+        match self.interpolation.next() {
+            Some(segment) => {
+                start_segment::<0, _, _>(tmc5072, spi, &segment)?;
+                start_segment::<1, _, _>(tmc5072, spi, &segment)?;
+                self.targets = Some(segment.targets);
+                Ok(self.status())
+            }
+            None => Ok(InterpolationStatus::Done),
+        }
+    }
+
+    fn status(&self) -> InterpolationStatus {
+        InterpolationStatus::Moving {
+            segment: self.interpolation.segment(),
+            segments: self.interpolation.segments(),
+        }
+    }
+}
+
+fn start_segment<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+    tmc5072: &mut Tmc5072<CS>,
+    spi: &mut SPI,
+    segment: &Segment,
+) -> Result<(), SpiError<SPI::Error, CS::Error>>
+where
+    AMax<M>: Register,
+    DMax<M>: Register,
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XTarget<M>: Register,
+{
+    let m = M as usize;
+    if let Some(a_max) = segment.a_max {
+        tmc5072.write_register(AMax::<M> { a_max: a_max[m] }, spi)?;
+        tmc5072.write_register(DMax::<M> { d_max: a_max[m] }, spi)?;
+    }
+    tmc5072.move_to::<M, _>(segment.targets[m], segment.v_max[m], spi)
+}
+
+/// Euclidean distance between two positions
+fn distance(a: [i32; 2], b: [i32; 2]) -> u64 {
+    let dx = (a[0] as i64 - b[0] as i64).unsigned_abs();
+    let dy = (a[1] as i64 - b[1] as i64).unsigned_abs();
+    isqrt(dx * dx + dy * dy)
+}
+
+/// Integer square root (floor)
+fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// Rounds to the nearest integer, core has no float rounding
+fn round(x: f64) -> f64 {
+    if x < 0.0 {
+        -((-x + 0.5) as i64 as f64)
+    } else {
+        (x + 0.5) as i64 as f64
+    }
+}
+
+/// Taylor series of sin, for angles up to [`MAX_ARC_STEP`]
+fn sin(x: f64) -> f64 {
+    let x2 = x * x;
+    x * (1.0 - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0)))
+}
+
+/// Taylor series of cos, for angles up to [`MAX_ARC_STEP`]
+fn cos(x: f64) -> f64 {
+    let x2 = x * x;
+    1.0 - x2 / 2.0 * (1.0 - x2 / 12.0 * (1.0 - x2 / 30.0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator, Timing};
+
+    const CONFIG: InterpolationConfig = InterpolationConfig {
+        feed: 1000,
+        acceleration: None,
+        max_segment: 100,
+    };
+
+    #[test]
+    fn line() {
+        let mut interpolation = Interpolation::new([0, 0], Path::Line { to: [300, -400] }, CONFIG);
+        assert_eq!(interpolation.segments(), 5);
+        let segment = interpolation.next().unwrap();
+        assert_eq!(segment.targets, [60, -80]);
+        assert_eq!(segment.v_max, [600, 800]);
+        assert_eq!(interpolation.last().unwrap().targets, [300, -400]);
+    }
+
+    #[test]
+    fn arc() {
+        // quarter circle from (1000, 0) around the origin
+        let interpolation = Interpolation::new(
+            [1000, 0],
+            Path::Arc {
+                center: [0, 0],
+                sweep: 90_000,
+            },
+            CONFIG,
+        );
+        assert_eq!(interpolation.segments(), 16);
+        let mut position = [1000, 0];
+        for segment in interpolation {
+            let radius = distance(segment.targets, [0, 0]);
+            assert!((999..=1001).contains(&radius));
+            // all segments are short
+            assert!(distance(segment.targets, position) <= 100);
+            position = segment.targets;
+        }
+        assert_eq!(position, [0, 1000]);
+    }
+
+    #[test]
+    fn interpolator() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        let config = InterpolationConfig {
+            feed: 100_000,
+            acceleration: Some(500),
+            max_segment: 1000,
+        };
+        let mut interpolator = Interpolator::new([0, 0], Path::Line { to: [3000, 0] }, config);
+        assert_eq!(
+            interpolator.poll(&mut tmc5072, &mut sim).unwrap(),
+            InterpolationStatus::Moving {
+                segment: 1,
+                segments: 3
+            }
+        );
+        assert_eq!(sim.get(AMax::<0>::addr()), 500);
+        assert_eq!(sim.get(VMax::<1>::addr()), 0);
+        // not reached yet
+        interpolator.poll(&mut tmc5072, &mut sim).unwrap();
+        assert_eq!(sim.get(XTarget::<0>::addr()), 1000);
+        let mut polls = 0;
+        while interpolator.poll(&mut tmc5072, &mut sim).unwrap() != InterpolationStatus::Done {
+            sim.advance(1000);
+            polls += 1;
+            assert!(polls < 1000);
+        }
+        assert_eq!(sim.get(XActual::<0>::addr()), 3000);
+        assert_eq!(sim.get(XActual::<1>::addr()), 0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Async wait on the INT output (`async` feature)
+//!
+//! The INT output of the TMC5072 is low while any RAMP_STAT or ENC_STATUS event of either motor is pending.
+//! [`wait_for_interrupt`] awaits the INT pin through [`embedded_hal_async::digital::Wait`], then reads and clears the
+//! event registers of a motor with [`poll_events`], so a motion task of an async executor (e.g. Embassy) sleeps until
+//! the chip signals an event instead of polling RAMP_STAT.
+//!
+//! SPI accesses stay blocking: only the wait is async.
+//!
+//! ```rust,ignore
+//! let mut motor = tmc5072.motor::<0, _>(&mut spi);
+//! let events = wait_for_interrupt(&mut motor, &mut int_pin, MotorEvents::RAMP_STAT).await?;
+//! if events.stall {
+//!     // stalled
+//! }
+//! ```
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+use embedded_hal_async::digital::Wait;
+
+use crate::events::{poll_events, MotorEvents};
+use crate::motor::Motor;
+use crate::registers::{
+    encoder_registers::EncStatus, ramp_generator_driver_feature_control_register::RampStat,
+    Register,
+};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Errors of [`wait_for_interrupt`]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptError<SPI, CS, PIN> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// INT pin error
+    PinError(PIN),
+}
+
+impl<SPI, CS, PIN> From<SpiError<SPI, CS>> for InterruptError<SPI, CS, PIN> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        InterruptError::SpiError(e)
+    }
+}
+
+/// Waits for INT to be low (returning at once if an event is already pending), then reads and clears the event
+/// registers of `mask` of the motor and returns their events
+///
+/// INT is shared by both motors: the events returned are empty when INT was asserted by the other motor, whose events
+/// are left pending.
+pub async fn wait_for_interrupt<CS, SPI, PIN, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    int_pin: &mut PIN,
+    mask: MotorEvents,
+) -> Result<MotorEvents, InterruptError<SPI::Error, CS::Error, PIN::Error>>
+where
+    CS: OutputPin,
+    SPI: Transfer<u8>,
+    PIN: Wait,
+    RampStat<M>: Register,
+    EncStatus<M>: Register,
+{
+    int_pin
+        .wait_for_low()
+        .await
+        .map_err(InterruptError::PinError)?;
+    Ok(poll_events(motor, mask)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    /// INT pin always low, counting the waits
+    struct IntPin {
+        polls: u8,
+    }
+
+    impl embedded_hal_1::digital::ErrorType for IntPin {
+        type Error = Infallible;
+    }
+
+    impl Wait for IntPin {
+        async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+            self.polls += 1;
+            Ok(())
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn wait() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            RampStat::<0>::addr(),
+            u32::from(RampStat::<0> {
+                event_pos_reached: true,
+                ..Default::default()
+            }),
+        );
+        let mut int_pin = IntPin { polls: 0 };
+        let mut motor = tmc5072.motor::<0, _>(&mut sim);
+        assert_eq!(
+            block_on(wait_for_interrupt(
+                &mut motor,
+                &mut int_pin,
+                MotorEvents::ALL
+            ))
+            .unwrap(),
+            MotorEvents {
+                position_reached: true,
+                ..Default::default()
+            }
+        );
+        assert!(block_on(wait_for_interrupt(
+            &mut motor,
+            &mut int_pin,
+            MotorEvents::ALL
+        ))
+        .unwrap()
+        .is_empty());
+        assert_eq!(int_pin.polls, 2);
+    }
+}
@@ -0,0 +1,157 @@
+//! Interrupt helpers
+//!
+//! The INT output of the TMC5072 signals RAMP_STAT events (stop switches, stallGuard2 stop, position reached)
+//! of both motors. [`handle_int`] is meant to be called from the INT pin interrupt handler:
+//! it only reads both RAMP_STAT registers (clearing their events) and publishes them to [`SharedEvents`].
+//! Everything else (DRV_STATUS, positions, recovery) is left to thread context, driven by these events.
+//!
+//! Only available on targets with atomic read-modify-write operations.
+//!
+//! ```rust,ignore
+//! static EVENTS: SharedEvents = SharedEvents::new();
+//!
+//! #[interrupt]
+//! fn EXTI0() {
+//!     // tmc5072 and spi are shared with the main loop (e.g. in a Mutex<RefCell<_>>)
+//!     handle_int(&mut tmc5072, &mut spi, &EVENTS).ok();
+//! }
+//!
+//! // main loop
+//! if EVENTS.take::<0>().stall {
+//!     // read DRV_STATUS, re-home...
+//! }
+//! ```
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+pub use crate::events::MotorEvents;
+use crate::registers::ramp_generator_driver_feature_control_register::RampStat;
+use crate::registers::{MotorIndexCheck, Register};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+
+/// Events published from interrupt context and consumed in thread context
+///
+/// Events accumulate until taken: each motion state machine takes the events of its own motor
+/// with [`SharedEvents::take`], without losing events published concurrently by the interrupt handler.
+#[derive(Debug, Default)]
+pub struct SharedEvents {
+    motors: [AtomicU8; 2],
+    fault: AtomicBool,
+}
+
+impl SharedEvents {
+    /// Creates an empty event set, usable in a `static`
+    pub const fn new() -> Self {
+        Self {
+            motors: [AtomicU8::new(0), AtomicU8::new(0)],
+            fault: AtomicBool::new(false),
+        }
+    }
+
+    /// Adds events of motor `M`
+    pub fn publish<const M: u8>(&self, events: MotorEvents) {
+        let () = MotorIndexCheck::<M>::OK;
+        let bits = events.bits();
+        if bits != 0 {
+            self.motors[M as usize].fetch_or(bits, Ordering::AcqRel);
+        }
+    }
+
+    /// Returns and clears the pending events of motor `M`
+    #[must_use = "the pending events are cleared"]
+    pub fn take<const M: u8>(&self) -> MotorEvents {
+        let () = MotorIndexCheck::<M>::OK;
+        MotorEvents::from_bits(self.motors[M as usize].swap(0, Ordering::AcqRel))
+    }
+
+    /// Pending events of motor `M`, without clearing them
+    pub fn peek<const M: u8>(&self) -> MotorEvents {
+        let () = MotorIndexCheck::<M>::OK;
+        MotorEvents::from_bits(self.motors[M as usize].load(Ordering::Acquire))
+    }
+
+    /// Signals a fault (reset or driver error in the SPI status)
+    pub fn publish_fault(&self) {
+        self.fault.store(true, Ordering::Release);
+    }
+
+    /// Returns and clears the fault flag
+    pub fn take_fault(&self) -> bool {
+        self.fault.swap(false, Ordering::AcqRel)
+    }
+}
+
+/// Reads and clears the RAMP_STAT events of both motors and publishes them
+///
+/// Designed to be called from the INT pin interrupt handler: both registers are read with a single pipelined
+/// [`Tmc5072::read_many`] of three datagrams.
+pub fn handle_int<CS: OutputPin, SPI: Transfer<u8>>(
+    tmc5072: &mut Tmc5072<CS>,
+    spi: &mut SPI,
+    events: &SharedEvents,
+) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+    let result = tmc5072.read_many(&[RampStat::<0>::addr(), RampStat::<1>::addr()], spi)?;
+    let [ramp_stat0, ramp_stat1] = result.data;
+    events.publish::<0>(RampStat::<0>::from(ramp_stat0).into());
+    events.publish::<1>(RampStat::<1>::from(ramp_stat1).into());
+    let status = result.status;
+    if status.reset_flag || status.driver_error1 || status.driver_error2 {
+        events.publish_fault();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn events() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        // acknowledge the power up reset
+        tmc5072.check_reset(&mut sim).unwrap();
+        let events = SharedEvents::new();
+        sim.set(
+            RampStat::<1>::addr(),
+            u32::from(RampStat::<1> {
+                event_stop_sg: true,
+                event_pos_reached: true,
+                ..Default::default()
+            }),
+        );
+        handle_int(&mut tmc5072, &mut sim, &events).unwrap();
+        assert!(events.take::<0>().is_empty());
+        assert_eq!(
+            events.peek::<1>(),
+            MotorEvents {
+                stall: true,
+                position_reached: true,
+                ..Default::default()
+            }
+        );
+        // events accumulate until taken
+        events.publish::<1>(MotorEvents {
+            stop_l: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            events.take::<1>(),
+            MotorEvents {
+                stall: true,
+                position_reached: true,
+                stop_l: true,
+                stop_r: false,
+                n_event: false,
+            }
+        );
+        assert!(events.take::<1>().is_empty());
+        assert!(!events.take_fault());
+        // events were cleared upon reading
+        handle_int(&mut tmc5072, &mut sim, &events).unwrap();
+        assert!(events.take::<1>().is_empty());
+    }
+}
@@ -90,6 +90,29 @@
 //! # }
 //! ```
 //!
+//! # Shared bus
+//!
+//! The driver only owns its chip select pin, the SPI bus is borrowed for each access: several drivers (or other
+//! devices) share a bus without a bus manager, e.g. through a `RefCell`, or a critical section mutex when the bus is
+//! also used from interrupts. [`Tmc5072::new_deferred`] creates a driver without any bus access, before the bus
+//! or the chip supply is ready.
+//!
+//! ```
+//! # use core::cell::RefCell;
+//! # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+//! # use tmc5072::registers::ramp_generator_register::XActual;
+//! # use tmc5072::Tmc5072;
+//! fn positions<SPI: Transfer<u8>, CS: OutputPin>(bus: &RefCell<SPI>, cs0: CS, cs1: CS) -> Option<[i32; 2]> {
+//!     let mut chip0 = Tmc5072::new_deferred(cs0);
+//!     let mut chip1 = Tmc5072::new_deferred(cs1);
+//!     chip0.initialize(&mut *bus.borrow_mut()).ok()?;
+//!     chip1.initialize(&mut *bus.borrow_mut()).ok()?;
+//!     let x0 = chip0.read_register::<XActual<0>, _>(&mut *bus.borrow_mut()).ok()?;
+//!     let x1 = chip1.read_register::<XActual<0>, _>(&mut *bus.borrow_mut()).ok()?;
+//!     Some([x0.data.x_actual, x1.data.x_actual])
+//! }
+//! ```
+//!
 //! # Warnings
 //!
 //! Not production ready yet, API could change in the future
@@ -99,22 +122,100 @@
 #![no_std]
 #![deny(missing_docs)]
 
+#[cfg(feature = "debug-unlock")]
+pub mod analog_test;
+pub mod batch;
 #[doc(hidden)]
+#[allow(clippy::identity_op)]
+#[cfg_attr(test, allow(clippy::module_inception, clippy::bool_assert_comparison))]
 mod bits;
+pub mod brake;
+pub mod chain;
+pub mod chopper;
+pub mod closed_loop;
+pub mod config;
+pub mod coolstep;
+pub mod coordinated;
+pub mod current;
+pub mod dcstep;
+pub mod direction;
+pub mod dump;
+pub mod encoder;
+pub mod events;
+pub mod exercise;
+pub mod fields;
+mod flags;
+pub mod gconf;
+pub mod governor;
+pub mod heartbeat;
+pub mod homing;
+pub mod identify;
+#[cfg(feature = "shadow-crc")]
+pub mod integrity;
+pub mod interlock;
+#[cfg(feature = "interpolation")]
+pub mod interpolation;
+#[cfg(feature = "async")]
+pub mod interrupt;
+#[cfg(target_has_atomic = "8")]
+pub mod isr;
+pub mod microstep;
+pub mod motion;
+#[cfg(feature = "motion-traits")]
+pub mod motion_traits;
+pub mod motor;
+pub mod plan;
+pub mod position;
+pub mod position_pulse;
+pub mod power;
+pub mod ramp;
+#[allow(clippy::unnecessary_cast, clippy::doc_lazy_continuation)]
+#[cfg_attr(test, allow(clippy::needless_update))]
 pub mod registers;
+pub mod reset;
+pub mod sequencer;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
 pub mod spi;
+pub mod stats;
 pub mod status;
+pub mod stealthchop;
+pub mod step_dir;
+pub mod supervisor;
+pub mod sync;
+pub mod telemetry;
+pub mod thermal;
+#[cfg(feature = "fugit")]
+pub mod time;
+#[cfg(feature = "tmcl")]
+pub mod tmcl;
+pub mod trajectory;
+pub mod trigger_train;
+pub mod uart;
+pub mod units;
+pub mod wait;
+pub mod watch;
 
+use config::Config;
 use embedded_hal as hal;
+use governor::{Governor, RampLimits, RampLimitsLocked};
 use hal::{blocking::spi::Transfer, digital::v2::OutputPin};
-use registers::{Register, IC_VERSION, READ_FLAG, WRITE_FLAG};
+use registers::{
+    address::RegisterAddress, MotorIndexCheck, ReadableRegister, Register, WritableRegister,
+    IC_VERSION,
+};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use spi::{SpiError, SpiOk, SpiResult};
+#[cfg(feature = "access-stats")]
+use stats::AccessStats;
+use stats::{Odometer, Stats};
+use status::{SpiStatus, StatusTracker};
 
 /// TMC5072 initialisation error
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InitError<SPI, CS> {
     /// SPI bus error
     SpiError(SpiError<SPI, CS>),
@@ -128,10 +229,78 @@ impl<SPI, CS> From<SpiError<SPI, CS>> for InitError<SPI, CS> {
     }
 }
 
+/// Checked raw register access error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AccessError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// The register is write only, nothing was read
+    WriteOnly(RegisterAddress),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for AccessError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        AccessError::SpiError(e)
+    }
+}
+
 /// TMC5072 driver
 pub struct Tmc5072<CS> {
     cs: CS,
     buffer: [u8; 5],
+    stats: Stats,
+    #[cfg(feature = "access-stats")]
+    access_stats: AccessStats,
+    governor: Governor,
+    shadow: Config,
+    #[cfg(feature = "shadow-crc")]
+    shadow_crc: u32,
+    status_tracker: StatusTracker,
+    reset_seen: bool,
+    reset_recovery: bool,
+    version: Option<u8>,
+}
+
+impl<CS> Tmc5072<CS> {
+    /// Creates a new Tmc5072 driver from a Chip Select pin without accessing the bus
+    ///
+    /// For when the bus or the chip supply is not ready yet: [`initialize`](Tmc5072::initialize) then checks the
+    /// chip, register accesses not being checked until then.
+    pub fn new_deferred(cs: CS) -> Self {
+        Tmc5072 {
+            buffer: [0; 5],
+            cs,
+            stats: Stats::default(),
+            #[cfg(feature = "access-stats")]
+            access_stats: AccessStats::default(),
+            governor: Governor::default(),
+            shadow: Config::default(),
+            #[cfg(feature = "shadow-crc")]
+            shadow_crc: integrity::config_crc(&Config::default()),
+            status_tracker: StatusTracker::default(),
+            reset_seen: false,
+            reset_recovery: false,
+            version: None,
+        }
+    }
+    /// Releases the Chip Select pin
+    ///
+    /// The SPI bus is only borrowed by each access, the caller keeps it.
+    pub fn release(self) -> CS {
+        self.cs
+    }
+    /// Chip Select pin, e.g. to reconfigure it, it must be left high (inactive)
+    pub fn cs_mut(&mut self) -> &mut CS {
+        &mut self.cs
+    }
+    /// IC version read by [`initialize`](Tmc5072::initialize), `None` before
+    ///
+    /// Also set when the version check failed.
+    pub fn version(&self) -> Option<u8> {
+        self.version
+    }
 }
 
 impl<CS: OutputPin> Tmc5072<CS> {
@@ -140,94 +309,231 @@ impl<CS: OutputPin> Tmc5072<CS> {
         spi: &mut SPI,
         cs: CS,
     ) -> Result<Self, InitError<SPI::Error, CS::Error>> {
-        let mut tmc5072 = Tmc5072 { buffer: [0; 5], cs };
-        // check IC version
-        let version = tmc5072
+        let mut tmc5072 = Self::new_deferred(cs);
+        tmc5072.initialize(spi)?;
+        Ok(tmc5072)
+    }
+    /// Checks the IC version, done by [`new`](Tmc5072::new)
+    pub fn initialize<SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<(), InitError<SPI::Error, CS::Error>> {
+        let version = self
             .read_register::<registers::general_configuration_register::Input, _>(spi)?
             .data
             .version;
+        self.version = Some(version);
         if version != IC_VERSION {
             return Err(InitError::VersionError(version));
         };
-        Ok(tmc5072)
+        Ok(())
+    }
+    /// Usage statistics gathered from the register traffic
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    /// Status bits seen in any reply since the last [`take_status_summary`](Tmc5072::take_status_summary)
+    pub fn status_summary(&self) -> SpiStatus {
+        self.status_tracker.summary()
+    }
+    /// Returns the status bits seen in any reply and clears them
+    pub fn take_status_summary(&mut self) -> SpiStatus {
+        self.status_tracker.take()
+    }
+    /// Resets the usage statistics, the odometer is kept
+    pub fn reset_stats(&mut self) {
+        self.stats.reset();
+    }
+    /// Read and write counts of each register address
+    #[cfg(feature = "access-stats")]
+    pub fn access_stats(&self) -> &AccessStats {
+        &self.access_stats
+    }
+    /// Resets the read and write counts
+    #[cfg(feature = "access-stats")]
+    pub fn reset_access_stats(&mut self) {
+        self.access_stats.reset();
+    }
+    /// Lifetime travel of both motors
+    pub fn odometer(&self) -> Odometer {
+        self.stats.odometer
+    }
+    /// Restores a lifetime travel previously saved with [`Tmc5072::odometer`]
+    pub fn restore_odometer(&mut self, odometer: Odometer) {
+        self.stats.odometer = odometer;
+    }
+    /// Ramp limits applied to the ramp register writes of motor `M`
+    pub fn ramp_limits<const M: u8>(&self) -> RampLimits {
+        let () = MotorIndexCheck::<M>::OK;
+        self.governor.limits[M as usize]
+    }
+    /// Sets the ramp limits of motor `M`, fails once the limits are locked
+    pub fn set_ramp_limits<const M: u8>(
+        &mut self,
+        limits: RampLimits,
+    ) -> Result<(), RampLimitsLocked> {
+        let () = MotorIndexCheck::<M>::OK;
+        if self.governor.locked {
+            return Err(RampLimitsLocked);
+        }
+        self.governor.limits[M as usize] = limits;
+        Ok(())
+    }
+    /// Prevents any further change of the ramp limits
+    pub fn lock_ramp_limits(&mut self) {
+        self.governor.locked = true;
+    }
+    /// Whether motor `M` is position critical
+    pub fn is_position_critical<const M: u8>(&self) -> bool {
+        let () = MotorIndexCheck::<M>::OK;
+        self.governor.position_critical[M as usize]
+    }
+    /// Marks motor `M` as position critical: IHOLD=0 is never written to it
+    pub fn set_position_critical<const M: u8>(&mut self, critical: bool) {
+        let () = MotorIndexCheck::<M>::OK;
+        self.governor.position_critical[M as usize] = critical;
     }
     /// Read a typed register from the Tmc5072
+    #[allow(clippy::extra_unused_lifetimes)]
     pub fn read_register<'a, R, SPI: Transfer<u8>>(
         &mut self,
         spi: &mut SPI,
     ) -> SpiResult<R, SPI::Error, CS::Error>
     where
-        R: Register,
+        R: Register + ReadableRegister,
         u32: From<R>,
     {
         self.read_raw(R::addr(), spi).map(|x| x.map(|x| R::from(x)))
     }
     /// Write a typed register from the Tmc5072
+    #[allow(clippy::extra_unused_lifetimes)]
     pub fn write_register<'a, R, SPI: Transfer<u8>>(
         &mut self,
         r: R,
         spi: &mut SPI,
     ) -> SpiResult<(), SPI::Error, CS::Error>
     where
-        R: Register,
+        R: Register + WritableRegister,
         u32: From<R>,
     {
         let data = u32::from(r);
         self.write_raw(R::addr(), data, spi)
     }
-    // TODO: optimize read (multiple commands (maybe iterators ?) to divide transfers by 2)
     /// Read a raw register from the Tmc5072
     pub fn read_raw<SPI: Transfer<u8>>(
         &mut self,
         addr: u8,
         spi: &mut SPI,
     ) -> SpiResult<u32, SPI::Error, CS::Error> {
-        self.buffer[0] = READ_FLAG | addr;
-        self.buffer[1] = 0;
-        self.buffer[2] = 0;
-        self.buffer[3] = 0;
-        self.buffer[4] = 0;
-        self.cs.set_low().map_err(SpiError::CSError)?;
         // send read command
-        spi.transfer(&mut self.buffer).map_err(SpiError::SpiError)?;
-        self.cs.set_high().map_err(SpiError::CSError)?;
-        // received previous command junk ignore
-        self.buffer[0] = READ_FLAG | addr;
-        self.cs.set_low().map_err(SpiError::CSError)?;
-        // repeat command to get result
-        spi.transfer(&mut self.buffer).map_err(SpiError::SpiError)?;
-        self.cs.set_high().map_err(SpiError::CSError)?;
-        Ok(SpiOk::<u32>::from_buffer(&self.buffer))
+        self.transfer(spi::read_frame(addr), spi)?;
+        // received previous command junk ignore, repeat command to get result
+        self.transfer(spi::read_frame(addr), spi)?;
+        let result = spi::parse_frame(&self.buffer);
+        self.stats.observe_status(&result.status);
+        self.status_tracker.observe(result.status);
+        self.reset_seen |= result.status.reset_flag;
+        self.stats.observe_read(addr, result.data);
+        #[cfg(feature = "access-stats")]
+        self.access_stats.observe_read(addr);
+        Ok(result)
+    }
+    /// Read a raw register from the Tmc5072, rejecting write only registers
+    pub fn read_raw_checked<SPI: Transfer<u8>>(
+        &mut self,
+        addr: RegisterAddress,
+        spi: &mut SPI,
+    ) -> Result<SpiOk<u32>, AccessError<SPI::Error, CS::Error>> {
+        if !addr.access().is_readable() {
+            return Err(AccessError::WriteOnly(addr));
+        }
+        Ok(self.read_raw(addr.addr(), spi)?)
+    }
+    /// Read several raw registers from the Tmc5072, pipelining the read commands
+    ///
+    /// Each reply holds the data requested by the previous command, so `N` reads take `N + 1` transfers instead of `2 * N`.
+    /// The status is the one of the last reply.
+    pub fn read_many<const N: usize, SPI: Transfer<u8>>(
+        &mut self,
+        addrs: &[u8; N],
+        spi: &mut SPI,
+    ) -> SpiResult<[u32; N], SPI::Error, CS::Error> {
+        let mut data = [0; N];
+        let mut status = SpiStatus::default();
+        if let Some(&first) = addrs.first() {
+            self.transfer(spi::read_frame(first), spi)?;
+        }
+        for (i, &addr) in addrs.iter().enumerate() {
+            // send the next command while receiving this one, the last one is repeated
+            let next = addrs.get(i + 1).copied().unwrap_or(addr);
+            self.transfer(spi::read_frame(next), spi)?;
+            let result = spi::parse_frame(&self.buffer);
+            self.stats.observe_status(&result.status);
+            self.status_tracker.observe(result.status);
+            self.reset_seen |= result.status.reset_flag;
+            self.stats.observe_read(addr, result.data);
+            #[cfg(feature = "access-stats")]
+            self.access_stats.observe_read(addr);
+            data[i] = result.data;
+            status = result.status;
+        }
+        Ok(SpiOk { status, data })
     }
     /// Write a raw register from the Tmc5072
+    ///
+    /// Ramp register values are clamped to the [ramp limits](Tmc5072::set_ramp_limits),
+    /// IHOLD=0 is replaced for [position critical](Tmc5072::set_position_critical) motors.
     pub fn write_raw<SPI: Transfer<u8>>(
         &mut self,
         addr: u8,
         data: u32,
         spi: &mut SPI,
     ) -> SpiResult<(), SPI::Error, CS::Error> {
-        self.buffer[0] = WRITE_FLAG | addr;
-        self.buffer[1] = (data >> 24) as u8;
-        self.buffer[2] = (data >> 16) as u8;
-        self.buffer[3] = (data >> 8) as u8;
-        self.buffer[4] = data as u8;
-        self.cs.set_low().map_err(SpiError::CSError)?;
+        let data = self.governor.limit(addr, data);
         // send write command
+        self.transfer(spi::write_frame(addr, data), spi)?;
+        let result = SpiOk::<()>::from_buffer(&self.buffer);
+        self.stats.observe_status(&result.status);
+        self.status_tracker.observe(result.status);
+        self.reset_seen |= result.status.reset_flag;
+        self.stats.observe_write(addr, data);
+        #[cfg(feature = "access-stats")]
+        self.access_stats.observe_write(addr);
+        if self.shadow.observe_write(addr, data) {
+            #[cfg(feature = "shadow-crc")]
+            {
+                self.shadow_crc = integrity::config_crc(&self.shadow);
+            }
+        }
+        Ok(result)
+    }
+    /// Sends a datagram, the reply is left in the buffer
+    fn transfer<SPI: Transfer<u8>>(
+        &mut self,
+        frame: [u8; 5],
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        self.buffer = frame;
+        self.cs.set_low().map_err(SpiError::CSError)?;
         spi.transfer(&mut self.buffer).map_err(SpiError::SpiError)?;
         self.cs.set_high().map_err(SpiError::CSError)?;
-        Ok(SpiOk::<()>::from_buffer(&self.buffer))
+        Ok(())
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_update)]
 mod test {
     use super::*;
     use crate::registers::{
-        general_configuration_register::GConf,
+        general_configuration_register::{GConf, GStat},
         motor_driver_register::ChopConf,
         ramp_generator_driver_feature_control_register::{IHoldIRun, VCoolThrs, VHigh},
-        ramp_generator_register::{AMax, DMax, RampMode, VMax, VStop, XActual, A1, D1, V1},
+        ramp_generator_register::{
+            AMax, DMax, RampMode, RampModeValue, VMax, VStop, XActual, A1, D1, V1,
+        },
         voltage_pwm_mode_stealth_chop::PwmConf,
+        READ_FLAG, WRITE_FLAG,
     };
 
     #[test]
@@ -348,11 +654,114 @@ mod test {
         assert_eq!(RampMode::<0>::addr() | WRITE_FLAG, 0xA0);
         assert_eq!(
             u32::from(RampMode::<0> {
-                ramp_mode: 0,
+                ramp_mode: RampModeValue::Positioning,
                 ..Default::default()
             }),
             0x00000000
         );
         assert_eq!(XActual::<0>::addr() | READ_FLAG, 0x21);
     }
+
+    /// Simulator counting the transfers
+    struct Counting(sim::Simulator, usize);
+
+    impl Transfer<u8> for Counting {
+        type Error = core::convert::Infallible;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+            self.1 += 1;
+            self.0.transfer(words)
+        }
+    }
+
+    #[test]
+    fn status_summary() {
+        let mut sim = sim::Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, sim::CsPin).unwrap();
+        sim.set(GStat::addr(), 0x02);
+        tmc5072.read_raw(XActual::<0>::addr(), &mut sim).unwrap();
+        // clears GSTAT
+        tmc5072.read_register::<GStat, _>(&mut sim).unwrap();
+        let summary = tmc5072.take_status_summary();
+        assert!(summary.driver_error1);
+        assert_eq!(tmc5072.status_summary(), SpiStatus::default());
+    }
+
+    #[test]
+    fn read_raw_checked() {
+        let mut sim = sim::Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, sim::CsPin).unwrap();
+        tmc5072
+            .write_register(XActual::<1> { x_actual: 42 }, &mut sim)
+            .unwrap();
+        assert_eq!(
+            tmc5072
+                .read_raw_checked(RegisterAddress::XActual1, &mut sim)
+                .unwrap()
+                .data,
+            42
+        );
+        assert_eq!(
+            tmc5072.read_raw_checked(RegisterAddress::VMax1, &mut sim),
+            Err(AccessError::WriteOnly(RegisterAddress::VMax1))
+        );
+    }
+
+    #[test]
+    fn read_many() {
+        let mut spi = Counting(sim::Simulator::new(), 0);
+        let mut tmc5072 = Tmc5072::new(&mut spi, sim::CsPin).unwrap();
+        tmc5072
+            .write_register(XActual::<0> { x_actual: -20 }, &mut spi)
+            .unwrap();
+        tmc5072
+            .write_register(VMax::<1> { v_max: 3000 }, &mut spi)
+            .unwrap();
+        spi.1 = 0;
+        let result = tmc5072
+            .read_many(
+                &[XActual::<0>::addr(), VMax::<1>::addr(), AMax::<0>::addr()],
+                &mut spi,
+            )
+            .unwrap();
+        assert_eq!(result.data, [-20i32 as u32, 3000, 0]);
+        assert_eq!(spi.1, 4);
+        assert_eq!(tmc5072.read_many(&[], &mut spi).unwrap().data, []);
+        assert_eq!(spi.1, 4);
+    }
+
+    #[test]
+    fn deferred() {
+        let mut spi = Counting(sim::Simulator::new(), 0);
+        let mut tmc5072 = Tmc5072::new_deferred(sim::CsPin);
+        assert_eq!(spi.1, 0);
+        assert_eq!(tmc5072.version(), None);
+        tmc5072.initialize(&mut spi).unwrap();
+        assert_eq!(spi.1, 2);
+        assert_eq!(tmc5072.version(), Some(IC_VERSION));
+        let _: &mut sim::CsPin = tmc5072.cs_mut();
+        let _: sim::CsPin = tmc5072.release();
+    }
+
+    #[cfg(feature = "access-stats")]
+    #[test]
+    fn access_stats() {
+        let mut sim = sim::Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, sim::CsPin).unwrap();
+        tmc5072
+            .write_register(VMax::<1> { v_max: 3000 }, &mut sim)
+            .unwrap();
+        tmc5072
+            .read_many(&[XActual::<0>::addr(), XActual::<0>::addr()], &mut sim)
+            .unwrap();
+        let access = tmc5072.access_stats();
+        assert_eq!(
+            access.reads_of::<registers::general_configuration_register::Input>(),
+            1
+        );
+        assert_eq!(access.reads_of::<XActual<0>>(), 2);
+        assert_eq!(access.writes_of::<VMax<1>>(), 1);
+        tmc5072.reset_access_stats();
+        assert_eq!(tmc5072.access_stats().touched().count(), 0);
+    }
 }
@@ -0,0 +1,399 @@
+//! Microstep table
+//!
+//! The TMC5072 drives the coils along a quarter wave table of 256 entries, stored differentially: each MSLUT bit,
+//! combined with the width W of its segment (MSLUTSEL), gives the difference to the previous entry, W-1 or W.
+//! [`MicrostepTable`] holds the entries as absolute values and finds the segmentation (up to 4 segments whose
+//! differences differ by at most 1) when encoding them into [`MicrostepTableRegisters`].
+//!
+//! Entry `i` is the coil current after the difference of bit `i` is applied to START_SIN, the current at MSCNT 0.
+//!
+//! Presets generate usual wave shapes: [`MicrostepTable::sine`] (the reset table), a reduced amplitude sine
+//! ([`MicrostepTable::with_amplitude`]) for low inductance motors, and [`MicrostepTable::trapezoidal`].
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::config::Config;
+use crate::registers::microstep_table_register::{
+    MsLut0, MsLut1, MsLut2, MsLut3, MsLut4, MsLut5, MsLut6, MsLut7, MsLutSel, MsLutStart,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of entries of the quarter wave table
+pub const TABLE_ENTRIES: usize = 256;
+
+/// Quarter wave microstep table
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct MicrostepTable {
+    /// START_SIN: current at MSCNT 0
+    pub start_sin: u8,
+    /// START_SIN90: current of the cosine coil at MSCNT 0
+    pub start_sin90: u8,
+    /// Absolute currents of the table entries
+    pub entries: [u8; TABLE_ENTRIES],
+}
+
+/// Registers holding the microstep table
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MicrostepTableRegisters {
+    /// MSLUT\[0\]
+    pub ms_lut0: MsLut0,
+    /// MSLUT\[1\]
+    pub ms_lut1: MsLut1,
+    /// MSLUT\[2\]
+    pub ms_lut2: MsLut2,
+    /// MSLUT\[3\]
+    pub ms_lut3: MsLut3,
+    /// MSLUT\[4\]
+    pub ms_lut4: MsLut4,
+    /// MSLUT\[5\]
+    pub ms_lut5: MsLut5,
+    /// MSLUT\[6\]
+    pub ms_lut6: MsLut6,
+    /// MSLUT\[7\]
+    pub ms_lut7: MsLut7,
+    /// MSLUTSEL
+    pub ms_lut_sel: MsLutSel,
+    /// MSLUTSTART
+    pub ms_lut_start: MsLutStart,
+}
+
+impl MicrostepTableRegisters {
+    /// Microstep table registers of a configuration
+    pub fn of(config: &Config) -> Self {
+        Self {
+            ms_lut0: config.ms_lut0,
+            ms_lut1: config.ms_lut1,
+            ms_lut2: config.ms_lut2,
+            ms_lut3: config.ms_lut3,
+            ms_lut4: config.ms_lut4,
+            ms_lut5: config.ms_lut5,
+            ms_lut6: config.ms_lut6,
+            ms_lut7: config.ms_lut7,
+            ms_lut_sel: config.ms_lut_sel,
+            ms_lut_start: config.ms_lut_start,
+        }
+    }
+
+    /// Copies the registers into a configuration
+    pub fn apply(&self, config: &mut Config) {
+        config.ms_lut0 = self.ms_lut0;
+        config.ms_lut1 = self.ms_lut1;
+        config.ms_lut2 = self.ms_lut2;
+        config.ms_lut3 = self.ms_lut3;
+        config.ms_lut4 = self.ms_lut4;
+        config.ms_lut5 = self.ms_lut5;
+        config.ms_lut6 = self.ms_lut6;
+        config.ms_lut7 = self.ms_lut7;
+        config.ms_lut_sel = self.ms_lut_sel;
+        config.ms_lut_start = self.ms_lut_start;
+    }
+
+    fn ms_lut(&self) -> [u32; 8] {
+        [
+            self.ms_lut0.ms_lut0,
+            self.ms_lut1.ms_lut1,
+            self.ms_lut2.ms_lut2,
+            self.ms_lut3.ms_lut3,
+            self.ms_lut4.ms_lut4,
+            self.ms_lut5.ms_lut5,
+            self.ms_lut6.ms_lut6,
+            self.ms_lut7.ms_lut7,
+        ]
+    }
+}
+
+/// Microstep table that cannot be encoded
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MicrostepTableConfigError {
+    /// The difference between the entry (index) and the previous one is not within -1..=3
+    DeltaOutOfRange(u8),
+    /// The differences need more than 4 segments
+    TooManySegments,
+}
+
+/// Microstep table error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MicrostepTableError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// The table cannot be encoded, nothing was written
+    Invalid(MicrostepTableConfigError),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for MicrostepTableError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        MicrostepTableError::SpiError(e)
+    }
+}
+
+impl Default for MicrostepTable {
+    /// Table of the chip after a reset
+    fn default() -> Self {
+        Self::from_registers(&MicrostepTableRegisters::of(&Config::reset_defaults()))
+    }
+}
+
+impl MicrostepTable {
+    /// Sine wave of amplitude 248, the table of the chip after a reset
+    pub fn sine() -> Self {
+        Self::with_amplitude(248)
+    }
+
+    /// Sine wave of amplitude `amplitude`, START_SIN90 being one below it as in the reset table
+    ///
+    /// Entries are sampled in the middle of their microstep.
+    pub fn with_amplitude(amplitude: u8) -> Self {
+        let mut entries = [0; TABLE_ENTRIES];
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let angle = core::f64::consts::FRAC_PI_2 * (i as f64 + 0.5) / TABLE_ENTRIES as f64;
+            *entry = (amplitude as f64 * sin(angle) + 0.5) as u8;
+        }
+        Self {
+            start_sin: 0,
+            start_sin90: amplitude.saturating_sub(1),
+            entries,
+        }
+    }
+
+    /// Trapezoidal wave rising linearly to `amplitude` over `ramp` entries, then flat
+    ///
+    /// The ramp is lengthened to at least `amplitude / 3` entries, the steepest encodable slope.
+    pub fn trapezoidal(amplitude: u8, ramp: u8) -> Self {
+        let amplitude = amplitude as u32;
+        let ramp = (ramp as u32).max(amplitude.div_ceil(3)).max(1);
+        let mut entries = [0; TABLE_ENTRIES];
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let rising = (2 * amplitude * (i as u32 + 1) + ramp) / (2 * ramp);
+            *entry = rising.min(amplitude) as u8;
+        }
+        Self {
+            start_sin: 0,
+            start_sin90: amplitude as u8,
+            entries,
+        }
+    }
+
+    /// Decodes the microstep table registers
+    pub fn from_registers(registers: &MicrostepTableRegisters) -> Self {
+        let ms_lut = registers.ms_lut();
+        let sel = registers.ms_lut_sel;
+        let widths = [sel.w0, sel.w1, sel.w2, sel.w3];
+        let mut entries = [0; TABLE_ENTRIES];
+        let mut current = registers.ms_lut_start.start_sin as i32;
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let segment = [sel.x1, sel.x2, sel.x3]
+                .iter()
+                .filter(|&&x| i >= x as usize)
+                .count();
+            let bit = (ms_lut[i / 32] >> (i % 32)) & 1;
+            current += widths[segment] as i32 - 1 + bit as i32;
+            *entry = current as u8;
+        }
+        Self {
+            start_sin: registers.ms_lut_start.start_sin,
+            start_sin90: registers.ms_lut_start.start_sin90,
+            entries,
+        }
+    }
+
+    /// Encodes the table into the microstep table registers
+    pub fn to_registers(&self) -> Result<MicrostepTableRegisters, MicrostepTableConfigError> {
+        let mut deltas = [0i8; TABLE_ENTRIES];
+        let mut previous = self.start_sin as i16;
+        for (i, (delta, &entry)) in deltas.iter_mut().zip(&self.entries).enumerate() {
+            let d = entry as i16 - previous;
+            if !(-1..=3).contains(&d) {
+                return Err(MicrostepTableConfigError::DeltaOutOfRange(i as u8));
+            }
+            *delta = d as i8;
+            previous = entry as i16;
+        }
+        // greedy segmentation: each segment as long as its differences span at most 1
+        let mut starts = [TABLE_ENTRIES; 4];
+        let mut widths = [0u8; 4];
+        let mut segment = 0;
+        let (mut min, mut max) = (deltas[0], deltas[0]);
+        starts[0] = 0;
+        for (i, &d) in deltas.iter().enumerate().skip(1) {
+            if d.max(max) - d.min(min) > 1 {
+                widths[segment] = width(min, max);
+                segment += 1;
+                if segment == 4 {
+                    return Err(MicrostepTableConfigError::TooManySegments);
+                }
+                starts[segment] = i;
+                (min, max) = (d, d);
+            } else {
+                (min, max) = (d.min(min), d.max(max));
+            }
+        }
+        widths[segment] = width(min, max);
+        // unused segments: empty up to the last one, covering entry 255 with the width of the last segment
+        for unused in segment + 1..4 {
+            starts[unused] = TABLE_ENTRIES - 1;
+            widths[unused] = widths[segment];
+        }
+        let mut ms_lut = [0u32; 8];
+        for (i, &d) in deltas.iter().enumerate() {
+            let segment = starts.iter().rposition(|&start| i >= start).unwrap_or(0);
+            if d as i16 == widths[segment] as i16 {
+                ms_lut[i / 32] |= 1 << (i % 32);
+            }
+        }
+        Ok(MicrostepTableRegisters {
+            ms_lut0: MsLut0::from(ms_lut[0]),
+            ms_lut1: MsLut1::from(ms_lut[1]),
+            ms_lut2: MsLut2::from(ms_lut[2]),
+            ms_lut3: MsLut3::from(ms_lut[3]),
+            ms_lut4: MsLut4::from(ms_lut[4]),
+            ms_lut5: MsLut5::from(ms_lut[5]),
+            ms_lut6: MsLut6::from(ms_lut[6]),
+            ms_lut7: MsLut7::from(ms_lut[7]),
+            ms_lut_sel: MsLutSel {
+                w0: widths[0],
+                w1: widths[1],
+                w2: widths[2],
+                w3: widths[3],
+                x1: starts[1] as u8,
+                x2: starts[2] as u8,
+                x3: starts[3] as u8,
+            },
+            ms_lut_start: MsLutStart {
+                start_sin: self.start_sin,
+                start_sin90: self.start_sin90,
+            },
+        })
+    }
+}
+
+/// Sine of `x` within 0..=π/2, by its Taylor series (error below 1e-6)
+fn sin(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..7 {
+        term = -term * x2 / ((2 * n) * (2 * n + 1)) as f64;
+        sum += term;
+    }
+    sum
+}
+
+/// Width W of a segment whose differences are within `min..=max`, a difference W-1 being encoded by a 0 bit
+fn width(min: i8, max: i8) -> u8 {
+    if max > min || max == 3 {
+        max as u8
+    } else {
+        (max + 1) as u8
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Encodes and writes the microstep table registers
+    pub fn write_microstep_table<SPI: Transfer<u8>>(
+        &mut self,
+        table: &MicrostepTable,
+        spi: &mut SPI,
+    ) -> Result<(), MicrostepTableError<SPI::Error, CS::Error>> {
+        let registers = table.to_registers().map_err(MicrostepTableError::Invalid)?;
+        self.write_register(registers.ms_lut0, spi)?;
+        self.write_register(registers.ms_lut1, spi)?;
+        self.write_register(registers.ms_lut2, spi)?;
+        self.write_register(registers.ms_lut3, spi)?;
+        self.write_register(registers.ms_lut4, spi)?;
+        self.write_register(registers.ms_lut5, spi)?;
+        self.write_register(registers.ms_lut6, spi)?;
+        self.write_register(registers.ms_lut7, spi)?;
+        self.write_register(registers.ms_lut_sel, spi)?;
+        self.write_register(registers.ms_lut_start, spi)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::Register;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn reset_table() {
+        let table = MicrostepTable::default();
+        assert_eq!(&table.entries[..9], &[1, 2, 4, 5, 7, 8, 10, 11, 13]);
+        assert_eq!(table.entries[255], 248);
+        assert_eq!(table.start_sin90, 247);
+        let registers = MicrostepTableRegisters::of(&Config::reset_defaults());
+        assert_eq!(MicrostepTable::from_registers(&registers), table);
+        // the segmentation may differ from the reset one, not the entries
+        assert_eq!(
+            MicrostepTable::from_registers(&table.to_registers().unwrap()),
+            table
+        );
+    }
+
+    #[test]
+    fn presets() {
+        assert_eq!(MicrostepTable::sine(), MicrostepTable::default());
+        let reduced = MicrostepTable::with_amplitude(200);
+        assert_eq!(reduced.entries[255], 200);
+        assert_eq!(reduced.start_sin90, 199);
+        let trapezoidal = MicrostepTable::trapezoidal(240, 100);
+        assert_eq!(&trapezoidal.entries[..3], &[2, 5, 7]);
+        assert_eq!(trapezoidal.entries[99], 240);
+        assert_eq!(trapezoidal.entries[200], 240);
+        // steepest slope
+        assert_eq!(MicrostepTable::trapezoidal(240, 0).entries[79], 240);
+        for amplitude in [1, 64, 128, 200, 255] {
+            for table in [
+                MicrostepTable::with_amplitude(amplitude),
+                MicrostepTable::trapezoidal(amplitude, 0),
+                MicrostepTable::trapezoidal(amplitude, 255),
+            ] {
+                let registers = table.to_registers().unwrap();
+                assert_eq!(MicrostepTable::from_registers(&registers), table);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let mut table = MicrostepTable::default();
+        table.entries[10] = table.entries[9] + 4;
+        assert_eq!(
+            table.to_registers(),
+            Err(MicrostepTableConfigError::DeltaOutOfRange(10))
+        );
+        let mut table = MicrostepTable::default();
+        let mut value = 0;
+        for (i, entry) in table.entries.iter_mut().enumerate() {
+            // differences of 0 and 2 alternating on 5 segments
+            value += [0, 2, 0, 2, 0][i / 52];
+            *entry = value;
+        }
+        table.start_sin = 0;
+        assert_eq!(
+            table.to_registers(),
+            Err(MicrostepTableConfigError::TooManySegments)
+        );
+    }
+
+    #[test]
+    fn write() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut table = MicrostepTable::default();
+        table.entries[255] -= 1;
+        table.start_sin90 = 240;
+        tmc5072.write_microstep_table(&table, &mut sim).unwrap();
+        let registers = MicrostepTableRegisters::of(tmc5072.config());
+        assert_eq!(MicrostepTable::from_registers(&registers), table);
+        assert_eq!(sim.get(MsLutSel::addr()), u32::from(registers.ms_lut_sel));
+    }
+}
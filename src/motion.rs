@@ -0,0 +1,162 @@
+//! Positioning helpers
+//!
+//! The register sequences of the datasheet for the usual moves:
+//! - positioning: RAMPMODE=0, VMAX, then XTARGET starts the move
+//! - velocity: VMAX, then RAMPMODE=1 (positive) or 2 (negative)
+//! - stop: VMAX=0 in velocity mode, the motor decelerates using AMAX
+//!
+//! The other ramp parameters (VSTART, A1, V1, AMAX, DMAX, D1, VSTOP) are left as configured.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    ramp_generator_driver_feature_control_register::RampStat,
+    ramp_generator_register::{RampMode, RampModeValue, VMax, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Moves motor `M` to `x_target` in positioning mode, with a maximum velocity of `v_max`
+    pub fn move_to<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        x_target: i32,
+        v_max: u32,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+        XTarget<M>: Register,
+    {
+        self.write_register(
+            RampMode::<M> {
+                ramp_mode: RampModeValue::Positioning,
+            },
+            spi,
+        )?;
+        self.write_register(VMax::<M> { v_max }, spi)?;
+        self.write_register(XTarget::<M> { x_target }, spi)?;
+        Ok(())
+    }
+
+    /// Moves motor `M` by `offset` from its actual position, returns the new target
+    pub fn move_by<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        offset: i32,
+        v_max: u32,
+        spi: &mut SPI,
+    ) -> Result<i32, SpiError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+        XActual<M>: Register,
+        XTarget<M>: Register,
+    {
+        let x_actual = self.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+        // XACTUAL wraps around, so do targets
+        let x_target = x_actual.wrapping_add(offset);
+        self.move_to::<M, _>(x_target, v_max, spi)?;
+        Ok(x_target)
+    }
+
+    /// Rotates motor `M` at `velocity` (signed) in velocity mode
+    pub fn rotate<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        velocity: i32,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+    {
+        self.write_register(
+            VMax::<M> {
+                v_max: velocity.unsigned_abs(),
+            },
+            spi,
+        )?;
+        let ramp_mode = if velocity < 0 {
+            RampModeValue::VelocityNeg
+        } else {
+            RampModeValue::VelocityPos
+        };
+        self.write_register(RampMode::<M> { ramp_mode }, spi)?;
+        Ok(())
+    }
+
+    /// Decelerates motor `M` to a stop
+    ///
+    /// The motor is left in velocity mode with VMAX=0.
+    pub fn stop<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+    {
+        // velocity mode with VMAX=0 decelerates using AMAX
+        self.write_register(VMax::<M> { v_max: 0 }, spi)?;
+        self.write_register(
+            RampMode::<M> {
+                ramp_mode: RampModeValue::VelocityPos,
+            },
+            spi,
+        )?;
+        Ok(())
+    }
+
+    /// Whether motor `M` reached its target position
+    ///
+    /// Reads RAMP_STAT, which clears its event flags.
+    pub fn is_position_reached<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<bool, SpiError<SPI::Error, CS::Error>>
+    where
+        RampStat<M>: Register,
+    {
+        Ok(self
+            .read_register::<RampStat<M>, _>(spi)?
+            .data
+            .position_reached)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator, Timing};
+
+    #[test]
+    fn positioning() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set_timing(Timing::Scaled(1));
+        tmc5072.move_to::<0, _>(500, 100_000, &mut sim).unwrap();
+        assert!(!tmc5072.is_position_reached::<0, _>(&mut sim).unwrap());
+        while !tmc5072.is_position_reached::<0, _>(&mut sim).unwrap() {
+            sim.advance(1000);
+        }
+        assert_eq!(sim.get(XActual::<0>::addr()), 500);
+        assert_eq!(
+            tmc5072.move_by::<0, _>(-700, 100_000, &mut sim).unwrap(),
+            -200
+        );
+        assert_eq!(sim.get(XTarget::<0>::addr()) as i32, -200);
+    }
+
+    #[test]
+    fn velocity() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072.rotate::<1, _>(-2000, &mut sim).unwrap();
+        assert_eq!(sim.get(RampMode::<1>::addr()), 2);
+        assert_eq!(sim.get(VMax::<1>::addr()), 2000);
+        tmc5072.stop::<1, _>(&mut sim).unwrap();
+        assert_eq!(sim.get(RampMode::<1>::addr()), 1);
+        assert_eq!(sim.get(VMax::<1>::addr()), 0);
+    }
+}
@@ -0,0 +1,230 @@
+//! Chip independent motion controller traits
+//!
+//! A minimal interface shared by Trinamic motion controllers (TMC5072, TMC5130, TMC5160...),
+//! so applications can drive them interchangeably. Motors are addressed by a runtime index.
+//!
+//! The bus (e.g. the SPI interface) is passed to every call, like for [`Tmc5072`] methods.
+//!
+//! Requires the `motion-traits` feature.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    motor_driver_register::DrvStatus,
+    ramp_generator_register::{VActual, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Raw register access
+pub trait RegisterInterface<BUS> {
+    /// Communication error
+    type Error;
+
+    /// Reads a register
+    fn read_register_raw(&mut self, bus: &mut BUS, addr: u8) -> Result<u32, Self::Error>;
+
+    /// Writes a register
+    fn write_register_raw(&mut self, bus: &mut BUS, addr: u8, data: u32)
+        -> Result<(), Self::Error>;
+}
+
+/// Motion status of a motor
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MotionStatus {
+    /// Actual position (microsteps)
+    pub position: i32,
+    /// Actual velocity (signed)
+    pub velocity: i32,
+    /// Target position is reached
+    pub position_reached: bool,
+    /// Velocity is zero
+    pub standstill: bool,
+    /// A stall is detected
+    pub stalled: bool,
+    /// The driver signals an error
+    pub driver_error: bool,
+}
+
+/// Ramp generator based motion control
+pub trait MotionController<BUS>: RegisterInterface<BUS> {
+    /// Number of motors driven by the chip
+    fn motor_count(&self) -> u8;
+
+    /// Moves `motor` to `position` in positioning mode, with a maximum velocity of `v_max`
+    fn move_to(
+        &mut self,
+        bus: &mut BUS,
+        motor: u8,
+        position: i32,
+        v_max: u32,
+    ) -> Result<(), Self::Error>;
+
+    /// Rotates `motor` at `velocity` (signed) in velocity mode
+    fn rotate(&mut self, bus: &mut BUS, motor: u8, velocity: i32) -> Result<(), Self::Error>;
+
+    /// Decelerates `motor` to a stop
+    fn stop(&mut self, bus: &mut BUS, motor: u8) -> Result<(), Self::Error>;
+
+    /// Motion status of `motor`, without clearing any latched event
+    fn status(&mut self, bus: &mut BUS, motor: u8) -> Result<MotionStatus, Self::Error>;
+}
+
+/// Motion controller error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MotionError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// There is no such motor
+    InvalidMotor(u8),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for MotionError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        MotionError::SpiError(e)
+    }
+}
+
+impl<CS: OutputPin, SPI: Transfer<u8>> RegisterInterface<SPI> for Tmc5072<CS> {
+    type Error = MotionError<SPI::Error, CS::Error>;
+
+    fn read_register_raw(&mut self, bus: &mut SPI, addr: u8) -> Result<u32, Self::Error> {
+        Ok(self.read_raw(addr, bus)?.data)
+    }
+
+    fn write_register_raw(
+        &mut self,
+        bus: &mut SPI,
+        addr: u8,
+        data: u32,
+    ) -> Result<(), Self::Error> {
+        self.write_raw(addr, data, bus)?;
+        Ok(())
+    }
+}
+
+impl<CS: OutputPin, SPI: Transfer<u8>> MotionController<SPI> for Tmc5072<CS> {
+    fn motor_count(&self) -> u8 {
+        2
+    }
+
+    fn move_to(
+        &mut self,
+        bus: &mut SPI,
+        motor: u8,
+        position: i32,
+        v_max: u32,
+    ) -> Result<(), Self::Error> {
+        match motor {
+            0 => Ok(Tmc5072::move_to::<0, _>(self, position, v_max, bus)?),
+            1 => Ok(Tmc5072::move_to::<1, _>(self, position, v_max, bus)?),
+            m => Err(MotionError::InvalidMotor(m)),
+        }
+    }
+
+    fn rotate(&mut self, bus: &mut SPI, motor: u8, velocity: i32) -> Result<(), Self::Error> {
+        match motor {
+            0 => Ok(Tmc5072::rotate::<0, _>(self, velocity, bus)?),
+            1 => Ok(Tmc5072::rotate::<1, _>(self, velocity, bus)?),
+            m => Err(MotionError::InvalidMotor(m)),
+        }
+    }
+
+    fn stop(&mut self, bus: &mut SPI, motor: u8) -> Result<(), Self::Error> {
+        match motor {
+            0 => Ok(Tmc5072::stop::<0, _>(self, bus)?),
+            1 => Ok(Tmc5072::stop::<1, _>(self, bus)?),
+            m => Err(MotionError::InvalidMotor(m)),
+        }
+    }
+
+    fn status(&mut self, bus: &mut SPI, motor: u8) -> Result<MotionStatus, Self::Error> {
+        match motor {
+            0 => status::<0, _, _>(self, bus),
+            1 => status::<1, _, _>(self, bus),
+            m => Err(MotionError::InvalidMotor(m)),
+        }
+    }
+}
+
+fn status<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+    tmc5072: &mut Tmc5072<CS>,
+    spi: &mut SPI,
+) -> Result<MotionStatus, MotionError<SPI::Error, CS::Error>>
+where
+    XActual<M>: Register,
+    XTarget<M>: Register,
+    VActual<M>: Register,
+    DrvStatus<M>: Register,
+{
+    // RAMP_STAT is not read, polling the status must not clear the ramp events
+    let result = tmc5072.read_many(
+        &[
+            XActual::<M>::addr(),
+            XTarget::<M>::addr(),
+            VActual::<M>::addr(),
+            DrvStatus::<M>::addr(),
+        ],
+        spi,
+    )?;
+    let [x_actual, x_target, v_actual, drv_status] = result.data;
+    let position = XActual::<M>::from(x_actual).x_actual;
+    let velocity = VActual::<M>::from(v_actual).v_actual;
+    let driver_error = if M == 0 {
+        result.status.driver_error1
+    } else {
+        result.status.driver_error2
+    };
+    Ok(MotionStatus {
+        position,
+        velocity,
+        position_reached: position == XTarget::<M>::from(x_target).x_target,
+        standstill: velocity == 0,
+        stalled: DrvStatus::<M>::from(drv_status).stall_guard,
+        driver_error,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::ramp_generator_driver_feature_control_register::RampStat;
+    use crate::registers::ramp_generator_register::{RampMode, VMax};
+    use crate::sim::{CsPin, Simulator};
+
+    fn run<C: MotionController<Simulator>>(controller: &mut C, sim: &mut Simulator) {
+        controller.move_to(sim, 1, 500, 1000).ok().unwrap();
+        let status = controller.status(sim, 1).ok().unwrap();
+        assert_eq!(status.position, 500);
+        assert!(status.position_reached);
+        assert!(status.standstill);
+        assert!(controller
+            .rotate(sim, controller.motor_count(), 10)
+            .is_err());
+    }
+
+    #[test]
+    fn motion_controller() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        run(&mut tmc5072, &mut sim);
+        // the status query leaves the ramp events to their owner
+        assert!(RampStat::<1>::from(sim.get(RampStat::<1>::addr())).event_pos_reached);
+        MotionController::rotate(&mut tmc5072, &mut sim, 0, -2000).unwrap();
+        assert_eq!(sim.get(RampMode::<0>::addr()), 2);
+        assert_eq!(sim.get(VMax::<0>::addr()), 2000);
+        MotionController::stop(&mut tmc5072, &mut sim, 0).unwrap();
+        assert_eq!(sim.get(RampMode::<0>::addr()), 1);
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+        assert_eq!(
+            MotionController::stop(&mut tmc5072, &mut sim, 2),
+            Err(MotionError::InvalidMotor(2))
+        );
+    }
+}
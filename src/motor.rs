@@ -0,0 +1,237 @@
+//! Motor scoped register access
+//!
+//! [`Motor`] borrows the driver and the SPI bus for motor `M`: per-axis registers are inferred from the handle,
+//! so the motor index is written once and registers of the other motor (or global registers) are rejected at compile time.
+//!
+//! ```
+//! # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+//! # use tmc5072::registers::ramp_generator_register::{VMax, XActual};
+//! # use tmc5072::Tmc5072;
+//! # fn example<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI) {
+//! let mut motor = tmc5072.motor::<1, _>(spi);
+//! motor.write(VMax { v_max: 1000 }).ok();
+//! let x_actual: Option<XActual<1>> = motor.read().ok().map(|r| r.data);
+//! # }
+//! ```
+//!
+//! When the motor index is only known at runtime, e.g. looping over both axes, [`Tmc5072::read_register_for`] and
+//! [`Tmc5072::write_register_for`] take a [`MotorId`] and a register type of motor 0: both motors share their
+//! register layouts, the address is moved to the bank of the selected motor.
+//!
+//! ```
+//! # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+//! # use tmc5072::registers::ramp_generator_register::XActual;
+//! # use tmc5072::{motor::MotorId, Tmc5072};
+//! # fn example<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI) {
+//! for motor in MotorId::ALL {
+//!     let x_actual = tmc5072.read_register_for::<XActual<0>, _>(motor, spi).ok();
+//! #   let _ = x_actual;
+//! }
+//! # }
+//! ```
+//!
+//! ```compile_fail
+//! # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+//! # use tmc5072::registers::ramp_generator_register::VMax;
+//! # use tmc5072::Tmc5072;
+//! # fn example<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI) {
+//! tmc5072.motor::<0, _>(spi).write(VMax::<1> { v_max: 1000 }).ok();
+//! # }
+//! ```
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{MotorRegister, ReadableRegister, WritableRegister};
+use crate::spi::SpiResult;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Motor selected at runtime
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MotorId {
+    /// Motor 0 (motor 1 of the datasheet)
+    Motor0,
+    /// Motor 1 (motor 2 of the datasheet)
+    Motor1,
+}
+
+impl MotorId {
+    /// Both motors
+    pub const ALL: [MotorId; 2] = [MotorId::Motor0, MotorId::Motor1];
+
+    /// Index of the motor
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// Motor of index `index`, `None` above 1
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(MotorId::Motor0),
+            1 => Some(MotorId::Motor1),
+            _ => None,
+        }
+    }
+
+    /// Address of the motor 0 register `R` in the bank of this motor
+    pub fn addr_of<R>(self) -> u8
+    where
+        R: MotorRegister<0>,
+        u32: From<R>,
+    {
+        let addr = R::addr();
+        match self {
+            MotorId::Motor0 => addr,
+            // PWM registers, then ramp generator and encoder, then driver
+            MotorId::Motor1 if addr < 0x20 => addr + 0x08,
+            MotorId::Motor1 if addr < 0x60 => addr + 0x20,
+            MotorId::Motor1 => addr + 0x10,
+        }
+    }
+}
+
+/// Register access to motor `M`
+pub struct Motor<'a, CS, SPI, const M: u8> {
+    tmc5072: &'a mut Tmc5072<CS>,
+    spi: &'a mut SPI,
+}
+
+impl<'a, CS: OutputPin, SPI: Transfer<u8>, const M: u8> Motor<'a, CS, SPI, M> {
+    /// Index of the motor
+    pub fn index(&self) -> u8 {
+        M
+    }
+    /// Read a register of motor `M`
+    pub fn read<R>(&mut self) -> SpiResult<R, SPI::Error, CS::Error>
+    where
+        R: MotorRegister<M> + ReadableRegister,
+        u32: From<R>,
+    {
+        self.tmc5072.read_register::<R, _>(self.spi)
+    }
+    /// Write a register of motor `M`
+    pub fn write<R>(&mut self, r: R) -> SpiResult<(), SPI::Error, CS::Error>
+    where
+        R: MotorRegister<M> + WritableRegister,
+        u32: From<R>,
+    {
+        self.tmc5072.write_register(r, self.spi)
+    }
+    /// Driver and bus, for the APIs taking them separately
+    pub fn parts(&mut self) -> (&mut Tmc5072<CS>, &mut SPI) {
+        (self.tmc5072, self.spi)
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Read the register `R` (given for motor 0) of `motor`
+    pub fn read_register_for<R, SPI: Transfer<u8>>(
+        &mut self,
+        motor: MotorId,
+        spi: &mut SPI,
+    ) -> SpiResult<R, SPI::Error, CS::Error>
+    where
+        R: MotorRegister<0> + ReadableRegister,
+        u32: From<R>,
+    {
+        self.read_raw(motor.addr_of::<R>(), spi)
+            .map(|x| x.map(|x| R::from(x)))
+    }
+    /// Write the register `r` (given for motor 0) to `motor`
+    pub fn write_register_for<R, SPI: Transfer<u8>>(
+        &mut self,
+        motor: MotorId,
+        r: R,
+        spi: &mut SPI,
+    ) -> SpiResult<(), SPI::Error, CS::Error>
+    where
+        R: MotorRegister<0> + WritableRegister,
+        u32: From<R>,
+    {
+        self.write_raw(motor.addr_of::<R>(), u32::from(r), spi)
+    }
+    /// Register access to motor `M` through `spi`
+    pub fn motor<'a, const M: u8, SPI: Transfer<u8>>(
+        &'a mut self,
+        spi: &'a mut SPI,
+    ) -> Motor<'a, CS, SPI, M> {
+        Motor { tmc5072: self, spi }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        encoder_registers::EncLatch,
+        motor_driver_register::DrvStatus,
+        ramp_generator_register::{RampMode, RampModeValue, VMax, XActual, XTarget},
+        voltage_pwm_mode_stealth_chop::PwmConf,
+        Register,
+    };
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn motor() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        assert_eq!(motor.index(), 1);
+        motor
+            .write(RampMode {
+                ramp_mode: RampModeValue::Positioning,
+            })
+            .unwrap();
+        motor.write(VMax { v_max: 5000 }).unwrap();
+        motor.write(XTarget { x_target: -300 }).unwrap();
+        let x_actual: XActual<1> = motor.read().unwrap().data;
+        assert_eq!(x_actual.x_actual, -300);
+        assert_eq!(sim.get(VMax::<1>::addr()), 5000);
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+    }
+
+    #[test]
+    fn runtime_index() {
+        assert_eq!(
+            MotorId::Motor1.addr_of::<XActual<0>>(),
+            XActual::<1>::addr()
+        );
+        assert_eq!(
+            MotorId::Motor1.addr_of::<PwmConf<0>>(),
+            PwmConf::<1>::addr()
+        );
+        assert_eq!(
+            MotorId::Motor1.addr_of::<EncLatch<0>>(),
+            EncLatch::<1>::addr()
+        );
+        assert_eq!(
+            MotorId::Motor1.addr_of::<DrvStatus<0>>(),
+            DrvStatus::<1>::addr()
+        );
+        assert_eq!(
+            MotorId::Motor0.addr_of::<DrvStatus<0>>(),
+            DrvStatus::<0>::addr()
+        );
+        assert_eq!(MotorId::from_index(1), Some(MotorId::Motor1));
+        assert_eq!(MotorId::from_index(2), None);
+
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        for motor in MotorId::ALL {
+            let x_actual = XActual::<0> {
+                x_actual: 100 + motor.index() as i32,
+            };
+            tmc5072
+                .write_register_for(motor, x_actual, &mut sim)
+                .unwrap();
+        }
+        assert_eq!(sim.get(XActual::<0>::addr()), 100);
+        assert_eq!(sim.get(XActual::<1>::addr()), 101);
+        let x_actual = tmc5072
+            .read_register_for::<XActual<0>, _>(MotorId::Motor1, &mut sim)
+            .unwrap();
+        assert_eq!(x_actual.x_actual, 101);
+    }
+}
@@ -0,0 +1,267 @@
+//! Move planning
+//!
+//! [`RampProfile::plan`] predicts the velocity profile of a positioning move from standstill without touching the
+//! chip: the sixPoint ramp starts at VSTART, accelerates with A1 up to V1 and with AMAX up to VMAX, then decelerates
+//! with DMAX down to V1 and with D1 down to VSTOP before stopping. On short moves, VMAX is not reached and the peak
+//! velocity is the highest one leaving room for the deceleration. V1 = 0 disables the A1 and D1 phases.
+//!
+//! [`Tmc5072::plan_move`] plans with the ramp parameters of the shadow configuration ([`Tmc5072::config`]), letting
+//! applications check cycle times before executing a move. Durations are in clock cycles, the TZEROWAIT pause after
+//! the stop is not included.
+//!
+//! ```
+//! # use tmc5072::ramp::RampProfile;
+//! # use tmc5072::units::F_CLK_INTERNAL;
+//! let plan = RampProfile::trapezoidal(100_000, 1000).plan(200_000).unwrap();
+//! assert_eq!(plan.peak_velocity, 100_000);
+//! assert!(plan.duration_secs(F_CLK_INTERNAL) > 2.0);
+//! ```
+
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::ramp::{RampProfile, RampProfileError};
+use crate::registers::{ramp_generator_register::VMax, Register};
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Largest number of segments of a move
+pub const MAX_SEGMENTS: usize = 5;
+
+/// Phase of a sixPoint ramp
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RampPhase {
+    /// Acceleration with A1, from VSTART to V1
+    #[default]
+    A1,
+    /// Acceleration with AMAX, from V1 to the peak velocity
+    AMax,
+    /// Constant peak velocity
+    Cruise,
+    /// Deceleration with DMAX, from the peak velocity to V1
+    DMax,
+    /// Deceleration with D1, from V1 to VSTOP
+    D1,
+}
+
+/// Part of a move with a constant acceleration
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RampSegment {
+    /// Ramp phase
+    pub phase: RampPhase,
+    /// Velocity at the start of the segment
+    pub v_start: u32,
+    /// Velocity at the end of the segment
+    pub v_end: u32,
+    /// Traveled microsteps
+    pub distance: u32,
+    /// Duration (clock cycles)
+    pub duration: u64,
+}
+
+/// Predicted profile of a move
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MovePlan {
+    segments: [RampSegment; MAX_SEGMENTS],
+    len: usize,
+    /// Highest velocity of the move
+    pub peak_velocity: u32,
+    /// Traveled microsteps
+    pub distance: u32,
+    /// Duration until the stop (clock cycles)
+    pub duration: u64,
+}
+
+impl MovePlan {
+    /// Segments of the move, in order
+    pub fn segments(&self) -> &[RampSegment] {
+        &self.segments[..self.len]
+    }
+
+    /// Duration until the stop in seconds, for a clock frequency `f_clk` (Hz)
+    pub fn duration_secs(&self, f_clk: u32) -> f32 {
+        self.duration as f32 / f_clk as f32
+    }
+
+    fn push(&mut self, segment: RampSegment) {
+        if segment.distance != 0 || segment.duration != 0 {
+            self.segments[self.len] = segment;
+            self.len += 1;
+            self.distance += segment.distance;
+            self.duration += segment.duration;
+        }
+    }
+}
+
+/// Segment changing the velocity from `v_start` to `v_end` with acceleration `a`
+///
+/// The velocity changes by `a` every 2^17 clock cycles, and a velocity counts microsteps per 2^24 clock cycles.
+fn ramp(phase: RampPhase, v_start: u32, v_end: u32, a: u16) -> RampSegment {
+    let (low, high) = (v_start.min(v_end) as u64, v_start.max(v_end) as u64);
+    if low == high {
+        return RampSegment {
+            phase,
+            v_start,
+            v_end,
+            ..Default::default()
+        };
+    }
+    let a = a as u64;
+    RampSegment {
+        phase,
+        v_start,
+        v_end,
+        distance: ((high * high - low * low) / (a << 8)) as u32,
+        duration: ((high - low) << 17) / a,
+    }
+}
+
+impl RampProfile {
+    /// Acceleration and deceleration segments of a move peaking at `peak`
+    fn ramps(&self, peak: u32) -> [RampSegment; 4] {
+        let start = self.v_start.min(peak);
+        let stop = self.v_stop.min(peak);
+        let (accel_knee, decel_knee) = if self.v1 == 0 {
+            (start, stop)
+        } else {
+            (self.v1.clamp(start, peak), self.v1.clamp(stop, peak))
+        };
+        [
+            ramp(RampPhase::A1, start, accel_knee, self.a1),
+            ramp(RampPhase::AMax, accel_knee, peak, self.a_max),
+            ramp(RampPhase::DMax, peak, decel_knee, self.d_max),
+            ramp(RampPhase::D1, decel_knee, stop, self.d1),
+        ]
+    }
+
+    /// Microsteps traveled while accelerating to `peak` and decelerating from it
+    fn ramps_distance(&self, peak: u32) -> u64 {
+        self.ramps(peak).iter().map(|s| s.distance as u64).sum()
+    }
+
+    /// Predicts the profile of a move of `distance` microsteps from standstill
+    ///
+    /// A VMAX of 0 never reaches the target, its plan has no segment.
+    pub fn plan(&self, distance: u32) -> Result<MovePlan, RampProfileError> {
+        self.validate()?;
+        if self.a1 == 0 && self.v1 > self.v_start {
+            return Err(RampProfileError::AccelerationZero);
+        }
+        let mut plan = MovePlan {
+            segments: Default::default(),
+            len: 0,
+            peak_velocity: 0,
+            distance: 0,
+            duration: 0,
+        };
+        if distance == 0 || self.v_max == 0 {
+            return Ok(plan);
+        }
+        let lowest = self.v_start.max(self.v_stop).min(self.v_max);
+        let peak = if self.ramps_distance(self.v_max) <= distance as u64 {
+            self.v_max
+        } else {
+            // highest peak whose ramps fit, the distance growing with the peak
+            let (mut low, mut high) = (lowest, self.v_max);
+            while low < high {
+                let mid = low + (high - low).div_ceil(2);
+                if self.ramps_distance(mid) <= distance as u64 {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+            low
+        };
+        let [a1, a_max, d_max, d1] = self.ramps(peak);
+        let cruise = (distance as u64).saturating_sub(self.ramps_distance(peak));
+        plan.peak_velocity = peak;
+        plan.push(a1);
+        plan.push(a_max);
+        plan.push(RampSegment {
+            phase: RampPhase::Cruise,
+            v_start: peak,
+            v_end: peak,
+            distance: cruise as u32,
+            duration: (cruise << 24) / peak as u64,
+        });
+        plan.push(d_max);
+        plan.push(d1);
+        Ok(plan)
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Predicts the profile of a move of motor `M` from `x_actual` to `x_target`, with the shadow ramp parameters
+    pub fn plan_move<const M: u8>(
+        &self,
+        x_actual: i32,
+        x_target: i32,
+    ) -> Result<MovePlan, RampProfileError>
+    where
+        VMax<M>: Register,
+    {
+        let profile = match M {
+            0 => RampProfile::of(&self.shadow.motor0),
+            _ => RampProfile::of(&self.shadow.motor1),
+        };
+        profile.plan(x_target.wrapping_sub(x_actual).unsigned_abs())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn long_move() {
+        let plan = RampProfile::trapezoidal(100_000, 1000)
+            .plan(200_000)
+            .unwrap();
+        assert_eq!(plan.peak_velocity, 100_000);
+        assert_eq!(plan.distance, 200_000);
+        let phases = plan.segments().iter().map(|s| s.phase);
+        assert!(phases.eq([RampPhase::AMax, RampPhase::Cruise, RampPhase::DMax]));
+        // 100000 * 2^17 / 1000 clock cycles
+        assert_eq!(plan.segments()[0].duration, 13_107_200);
+        assert_eq!(plan.segments()[0].distance, 39_062);
+    }
+
+    #[test]
+    fn short_move() {
+        let profile = RampProfile {
+            v1: 20_000,
+            a1: 2000,
+            ..RampProfile::trapezoidal(100_000, 1000)
+        };
+        let plan = profile.plan(20_000).unwrap();
+        assert!(plan.peak_velocity < 100_000);
+        assert!(plan.distance <= 20_000);
+        let phases = plan.segments().iter().map(|s| s.phase);
+        assert!(phases.eq([
+            RampPhase::A1,
+            RampPhase::AMax,
+            RampPhase::Cruise,
+            RampPhase::DMax,
+            RampPhase::D1
+        ]));
+        assert!(plan.segments()[2].distance < 100);
+    }
+
+    #[test]
+    fn plan_move() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        assert_eq!(
+            tmc5072.plan_move::<1>(0, 100),
+            Err(RampProfileError::VStopZero)
+        );
+        let profile = RampProfile::trapezoidal(50_000, 500);
+        tmc5072.apply_ramp::<1, _>(&profile, &mut sim).unwrap();
+        assert_eq!(tmc5072.plan_move::<1>(1000, -99_000), profile.plan(100_000));
+    }
+}
@@ -0,0 +1,233 @@
+//! Position helpers
+//!
+//! XACTUAL gives the position in microsteps, MSCNT gives the electrical phase of the motor:
+//! 1024 MSCNT counts per electrical period, i.e. 256 per full step.
+//! Full step positions (45° of the electrical period, both coil currents equal) are at MSCNT 128, 384, 640 and 896.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    general_configuration_register::GConf,
+    motor_driver_register::{ChopConf, MsCnt, MsCurAct},
+    ramp_generator_register::{RampMode, RampModeValue, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// MSCNT counts per full step
+pub const MSCNT_PER_FULL_STEP: u16 = 256;
+
+/// Full step alignment error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlignError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// The MSCNT distance to the full step is not a whole number of microsteps at the actual MRES,
+    /// nothing was written
+    Unreachable,
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for AlignError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        AlignError::SpiError(e)
+    }
+}
+
+/// Position combined with the phase of the motor
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FinePosition {
+    /// XACTUAL (microsteps)
+    pub x_actual: i32,
+    /// MSCNT, position in the microstep table (0..1023)
+    pub ms_cnt: u16,
+    /// Actual microstep current of coil A
+    pub cur_a: i16,
+    /// Actual microstep current of coil B
+    pub cur_b: i16,
+}
+
+impl FinePosition {
+    /// Full step (0..3) of the electrical period the motor is in
+    pub fn full_step(&self) -> u8 {
+        (self.ms_cnt / MSCNT_PER_FULL_STEP) as u8
+    }
+
+    /// Signed MSCNT distance from the nearest full step position (-128..127)
+    pub fn full_step_error(&self) -> i16 {
+        let half = (MSCNT_PER_FULL_STEP / 2) as i16;
+        let in_step = (self.ms_cnt % MSCNT_PER_FULL_STEP) as i16;
+        // full step positions are at the middle of each quarter wave
+        let error = in_step - half;
+        if error >= half {
+            error - MSCNT_PER_FULL_STEP as i16
+        } else {
+            error
+        }
+    }
+
+    /// Whether the motor sits on a full step position
+    pub fn is_full_step_aligned(&self) -> bool {
+        self.full_step_error() == 0
+    }
+
+    /// Whether the motor sits on MSCNT 0, required before reprogramming MSLUT, MSLUTSEL or MSLUTSTART
+    pub fn is_table_start(&self) -> bool {
+        self.ms_cnt == 0
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Reads XACTUAL, MSCNT and MSCURACT of motor `M`
+    pub fn fine_position<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<FinePosition, SpiError<SPI::Error, CS::Error>>
+    where
+        XActual<M>: Register,
+        MsCnt<M>: Register,
+        MsCurAct<M>: Register,
+    {
+        let x_actual = self.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+        let ms_cnt = self.read_register::<MsCnt<M>, _>(spi)?.data.ms_cnt;
+        let ms_cur_act = self.read_register::<MsCurAct<M>, _>(spi)?.data;
+        Ok(FinePosition {
+            x_actual,
+            ms_cnt,
+            cur_a: ms_cur_act.cur_a,
+            cur_b: ms_cur_act.cur_b,
+        })
+    }
+
+    /// Moves motor `M` by the minimal distance bringing MSCNT on a full step position
+    ///
+    /// Required before enabling vhighfs, changing MRES or reprogramming the microstep table.
+    /// The motor must be at standstill, it is switched to positioning mode.
+    /// Returns the new XTARGET, or `None` when the motor is already aligned and nothing was written;
+    /// wait for position_reached before going on.
+    /// Fails with [`AlignError::Unreachable`] when MRES was changed off a full step position and no whole number
+    /// of microsteps leads back to one.
+    pub fn align_to_fullstep<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Option<i32>, AlignError<SPI::Error, CS::Error>>
+    where
+        XActual<M>: Register,
+        MsCnt<M>: Register,
+        MsCurAct<M>: Register,
+        ChopConf<M>: Register,
+        RampMode<M>: Register,
+        XTarget<M>: Register,
+    {
+        let position = self.fine_position::<M, _>(spi)?;
+        let error = position.full_step_error();
+        if error == 0 {
+            return Ok(None);
+        }
+        let mres = self.read_register::<ChopConf<M>, _>(spi)?.data.mres.mres();
+        if error % (1 << mres) != 0 {
+            return Err(AlignError::Unreachable);
+        }
+        let g_conf = self.read_register::<GConf, _>(spi)?.data;
+        let shaft = if M == 0 { g_conf.shaft1 } else { g_conf.shaft2 };
+        // one microstep moves MSCNT by 2^MRES, in the opposite direction when the shaft is inverted
+        let mut offset = -(error as i32) >> mres;
+        if shaft {
+            offset = -offset;
+        }
+        let x_target = position.x_actual.wrapping_add(offset);
+        self.write_register(
+            RampMode::<M> {
+                ramp_mode: RampModeValue::Positioning,
+            },
+            spi,
+        )?;
+        self.write_register(XTarget::<M> { x_target }, spi)?;
+        Ok(Some(x_target))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::motor_driver_register::MicrostepResolution;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn full_step_error() {
+        let position = |ms_cnt| FinePosition {
+            ms_cnt,
+            ..Default::default()
+        };
+        assert_eq!(position(128).full_step_error(), 0);
+        assert!(position(896).is_full_step_aligned());
+        assert_eq!(position(130).full_step_error(), 2);
+        assert_eq!(position(100).full_step_error(), -28);
+        assert_eq!(position(0).full_step_error(), -128);
+        assert_eq!(position(255).full_step_error(), 127);
+        assert_eq!(position(1023).full_step_error(), 127);
+        assert_eq!(position(700).full_step(), 2);
+    }
+
+    #[test]
+    fn fine_position() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072
+            .write_register(XTarget::<1> { x_target: 1100 }, &mut sim)
+            .unwrap();
+        let position = tmc5072.fine_position::<1, _>(&mut sim).unwrap();
+        assert_eq!(position.x_actual, 1100);
+        assert_eq!(position.ms_cnt, 76);
+        assert_eq!(position.full_step_error(), -52);
+    }
+
+    #[test]
+    fn align_to_fullstep() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072
+            .write_register(XTarget::<0> { x_target: 1100 }, &mut sim)
+            .unwrap();
+        assert_eq!(
+            tmc5072.align_to_fullstep::<0, _>(&mut sim).unwrap(),
+            Some(1152)
+        );
+        assert!(tmc5072
+            .fine_position::<0, _>(&mut sim)
+            .unwrap()
+            .is_full_step_aligned());
+        assert_eq!(tmc5072.align_to_fullstep::<0, _>(&mut sim).unwrap(), None);
+        // 16 microsteps: MSCNT moves by 16 per microstep
+        tmc5072
+            .write_register(
+                ChopConf::<1> {
+                    mres: MicrostepResolution::M16,
+                    ..Default::default()
+                },
+                &mut sim,
+            )
+            .unwrap();
+        tmc5072
+            .write_register(XTarget::<1> { x_target: 3 }, &mut sim)
+            .unwrap();
+        assert_eq!(
+            tmc5072.align_to_fullstep::<1, _>(&mut sim).unwrap(),
+            Some(8)
+        );
+        assert_eq!(tmc5072.fine_position::<1, _>(&mut sim).unwrap().ms_cnt, 128);
+        // MSCNT 131 is 3 entries off, not a multiple of the 16 entries of a microstep
+        sim.set(MsCnt::<1>::addr(), 131);
+        let x_target = sim.get(XTarget::<1>::addr());
+        assert_eq!(
+            tmc5072.align_to_fullstep::<1, _>(&mut sim),
+            Err(AlignError::Unreachable)
+        );
+        assert_eq!(sim.get(XTarget::<1>::addr()), x_target);
+    }
+}
@@ -0,0 +1,110 @@
+//! Position compare pulse
+//!
+//! With GCONF poscmp_enable, the PP output pulses when XACTUAL of motor 1 equals X_COMPARE, and the INT output
+//! becomes available. Both share their pins with the A and B inputs of encoder 1, which is then unusable.
+//!
+//! [`Tmc5072::enable_position_pulse`] refuses to take the pins over when encoder 1 is configured in the shadow
+//! configuration ([`Tmc5072::config`]): ENCMODE set, or a non zero ENC_CONST (X_ENC does not count with 0).
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::gconf::GConfError;
+use crate::registers::general_configuration_register::XCompare;
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Position compare pulse error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PositionPulseError<SPI, CS> {
+    /// GCONF access error
+    GConf(GConfError<SPI, CS>),
+    /// Encoder 1 is configured, nothing was written
+    EncoderInUse,
+}
+
+impl<SPI, CS> From<GConfError<SPI, CS>> for PositionPulseError<SPI, CS> {
+    fn from(e: GConfError<SPI, CS>) -> Self {
+        PositionPulseError::GConf(e)
+    }
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for PositionPulseError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        PositionPulseError::GConf(GConfError::SpiError(e))
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Whether encoder 1 is configured in the shadow configuration
+    pub fn encoder1_in_use(&self) -> bool {
+        let motor0 = &self.config().motor0;
+        motor0.enc_mode != Default::default() || motor0.enc_const != Default::default()
+    }
+
+    /// Writes X_COMPARE, then sets GCONF poscmp_enable: PP pulses when motor 1 passes position `x`
+    pub fn enable_position_pulse<SPI: Transfer<u8>>(
+        &mut self,
+        x: i32,
+        spi: &mut SPI,
+    ) -> Result<(), PositionPulseError<SPI::Error, CS::Error>> {
+        if self.encoder1_in_use() {
+            return Err(PositionPulseError::EncoderInUse);
+        }
+        self.write_register(
+            XCompare {
+                x_compare: x as u32,
+            },
+            spi,
+        )?;
+        self.update_gconf(|g_conf| g_conf.poscmp_enable = true, spi)?;
+        Ok(())
+    }
+
+    /// Clears GCONF poscmp_enable, mapping the encoder 1 inputs again
+    pub fn disable_position_pulse<SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<(), GConfError<SPI::Error, CS::Error>> {
+        self.update_gconf(|g_conf| g_conf.poscmp_enable = false, spi)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        encoder_registers::EncConst, general_configuration_register::GConf, Register,
+    };
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn position_pulse() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072.enable_position_pulse(-100, &mut sim).unwrap();
+        assert!(GConf::from(sim.get(GConf::addr())).poscmp_enable);
+        assert_eq!(sim.get(XCompare::addr()), -100i32 as u32);
+        tmc5072.disable_position_pulse(&mut sim).unwrap();
+        assert!(!GConf::from(sim.get(GConf::addr())).poscmp_enable);
+    }
+
+    #[test]
+    fn encoder_in_use() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072
+            .write_register(EncConst::<0>::from(0x0001_0000), &mut sim)
+            .unwrap();
+        assert_eq!(
+            tmc5072.enable_position_pulse(100, &mut sim),
+            Err(PositionPulseError::EncoderInUse)
+        );
+        assert!(!GConf::from(sim.get(GConf::addr())).poscmp_enable);
+        assert_eq!(sim.get(XCompare::addr()), 0);
+    }
+}
@@ -0,0 +1,359 @@
+//! Motor power down sequencing
+//!
+//! De-energizing a motor by clearing TOFF drops its current at once, re-enabling it snaps the rotor to the
+//! microstep position. A [`PowerSequencer`] rather goes through the datasheet power down of the ramp generator:
+//! 1. TZEROWAIT holds the motor at zero velocity for a while after the ramp ends
+//! 2. after standstill (DRV_STATUS stst), the current decays from IRUN to IHOLD=0 in steps of IHOLDDELAY
+//! 3. once the actual current scale (CS_ACTUAL) is 0, TOFF=0 disables the driver
+//!
+//! Powering up restores TOFF at IHOLD=0 and raises IHOLD back one step per `ramp_up_step` before restoring the
+//! settings saved at power down. [`PowerSequencer::poll`] moves through these stages and returns the
+//! [`PowerState`], which tells whether standstill was detected yet.
+//!
+//! The motor must not be commanded to move while powered down or powering up: moving applies IRUN at once.
+//! [Position critical](Tmc5072::set_position_critical) motors are never powered down, their IHOLD=0 write would be
+//! replaced and the current would not decay.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    motor_driver_register::{ChopConf, DrvStatus},
+    ramp_generator_driver_feature_control_register::IHoldIRun,
+    ramp_generator_register::TZeroWait,
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Power sequencing settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PowerConfig {
+    /// TZEROWAIT during power down: wait at zero velocity before the current decays
+    pub t_zero_wait: u16,
+    /// IHOLDDELAY during power down (0..=15): 2^18 clocks times this value per current step
+    pub i_hold_delay: u8,
+    /// Time between two IHOLD steps when powering up, in the unit of the `now` argument of [`PowerSequencer::poll`]
+    pub ramp_up_step: u32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            t_zero_wait: 0x1000,
+            i_hold_delay: 6,
+            ramp_up_step: 1,
+        }
+    }
+}
+
+/// Power sequencing error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// The motor is position critical and must stay energized, nothing was written
+    PositionCritical,
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for PowerError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        PowerError::SpiError(e)
+    }
+}
+
+/// Power stage of a motor
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PowerState {
+    /// Driver on with the saved current settings
+    #[default]
+    Energized,
+    /// Power down requested, waiting for standstill (stst)
+    WaitingStandstill,
+    /// At standstill, the current decays to IHOLD=0
+    Reducing,
+    /// Driver disabled (TOFF=0)
+    Off,
+    /// Driver on, IHOLD raised back
+    Energizing {
+        /// Actual IHOLD
+        i_hold: u8,
+    },
+}
+
+/// Settings restored at power up
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+struct Saved<const M: u8> {
+    i_hold_i_run: IHoldIRun<M>,
+    t_zero_wait: TZeroWait<M>,
+    toff: u8,
+}
+
+/// Power down and power up sequencer of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct PowerSequencer<const M: u8> {
+    config: PowerConfig,
+    state: PowerState,
+    saved: Option<Saved<M>>,
+    since: u32,
+}
+
+impl<const M: u8> PowerSequencer<M>
+where
+    ChopConf<M>: Register,
+    DrvStatus<M>: Register,
+    IHoldIRun<M>: Register,
+    TZeroWait<M>: Register,
+{
+    /// Creates a sequencer of an energized motor
+    pub fn new(config: PowerConfig) -> Self {
+        Self {
+            config,
+            state: PowerState::Energized,
+            saved: None,
+            since: 0,
+        }
+    }
+
+    /// Actual state
+    pub fn state(&self) -> PowerState {
+        self.state
+    }
+
+    /// Starts the power down, completed by [`PowerSequencer::poll`]
+    ///
+    /// The current settings, TZEROWAIT and TOFF are taken from the shadow configuration and restored at power up.
+    /// Fails with [`PowerError::PositionCritical`] for a [position critical](Tmc5072::set_position_critical) motor.
+    pub fn power_down<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<PowerState, PowerError<SPI::Error, CS::Error>> {
+        if !matches!(self.state, PowerState::Energized) {
+            return Ok(self.state);
+        }
+        if tmc5072.is_position_critical::<M>() {
+            return Err(PowerError::PositionCritical);
+        }
+        let saved = Self::shadow(tmc5072);
+        tmc5072.write_register(
+            TZeroWait::<M> {
+                t_zero_wait: self.config.t_zero_wait,
+            },
+            spi,
+        )?;
+        tmc5072.write_register(
+            IHoldIRun::<M> {
+                i_hold: 0,
+                i_hold_delay: self.config.i_hold_delay,
+                ..saved.i_hold_i_run
+            },
+            spi,
+        )?;
+        self.saved = Some(saved);
+        self.state = PowerState::WaitingStandstill;
+        Ok(self.state)
+    }
+
+    /// Starts the power up, completed by [`PowerSequencer::poll`]
+    ///
+    /// A power down not completed yet is aborted at once.
+    pub fn power_up<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<PowerState, SpiError<SPI::Error, CS::Error>> {
+        let Some(saved) = self.saved else {
+            return Ok(self.state);
+        };
+        match self.state {
+            PowerState::WaitingStandstill | PowerState::Reducing => self.restore(tmc5072, spi)?,
+            PowerState::Off => {
+                let chop_conf = tmc5072.read_register::<ChopConf<M>, _>(spi)?.data;
+                tmc5072.write_register(
+                    ChopConf::<M> {
+                        toff: saved.toff,
+                        ..chop_conf
+                    },
+                    spi,
+                )?;
+                self.state = PowerState::Energizing { i_hold: 0 };
+                self.since = now;
+            }
+            PowerState::Energized | PowerState::Energizing { .. } => {}
+        }
+        Ok(self.state)
+    }
+
+    /// Moves to the next stage once the actual one is over
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<PowerState, SpiError<SPI::Error, CS::Error>> {
+        match self.state {
+            PowerState::WaitingStandstill | PowerState::Reducing => {
+                let drv_status = tmc5072.read_register::<DrvStatus<M>, _>(spi)?.data;
+                self.state = if !drv_status.stst {
+                    PowerState::WaitingStandstill
+                } else if drv_status.cs_actual > 0 {
+                    PowerState::Reducing
+                } else {
+                    let chop_conf = tmc5072.read_register::<ChopConf<M>, _>(spi)?.data;
+                    tmc5072.write_register(
+                        ChopConf::<M> {
+                            toff: 0,
+                            ..chop_conf
+                        },
+                        spi,
+                    )?;
+                    PowerState::Off
+                };
+            }
+            PowerState::Energizing { i_hold }
+                if now.wrapping_sub(self.since) >= self.config.ramp_up_step =>
+            {
+                let Some(saved) = self.saved else {
+                    return Ok(self.state);
+                };
+                if i_hold >= saved.i_hold_i_run.i_hold {
+                    self.restore(tmc5072, spi)?;
+                } else {
+                    tmc5072.write_register(
+                        IHoldIRun::<M> {
+                            i_hold: i_hold + 1,
+                            ..saved.i_hold_i_run
+                        },
+                        spi,
+                    )?;
+                    self.state = PowerState::Energizing { i_hold: i_hold + 1 };
+                    self.since = now;
+                }
+            }
+            _ => {}
+        }
+        Ok(self.state)
+    }
+
+    /// Writes back the settings saved at power down
+    fn restore<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        if let Some(saved) = self.saved.take() {
+            tmc5072.write_register(saved.i_hold_i_run, spi)?;
+            tmc5072.write_register(saved.t_zero_wait, spi)?;
+        }
+        self.state = PowerState::Energized;
+        Ok(())
+    }
+
+    fn shadow<CS: OutputPin>(tmc5072: &Tmc5072<CS>) -> Saved<M> {
+        let config = tmc5072.config();
+        let (i_hold_i_run, t_zero_wait, chop_conf) = match M {
+            0 => (
+                u32::from(config.motor0.i_hold_i_run),
+                u32::from(config.motor0.t_zero_wait),
+                u32::from(config.motor0.chop_conf),
+            ),
+            _ => (
+                u32::from(config.motor1.i_hold_i_run),
+                u32::from(config.motor1.t_zero_wait),
+                u32::from(config.motor1.chop_conf),
+            ),
+        };
+        Saved {
+            i_hold_i_run: i_hold_i_run.into(),
+            t_zero_wait: t_zero_wait.into(),
+            toff: ChopConf::<M>::from(chop_conf).toff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn down_up() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let current = IHoldIRun::<1> {
+            i_hold: 2,
+            i_run: 16,
+            i_hold_delay: 1,
+        };
+        tmc5072.write_register(current, &mut sim).unwrap();
+        let chop_conf = ChopConf::<1> {
+            toff: 5,
+            ..Default::default()
+        };
+        tmc5072.write_register(chop_conf, &mut sim).unwrap();
+        let mut power = PowerSequencer::<1>::new(PowerConfig::default());
+        tmc5072.set_position_critical::<1>(true);
+        assert_eq!(
+            power.power_down(&mut tmc5072, &mut sim),
+            Err(PowerError::PositionCritical)
+        );
+        assert_eq!(power.state(), PowerState::Energized);
+        assert_eq!(sim.get(IHoldIRun::<1>::addr()), u32::from(current));
+        tmc5072.set_position_critical::<1>(false);
+        assert_eq!(
+            power.power_down(&mut tmc5072, &mut sim),
+            Ok(PowerState::WaitingStandstill)
+        );
+        let i_hold_i_run =
+            |sim: &mut Simulator| IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr()));
+        assert_eq!(i_hold_i_run(&mut sim).i_hold, 0);
+        assert_eq!(i_hold_i_run(&mut sim).i_hold_delay, 6);
+        assert_eq!(
+            power.poll(&mut tmc5072, &mut sim, 0),
+            Ok(PowerState::WaitingStandstill)
+        );
+        let drv_status = |stst, cs_actual| {
+            u32::from(DrvStatus::<1> {
+                stst,
+                cs_actual,
+                ..Default::default()
+            })
+        };
+        sim.set(DrvStatus::<1>::addr(), drv_status(true, 8));
+        assert_eq!(
+            power.poll(&mut tmc5072, &mut sim, 0),
+            Ok(PowerState::Reducing)
+        );
+        sim.set(DrvStatus::<1>::addr(), drv_status(true, 0));
+        assert_eq!(power.poll(&mut tmc5072, &mut sim, 0), Ok(PowerState::Off));
+        assert_eq!(ChopConf::<1>::from(sim.get(ChopConf::<1>::addr())).toff, 0);
+
+        assert_eq!(
+            power.power_up(&mut tmc5072, &mut sim, 10),
+            Ok(PowerState::Energizing { i_hold: 0 })
+        );
+        assert_eq!(ChopConf::<1>::from(sim.get(ChopConf::<1>::addr())).toff, 5);
+        assert_eq!(
+            power.poll(&mut tmc5072, &mut sim, 11),
+            Ok(PowerState::Energizing { i_hold: 1 })
+        );
+        assert_eq!(i_hold_i_run(&mut sim).i_hold, 1);
+        power.poll(&mut tmc5072, &mut sim, 12).unwrap();
+        assert_eq!(
+            power.poll(&mut tmc5072, &mut sim, 13),
+            Ok(PowerState::Energized)
+        );
+        assert_eq!(i_hold_i_run(&mut sim), current);
+        assert_eq!(
+            sim.get(TZeroWait::<1>::addr()),
+            u32::from(TZeroWait::<1>::default())
+        );
+    }
+}
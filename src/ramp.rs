@@ -0,0 +1,270 @@
+//! sixPoint ramp profiles
+//!
+//! A [`RampProfile`] holds the nine ramp parameters of a motor. [`Tmc5072::apply_ramp`] checks the datasheet
+//! constraints before writing them, a rejected profile leaves the registers unchanged:
+//! - VSTOP ≥ VSTART, and VSTOP ≠ 0 (positioning mode needs a stop velocity)
+//! - D1 ≠ 0, even with V1 = 0
+//! - AMAX ≠ 0 and DMAX ≠ 0
+//! - each value fits its register
+//!
+//! ```
+//! # use tmc5072::ramp::RampProfile;
+//! let profile = RampProfile {
+//!     v1: 50_000,
+//!     a1: 1_000,
+//!     ..RampProfile::trapezoidal(200_000, 500)
+//! };
+//! assert_eq!(profile.validate(), Ok(()));
+//! ```
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::config::MotorConfig;
+use crate::registers::{
+    ramp_generator_register::{AMax, DMax, TZeroWait, VMax, VStart, VStop, A1, D1, V1},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Ramp parameters of a motor
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RampProfile {
+    /// VSTART: start velocity
+    pub v_start: u32,
+    /// A1: acceleration between VSTART and V1
+    pub a1: u16,
+    /// V1: threshold velocity between A1/D1 and AMAX/DMAX, 0 disables the A1 and D1 phases
+    pub v1: u32,
+    /// AMAX: acceleration between V1 and VMAX
+    pub a_max: u16,
+    /// VMAX: target velocity
+    pub v_max: u32,
+    /// DMAX: deceleration between VMAX and V1
+    pub d_max: u16,
+    /// D1: deceleration between V1 and VSTOP
+    pub d1: u16,
+    /// VSTOP: stop velocity
+    pub v_stop: u32,
+    /// TZEROWAIT: wait time after a stop (in 512 clocks)
+    pub t_zero_wait: u16,
+}
+
+impl RampProfile {
+    /// Trapezoidal profile: VMAX reached with a constant acceleration `a`, VSTOP=10
+    pub fn trapezoidal(v_max: u32, a: u16) -> Self {
+        Self {
+            v_start: 0,
+            a1: a,
+            v1: 0,
+            a_max: a,
+            v_max,
+            d_max: a,
+            d1: a,
+            v_stop: 10,
+            t_zero_wait: 0,
+        }
+    }
+
+    /// Ramp parameters of a motor configuration
+    pub fn of<const M: u8>(motor: &MotorConfig<M>) -> Self {
+        Self {
+            v_start: motor.v_start.v_start,
+            a1: motor.a1.a1,
+            v1: motor.v1.v1,
+            a_max: motor.a_max.a_max,
+            v_max: motor.v_max.v_max,
+            d_max: motor.d_max.d_max,
+            d1: motor.d1.d1,
+            v_stop: motor.v_stop.v_stop,
+            t_zero_wait: motor.t_zero_wait.t_zero_wait,
+        }
+    }
+
+    /// Checks the datasheet constraints
+    pub fn validate(&self) -> Result<(), RampProfileError> {
+        if self.v_start >= 1 << 18
+            || self.v1 >= 1 << 20
+            || self.v_max > (1 << 23) - 512
+            || self.v_stop >= 1 << 18
+        {
+            Err(RampProfileError::OutOfRange)
+        } else if self.v_stop == 0 {
+            Err(RampProfileError::VStopZero)
+        } else if self.v_stop < self.v_start {
+            Err(RampProfileError::VStopBelowVStart)
+        } else if self.d1 == 0 {
+            Err(RampProfileError::D1Zero)
+        } else if self.a_max == 0 || self.d_max == 0 {
+            Err(RampProfileError::AccelerationZero)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Datasheet constraint violated by a ramp profile
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RampProfileError {
+    /// A velocity does not fit its register
+    OutOfRange,
+    /// VSTOP is 0
+    VStopZero,
+    /// VSTOP is below VSTART
+    VStopBelowVStart,
+    /// D1 is 0
+    D1Zero,
+    /// AMAX or DMAX is 0
+    AccelerationZero,
+}
+
+/// Ramp profile error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RampError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Invalid profile, nothing was written
+    Invalid(RampProfileError),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for RampError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        RampError::SpiError(e)
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Validates and writes the ramp parameters of motor `M`, VMAX last
+    pub fn apply_ramp<const M: u8, SPI: Transfer<u8>>(
+        &mut self,
+        profile: &RampProfile,
+        spi: &mut SPI,
+    ) -> Result<(), RampError<SPI::Error, CS::Error>>
+    where
+        VStart<M>: Register,
+        A1<M>: Register,
+        V1<M>: Register,
+        AMax<M>: Register,
+        VMax<M>: Register,
+        DMax<M>: Register,
+        D1<M>: Register,
+        VStop<M>: Register,
+        TZeroWait<M>: Register,
+    {
+        profile.validate().map_err(RampError::Invalid)?;
+        self.write_register(
+            VStart::<M> {
+                v_start: profile.v_start,
+            },
+            spi,
+        )?;
+        self.write_register(A1::<M> { a1: profile.a1 }, spi)?;
+        self.write_register(V1::<M> { v1: profile.v1 }, spi)?;
+        self.write_register(
+            AMax::<M> {
+                a_max: profile.a_max,
+            },
+            spi,
+        )?;
+        self.write_register(
+            DMax::<M> {
+                d_max: profile.d_max,
+            },
+            spi,
+        )?;
+        self.write_register(D1::<M> { d1: profile.d1 }, spi)?;
+        self.write_register(
+            VStop::<M> {
+                v_stop: profile.v_stop,
+            },
+            spi,
+        )?;
+        self.write_register(
+            TZeroWait::<M> {
+                t_zero_wait: profile.t_zero_wait,
+            },
+            spi,
+        )?;
+        self.write_register(
+            VMax::<M> {
+                v_max: profile.v_max,
+            },
+            spi,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn validate() {
+        let profile = RampProfile::trapezoidal(100_000, 1000);
+        assert_eq!(profile.validate(), Ok(()));
+        let invalid = |profile: RampProfile| profile.validate().unwrap_err();
+        assert_eq!(
+            invalid(RampProfile {
+                v_start: 20,
+                ..profile
+            }),
+            RampProfileError::VStopBelowVStart
+        );
+        assert_eq!(
+            invalid(RampProfile {
+                v_stop: 0,
+                ..profile
+            }),
+            RampProfileError::VStopZero
+        );
+        assert_eq!(
+            invalid(RampProfile { d1: 0, ..profile }),
+            RampProfileError::D1Zero
+        );
+        assert_eq!(
+            invalid(RampProfile {
+                d_max: 0,
+                ..profile
+            }),
+            RampProfileError::AccelerationZero
+        );
+        assert_eq!(
+            invalid(RampProfile {
+                v_max: 1 << 23,
+                ..profile
+            }),
+            RampProfileError::OutOfRange
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let profile = RampProfile {
+            v1: 30_000,
+            a1: 2000,
+            ..RampProfile::trapezoidal(100_000, 1000)
+        };
+        tmc5072.apply_ramp::<1, _>(&profile, &mut sim).unwrap();
+        assert_eq!(sim.get(VMax::<1>::addr()), 100_000);
+        assert_eq!(sim.get(V1::<1>::addr()), 30_000);
+        assert_eq!(sim.get(A1::<1>::addr()), 2000);
+        assert_eq!(sim.get(D1::<1>::addr()), 1000);
+        assert_eq!(sim.get(VStop::<1>::addr()), 10);
+        assert_eq!(
+            tmc5072.apply_ramp::<0, _>(&RampProfile { d1: 0, ..profile }, &mut sim),
+            Err(RampError::Invalid(RampProfileError::D1Zero))
+        );
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+    }
+}
@@ -0,0 +1,489 @@
+//! Register addresses
+//!
+//! [`RegisterAddress`] lists every register address of the TMC5072 with its datasheet name and [`Access`] mode, for
+//! tools dumping or bridging registers without their typed structs. Per motor registers end with the motor index.
+//! Address 0x04 reads INPUT and writes OUTPUT.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Access mode of a register
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Access {
+    /// Read only (R)
+    Read,
+    /// Write only (W)
+    Write,
+    /// Read and write (RW)
+    ReadWrite,
+    /// Read only, flags cleared by the read (RC)
+    ReadClear,
+}
+
+impl Access {
+    /// Whether reading returns the register value
+    pub const fn is_readable(self) -> bool {
+        !matches!(self, Access::Write)
+    }
+
+    /// Whether the register can be written
+    pub const fn is_writable(self) -> bool {
+        matches!(self, Access::Write | Access::ReadWrite)
+    }
+}
+
+/// Number of register addresses
+pub const REGISTER_COUNT: usize = 82;
+
+/// Number of readable register addresses
+pub const READABLE_COUNT: usize = 36;
+
+/// Register address of the TMC5072
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u8)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RegisterAddress {
+    /// GCONF
+    GConf = 0x00,
+    /// GSTAT
+    GStat = 0x01,
+    /// IFCNT
+    IfCnt = 0x02,
+    /// SLAVECONF
+    SlaveConf = 0x03,
+    /// INPUT/OUTPUT
+    InputOutput = 0x04,
+    /// X_COMPARE
+    XCompare = 0x05,
+    /// PWMCONF\[0\]
+    PwmConf0 = 0x10,
+    /// PWM_STATUS\[0\]
+    PwmStatus0 = 0x11,
+    /// PWMCONF\[1\]
+    PwmConf1 = 0x18,
+    /// PWM_STATUS\[1\]
+    PwmStatus1 = 0x19,
+    /// RAMPMODE\[0\]
+    RampMode0 = 0x20,
+    /// XACTUAL\[0\]
+    XActual0 = 0x21,
+    /// VACTUAL\[0\]
+    VActual0 = 0x22,
+    /// VSTART\[0\]
+    VStart0 = 0x23,
+    /// A1\[0\]
+    A10 = 0x24,
+    /// V1\[0\]
+    V10 = 0x25,
+    /// AMAX\[0\]
+    AMax0 = 0x26,
+    /// VMAX\[0\]
+    VMax0 = 0x27,
+    /// DMAX\[0\]
+    DMax0 = 0x28,
+    /// D1\[0\]
+    D10 = 0x2A,
+    /// VSTOP\[0\]
+    VStop0 = 0x2B,
+    /// TZEROWAIT\[0\]
+    TZeroWait0 = 0x2C,
+    /// XTARGET\[0\]
+    XTarget0 = 0x2D,
+    /// IHOLD_IRUN\[0\]
+    IHoldIRun0 = 0x30,
+    /// VCOOLTHRS\[0\]
+    VCoolThrs0 = 0x31,
+    /// VHIGH\[0\]
+    VHigh0 = 0x32,
+    /// VDCMIN\[0\]
+    VDcMin0 = 0x33,
+    /// SW_MODE\[0\]
+    SwMode0 = 0x34,
+    /// RAMP_STAT\[0\]
+    RampStat0 = 0x35,
+    /// XLATCH\[0\]
+    XLatch0 = 0x36,
+    /// ENCMODE\[0\]
+    EncMode0 = 0x38,
+    /// X_ENC\[0\]
+    XEnc0 = 0x39,
+    /// ENC_CONST\[0\]
+    EncConst0 = 0x3A,
+    /// ENC_STATUS\[0\]
+    EncStatus0 = 0x3B,
+    /// ENC_LATCH\[0\]
+    EncLatch0 = 0x3C,
+    /// RAMPMODE\[1\]
+    RampMode1 = 0x40,
+    /// XACTUAL\[1\]
+    XActual1 = 0x41,
+    /// VACTUAL\[1\]
+    VActual1 = 0x42,
+    /// VSTART\[1\]
+    VStart1 = 0x43,
+    /// A1\[1\]
+    A11 = 0x44,
+    /// V1\[1\]
+    V11 = 0x45,
+    /// AMAX\[1\]
+    AMax1 = 0x46,
+    /// VMAX\[1\]
+    VMax1 = 0x47,
+    /// DMAX\[1\]
+    DMax1 = 0x48,
+    /// D1\[1\]
+    D11 = 0x4A,
+    /// VSTOP\[1\]
+    VStop1 = 0x4B,
+    /// TZEROWAIT\[1\]
+    TZeroWait1 = 0x4C,
+    /// XTARGET\[1\]
+    XTarget1 = 0x4D,
+    /// IHOLD_IRUN\[1\]
+    IHoldIRun1 = 0x50,
+    /// VCOOLTHRS\[1\]
+    VCoolThrs1 = 0x51,
+    /// VHIGH\[1\]
+    VHigh1 = 0x52,
+    /// VDCMIN\[1\]
+    VDcMin1 = 0x53,
+    /// SW_MODE\[1\]
+    SwMode1 = 0x54,
+    /// RAMP_STAT\[1\]
+    RampStat1 = 0x55,
+    /// XLATCH\[1\]
+    XLatch1 = 0x56,
+    /// ENCMODE\[1\]
+    EncMode1 = 0x58,
+    /// X_ENC\[1\]
+    XEnc1 = 0x59,
+    /// ENC_CONST\[1\]
+    EncConst1 = 0x5A,
+    /// ENC_STATUS\[1\]
+    EncStatus1 = 0x5B,
+    /// ENC_LATCH\[1\]
+    EncLatch1 = 0x5C,
+    /// MSLUT\[0\]
+    MsLut0 = 0x60,
+    /// MSLUT\[1\]
+    MsLut1 = 0x61,
+    /// MSLUT\[2\]
+    MsLut2 = 0x62,
+    /// MSLUT\[3\]
+    MsLut3 = 0x63,
+    /// MSLUT\[4\]
+    MsLut4 = 0x64,
+    /// MSLUT\[5\]
+    MsLut5 = 0x65,
+    /// MSLUT\[6\]
+    MsLut6 = 0x66,
+    /// MSLUT\[7\]
+    MsLut7 = 0x67,
+    /// MSLUTSEL
+    MsLutSel = 0x68,
+    /// MSLUTSTART
+    MsLutStart = 0x69,
+    /// MSCNT\[0\]
+    MsCnt0 = 0x6A,
+    /// MSCURACT\[0\]
+    MsCurAct0 = 0x6B,
+    /// CHOPCONF\[0\]
+    ChopConf0 = 0x6C,
+    /// COOLCONF\[0\]
+    CoolConf0 = 0x6D,
+    /// DCCTRL\[0\]
+    DcCtrl0 = 0x6E,
+    /// DRV_STATUS\[0\]
+    DrvStatus0 = 0x6F,
+    /// MSCNT\[1\]
+    MsCnt1 = 0x7A,
+    /// MSCURACT\[1\]
+    MsCurAct1 = 0x7B,
+    /// CHOPCONF\[1\]
+    ChopConf1 = 0x7C,
+    /// COOLCONF\[1\]
+    CoolConf1 = 0x7D,
+    /// DCCTRL\[1\]
+    DcCtrl1 = 0x7E,
+    /// DRV_STATUS\[1\]
+    DrvStatus1 = 0x7F,
+}
+
+impl RegisterAddress {
+    /// All register addresses, in ascending order
+    pub const ALL: [RegisterAddress; REGISTER_COUNT] = [
+        RegisterAddress::GConf,
+        RegisterAddress::GStat,
+        RegisterAddress::IfCnt,
+        RegisterAddress::SlaveConf,
+        RegisterAddress::InputOutput,
+        RegisterAddress::XCompare,
+        RegisterAddress::PwmConf0,
+        RegisterAddress::PwmStatus0,
+        RegisterAddress::PwmConf1,
+        RegisterAddress::PwmStatus1,
+        RegisterAddress::RampMode0,
+        RegisterAddress::XActual0,
+        RegisterAddress::VActual0,
+        RegisterAddress::VStart0,
+        RegisterAddress::A10,
+        RegisterAddress::V10,
+        RegisterAddress::AMax0,
+        RegisterAddress::VMax0,
+        RegisterAddress::DMax0,
+        RegisterAddress::D10,
+        RegisterAddress::VStop0,
+        RegisterAddress::TZeroWait0,
+        RegisterAddress::XTarget0,
+        RegisterAddress::IHoldIRun0,
+        RegisterAddress::VCoolThrs0,
+        RegisterAddress::VHigh0,
+        RegisterAddress::VDcMin0,
+        RegisterAddress::SwMode0,
+        RegisterAddress::RampStat0,
+        RegisterAddress::XLatch0,
+        RegisterAddress::EncMode0,
+        RegisterAddress::XEnc0,
+        RegisterAddress::EncConst0,
+        RegisterAddress::EncStatus0,
+        RegisterAddress::EncLatch0,
+        RegisterAddress::RampMode1,
+        RegisterAddress::XActual1,
+        RegisterAddress::VActual1,
+        RegisterAddress::VStart1,
+        RegisterAddress::A11,
+        RegisterAddress::V11,
+        RegisterAddress::AMax1,
+        RegisterAddress::VMax1,
+        RegisterAddress::DMax1,
+        RegisterAddress::D11,
+        RegisterAddress::VStop1,
+        RegisterAddress::TZeroWait1,
+        RegisterAddress::XTarget1,
+        RegisterAddress::IHoldIRun1,
+        RegisterAddress::VCoolThrs1,
+        RegisterAddress::VHigh1,
+        RegisterAddress::VDcMin1,
+        RegisterAddress::SwMode1,
+        RegisterAddress::RampStat1,
+        RegisterAddress::XLatch1,
+        RegisterAddress::EncMode1,
+        RegisterAddress::XEnc1,
+        RegisterAddress::EncConst1,
+        RegisterAddress::EncStatus1,
+        RegisterAddress::EncLatch1,
+        RegisterAddress::MsLut0,
+        RegisterAddress::MsLut1,
+        RegisterAddress::MsLut2,
+        RegisterAddress::MsLut3,
+        RegisterAddress::MsLut4,
+        RegisterAddress::MsLut5,
+        RegisterAddress::MsLut6,
+        RegisterAddress::MsLut7,
+        RegisterAddress::MsLutSel,
+        RegisterAddress::MsLutStart,
+        RegisterAddress::MsCnt0,
+        RegisterAddress::MsCurAct0,
+        RegisterAddress::ChopConf0,
+        RegisterAddress::CoolConf0,
+        RegisterAddress::DcCtrl0,
+        RegisterAddress::DrvStatus0,
+        RegisterAddress::MsCnt1,
+        RegisterAddress::MsCurAct1,
+        RegisterAddress::ChopConf1,
+        RegisterAddress::CoolConf1,
+        RegisterAddress::DcCtrl1,
+        RegisterAddress::DrvStatus1,
+    ];
+
+    /// Readable register addresses, in ascending order
+    pub fn readable() -> impl Iterator<Item = RegisterAddress> {
+        Self::ALL.into_iter().filter(|r| r.access().is_readable())
+    }
+
+    /// Address
+    pub const fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Register at `addr`, `None` for unused addresses
+    pub fn from_addr(addr: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|r| r.addr() == addr)
+    }
+
+    /// Datasheet name, with the motor index in brackets
+    pub fn name(self) -> &'static str {
+        match self {
+            RegisterAddress::GConf => "GCONF",
+            RegisterAddress::GStat => "GSTAT",
+            RegisterAddress::IfCnt => "IFCNT",
+            RegisterAddress::SlaveConf => "SLAVECONF",
+            RegisterAddress::InputOutput => "INPUT/OUTPUT",
+            RegisterAddress::XCompare => "X_COMPARE",
+            RegisterAddress::PwmConf0 => "PWMCONF[0]",
+            RegisterAddress::PwmStatus0 => "PWM_STATUS[0]",
+            RegisterAddress::PwmConf1 => "PWMCONF[1]",
+            RegisterAddress::PwmStatus1 => "PWM_STATUS[1]",
+            RegisterAddress::RampMode0 => "RAMPMODE[0]",
+            RegisterAddress::XActual0 => "XACTUAL[0]",
+            RegisterAddress::VActual0 => "VACTUAL[0]",
+            RegisterAddress::VStart0 => "VSTART[0]",
+            RegisterAddress::A10 => "A1[0]",
+            RegisterAddress::V10 => "V1[0]",
+            RegisterAddress::AMax0 => "AMAX[0]",
+            RegisterAddress::VMax0 => "VMAX[0]",
+            RegisterAddress::DMax0 => "DMAX[0]",
+            RegisterAddress::D10 => "D1[0]",
+            RegisterAddress::VStop0 => "VSTOP[0]",
+            RegisterAddress::TZeroWait0 => "TZEROWAIT[0]",
+            RegisterAddress::XTarget0 => "XTARGET[0]",
+            RegisterAddress::IHoldIRun0 => "IHOLD_IRUN[0]",
+            RegisterAddress::VCoolThrs0 => "VCOOLTHRS[0]",
+            RegisterAddress::VHigh0 => "VHIGH[0]",
+            RegisterAddress::VDcMin0 => "VDCMIN[0]",
+            RegisterAddress::SwMode0 => "SW_MODE[0]",
+            RegisterAddress::RampStat0 => "RAMP_STAT[0]",
+            RegisterAddress::XLatch0 => "XLATCH[0]",
+            RegisterAddress::EncMode0 => "ENCMODE[0]",
+            RegisterAddress::XEnc0 => "X_ENC[0]",
+            RegisterAddress::EncConst0 => "ENC_CONST[0]",
+            RegisterAddress::EncStatus0 => "ENC_STATUS[0]",
+            RegisterAddress::EncLatch0 => "ENC_LATCH[0]",
+            RegisterAddress::RampMode1 => "RAMPMODE[1]",
+            RegisterAddress::XActual1 => "XACTUAL[1]",
+            RegisterAddress::VActual1 => "VACTUAL[1]",
+            RegisterAddress::VStart1 => "VSTART[1]",
+            RegisterAddress::A11 => "A1[1]",
+            RegisterAddress::V11 => "V1[1]",
+            RegisterAddress::AMax1 => "AMAX[1]",
+            RegisterAddress::VMax1 => "VMAX[1]",
+            RegisterAddress::DMax1 => "DMAX[1]",
+            RegisterAddress::D11 => "D1[1]",
+            RegisterAddress::VStop1 => "VSTOP[1]",
+            RegisterAddress::TZeroWait1 => "TZEROWAIT[1]",
+            RegisterAddress::XTarget1 => "XTARGET[1]",
+            RegisterAddress::IHoldIRun1 => "IHOLD_IRUN[1]",
+            RegisterAddress::VCoolThrs1 => "VCOOLTHRS[1]",
+            RegisterAddress::VHigh1 => "VHIGH[1]",
+            RegisterAddress::VDcMin1 => "VDCMIN[1]",
+            RegisterAddress::SwMode1 => "SW_MODE[1]",
+            RegisterAddress::RampStat1 => "RAMP_STAT[1]",
+            RegisterAddress::XLatch1 => "XLATCH[1]",
+            RegisterAddress::EncMode1 => "ENCMODE[1]",
+            RegisterAddress::XEnc1 => "X_ENC[1]",
+            RegisterAddress::EncConst1 => "ENC_CONST[1]",
+            RegisterAddress::EncStatus1 => "ENC_STATUS[1]",
+            RegisterAddress::EncLatch1 => "ENC_LATCH[1]",
+            RegisterAddress::MsLut0 => "MSLUT[0]",
+            RegisterAddress::MsLut1 => "MSLUT[1]",
+            RegisterAddress::MsLut2 => "MSLUT[2]",
+            RegisterAddress::MsLut3 => "MSLUT[3]",
+            RegisterAddress::MsLut4 => "MSLUT[4]",
+            RegisterAddress::MsLut5 => "MSLUT[5]",
+            RegisterAddress::MsLut6 => "MSLUT[6]",
+            RegisterAddress::MsLut7 => "MSLUT[7]",
+            RegisterAddress::MsLutSel => "MSLUTSEL",
+            RegisterAddress::MsLutStart => "MSLUTSTART",
+            RegisterAddress::MsCnt0 => "MSCNT[0]",
+            RegisterAddress::MsCurAct0 => "MSCURACT[0]",
+            RegisterAddress::ChopConf0 => "CHOPCONF[0]",
+            RegisterAddress::CoolConf0 => "COOLCONF[0]",
+            RegisterAddress::DcCtrl0 => "DCCTRL[0]",
+            RegisterAddress::DrvStatus0 => "DRV_STATUS[0]",
+            RegisterAddress::MsCnt1 => "MSCNT[1]",
+            RegisterAddress::MsCurAct1 => "MSCURACT[1]",
+            RegisterAddress::ChopConf1 => "CHOPCONF[1]",
+            RegisterAddress::CoolConf1 => "COOLCONF[1]",
+            RegisterAddress::DcCtrl1 => "DCCTRL[1]",
+            RegisterAddress::DrvStatus1 => "DRV_STATUS[1]",
+        }
+    }
+
+    /// Access mode
+    pub const fn access(self) -> Access {
+        match self {
+            RegisterAddress::GConf
+            | RegisterAddress::InputOutput
+            | RegisterAddress::RampMode0
+            | RegisterAddress::XActual0
+            | RegisterAddress::XTarget0
+            | RegisterAddress::SwMode0
+            | RegisterAddress::EncMode0
+            | RegisterAddress::XEnc0
+            | RegisterAddress::RampMode1
+            | RegisterAddress::XActual1
+            | RegisterAddress::XTarget1
+            | RegisterAddress::SwMode1
+            | RegisterAddress::EncMode1
+            | RegisterAddress::XEnc1
+            | RegisterAddress::ChopConf0
+            | RegisterAddress::ChopConf1 => Access::ReadWrite,
+            RegisterAddress::GStat
+            | RegisterAddress::RampStat0
+            | RegisterAddress::EncStatus0
+            | RegisterAddress::RampStat1
+            | RegisterAddress::EncStatus1 => Access::ReadClear,
+            RegisterAddress::IfCnt
+            | RegisterAddress::PwmStatus0
+            | RegisterAddress::PwmStatus1
+            | RegisterAddress::VActual0
+            | RegisterAddress::XLatch0
+            | RegisterAddress::EncLatch0
+            | RegisterAddress::VActual1
+            | RegisterAddress::XLatch1
+            | RegisterAddress::EncLatch1
+            | RegisterAddress::MsCnt0
+            | RegisterAddress::MsCurAct0
+            | RegisterAddress::DrvStatus0
+            | RegisterAddress::MsCnt1
+            | RegisterAddress::MsCurAct1
+            | RegisterAddress::DrvStatus1 => Access::Read,
+            _ => Access::Write,
+        }
+    }
+}
+
+impl From<RegisterAddress> for u8 {
+    fn from(r: RegisterAddress) -> Self {
+        r.addr()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        encoder_registers::EncLatch, general_configuration_register::GStat,
+        motor_driver_register::DrvStatus, ramp_generator_register::XTarget, Register,
+    };
+
+    #[test]
+    fn addresses() {
+        assert_eq!(RegisterAddress::GStat.addr(), GStat::addr());
+        assert_eq!(RegisterAddress::XTarget1.addr(), XTarget::<1>::addr());
+        assert_eq!(RegisterAddress::EncLatch0.addr(), EncLatch::<0>::addr());
+        assert_eq!(RegisterAddress::DrvStatus1.addr(), DrvStatus::<1>::addr());
+        assert_eq!(
+            RegisterAddress::from_addr(0x7F),
+            Some(RegisterAddress::DrvStatus1)
+        );
+        assert_eq!(RegisterAddress::from_addr(0x06), None);
+        assert!(RegisterAddress::ALL.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(RegisterAddress::RampStat1.name(), "RAMP_STAT[1]");
+    }
+
+    #[test]
+    fn access() {
+        assert_eq!(RegisterAddress::VMax0.access(), Access::Write);
+        assert_eq!(RegisterAddress::ChopConf1.access(), Access::ReadWrite);
+        assert_eq!(RegisterAddress::GStat.access(), Access::ReadClear);
+        assert!(RegisterAddress::ALL
+            .iter()
+            .all(|r| r.access().is_readable() || r.access().is_writable()));
+        assert_eq!(RegisterAddress::readable().count(), READABLE_COUNT);
+    }
+}
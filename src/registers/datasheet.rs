@@ -0,0 +1,113 @@
+//! Datasheet register names
+//!
+//! Aliases of the register types using the exact names of the datasheet (and of TMCL code),
+//! to find the types when cross-referencing them. [`A1`], [`V1`] and [`D1`] already carry their datasheet name.
+
+#![allow(non_camel_case_types)]
+
+use super::{
+    encoder_registers::*, general_configuration_register::*, microstep_table_register::*,
+    motor_driver_register::*, ramp_generator_driver_feature_control_register::*,
+    ramp_generator_register::*, voltage_pwm_mode_stealth_chop::*,
+};
+
+/// [`GConf`]
+pub type GCONF = GConf;
+/// [`GStat`]
+pub type GSTAT = GStat;
+/// [`IfCnt`]
+pub type IFCNT = IfCnt;
+/// [`SlaveConf`]
+pub type SLAVECONF = SlaveConf;
+/// [`Input`]
+pub type INPUT = Input;
+/// [`Output`]
+pub type OUTPUT = Output;
+/// [`XCompare`]
+pub type X_COMPARE = XCompare;
+
+/// [`PwmConf`]
+pub type PWMCONF<const M: u8> = PwmConf<M>;
+/// [`PwmStatus`]
+pub type PWM_STATUS<const M: u8> = PwmStatus<M>;
+
+/// [`RampMode`]
+pub type RAMPMODE<const M: u8> = RampMode<M>;
+/// [`XActual`]
+pub type XACTUAL<const M: u8> = XActual<M>;
+/// [`VActual`]
+pub type VACTUAL<const M: u8> = VActual<M>;
+/// [`VStart`]
+pub type VSTART<const M: u8> = VStart<M>;
+/// [`AMax`]
+pub type AMAX<const M: u8> = AMax<M>;
+/// [`VMax`]
+pub type VMAX<const M: u8> = VMax<M>;
+/// [`DMax`]
+pub type DMAX<const M: u8> = DMax<M>;
+/// [`VStop`]
+pub type VSTOP<const M: u8> = VStop<M>;
+/// [`TZeroWait`]
+pub type TZEROWAIT<const M: u8> = TZeroWait<M>;
+/// [`XTarget`]
+pub type XTARGET<const M: u8> = XTarget<M>;
+
+/// [`IHoldIRun`]
+pub type IHOLD_IRUN<const M: u8> = IHoldIRun<M>;
+/// [`VCoolThrs`]
+pub type VCOOLTHRS<const M: u8> = VCoolThrs<M>;
+/// [`VHigh`]
+pub type VHIGH<const M: u8> = VHigh<M>;
+/// [`VDcMin`]
+pub type VDCMIN<const M: u8> = VDcMin<M>;
+/// [`SwMode`]
+pub type SW_MODE<const M: u8> = SwMode<M>;
+/// [`RampStat`]
+pub type RAMP_STAT<const M: u8> = RampStat<M>;
+/// [`XLatch`]
+pub type XLATCH<const M: u8> = XLatch<M>;
+
+/// [`EncMode`]
+pub type ENCMODE<const M: u8> = EncMode<M>;
+/// [`XEnc`]
+pub type X_ENC<const M: u8> = XEnc<M>;
+/// [`EncConst`]
+pub type ENC_CONST<const M: u8> = EncConst<M>;
+/// [`EncStatus`]
+pub type ENC_STATUS<const M: u8> = EncStatus<M>;
+/// [`EncLatch`]
+pub type ENC_LATCH<const M: u8> = EncLatch<M>;
+
+/// [`MsLut0`]
+pub type MSLUT0 = MsLut0;
+/// [`MsLut1`]
+pub type MSLUT1 = MsLut1;
+/// [`MsLut2`]
+pub type MSLUT2 = MsLut2;
+/// [`MsLut3`]
+pub type MSLUT3 = MsLut3;
+/// [`MsLut4`]
+pub type MSLUT4 = MsLut4;
+/// [`MsLut5`]
+pub type MSLUT5 = MsLut5;
+/// [`MsLut6`]
+pub type MSLUT6 = MsLut6;
+/// [`MsLut7`]
+pub type MSLUT7 = MsLut7;
+/// [`MsLutSel`]
+pub type MSLUTSEL = MsLutSel;
+/// [`MsLutStart`]
+pub type MSLUTSTART = MsLutStart;
+
+/// [`MsCnt`]
+pub type MSCNT<const M: u8> = MsCnt<M>;
+/// [`MsCurAct`]
+pub type MSCURACT<const M: u8> = MsCurAct<M>;
+/// [`ChopConf`]
+pub type CHOPCONF<const M: u8> = ChopConf<M>;
+/// [`CoolConf`]
+pub type COOLCONF<const M: u8> = CoolConf<M>;
+/// [`DcCtrl`]
+pub type DCCTRL<const M: u8> = DcCtrl<M>;
+/// [`DrvStatus`]
+pub type DRV_STATUS<const M: u8> = DrvStatus<M>;
@@ -2,7 +2,10 @@
 //!
 //! The encoder register set offers all registers needed for proper ABN encoder operation.
 
-use super::Register;
+use super::{
+    address::Access, ClearOnRead, MotorIndexCheck, MotorRegister, ReadableRegister, Register,
+    WritableRegister,
+};
 use crate::bits::{read_bool_from_bit, read_from_bit, write_bool_to_bit, write_from_bit};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -10,6 +13,7 @@ use serde::{Deserialize, Serialize};
 /// ENCMODE: Encoder configuration and use of N channel
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EncMode<const N: u8> {
     /// pol_A: Required A polarity for an N channel event (false=neg., true=pos.)
     pub pol_a: bool,
@@ -56,6 +60,68 @@ pub struct EncMode<const N: u8> {
     pub latch_now: bool,
 }
 
+impl<const N: u8> EncMode<N> {
+    /// Bit offset of [`pol_a`](EncMode::pol_a)
+    pub const POL_A_SHIFT: u32 = 0;
+    /// Bits of [`pol_a`](EncMode::pol_a) in the register value
+    pub const POL_A_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`pol_b`](EncMode::pol_b)
+    pub const POL_B_SHIFT: u32 = 1;
+    /// Bits of [`pol_b`](EncMode::pol_b) in the register value
+    pub const POL_B_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`pol_n`](EncMode::pol_n)
+    pub const POL_N_SHIFT: u32 = 2;
+    /// Bits of [`pol_n`](EncMode::pol_n) in the register value
+    pub const POL_N_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`ignore_ab`](EncMode::ignore_ab)
+    pub const IGNORE_AB_SHIFT: u32 = 3;
+    /// Bits of [`ignore_ab`](EncMode::ignore_ab) in the register value
+    pub const IGNORE_AB_MASK: u32 = 0x00000008;
+
+    /// Bit offset of [`clr_cont`](EncMode::clr_cont)
+    pub const CLR_CONT_SHIFT: u32 = 4;
+    /// Bits of [`clr_cont`](EncMode::clr_cont) in the register value
+    pub const CLR_CONT_MASK: u32 = 0x00000010;
+
+    /// Bit offset of [`clr_once`](EncMode::clr_once)
+    pub const CLR_ONCE_SHIFT: u32 = 5;
+    /// Bits of [`clr_once`](EncMode::clr_once) in the register value
+    pub const CLR_ONCE_MASK: u32 = 0x00000020;
+
+    /// Bit offset of [`pos_edge`](EncMode::pos_edge)
+    pub const POS_EDGE_SHIFT: u32 = 6;
+    /// Bits of [`pos_edge`](EncMode::pos_edge) in the register value
+    pub const POS_EDGE_MASK: u32 = 0x00000040;
+
+    /// Bit offset of [`neg_edge`](EncMode::neg_edge)
+    pub const NEG_EDGE_SHIFT: u32 = 7;
+    /// Bits of [`neg_edge`](EncMode::neg_edge) in the register value
+    pub const NEG_EDGE_MASK: u32 = 0x00000080;
+
+    /// Bit offset of [`clr_enc_x`](EncMode::clr_enc_x)
+    pub const CLR_ENC_X_SHIFT: u32 = 8;
+    /// Bits of [`clr_enc_x`](EncMode::clr_enc_x) in the register value
+    pub const CLR_ENC_X_MASK: u32 = 0x00000100;
+
+    /// Bit offset of [`latch_x_act`](EncMode::latch_x_act)
+    pub const LATCH_X_ACT_SHIFT: u32 = 9;
+    /// Bits of [`latch_x_act`](EncMode::latch_x_act) in the register value
+    pub const LATCH_X_ACT_MASK: u32 = 0x00000200;
+
+    /// Bit offset of [`enc_sel_decimal`](EncMode::enc_sel_decimal)
+    pub const ENC_SEL_DECIMAL_SHIFT: u32 = 10;
+    /// Bits of [`enc_sel_decimal`](EncMode::enc_sel_decimal) in the register value
+    pub const ENC_SEL_DECIMAL_MASK: u32 = 0x00000400;
+
+    /// Bit offset of [`latch_now`](EncMode::latch_now)
+    pub const LATCH_NOW_SHIFT: u32 = 11;
+    /// Bits of [`latch_now`](EncMode::latch_now) in the register value
+    pub const LATCH_NOW_MASK: u32 = 0x00000800;
+}
+
 impl<const N: u8> Default for EncMode<N> {
     fn default() -> Self {
         Self::from(0u32)
@@ -64,6 +130,7 @@ impl<const N: u8> Default for EncMode<N> {
 
 impl<const N: u8> From<u32> for EncMode<N> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         Self {
             pol_a: read_bool_from_bit(data, 0),
             pol_b: read_bool_from_bit(data, 1),
@@ -83,6 +150,7 @@ impl<const N: u8> From<u32> for EncMode<N> {
 
 impl<const N: u8> From<EncMode<N>> for u32 {
     fn from(data: EncMode<N>) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         let mut value = 0;
         write_bool_to_bit(&mut value, 0, data.pol_a);
         write_bool_to_bit(&mut value, 1, data.pol_b);
@@ -101,16 +169,52 @@ impl<const N: u8> From<EncMode<N>> for u32 {
 }
 
 impl Register for EncMode<0> {
+    const MASK: u32 = Self::POL_A_MASK
+        | Self::POL_B_MASK
+        | Self::POL_N_MASK
+        | Self::IGNORE_AB_MASK
+        | Self::CLR_CONT_MASK
+        | Self::CLR_ONCE_MASK
+        | Self::POS_EDGE_MASK
+        | Self::NEG_EDGE_MASK
+        | Self::CLR_ENC_X_MASK
+        | Self::LATCH_X_ACT_MASK
+        | Self::ENC_SEL_DECIMAL_MASK
+        | Self::LATCH_NOW_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x38
     }
 }
+
+impl MotorRegister<0> for EncMode<0> {}
 impl Register for EncMode<1> {
+    const MASK: u32 = Self::POL_A_MASK
+        | Self::POL_B_MASK
+        | Self::POL_N_MASK
+        | Self::IGNORE_AB_MASK
+        | Self::CLR_CONT_MASK
+        | Self::CLR_ONCE_MASK
+        | Self::POS_EDGE_MASK
+        | Self::NEG_EDGE_MASK
+        | Self::CLR_ENC_X_MASK
+        | Self::LATCH_X_ACT_MASK
+        | Self::ENC_SEL_DECIMAL_MASK
+        | Self::LATCH_NOW_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x58
     }
 }
 
+impl MotorRegister<1> for EncMode<1> {}
+impl<const M: u8> ReadableRegister for EncMode<M> {}
+impl<const M: u8> WritableRegister for EncMode<M> {}
+
 #[cfg(test)]
 mod enc_mode {
     use super::*;
@@ -142,11 +246,19 @@ mod enc_mode {
 /// X_ENC: Actual encoder position (signed)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct XEnc<const N: u8> {
     /// Actual encoder position (signed)
     pub x_enc: i32,
 }
 
+impl<const N: u8> XEnc<N> {
+    /// Bit offset of [`x_enc`](XEnc::x_enc)
+    pub const X_ENC_SHIFT: u32 = 0;
+    /// Bits of [`x_enc`](XEnc::x_enc) in the register value
+    pub const X_ENC_MASK: u32 = 0xffffffff;
+}
+
 impl<const N: u8> Default for XEnc<N> {
     fn default() -> Self {
         Self::from(0u32)
@@ -155,6 +267,7 @@ impl<const N: u8> Default for XEnc<N> {
 
 impl<const N: u8> From<u32> for XEnc<N> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         Self {
             x_enc: read_from_bit(data, 0, 0xffffffff) as i32,
         }
@@ -163,6 +276,7 @@ impl<const N: u8> From<u32> for XEnc<N> {
 
 impl<const N: u8> From<XEnc<N>> for u32 {
     fn from(data: XEnc<N>) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffffffff, data.x_enc as u32);
         value
@@ -170,16 +284,30 @@ impl<const N: u8> From<XEnc<N>> for u32 {
 }
 
 impl Register for XEnc<0> {
+    const MASK: u32 = Self::X_ENC_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x39
     }
 }
+
+impl MotorRegister<0> for XEnc<0> {}
 impl Register for XEnc<1> {
+    const MASK: u32 = Self::X_ENC_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x59
     }
 }
 
+impl MotorRegister<1> for XEnc<1> {}
+impl<const M: u8> ReadableRegister for XEnc<M> {}
+impl<const M: u8> WritableRegister for XEnc<M> {}
+
 #[cfg(test)]
 mod x_enc {
     use super::*;
@@ -220,6 +348,7 @@ mod x_enc {
 /// Use the sign, to match rotation direction!
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EncConst<const N: u8> {
     /// integer part
     pub enc_const_int: i16,
@@ -248,6 +377,18 @@ impl<const N: u8> EncConst<N> {
     }
 }
 
+impl<const N: u8> EncConst<N> {
+    /// Bit offset of [`enc_const_frac`](EncConst::enc_const_frac)
+    pub const ENC_CONST_FRAC_SHIFT: u32 = 0;
+    /// Bits of [`enc_const_frac`](EncConst::enc_const_frac) in the register value
+    pub const ENC_CONST_FRAC_MASK: u32 = 0x0000ffff;
+
+    /// Bit offset of [`enc_const_int`](EncConst::enc_const_int)
+    pub const ENC_CONST_INT_SHIFT: u32 = 16;
+    /// Bits of [`enc_const_int`](EncConst::enc_const_int) in the register value
+    pub const ENC_CONST_INT_MASK: u32 = 0xffff0000;
+}
+
 impl<const N: u8> Default for EncConst<N> {
     fn default() -> Self {
         Self::from(0u32)
@@ -256,6 +397,7 @@ impl<const N: u8> Default for EncConst<N> {
 
 impl<const N: u8> From<u32> for EncConst<N> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         Self {
             enc_const_frac: read_from_bit(data, 0, 0xffff) as u16,
             enc_const_int: read_from_bit(data, 16, 0xffff) as i16,
@@ -265,6 +407,7 @@ impl<const N: u8> From<u32> for EncConst<N> {
 
 impl<const N: u8> From<EncConst<N>> for u32 {
     fn from(data: EncConst<N>) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffff, data.enc_const_frac as u32);
         write_from_bit(&mut value, 16, 0xffff, data.enc_const_int as u32);
@@ -273,16 +416,29 @@ impl<const N: u8> From<EncConst<N>> for u32 {
 }
 
 impl Register for EncConst<0> {
+    const MASK: u32 = Self::ENC_CONST_FRAC_MASK | Self::ENC_CONST_INT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x3A
     }
 }
+
+impl MotorRegister<0> for EncConst<0> {}
 impl Register for EncConst<1> {
+    const MASK: u32 = Self::ENC_CONST_FRAC_MASK | Self::ENC_CONST_INT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x5A
     }
 }
 
+impl MotorRegister<1> for EncConst<1> {}
+impl<const M: u8> WritableRegister for EncConst<M> {}
+
 #[cfg(test)]
 mod enc_const {
     use super::*;
@@ -353,6 +509,7 @@ mod enc_const {
 /// ENC_STATUS
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EncStatus<const N: u8> {
     /// n_event:
     /// - true: Encoder N event detected. Status bit is cleared on read: Read (R) + clear (C)
@@ -360,6 +517,13 @@ pub struct EncStatus<const N: u8> {
     pub enc_status: bool,
 }
 
+impl<const N: u8> EncStatus<N> {
+    /// Bit offset of [`enc_status`](EncStatus::enc_status)
+    pub const ENC_STATUS_SHIFT: u32 = 0;
+    /// Bits of [`enc_status`](EncStatus::enc_status) in the register value
+    pub const ENC_STATUS_MASK: u32 = 0x00000001;
+}
+
 impl<const N: u8> Default for EncStatus<N> {
     fn default() -> Self {
         Self::from(0u32)
@@ -368,6 +532,7 @@ impl<const N: u8> Default for EncStatus<N> {
 
 impl<const N: u8> From<u32> for EncStatus<N> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         Self {
             enc_status: read_bool_from_bit(data, 0),
         }
@@ -376,6 +541,7 @@ impl<const N: u8> From<u32> for EncStatus<N> {
 
 impl<const N: u8> From<EncStatus<N>> for u32 {
     fn from(data: EncStatus<N>) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         let mut value = 0;
         write_bool_to_bit(&mut value, 0, data.enc_status);
         value
@@ -383,16 +549,30 @@ impl<const N: u8> From<EncStatus<N>> for u32 {
 }
 
 impl Register for EncStatus<0> {
+    const MASK: u32 = Self::ENC_STATUS_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadClear;
+
     fn addr() -> u8 {
         0x3B
     }
 }
+
+impl MotorRegister<0> for EncStatus<0> {}
 impl Register for EncStatus<1> {
+    const MASK: u32 = Self::ENC_STATUS_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadClear;
+
     fn addr() -> u8 {
         0x5B
     }
 }
 
+impl MotorRegister<1> for EncStatus<1> {}
+impl<const M: u8> ReadableRegister for EncStatus<M> {}
+impl<const M: u8> ClearOnRead for EncStatus<M> {}
+
 #[cfg(test)]
 mod enc_status {
     use super::*;
@@ -421,11 +601,19 @@ mod enc_status {
 /// ENC_LATCH: Encoder position X_ENC latched on N event
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EncLatch<const N: u8> {
     /// Encoder position X_ENC latched on N event
     pub enc_latch: i32,
 }
 
+impl<const N: u8> EncLatch<N> {
+    /// Bit offset of [`enc_latch`](EncLatch::enc_latch)
+    pub const ENC_LATCH_SHIFT: u32 = 0;
+    /// Bits of [`enc_latch`](EncLatch::enc_latch) in the register value
+    pub const ENC_LATCH_MASK: u32 = 0xffffffff;
+}
+
 impl<const N: u8> Default for EncLatch<N> {
     fn default() -> Self {
         Self::from(0u32)
@@ -434,6 +622,7 @@ impl<const N: u8> Default for EncLatch<N> {
 
 impl<const N: u8> From<u32> for EncLatch<N> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         Self {
             enc_latch: read_from_bit(data, 0, 0xffffffff) as i32,
         }
@@ -442,6 +631,7 @@ impl<const N: u8> From<u32> for EncLatch<N> {
 
 impl<const N: u8> From<EncLatch<N>> for u32 {
     fn from(data: EncLatch<N>) -> Self {
+        let () = MotorIndexCheck::<N>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffffffff, data.enc_latch as u32);
         value
@@ -449,16 +639,29 @@ impl<const N: u8> From<EncLatch<N>> for u32 {
 }
 
 impl Register for EncLatch<0> {
+    const MASK: u32 = Self::ENC_LATCH_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x3C
     }
 }
+
+impl MotorRegister<0> for EncLatch<0> {}
 impl Register for EncLatch<1> {
+    const MASK: u32 = Self::ENC_LATCH_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x5C
     }
 }
 
+impl MotorRegister<1> for EncLatch<1> {}
+impl<const M: u8> ReadableRegister for EncLatch<M> {}
+
 #[cfg(test)]
 mod enc_latch {
     use super::*;
@@ -6,14 +6,20 @@
 //! - slave address configuration
 //! - and I/O configuration
 
-use super::Register;
+use super::{
+    address::Access, check_field, ClearOnRead, FieldOutOfRange, GlobalRegister, ReadableRegister,
+    Register, WritableRegister,
+};
 use crate::bits::{read_bool_from_bit, read_from_bit, write_bool_to_bit, write_from_bit};
+use crate::flags::fmt_flags;
+use core::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// GCONF: Global configuration flags
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GConf {
     /// single_driver:
     /// - false: Two motors can be operated.
@@ -53,6 +59,7 @@ pub struct GConf {
     /// - true: Enable analog test output on pin REFR2 SLAVEADDR selects the function of REFR2: 0..4: T120, DAC1, VDDH1, DAC2, VDDH2
     ///
     /// Attention: Not for user, set to false for normal operation!
+    /// It is never written to the chip (encoded as false) unless the `debug-unlock` feature is enabled.
     pub test_mode: bool,
     /// shaft1:
     /// - true: Inverse motor 1 direction
@@ -68,6 +75,73 @@ pub struct GConf {
     pub dc_sync: bool,
 }
 
+impl GConf {
+    /// Bit offset of [`single_diver`](GConf::single_diver)
+    pub const SINGLE_DIVER_SHIFT: u32 = 0;
+    /// Bits of [`single_diver`](GConf::single_diver) in the register value
+    pub const SINGLE_DIVER_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`stepdir1_enable`](GConf::stepdir1_enable)
+    pub const STEPDIR1_ENABLE_SHIFT: u32 = 1;
+    /// Bits of [`stepdir1_enable`](GConf::stepdir1_enable) in the register value
+    pub const STEPDIR1_ENABLE_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`stepdir2_enable`](GConf::stepdir2_enable)
+    pub const STEPDIR2_ENABLE_SHIFT: u32 = 2;
+    /// Bits of [`stepdir2_enable`](GConf::stepdir2_enable) in the register value
+    pub const STEPDIR2_ENABLE_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`poscmp_enable`](GConf::poscmp_enable)
+    pub const POSCMP_ENABLE_SHIFT: u32 = 3;
+    /// Bits of [`poscmp_enable`](GConf::poscmp_enable) in the register value
+    pub const POSCMP_ENABLE_MASK: u32 = 0x00000008;
+
+    /// Bit offset of [`enc1_refsel`](GConf::enc1_refsel)
+    pub const ENC1_REFSEL_SHIFT: u32 = 4;
+    /// Bits of [`enc1_refsel`](GConf::enc1_refsel) in the register value
+    pub const ENC1_REFSEL_MASK: u32 = 0x00000010;
+
+    /// Bit offset of [`enc2_enable`](GConf::enc2_enable)
+    pub const ENC2_ENABLE_SHIFT: u32 = 5;
+    /// Bits of [`enc2_enable`](GConf::enc2_enable) in the register value
+    pub const ENC2_ENABLE_MASK: u32 = 0x00000020;
+
+    /// Bit offset of [`enc2_refsel`](GConf::enc2_refsel)
+    pub const ENC2_REFSEL_SHIFT: u32 = 6;
+    /// Bits of [`enc2_refsel`](GConf::enc2_refsel) in the register value
+    pub const ENC2_REFSEL_MASK: u32 = 0x00000040;
+
+    /// Bit offset of [`test_mode`](GConf::test_mode)
+    pub const TEST_MODE_SHIFT: u32 = 7;
+    /// Bits of [`test_mode`](GConf::test_mode) in the register value
+    pub const TEST_MODE_MASK: u32 = 0x00000080;
+
+    /// Bit offset of [`shaft1`](GConf::shaft1)
+    pub const SHAFT1_SHIFT: u32 = 8;
+    /// Bits of [`shaft1`](GConf::shaft1) in the register value
+    pub const SHAFT1_MASK: u32 = 0x00000100;
+
+    /// Bit offset of [`shaft2`](GConf::shaft2)
+    pub const SHAFT2_SHIFT: u32 = 9;
+    /// Bits of [`shaft2`](GConf::shaft2) in the register value
+    pub const SHAFT2_MASK: u32 = 0x00000200;
+
+    /// Bit offset of [`lock_gconf`](GConf::lock_gconf)
+    pub const LOCK_GCONF_SHIFT: u32 = 10;
+    /// Bits of [`lock_gconf`](GConf::lock_gconf) in the register value
+    pub const LOCK_GCONF_MASK: u32 = 0x00000400;
+
+    /// Bit offset of [`dc_sync`](GConf::dc_sync)
+    pub const DC_SYNC_SHIFT: u32 = 11;
+    /// Bits of [`dc_sync`](GConf::dc_sync) in the register value
+    pub const DC_SYNC_MASK: u32 = 0x00000800;
+    // test_mode is only encoded with the debug-unlock feature
+    #[cfg(feature = "debug-unlock")]
+    const TEST_MODE_BITS: u32 = Self::TEST_MODE_MASK;
+    #[cfg(not(feature = "debug-unlock"))]
+    const TEST_MODE_BITS: u32 = 0;
+}
+
 impl Default for GConf {
     fn default() -> Self {
         Self::from(0u32)
@@ -103,6 +177,8 @@ impl From<GConf> for u32 {
         write_bool_to_bit(&mut value, 4, data.enc1_refsel);
         write_bool_to_bit(&mut value, 5, data.enc2_enable);
         write_bool_to_bit(&mut value, 6, data.enc2_refsel);
+        // test_mode is only encoded with the debug-unlock feature
+        #[cfg(feature = "debug-unlock")]
         write_bool_to_bit(&mut value, 7, data.test_mode);
         write_bool_to_bit(&mut value, 8, data.shaft1);
         write_bool_to_bit(&mut value, 9, data.shaft2);
@@ -113,11 +189,30 @@ impl From<GConf> for u32 {
 }
 
 impl Register for GConf {
+    const MASK: u32 = Self::SINGLE_DIVER_MASK
+        | Self::STEPDIR1_ENABLE_MASK
+        | Self::STEPDIR2_ENABLE_MASK
+        | Self::POSCMP_ENABLE_MASK
+        | Self::ENC1_REFSEL_MASK
+        | Self::ENC2_ENABLE_MASK
+        | Self::ENC2_REFSEL_MASK
+        | Self::TEST_MODE_BITS
+        | Self::SHAFT1_MASK
+        | Self::SHAFT2_MASK
+        | Self::LOCK_GCONF_MASK
+        | Self::DC_SYNC_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x00
     }
 }
 
+impl GlobalRegister for GConf {}
+impl ReadableRegister for GConf {}
+impl WritableRegister for GConf {}
+
 #[cfg(test)]
 mod g_conf {
     use super::*;
@@ -132,6 +227,20 @@ mod g_conf {
         )
     }
     #[test]
+    fn test_mode() {
+        let g_conf = GConf {
+            test_mode: true,
+            ..Default::default()
+        };
+        let expected = if cfg!(feature = "debug-unlock") {
+            0x00000080
+        } else {
+            0
+        };
+        assert_eq!(u32::from(g_conf), expected);
+        assert!(GConf::from(0x00000080).test_mode);
+    }
+    #[test]
     fn from_u32() {
         assert_eq!(
             GConf::from(0x00000008),
@@ -146,6 +255,7 @@ mod g_conf {
 /// GSTAT: Global status flags
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GStat {
     /// reset:
     /// - true: Indicates that the IC has been reset since the last read access to GSTAT. All registers have been cleared to reset values.
@@ -163,6 +273,28 @@ pub struct GStat {
     pub uv_cp: bool,
 }
 
+impl GStat {
+    /// Bit offset of [`reset`](GStat::reset)
+    pub const RESET_SHIFT: u32 = 0;
+    /// Bits of [`reset`](GStat::reset) in the register value
+    pub const RESET_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`drv_err1`](GStat::drv_err1)
+    pub const DRV_ERR1_SHIFT: u32 = 1;
+    /// Bits of [`drv_err1`](GStat::drv_err1) in the register value
+    pub const DRV_ERR1_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`drv_err2`](GStat::drv_err2)
+    pub const DRV_ERR2_SHIFT: u32 = 2;
+    /// Bits of [`drv_err2`](GStat::drv_err2) in the register value
+    pub const DRV_ERR2_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`uv_cp`](GStat::uv_cp)
+    pub const UV_CP_SHIFT: u32 = 3;
+    /// Bits of [`uv_cp`](GStat::uv_cp) in the register value
+    pub const UV_CP_MASK: u32 = 0x00000008;
+}
+
 impl Default for GStat {
     fn default() -> Self {
         Self::from(0u32)
@@ -190,11 +322,39 @@ impl From<GStat> for u32 {
 }
 
 impl Register for GStat {
+    const MASK: u32 =
+        Self::RESET_MASK | Self::DRV_ERR1_MASK | Self::DRV_ERR2_MASK | Self::UV_CP_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadClear;
+
     fn addr() -> u8 {
         0x01
     }
 }
 
+impl GlobalRegister for GStat {}
+impl ReadableRegister for GStat {}
+impl ClearOnRead for GStat {}
+
+impl GStat {
+    /// Flags with their datasheet names
+    pub(crate) fn flags(&self) -> [(&'static str, bool); 4] {
+        [
+            ("reset", self.reset),
+            ("drv_err1", self.drv_err1),
+            ("drv_err2", self.drv_err2),
+            ("uv_cp", self.uv_cp),
+        ]
+    }
+}
+
+impl fmt::Display for GStat {
+    /// Names of the set flags, e.g. `[reset drv_err1]`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_flags(f, &self.flags())
+    }
+}
+
 #[cfg(test)]
 mod g_stat {
     use super::*;
@@ -225,6 +385,7 @@ mod g_stat {
 /// IFCNT: Interface transmission counter
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IfCnt {
     /// Interface transmission counter. This register becomes incremented with each successful UART interface write access.
     /// It can be read out to check the serial transmission for lost data.
@@ -233,6 +394,13 @@ pub struct IfCnt {
     pub if_cnt: u8,
 }
 
+impl IfCnt {
+    /// Bit offset of [`if_cnt`](IfCnt::if_cnt)
+    pub const IF_CNT_SHIFT: u32 = 0;
+    /// Bits of [`if_cnt`](IfCnt::if_cnt) in the register value
+    pub const IF_CNT_MASK: u32 = 0x000000ff;
+}
+
 impl Default for IfCnt {
     fn default() -> Self {
         Self::from(0u32)
@@ -255,11 +423,18 @@ impl From<IfCnt> for u32 {
 }
 
 impl Register for IfCnt {
+    const MASK: u32 = Self::IF_CNT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x02
     }
 }
 
+impl GlobalRegister for IfCnt {}
+impl ReadableRegister for IfCnt {}
+
 #[cfg(test)]
 mod if_cnt {
     use super::*;
@@ -289,6 +464,7 @@ mod if_cnt {
 /// SLAVECONF
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SlaveConf {
     /// SLAVEADDR:
     /// Sets the address of unit for the UART interface. The address becomes incremented by one when the external address pin NEXTADDR is active.
@@ -309,6 +485,28 @@ pub struct SlaveConf {
     pub send_delay: u8,
 }
 
+impl SlaveConf {
+    /// Bit offset of [`slave_addr`](SlaveConf::slave_addr)
+    pub const SLAVE_ADDR_SHIFT: u32 = 0;
+    /// Bits of [`slave_addr`](SlaveConf::slave_addr) in the register value
+    pub const SLAVE_ADDR_MASK: u32 = 0x000000ff;
+
+    /// Bit offset of [`send_delay`](SlaveConf::send_delay)
+    pub const SEND_DELAY_SHIFT: u32 = 8;
+    /// Bits of [`send_delay`](SlaveConf::send_delay) in the register value
+    pub const SEND_DELAY_MASK: u32 = 0x00000f00;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "send_delay",
+            self.send_delay as u32,
+            Self::SEND_DELAY_MASK >> Self::SEND_DELAY_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl Default for SlaveConf {
     fn default() -> Self {
         Self::from(0u32)
@@ -334,11 +532,18 @@ impl From<SlaveConf> for u32 {
 }
 
 impl Register for SlaveConf {
+    const MASK: u32 = Self::SLAVE_ADDR_MASK | Self::SEND_DELAY_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x03
     }
 }
 
+impl GlobalRegister for SlaveConf {}
+impl WritableRegister for SlaveConf {}
+
 #[cfg(test)]
 mod slave_conf {
     use super::*;
@@ -370,6 +575,7 @@ mod slave_conf {
 /// INPUT: Reads the digital state of all input pins available plus the state of IO pins set to output.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Input {
     /// io0_in: IO0 polarity
     pub io0: bool,
@@ -394,6 +600,58 @@ pub struct Input {
     pub version: u8,
 }
 
+impl Input {
+    /// Bit offset of [`io0`](Input::io0)
+    pub const IO0_SHIFT: u32 = 0;
+    /// Bits of [`io0`](Input::io0) in the register value
+    pub const IO0_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`io1`](Input::io1)
+    pub const IO1_SHIFT: u32 = 1;
+    /// Bits of [`io1`](Input::io1) in the register value
+    pub const IO1_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`io2`](Input::io2)
+    pub const IO2_SHIFT: u32 = 2;
+    /// Bits of [`io2`](Input::io2) in the register value
+    pub const IO2_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`io3`](Input::io3)
+    pub const IO3_SHIFT: u32 = 3;
+    /// Bits of [`io3`](Input::io3) in the register value
+    pub const IO3_MASK: u32 = 0x00000008;
+
+    /// Bit offset of [`iop`](Input::iop)
+    pub const IOP_SHIFT: u32 = 4;
+    /// Bits of [`iop`](Input::iop) in the register value
+    pub const IOP_MASK: u32 = 0x00000010;
+
+    /// Bit offset of [`ion`](Input::ion)
+    pub const ION_SHIFT: u32 = 5;
+    /// Bits of [`ion`](Input::ion) in the register value
+    pub const ION_MASK: u32 = 0x00000020;
+
+    /// Bit offset of [`next_addr`](Input::next_addr)
+    pub const NEXT_ADDR_SHIFT: u32 = 6;
+    /// Bits of [`next_addr`](Input::next_addr) in the register value
+    pub const NEXT_ADDR_MASK: u32 = 0x00000040;
+
+    /// Bit offset of [`drv_enn`](Input::drv_enn)
+    pub const DRV_ENN_SHIFT: u32 = 7;
+    /// Bits of [`drv_enn`](Input::drv_enn) in the register value
+    pub const DRV_ENN_MASK: u32 = 0x00000080;
+
+    /// Bit offset of [`sw_comp`](Input::sw_comp)
+    pub const SW_COMP_SHIFT: u32 = 8;
+    /// Bits of [`sw_comp`](Input::sw_comp) in the register value
+    pub const SW_COMP_MASK: u32 = 0x00000100;
+
+    /// Bit offset of [`version`](Input::version)
+    pub const VERSION_SHIFT: u32 = 24;
+    /// Bits of [`version`](Input::version) in the register value
+    pub const VERSION_MASK: u32 = 0xff000000;
+}
+
 impl Default for Input {
     fn default() -> Self {
         Self::from(0u32)
@@ -435,11 +693,27 @@ impl From<Input> for u32 {
 }
 
 impl Register for Input {
+    const MASK: u32 = Self::IO0_MASK
+        | Self::IO1_MASK
+        | Self::IO2_MASK
+        | Self::IO3_MASK
+        | Self::IOP_MASK
+        | Self::ION_MASK
+        | Self::NEXT_ADDR_MASK
+        | Self::DRV_ENN_MASK
+        | Self::SW_COMP_MASK
+        | Self::VERSION_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x04
     }
 }
 
+impl GlobalRegister for Input {}
+impl ReadableRegister for Input {}
+
 #[cfg(test)]
 mod input {
     use super::*;
@@ -469,6 +743,7 @@ mod input {
 /// OUTPUT: Sets the IO output pin polarity and data direction.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Output {
     /// io0_out: IO0 output polarity
     pub io0: bool,
@@ -484,6 +759,38 @@ pub struct Output {
     pub io_ddr2: bool,
 }
 
+impl Output {
+    /// Bit offset of [`io0`](Output::io0)
+    pub const IO0_SHIFT: u32 = 0;
+    /// Bits of [`io0`](Output::io0) in the register value
+    pub const IO0_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`io1`](Output::io1)
+    pub const IO1_SHIFT: u32 = 1;
+    /// Bits of [`io1`](Output::io1) in the register value
+    pub const IO1_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`io2`](Output::io2)
+    pub const IO2_SHIFT: u32 = 2;
+    /// Bits of [`io2`](Output::io2) in the register value
+    pub const IO2_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`io_ddr0`](Output::io_ddr0)
+    pub const IO_DDR0_SHIFT: u32 = 8;
+    /// Bits of [`io_ddr0`](Output::io_ddr0) in the register value
+    pub const IO_DDR0_MASK: u32 = 0x00000100;
+
+    /// Bit offset of [`io_ddr1`](Output::io_ddr1)
+    pub const IO_DDR1_SHIFT: u32 = 9;
+    /// Bits of [`io_ddr1`](Output::io_ddr1) in the register value
+    pub const IO_DDR1_MASK: u32 = 0x00000200;
+
+    /// Bit offset of [`io_ddr2`](Output::io_ddr2)
+    pub const IO_DDR2_SHIFT: u32 = 10;
+    /// Bits of [`io_ddr2`](Output::io_ddr2) in the register value
+    pub const IO_DDR2_MASK: u32 = 0x00000400;
+}
+
 impl Default for Output {
     fn default() -> Self {
         Self::from(0u32)
@@ -517,11 +824,23 @@ impl From<Output> for u32 {
 }
 
 impl Register for Output {
+    const MASK: u32 = Self::IO0_MASK
+        | Self::IO1_MASK
+        | Self::IO2_MASK
+        | Self::IO_DDR0_MASK
+        | Self::IO_DDR1_MASK
+        | Self::IO_DDR2_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x04
     }
 }
 
+impl GlobalRegister for Output {}
+impl WritableRegister for Output {}
+
 #[cfg(test)]
 mod output {
     use super::*;
@@ -559,11 +878,19 @@ mod output {
 /// - Output PP becomes high. It returns to a low state, if the positions mismatch.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct XCompare {
     /// Position comparison register for motor 1 position strobe.
     pub x_compare: u32,
 }
 
+impl XCompare {
+    /// Bit offset of [`x_compare`](XCompare::x_compare)
+    pub const X_COMPARE_SHIFT: u32 = 0;
+    /// Bits of [`x_compare`](XCompare::x_compare) in the register value
+    pub const X_COMPARE_MASK: u32 = 0xffffffff;
+}
+
 impl Default for XCompare {
     fn default() -> Self {
         Self::from(0u32)
@@ -587,11 +914,18 @@ impl From<XCompare> for u32 {
 }
 
 impl Register for XCompare {
+    const MASK: u32 = Self::X_COMPARE_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x05
     }
 }
 
+impl GlobalRegister for XCompare {}
+impl WritableRegister for XCompare {}
+
 #[cfg(test)]
 mod x_compare {
     use super::*;
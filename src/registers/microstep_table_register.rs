@@ -6,7 +6,9 @@
 //! This is the differential coding for the first quarter of a wave.
 //! Start values for CUR_A and CUR_B are stored for MSCNT position 0 in START_SIN and START_SIN90.
 
-use super::Register;
+use super::{
+    address::Access, check_field, FieldOutOfRange, GlobalRegister, Register, WritableRegister,
+};
 use crate::bits::{read_from_bit, write_from_bit};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -14,14 +16,22 @@ use serde::{Deserialize, Serialize};
 /// MSLUT\[0\]: Microstep table entries 0..31
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut0 {
     /// Microstep table entries 0..31
     pub ms_lut0: u32,
 }
 
+impl MsLut0 {
+    /// Bit offset of [`ms_lut0`](MsLut0::ms_lut0)
+    pub const MS_LUT0_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut0`](MsLut0::ms_lut0) in the register value
+    pub const MS_LUT0_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut0 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -42,11 +52,18 @@ impl From<MsLut0> for u32 {
 }
 
 impl Register for MsLut0 {
+    const MASK: u32 = Self::MS_LUT0_MASK;
+    const RESET_VALUE: u32 = 0xAAAA_B554;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x60
     }
 }
 
+impl GlobalRegister for MsLut0 {}
+impl WritableRegister for MsLut0 {}
+
 #[cfg(test)]
 mod ms_lut0 {
     use super::*;
@@ -75,14 +92,22 @@ mod ms_lut0 {
 /// MSLUT\[1\]: Microstep table entries 32..63
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut1 {
     /// Microstep table entries 32..63
     pub ms_lut1: u32,
 }
 
+impl MsLut1 {
+    /// Bit offset of [`ms_lut1`](MsLut1::ms_lut1)
+    pub const MS_LUT1_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut1`](MsLut1::ms_lut1) in the register value
+    pub const MS_LUT1_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut1 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -103,11 +128,18 @@ impl From<MsLut1> for u32 {
 }
 
 impl Register for MsLut1 {
+    const MASK: u32 = Self::MS_LUT1_MASK;
+    const RESET_VALUE: u32 = 0x4A95_54AA;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x61
     }
 }
 
+impl GlobalRegister for MsLut1 {}
+impl WritableRegister for MsLut1 {}
+
 #[cfg(test)]
 mod ms_lut1 {
     use super::*;
@@ -135,14 +167,22 @@ mod ms_lut1 {
 /// MSLUT\[2\]: Microstep table entries 64..95
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut2 {
     /// Microstep table entries 64..95
     pub ms_lut2: u32,
 }
 
+impl MsLut2 {
+    /// Bit offset of [`ms_lut2`](MsLut2::ms_lut2)
+    pub const MS_LUT2_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut2`](MsLut2::ms_lut2) in the register value
+    pub const MS_LUT2_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut2 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -163,11 +203,18 @@ impl From<MsLut2> for u32 {
 }
 
 impl Register for MsLut2 {
+    const MASK: u32 = Self::MS_LUT2_MASK;
+    const RESET_VALUE: u32 = 0x2449_2929;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x62
     }
 }
 
+impl GlobalRegister for MsLut2 {}
+impl WritableRegister for MsLut2 {}
+
 #[cfg(test)]
 mod ms_lut2 {
     use super::*;
@@ -195,14 +242,22 @@ mod ms_lut2 {
 /// MSLUT\[3\]: Microstep table entries 96..127
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut3 {
     /// Microstep table entries 96..127
     pub ms_lut3: u32,
 }
 
+impl MsLut3 {
+    /// Bit offset of [`ms_lut3`](MsLut3::ms_lut3)
+    pub const MS_LUT3_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut3`](MsLut3::ms_lut3) in the register value
+    pub const MS_LUT3_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut3 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -223,11 +278,18 @@ impl From<MsLut3> for u32 {
 }
 
 impl Register for MsLut3 {
+    const MASK: u32 = Self::MS_LUT3_MASK;
+    const RESET_VALUE: u32 = 0x1010_4222;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x63
     }
 }
 
+impl GlobalRegister for MsLut3 {}
+impl WritableRegister for MsLut3 {}
+
 #[cfg(test)]
 mod ms_lut3 {
     use super::*;
@@ -255,14 +317,22 @@ mod ms_lut3 {
 /// MSLUT\[4\]: Microstep table entries 128..159
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut4 {
     /// Microstep table entries 128..159
     pub ms_lut4: u32,
 }
 
+impl MsLut4 {
+    /// Bit offset of [`ms_lut4`](MsLut4::ms_lut4)
+    pub const MS_LUT4_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut4`](MsLut4::ms_lut4) in the register value
+    pub const MS_LUT4_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut4 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -283,11 +353,18 @@ impl From<MsLut4> for u32 {
 }
 
 impl Register for MsLut4 {
+    const MASK: u32 = Self::MS_LUT4_MASK;
+    const RESET_VALUE: u32 = 0xFBFF_FFFF;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x64
     }
 }
 
+impl GlobalRegister for MsLut4 {}
+impl WritableRegister for MsLut4 {}
+
 #[cfg(test)]
 mod ms_lut4 {
     use super::*;
@@ -316,14 +393,22 @@ mod ms_lut4 {
 /// MSLUT\[5\]: Microstep table entries 160..191
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut5 {
     /// Microstep table entries 160..191
     pub ms_lut5: u32,
 }
 
+impl MsLut5 {
+    /// Bit offset of [`ms_lut5`](MsLut5::ms_lut5)
+    pub const MS_LUT5_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut5`](MsLut5::ms_lut5) in the register value
+    pub const MS_LUT5_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut5 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -344,11 +429,18 @@ impl From<MsLut5> for u32 {
 }
 
 impl Register for MsLut5 {
+    const MASK: u32 = Self::MS_LUT5_MASK;
+    const RESET_VALUE: u32 = 0xB5BB_777D;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x65
     }
 }
 
+impl GlobalRegister for MsLut5 {}
+impl WritableRegister for MsLut5 {}
+
 #[cfg(test)]
 mod ms_lut5 {
     use super::*;
@@ -377,14 +469,22 @@ mod ms_lut5 {
 /// MSLUT\[6\]: Microstep table entries 192..223
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut6 {
     /// Microstep table entries 192..223
     pub ms_lut6: u32,
 }
 
+impl MsLut6 {
+    /// Bit offset of [`ms_lut6`](MsLut6::ms_lut6)
+    pub const MS_LUT6_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut6`](MsLut6::ms_lut6) in the register value
+    pub const MS_LUT6_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut6 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -405,11 +505,18 @@ impl From<MsLut6> for u32 {
 }
 
 impl Register for MsLut6 {
+    const MASK: u32 = Self::MS_LUT6_MASK;
+    const RESET_VALUE: u32 = 0x4929_5556;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x66
     }
 }
 
+impl GlobalRegister for MsLut6 {}
+impl WritableRegister for MsLut6 {}
+
 #[cfg(test)]
 mod ms_lut6 {
     use super::*;
@@ -438,14 +545,22 @@ mod ms_lut6 {
 /// MSLUT\[7\]: Microstep table entries 224..255
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLut7 {
     /// Microstep table entries 224..255
     pub ms_lut7: u32,
 }
 
+impl MsLut7 {
+    /// Bit offset of [`ms_lut7`](MsLut7::ms_lut7)
+    pub const MS_LUT7_SHIFT: u32 = 0;
+    /// Bits of [`ms_lut7`](MsLut7::ms_lut7) in the register value
+    pub const MS_LUT7_MASK: u32 = 0xffffffff;
+}
+
 impl Default for MsLut7 {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -466,11 +581,18 @@ impl From<MsLut7> for u32 {
 }
 
 impl Register for MsLut7 {
+    const MASK: u32 = Self::MS_LUT7_MASK;
+    const RESET_VALUE: u32 = 0x0040_4222;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x67
     }
 }
 
+impl GlobalRegister for MsLut7 {}
+impl WritableRegister for MsLut7 {}
+
 #[cfg(test)]
 mod ms_lut7 {
     use super::*;
@@ -513,6 +635,7 @@ mod ms_lut7 {
 /// For defined response the values shall satisfy: 0<X1<X2<X3
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLutSel {
     /// W0: LUT width select from ofs00 to ofs(X1-1)
     pub w0: u8,
@@ -530,9 +653,55 @@ pub struct MsLutSel {
     pub x3: u8,
 }
 
+impl MsLutSel {
+    /// Bit offset of [`w0`](MsLutSel::w0)
+    pub const W0_SHIFT: u32 = 0;
+    /// Bits of [`w0`](MsLutSel::w0) in the register value
+    pub const W0_MASK: u32 = 0x00000003;
+
+    /// Bit offset of [`w1`](MsLutSel::w1)
+    pub const W1_SHIFT: u32 = 2;
+    /// Bits of [`w1`](MsLutSel::w1) in the register value
+    pub const W1_MASK: u32 = 0x0000000c;
+
+    /// Bit offset of [`w2`](MsLutSel::w2)
+    pub const W2_SHIFT: u32 = 4;
+    /// Bits of [`w2`](MsLutSel::w2) in the register value
+    pub const W2_MASK: u32 = 0x00000030;
+
+    /// Bit offset of [`w3`](MsLutSel::w3)
+    pub const W3_SHIFT: u32 = 6;
+    /// Bits of [`w3`](MsLutSel::w3) in the register value
+    pub const W3_MASK: u32 = 0x000000c0;
+
+    /// Bit offset of [`x1`](MsLutSel::x1)
+    pub const X1_SHIFT: u32 = 8;
+    /// Bits of [`x1`](MsLutSel::x1) in the register value
+    pub const X1_MASK: u32 = 0x0000ff00;
+
+    /// Bit offset of [`x2`](MsLutSel::x2)
+    pub const X2_SHIFT: u32 = 16;
+    /// Bits of [`x2`](MsLutSel::x2) in the register value
+    pub const X2_MASK: u32 = 0x00ff0000;
+
+    /// Bit offset of [`x3`](MsLutSel::x3)
+    pub const X3_SHIFT: u32 = 24;
+    /// Bits of [`x3`](MsLutSel::x3) in the register value
+    pub const X3_MASK: u32 = 0xff000000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field("w0", self.w0 as u32, Self::W0_MASK >> Self::W0_SHIFT)?;
+        check_field("w1", self.w1 as u32, Self::W1_MASK >> Self::W1_SHIFT)?;
+        check_field("w2", self.w2 as u32, Self::W2_MASK >> Self::W2_SHIFT)?;
+        check_field("w3", self.w3 as u32, Self::W3_MASK >> Self::W3_SHIFT)?;
+        Ok(self)
+    }
+}
+
 impl Default for MsLutSel {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::reset()
     }
 }
 
@@ -565,11 +734,24 @@ impl From<MsLutSel> for u32 {
 }
 
 impl Register for MsLutSel {
+    const MASK: u32 = Self::W0_MASK
+        | Self::W1_MASK
+        | Self::W2_MASK
+        | Self::W3_MASK
+        | Self::X1_MASK
+        | Self::X2_MASK
+        | Self::X3_MASK;
+    const RESET_VALUE: u32 = 0xFFFF_8056;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x68
     }
 }
 
+impl GlobalRegister for MsLutSel {}
+impl WritableRegister for MsLutSel {}
+
 #[cfg(test)]
 mod ms_lut_sel {
     use super::*;
@@ -578,7 +760,7 @@ mod ms_lut_sel {
         assert_eq!(
             u32::from(MsLutSel {
                 x1: 0x66,
-                ..Default::default()
+                ..MsLutSel::from(0)
             }),
             0x00006600
         )
@@ -589,7 +771,7 @@ mod ms_lut_sel {
             MsLutSel::from(0x00006600),
             MsLutSel {
                 x1: 0x66,
-                ..Default::default()
+                ..MsLutSel::from(0)
             },
         )
     }
@@ -600,6 +782,7 @@ mod ms_lut_sel {
 /// Start values are transferred to the microstep registers CUR_A and CUR_B, whenever the reference position MSCNT=0 is passed.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsLutStart {
     /// START_SIN: gives the absolute current at microstep table entry 0.
     pub start_sin: u8,
@@ -607,6 +790,18 @@ pub struct MsLutStart {
     pub start_sin90: u8,
 }
 
+impl MsLutStart {
+    /// Bit offset of [`start_sin`](MsLutStart::start_sin)
+    pub const START_SIN_SHIFT: u32 = 0;
+    /// Bits of [`start_sin`](MsLutStart::start_sin) in the register value
+    pub const START_SIN_MASK: u32 = 0x000000ff;
+
+    /// Bit offset of [`start_sin90`](MsLutStart::start_sin90)
+    pub const START_SIN90_SHIFT: u32 = 16;
+    /// Bits of [`start_sin90`](MsLutStart::start_sin90) in the register value
+    pub const START_SIN90_MASK: u32 = 0x00ff0000;
+}
+
 impl Default for MsLutStart {
     fn default() -> Self {
         Self {
@@ -620,7 +815,7 @@ impl From<u32> for MsLutStart {
     fn from(data: u32) -> Self {
         Self {
             start_sin: read_from_bit(data, 0, 0xff) as u8,
-            start_sin90: read_from_bit(data, 8, 0xff) as u8,
+            start_sin90: read_from_bit(data, 16, 0xff) as u8,
         }
     }
 }
@@ -629,17 +824,24 @@ impl From<MsLutStart> for u32 {
     fn from(data: MsLutStart) -> Self {
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xff, data.start_sin as u32);
-        write_from_bit(&mut value, 8, 0xff, data.start_sin90 as u32);
+        write_from_bit(&mut value, 16, 0xff, data.start_sin90 as u32);
         value
     }
 }
 
 impl Register for MsLutStart {
+    const MASK: u32 = Self::START_SIN_MASK | Self::START_SIN90_MASK;
+    const RESET_VALUE: u32 = 0x00F7_0000;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x69
     }
 }
 
+impl GlobalRegister for MsLutStart {}
+impl WritableRegister for MsLutStart {}
+
 #[cfg(test)]
 mod ms_lut_start {
     use super::*;
@@ -650,13 +852,13 @@ mod ms_lut_start {
                 start_sin90: 247,
                 ..Default::default()
             }),
-            0x0000F700
+            0x00F70000
         )
     }
     #[test]
     fn from_u32() {
         assert_eq!(
-            MsLutStart::from(0x0000F700),
+            MsLutStart::from(0x00F70000),
             MsLutStart {
                 start_sin90: 247,
                 ..Default::default()
@@ -1,13 +1,39 @@
 //! TMC5072 registers
+//!
+//! Each register type exposes the layout of its fields as `<FIELD>_SHIFT` (bit offset) and `<FIELD>_MASK`
+//! (field bits in the register value) constants, e.g. [`ChopConf::MRES_MASK`](motor_driver_register::ChopConf::MRES_MASK),
+//! for raw read-modify-write accesses. The [`Register`] trait adds the bits of all fields ([`Register::MASK`]), the
+//! datasheet reset value ([`Register::RESET_VALUE`]) and the access mode ([`Register::ACCESS`]). `Default` of a
+//! register is its reset value ([`Register::reset`]): mostly cleared, but the microstep table and PWMCONF come out of
+//! reset with a sine wave and a stealthChop configuration.
+//!
+//! Converting a register to `u32` masks each field to its bits: registers with fields narrower than their Rust type
+//! provide a `checked` method returning [`FieldOutOfRange`] instead, e.g.
+//! [`IHoldIRun::checked`](ramp_generator_driver_feature_control_register::IHoldIRun::checked).
+//!
+//! Per motor registers take the motor index as a const generic parameter, only 0 and 1 exist. Any other index fails
+//! to compile where the register is converted from or to `u32`, rather than on a missing [`Register`] bound deep in
+//! generic code:
+//! ```compile_fail
+//! # use tmc5072::registers::ramp_generator_register::XActual;
+//! let raw = u32::from(XActual::<2> { x_actual: 0 });
+//! ```
 
 // TODO: use macro, bitfields or packed_struct for registers
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod address;
+pub mod datasheet;
 pub mod encoder_registers;
 pub mod general_configuration_register;
 pub mod microstep_table_register;
 pub mod motor_driver_register;
 pub mod ramp_generator_driver_feature_control_register;
 pub mod ramp_generator_register;
+#[cfg(feature = "tmc-register")]
+pub mod tmc_register;
 pub mod voltage_pwm_mode_stealth_chop;
 
 /// Address flag for read operation
@@ -17,17 +43,204 @@ pub(crate) const WRITE_FLAG: u8 = 0x80;
 /// IC Version expected
 pub const IC_VERSION: u8 = 0x10;
 
+/// Acknowledgment required by settings that disable a protection of the driver
+///
+/// Such settings can destroy the IC or the motor, they are never enabled by default.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DangerZone {
+    _private: (),
+}
+
+impl DangerZone {
+    /// Acknowledges the hazards of the protected setting
+    pub fn acknowledge() -> Self {
+        Self { _private: () }
+    }
+}
+
+/// Value outside of the values defined for a register field
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidValue(pub u8);
+
+/// Register field value that does not fit the bits of the field
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FieldOutOfRange {
+    /// Field name
+    pub field: &'static str,
+    /// Largest value of the field
+    pub max: u32,
+}
+
+/// Checks an unsigned field value against the largest value of the field
+pub(crate) fn check_field(
+    field: &'static str,
+    value: u32,
+    max: u32,
+) -> Result<(), FieldOutOfRange> {
+    if value > max {
+        Err(FieldOutOfRange { field, max })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks a signed field value against the bit width of the field
+pub(crate) fn check_signed_field(
+    field: &'static str,
+    value: i32,
+    width: u32,
+) -> Result<(), FieldOutOfRange> {
+    let max = (1i32 << (width - 1)) - 1;
+    if value > max || value < -max - 1 {
+        Err(FieldOutOfRange {
+            field,
+            max: max as u32,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Compile time check of the motor index of per motor registers
+pub(crate) struct MotorIndexCheck<const M: u8>;
+
+impl<const M: u8> MotorIndexCheck<M> {
+    /// Fails to evaluate unless `M` is 0 or 1
+    pub(crate) const OK: () = assert!(M < 2, "motor index must be 0 or 1");
+}
+
 /// Register trait
 ///
-/// Imposes u32 conversion and addressing capabilities
+/// Imposes u32 conversion and addressing capabilities, along with the register metadata
 pub trait Register
 where
     u32: From<Self>,
     Self: From<u32>,
     Self: Copy,
 {
+    /// Bits of all fields in the register value
+    const MASK: u32;
+    /// Value after power on or reset (datasheet)
+    const RESET_VALUE: u32;
+    /// Access mode
+    const ACCESS: address::Access;
+
     /// Actual address of the register
     fn addr() -> u8;
+
+    /// Register at its power on value, as after a reset
+    fn reset() -> Self {
+        Self::from(Self::RESET_VALUE)
+    }
+}
+
+/// Register shared by both motors
+pub trait GlobalRegister: Register
+where
+    u32: From<Self>,
+{
+}
+
+/// Register of the motor `M` bank
+///
+/// Per-axis APIs bound their registers with this trait, so passing a global register fails to compile:
+/// ```compile_fail
+/// # use tmc5072::registers::{general_configuration_register::GConf, MotorRegister};
+/// fn axis_register<const M: u8, R: MotorRegister<M>>(r: R) where u32: From<R> {}
+/// axis_register::<0, _>(GConf::default());
+/// ```
+pub trait MotorRegister<const M: u8>: Register
+where
+    u32: From<Self>,
+{
+}
+
+/// Register returning its value when read
+///
+/// Bounds the typed reads, so reading a write only register fails to compile instead of returning zero:
+/// ```compile_fail
+/// # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+/// # use tmc5072::{registers::ramp_generator_register::VStart, Tmc5072};
+/// fn v_start<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI) {
+///     let _ = tmc5072.read_register::<VStart<0>, _>(spi);
+/// }
+/// ```
+pub trait ReadableRegister {}
+
+/// Register accepting writes
+///
+/// Bounds the typed writes, so writing a read only register fails to compile instead of being ignored by the chip:
+/// ```compile_fail
+/// # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+/// # use tmc5072::{registers::general_configuration_register::Input, Tmc5072};
+/// fn input<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI) {
+///     let _ = tmc5072.write_register(Input::default(), spi);
+/// }
+/// ```
+pub trait WritableRegister {}
+
+/// Register with flags cleared by reading it (access RC: GSTAT, RAMP_STAT, ENC_STATUS)
+///
+/// Any read, including a debug read, consumes its events: see
+/// [`Tmc5072::read_and_clear`](crate::Tmc5072::read_and_clear).
+pub trait ClearOnRead: ReadableRegister {}
+
+#[cfg(test)]
+mod categories {
+    use super::*;
+
+    fn global<R: GlobalRegister>() -> u8
+    where
+        u32: From<R>,
+    {
+        R::addr()
+    }
+
+    fn motor<const M: u8, R: MotorRegister<M>>() -> u8
+    where
+        u32: From<R>,
+    {
+        R::addr()
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(global::<general_configuration_register::GConf>(), 0x00);
+        assert_eq!(global::<microstep_table_register::MsLutStart>(), 0x69);
+        assert_eq!(motor::<0, ramp_generator_register::XActual<0>>(), 0x21);
+        assert_eq!(motor::<1, motor_driver_register::ChopConf<1>>(), 0x7C);
+    }
+}
+
+#[cfg(test)]
+mod metadata {
+    use super::*;
+    use address::{Access, RegisterAddress};
+
+    #[test]
+    fn metadata() {
+        use general_configuration_register::GConf;
+        use voltage_pwm_mode_stealth_chop::PwmConf;
+        assert_eq!(GConf::ACCESS, RegisterAddress::GConf.access());
+        assert_eq!(
+            motor_driver_register::DrvStatus::<1>::ACCESS,
+            RegisterAddress::DrvStatus1.access()
+        );
+        assert_eq!(ramp_generator_register::VMax::<0>::ACCESS, Access::Write);
+        assert_eq!(PwmConf::<1>::RESET_VALUE, 0x0005_0480);
+        assert_eq!(PwmConf::<1>::reset().pwm_grad, 4);
+        assert_eq!(
+            microstep_table_register::MsLutStart::reset(),
+            microstep_table_register::MsLutStart::default()
+        );
+        assert_eq!(u32::from(GConf::reset()), GConf::RESET_VALUE);
+        assert_eq!(PwmConf::<0>::MASK, 0x0037_ffff);
+    }
 }
 
 #[cfg(test)]
@@ -179,6 +392,14 @@ mod addresses {
         assert_eq!(ramp_generator_register::XTarget::<1>::addr(), 0x4d);
     }
 
+    #[test]
+    fn datasheet() {
+        assert_eq!(datasheet::IHOLD_IRUN::<1>::addr(), 0x50);
+        assert_eq!(datasheet::RAMP_STAT::<0>::addr(), 0x35);
+        assert_eq!(datasheet::X_COMPARE::addr(), 0x05);
+        assert_eq!(datasheet::DRV_STATUS::<1>::addr(), 0x7F);
+    }
+
     #[test]
     fn voltage_pwm_mode_stealth_chop() {
         assert_eq!(voltage_pwm_mode_stealth_chop::PwmConf::<0>::addr(), 0x10);
@@ -187,3 +408,159 @@ mod addresses {
         assert_eq!(voltage_pwm_mode_stealth_chop::PwmStatus::<1>::addr(), 0x19);
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_support {
+    use super::{
+        encoder_registers::*, general_configuration_register::*, microstep_table_register::*,
+        motor_driver_register::*, ramp_generator_driver_feature_control_register::*,
+        ramp_generator_register::*, voltage_pwm_mode_stealth_chop::*,
+    };
+    use crate::spi::SpiOk;
+    use serde::{de::DeserializeOwned, Serialize};
+
+    fn serde<T: Serialize + DeserializeOwned>() {}
+
+    #[test]
+    fn registers() {
+        serde::<GConf>();
+        serde::<GStat>();
+        serde::<IfCnt>();
+        serde::<SlaveConf>();
+        serde::<Input>();
+        serde::<Output>();
+        serde::<XCompare>();
+        serde::<MsLut0>();
+        serde::<MsLut1>();
+        serde::<MsLut2>();
+        serde::<MsLut3>();
+        serde::<MsLut4>();
+        serde::<MsLut5>();
+        serde::<MsLut6>();
+        serde::<MsLut7>();
+        serde::<MsLutSel>();
+        serde::<MsLutStart>();
+        serde::<ShortProtection>();
+        serde::<EncMode<0>>();
+        serde::<XEnc<0>>();
+        serde::<EncConst<0>>();
+        serde::<EncStatus<0>>();
+        serde::<EncLatch<0>>();
+        serde::<MsCnt<0>>();
+        serde::<MsCurAct<0>>();
+        serde::<ChopConf<0>>();
+        serde::<CoolConf<0>>();
+        serde::<DcCtrl<0>>();
+        serde::<DrvStatus<0>>();
+        serde::<IHoldIRun<0>>();
+        serde::<VCoolThrs<0>>();
+        serde::<VHigh<0>>();
+        serde::<VDcMin<0>>();
+        serde::<SwMode<0>>();
+        serde::<RampStat<0>>();
+        serde::<XLatch<0>>();
+        serde::<RampMode<0>>();
+        serde::<XActual<0>>();
+        serde::<VActual<0>>();
+        serde::<VStart<0>>();
+        serde::<A1<0>>();
+        serde::<V1<0>>();
+        serde::<AMax<0>>();
+        serde::<VMax<0>>();
+        serde::<DMax<0>>();
+        serde::<D1<0>>();
+        serde::<VStop<0>>();
+        serde::<TZeroWait<0>>();
+        serde::<XTarget<0>>();
+        serde::<PwmConf<0>>();
+        serde::<PwmStatus<0>>();
+        serde::<SpiOk<DrvStatus<1>>>();
+    }
+}
+
+#[cfg(test)]
+mod field_range {
+    use super::{
+        motor_driver_register::CoolConf, ramp_generator_driver_feature_control_register::IHoldIRun,
+        FieldOutOfRange,
+    };
+
+    #[test]
+    fn checked() {
+        let i_hold_i_run = IHoldIRun::<0> {
+            i_hold: 10,
+            i_run: 31,
+            i_hold_delay: 6,
+        };
+        assert_eq!(i_hold_i_run.checked(), Ok(i_hold_i_run));
+        assert_eq!(
+            IHoldIRun::<0> {
+                i_run: 200,
+                ..i_hold_i_run
+            }
+            .checked(),
+            Err(FieldOutOfRange {
+                field: "i_run",
+                max: 31
+            })
+        );
+        let cool_conf = CoolConf::<1> {
+            sgt: -64,
+            ..Default::default()
+        };
+        assert_eq!(cool_conf.checked(), Ok(cool_conf));
+        assert_eq!(
+            CoolConf::<1> {
+                sgt: 64,
+                ..cool_conf
+            }
+            .checked(),
+            Err(FieldOutOfRange {
+                field: "sgt",
+                max: 63
+            })
+        );
+        assert!(CoolConf::<1> {
+            sgt: -65,
+            ..cool_conf
+        }
+        .checked()
+        .is_err());
+    }
+}
+
+#[cfg(test)]
+mod field_layout {
+    use super::{
+        encoder_registers::EncConst, general_configuration_register::GConf,
+        motor_driver_register::ChopConf, motor_driver_register::CoolConf,
+    };
+
+    #[test]
+    fn masks() {
+        let chop_conf = ChopConf::<0> {
+            hend: 0x0f,
+            ..Default::default()
+        };
+        assert_eq!(u32::from(chop_conf), ChopConf::<0>::HEND_MASK);
+        assert_eq!(ChopConf::<0>::MRES_MASK, 0x0f << ChopConf::<0>::MRES_SHIFT);
+        assert_eq!(
+            ChopConf::<1>::from(ChopConf::<1>::TOFF_MASK & (5 << ChopConf::<1>::TOFF_SHIFT)).toff,
+            5
+        );
+        let cool_conf = CoolConf::<0> {
+            sgt: -1,
+            ..Default::default()
+        };
+        assert_eq!(u32::from(cool_conf), CoolConf::<0>::SGT_MASK);
+        let gconf = GConf {
+            poscmp_enable: true,
+            ..Default::default()
+        };
+        assert_eq!(u32::from(gconf), GConf::POSCMP_ENABLE_MASK);
+        assert_eq!(
+            EncConst::<0>::ENC_CONST_INT_MASK | EncConst::<0>::ENC_CONST_FRAC_MASK,
+            u32::MAX
+        );
+    }
+}
@@ -7,17 +7,24 @@
 //! - dcStep configuration, and
 //! - reading out stallGuard2 values and driver error flags
 
-use super::Register;
+use super::{
+    address::Access, check_field, check_signed_field, DangerZone, FieldOutOfRange, InvalidValue,
+    MotorIndexCheck, MotorRegister, ReadableRegister, Register, WritableRegister,
+};
 use crate::bits::{
     convert_from_signed_n, convert_to_signed_n, read_bool_from_bit, read_from_bit,
     write_bool_to_bit, write_from_bit,
 };
+use crate::flags::fmt_flags;
+use core::convert::TryFrom;
+use core::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// MSCNT: Microstep counter.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsCnt<const M: u8> {
     /// Microstep counter
     ///
@@ -27,6 +34,23 @@ pub struct MsCnt<const M: u8> {
     pub ms_cnt: u16,
 }
 
+impl<const M: u8> MsCnt<M> {
+    /// Bit offset of [`ms_cnt`](MsCnt::ms_cnt)
+    pub const MS_CNT_SHIFT: u32 = 0;
+    /// Bits of [`ms_cnt`](MsCnt::ms_cnt) in the register value
+    pub const MS_CNT_MASK: u32 = 0x000003ff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "ms_cnt",
+            self.ms_cnt as u32,
+            Self::MS_CNT_MASK >> Self::MS_CNT_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for MsCnt<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -35,6 +59,7 @@ impl<const M: u8> Default for MsCnt<M> {
 
 impl<const M: u8> From<u32> for MsCnt<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             ms_cnt: read_from_bit(data, 0, 0x3ff) as u16,
         }
@@ -43,6 +68,7 @@ impl<const M: u8> From<u32> for MsCnt<M> {
 
 impl<const M: u8> From<MsCnt<M>> for u32 {
     fn from(data: MsCnt<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x3ff, data.ms_cnt as u32);
         value
@@ -50,16 +76,29 @@ impl<const M: u8> From<MsCnt<M>> for u32 {
 }
 
 impl Register for MsCnt<0> {
+    const MASK: u32 = Self::MS_CNT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x6A
     }
 }
+
+impl MotorRegister<0> for MsCnt<0> {}
 impl Register for MsCnt<1> {
+    const MASK: u32 = Self::MS_CNT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x7A
     }
 }
 
+impl MotorRegister<1> for MsCnt<1> {}
+impl<const M: u8> ReadableRegister for MsCnt<M> {}
+
 #[cfg(test)]
 mod ms_cnt {
     use super::*;
@@ -89,6 +128,7 @@ mod ms_cnt {
 /// MSCURACT
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MsCurAct<const M: u8> {
     /// CUR_A (signed): Actual microstep current for motor phase A as read from MSLUT (not scaled by current)
     pub cur_a: i16,
@@ -96,6 +136,25 @@ pub struct MsCurAct<const M: u8> {
     pub cur_b: i16,
 }
 
+impl<const M: u8> MsCurAct<M> {
+    /// Bit offset of [`cur_a`](MsCurAct::cur_a)
+    pub const CUR_A_SHIFT: u32 = 0;
+    /// Bits of [`cur_a`](MsCurAct::cur_a) in the register value
+    pub const CUR_A_MASK: u32 = 0x000001ff;
+
+    /// Bit offset of [`cur_b`](MsCurAct::cur_b)
+    pub const CUR_B_SHIFT: u32 = 16;
+    /// Bits of [`cur_b`](MsCurAct::cur_b) in the register value
+    pub const CUR_B_MASK: u32 = 0x01ff0000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_signed_field("cur_a", self.cur_a as i32, Self::CUR_A_MASK.count_ones())?;
+        check_signed_field("cur_b", self.cur_b as i32, Self::CUR_B_MASK.count_ones())?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for MsCurAct<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -104,6 +163,7 @@ impl<const M: u8> Default for MsCurAct<M> {
 
 impl<const M: u8> From<u32> for MsCurAct<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             cur_a: convert_to_signed_n(read_from_bit(data, 0, 0x1ff) as u32, 9) as i16,
             cur_b: convert_to_signed_n(read_from_bit(data, 16, 0x1ff) as u32, 9) as i16,
@@ -113,6 +173,7 @@ impl<const M: u8> From<u32> for MsCurAct<M> {
 
 impl<const M: u8> From<MsCurAct<M>> for u32 {
     fn from(data: MsCurAct<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(
             &mut value,
@@ -131,16 +192,29 @@ impl<const M: u8> From<MsCurAct<M>> for u32 {
 }
 
 impl Register for MsCurAct<0> {
+    const MASK: u32 = Self::CUR_A_MASK | Self::CUR_B_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x6B
     }
 }
+
+impl MotorRegister<0> for MsCurAct<0> {}
 impl Register for MsCurAct<1> {
+    const MASK: u32 = Self::CUR_A_MASK | Self::CUR_B_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x7B
     }
 }
 
+impl MotorRegister<1> for MsCurAct<1> {}
+impl<const M: u8> ReadableRegister for MsCurAct<M> {}
+
 #[cfg(test)]
 mod ms_cur_act {
     use super::*;
@@ -169,9 +243,92 @@ mod ms_cur_act {
     }
 }
 
+/// CHOPCONF diss2g: short to GND protection state
+///
+/// Disabling the protection requires a [`DangerZone`] acknowledgment.
+///
+/// The acknowledgment is not required by the raw conversion [`ChopConf::from`] nor by deserialization, which keep
+/// diss2g as is so that a read back reports the state of the chip. A disabled protection coming from a raw value or a
+/// deserialized configuration is only caught by [`validate_config`](crate::config::validate_config), or enabled again
+/// by [`Config::sanitize`](crate::config::Config::sanitize).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ShortProtection {
+    disabled: bool,
+}
+
+impl ShortProtection {
+    /// Short to GND protection is on
+    pub const ENABLED: Self = Self { disabled: false };
+
+    /// Short to GND protection is disabled
+    pub fn disabled(_acknowledgment: DangerZone) -> Self {
+        Self { disabled: true }
+    }
+
+    /// Whether the short to GND protection is disabled (diss2g=1)
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+}
+
+/// CHOPCONF MRES: microstep resolution, in microsteps per full step
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MicrostepResolution {
+    /// 256 microsteps, native resolution
+    #[default]
+    M256 = 0,
+    /// 128 microsteps
+    M128 = 1,
+    /// 64 microsteps
+    M64 = 2,
+    /// 32 microsteps
+    M32 = 3,
+    /// 16 microsteps
+    M16 = 4,
+    /// 8 microsteps
+    M8 = 5,
+    /// 4 microsteps
+    M4 = 6,
+    /// 2 microsteps
+    M2 = 7,
+    /// Full step
+    Fullstep = 8,
+}
+
+impl MicrostepResolution {
+    /// MRES value: step width of 2^MRES microsteps
+    pub fn mres(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for MicrostepResolution {
+    type Error = InvalidValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MicrostepResolution::M256),
+            1 => Ok(MicrostepResolution::M128),
+            2 => Ok(MicrostepResolution::M64),
+            3 => Ok(MicrostepResolution::M32),
+            4 => Ok(MicrostepResolution::M16),
+            5 => Ok(MicrostepResolution::M8),
+            6 => Ok(MicrostepResolution::M4),
+            7 => Ok(MicrostepResolution::M2),
+            8 => Ok(MicrostepResolution::Fullstep),
+            _ => Err(InvalidValue(value)),
+        }
+    }
+}
+
 /// CHOPCONF: Chopper and driver configuration
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ChopConf<const M: u8> {
     /// TOFF off time and driver enable
     ///
@@ -266,7 +423,7 @@ pub struct ChopConf<const M: u8> {
     /// step width=2^MRES \[microsteps\]
     ///
     /// Hint: Reduced microstep resolutions are also useful in special cases to extend the acceleration or position range
-    pub mres: u8,
+    pub mres: MicrostepResolution,
     /// intpol16: 16 microsteps with interpolation
     /// - true: In 16 microstep mode with Step/Dir interface, the microstep resolution becomes extrapolated to 256 microsteps for smoothest motor operation
     pub intpol16: bool,
@@ -278,7 +435,117 @@ pub struct ChopConf<const M: u8> {
     /// diss2g short to GND protection disable
     /// - false: Short to GND protection is on
     /// - true: Short to GND protection is disabled
-    pub diss2g: bool,
+    ///
+    /// Attention: Disabling the protection can destroy the driver, see [`ChopConf::disable_short_protection_unchecked`].
+    pub diss2g: ShortProtection,
+}
+
+impl<const M: u8> ChopConf<M> {
+    /// Disables the short to GND protection (diss2g=1)
+    pub fn disable_short_protection_unchecked(&mut self, acknowledgment: DangerZone) {
+        self.diss2g = ShortProtection::disabled(acknowledgment);
+    }
+
+    /// Enables the short to GND protection (diss2g=0)
+    pub fn enable_short_protection(&mut self) {
+        self.diss2g = ShortProtection::ENABLED;
+    }
+
+    /// Bit offset of [`toff`](ChopConf::toff)
+    pub const TOFF_SHIFT: u32 = 0;
+    /// Bits of [`toff`](ChopConf::toff) in the register value
+    pub const TOFF_MASK: u32 = 0x0000000f;
+
+    /// Bit offset of [`hstrt`](ChopConf::hstrt)
+    pub const HSTRT_SHIFT: u32 = 4;
+    /// Bits of [`hstrt`](ChopConf::hstrt) in the register value
+    pub const HSTRT_MASK: u32 = 0x00000070;
+
+    /// Bit offset of [`hend`](ChopConf::hend)
+    pub const HEND_SHIFT: u32 = 7;
+    /// Bits of [`hend`](ChopConf::hend) in the register value
+    pub const HEND_MASK: u32 = 0x00000780;
+
+    /// Bit offset of [`fd3`](ChopConf::fd3)
+    pub const FD3_SHIFT: u32 = 11;
+    /// Bits of [`fd3`](ChopConf::fd3) in the register value
+    pub const FD3_MASK: u32 = 0x00000800;
+
+    /// Bit offset of [`disfdcc`](ChopConf::disfdcc)
+    pub const DISFDCC_SHIFT: u32 = 12;
+    /// Bits of [`disfdcc`](ChopConf::disfdcc) in the register value
+    pub const DISFDCC_MASK: u32 = 0x00001000;
+
+    /// Bit offset of [`rndtf`](ChopConf::rndtf)
+    pub const RNDTF_SHIFT: u32 = 13;
+    /// Bits of [`rndtf`](ChopConf::rndtf) in the register value
+    pub const RNDTF_MASK: u32 = 0x00002000;
+
+    /// Bit offset of [`chm`](ChopConf::chm)
+    pub const CHM_SHIFT: u32 = 14;
+    /// Bits of [`chm`](ChopConf::chm) in the register value
+    pub const CHM_MASK: u32 = 0x00004000;
+
+    /// Bit offset of [`tbl`](ChopConf::tbl)
+    pub const TBL_SHIFT: u32 = 15;
+    /// Bits of [`tbl`](ChopConf::tbl) in the register value
+    pub const TBL_MASK: u32 = 0x00018000;
+
+    /// Bit offset of [`vsense`](ChopConf::vsense)
+    pub const VSENSE_SHIFT: u32 = 17;
+    /// Bits of [`vsense`](ChopConf::vsense) in the register value
+    pub const VSENSE_MASK: u32 = 0x00020000;
+
+    /// Bit offset of [`vhighfs`](ChopConf::vhighfs)
+    pub const VHIGHFS_SHIFT: u32 = 18;
+    /// Bits of [`vhighfs`](ChopConf::vhighfs) in the register value
+    pub const VHIGHFS_MASK: u32 = 0x00040000;
+
+    /// Bit offset of [`vhighchm`](ChopConf::vhighchm)
+    pub const VHIGHCHM_SHIFT: u32 = 19;
+    /// Bits of [`vhighchm`](ChopConf::vhighchm) in the register value
+    pub const VHIGHCHM_MASK: u32 = 0x00080000;
+
+    /// Bit offset of [`mres`](ChopConf::mres)
+    pub const MRES_SHIFT: u32 = 24;
+    /// Bits of [`mres`](ChopConf::mres) in the register value
+    pub const MRES_MASK: u32 = 0x0f000000;
+
+    /// Bit offset of [`intpol16`](ChopConf::intpol16)
+    pub const INTPOL16_SHIFT: u32 = 28;
+    /// Bits of [`intpol16`](ChopConf::intpol16) in the register value
+    pub const INTPOL16_MASK: u32 = 0x10000000;
+
+    /// Bit offset of [`dedge`](ChopConf::dedge)
+    pub const DEDGE_SHIFT: u32 = 29;
+    /// Bits of [`dedge`](ChopConf::dedge) in the register value
+    pub const DEDGE_MASK: u32 = 0x20000000;
+
+    /// Bit offset of [`diss2g`](ChopConf::diss2g)
+    pub const DISS2G_SHIFT: u32 = 30;
+    /// Bits of [`diss2g`](ChopConf::diss2g) in the register value
+    pub const DISS2G_MASK: u32 = 0x40000000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "toff",
+            self.toff as u32,
+            Self::TOFF_MASK >> Self::TOFF_SHIFT,
+        )?;
+        check_field(
+            "hstrt",
+            self.hstrt as u32,
+            Self::HSTRT_MASK >> Self::HSTRT_SHIFT,
+        )?;
+        check_field(
+            "hend",
+            self.hend as u32,
+            Self::HEND_MASK >> Self::HEND_SHIFT,
+        )?;
+        check_field("tbl", self.tbl as u32, Self::TBL_MASK >> Self::TBL_SHIFT)?;
+        Ok(self)
+    }
 }
 
 impl<const M: u8> Default for ChopConf<M> {
@@ -289,6 +556,7 @@ impl<const M: u8> Default for ChopConf<M> {
 
 impl<const M: u8> From<u32> for ChopConf<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             toff: read_from_bit(data, 0, 0x0f) as u8,
             hstrt: read_from_bit(data, 4, 0x07) as u8,
@@ -301,16 +569,21 @@ impl<const M: u8> From<u32> for ChopConf<M> {
             vsense: read_bool_from_bit(data, 17),
             vhighfs: read_bool_from_bit(data, 18),
             vhighchm: read_bool_from_bit(data, 19),
-            mres: read_from_bit(data, 24, 0x0f) as u8,
+            // %1001..%1111 are not defined, read as full step
+            mres: MicrostepResolution::try_from(read_from_bit(data, 24, 0x0f) as u8)
+                .unwrap_or(MicrostepResolution::Fullstep),
             intpol16: read_bool_from_bit(data, 28),
             dedge: read_bool_from_bit(data, 29),
-            diss2g: read_bool_from_bit(data, 30),
+            diss2g: ShortProtection {
+                disabled: read_bool_from_bit(data, 30),
+            },
         }
     }
 }
 
 impl<const M: u8> From<ChopConf<M>> for u32 {
     fn from(data: ChopConf<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x0f, data.toff as u32);
         write_from_bit(&mut value, 4, 0x07, data.hstrt as u32);
@@ -326,22 +599,64 @@ impl<const M: u8> From<ChopConf<M>> for u32 {
         write_from_bit(&mut value, 24, 0x0f, data.mres as u32);
         write_bool_to_bit(&mut value, 28, data.intpol16);
         write_bool_to_bit(&mut value, 29, data.dedge);
-        write_bool_to_bit(&mut value, 30, data.diss2g);
+        write_bool_to_bit(&mut value, 30, data.diss2g.is_disabled());
         value
     }
 }
 
 impl Register for ChopConf<0> {
+    const MASK: u32 = Self::TOFF_MASK
+        | Self::HSTRT_MASK
+        | Self::HEND_MASK
+        | Self::FD3_MASK
+        | Self::DISFDCC_MASK
+        | Self::RNDTF_MASK
+        | Self::CHM_MASK
+        | Self::TBL_MASK
+        | Self::VSENSE_MASK
+        | Self::VHIGHFS_MASK
+        | Self::VHIGHCHM_MASK
+        | Self::MRES_MASK
+        | Self::INTPOL16_MASK
+        | Self::DEDGE_MASK
+        | Self::DISS2G_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x6C
     }
 }
+
+impl MotorRegister<0> for ChopConf<0> {}
 impl Register for ChopConf<1> {
+    const MASK: u32 = Self::TOFF_MASK
+        | Self::HSTRT_MASK
+        | Self::HEND_MASK
+        | Self::FD3_MASK
+        | Self::DISFDCC_MASK
+        | Self::RNDTF_MASK
+        | Self::CHM_MASK
+        | Self::TBL_MASK
+        | Self::VSENSE_MASK
+        | Self::VHIGHFS_MASK
+        | Self::VHIGHCHM_MASK
+        | Self::MRES_MASK
+        | Self::INTPOL16_MASK
+        | Self::DEDGE_MASK
+        | Self::DISS2G_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x7C
     }
 }
 
+impl MotorRegister<1> for ChopConf<1> {}
+impl<const M: u8> ReadableRegister for ChopConf<M> {}
+impl<const M: u8> WritableRegister for ChopConf<M> {}
+
 #[cfg(test)]
 mod chop_conf {
     use super::*;
@@ -361,6 +676,15 @@ mod chop_conf {
         )
     }
     #[test]
+    fn short_protection() {
+        let mut chop_conf = ChopConf::<1>::from(0x400100C5);
+        assert!(chop_conf.diss2g.is_disabled());
+        chop_conf.enable_short_protection();
+        assert_eq!(u32::from(chop_conf), 0x000100C5);
+        chop_conf.disable_short_protection_unchecked(DangerZone::acknowledge());
+        assert_eq!(u32::from(chop_conf), 0x400100C5);
+    }
+    #[test]
     fn from_u32() {
         assert_eq!(
             ChopConf::<1>::from(0x000100C5),
@@ -376,9 +700,40 @@ mod chop_conf {
     }
 }
 
+/// COOLCONF sedn: current down step speed
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CurrentDownStep {
+    /// Decrease by one for each 32 stallGuard2 values
+    #[default]
+    Per32 = 0,
+    /// Decrease by one for each 8 stallGuard2 values
+    Per8 = 1,
+    /// Decrease by one for each 2 stallGuard2 values
+    Per2 = 2,
+    /// Decrease by one for each stallGuard2 value
+    Per1 = 3,
+}
+
+impl TryFrom<u8> for CurrentDownStep {
+    type Error = InvalidValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CurrentDownStep::Per32),
+            1 => Ok(CurrentDownStep::Per8),
+            2 => Ok(CurrentDownStep::Per2),
+            3 => Ok(CurrentDownStep::Per1),
+            _ => Err(InvalidValue(value)),
+        }
+    }
+}
+
 /// coolStep smart current control register and stallGuard2 configuration
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CoolConf<const M: u8> {
     /// semin: minimum stallGuard2 value for smart current control and smart current enable
     ///
@@ -403,7 +758,7 @@ pub struct CoolConf<const M: u8> {
     /// - %01: For each 8 stallGuard2 values decrease by one
     /// - %10: For each 2 stallGuard2 values decrease by one
     /// - %11: For each stallGuard2 value decrease by one
-    pub sedn: u8,
+    pub sedn: CurrentDownStep,
     /// seimin: minimum current for smart current control
     /// - false: 1/2 of current setting (IRUN)
     /// - true: 1/4 of current setting (IRUN)
@@ -421,6 +776,64 @@ pub struct CoolConf<const M: u8> {
     pub sfilt: bool,
 }
 
+impl<const M: u8> CoolConf<M> {
+    /// Bit offset of [`semin`](CoolConf::semin)
+    pub const SEMIN_SHIFT: u32 = 0;
+    /// Bits of [`semin`](CoolConf::semin) in the register value
+    pub const SEMIN_MASK: u32 = 0x0000000f;
+
+    /// Bit offset of [`seup`](CoolConf::seup)
+    pub const SEUP_SHIFT: u32 = 5;
+    /// Bits of [`seup`](CoolConf::seup) in the register value
+    pub const SEUP_MASK: u32 = 0x00000060;
+
+    /// Bit offset of [`semax`](CoolConf::semax)
+    pub const SEMAX_SHIFT: u32 = 8;
+    /// Bits of [`semax`](CoolConf::semax) in the register value
+    pub const SEMAX_MASK: u32 = 0x00000f00;
+
+    /// Bit offset of [`sedn`](CoolConf::sedn)
+    pub const SEDN_SHIFT: u32 = 13;
+    /// Bits of [`sedn`](CoolConf::sedn) in the register value
+    pub const SEDN_MASK: u32 = 0x00006000;
+
+    /// Bit offset of [`seimin`](CoolConf::seimin)
+    pub const SEIMIN_SHIFT: u32 = 15;
+    /// Bits of [`seimin`](CoolConf::seimin) in the register value
+    pub const SEIMIN_MASK: u32 = 0x00008000;
+
+    /// Bit offset of [`sgt`](CoolConf::sgt)
+    pub const SGT_SHIFT: u32 = 16;
+    /// Bits of [`sgt`](CoolConf::sgt) in the register value
+    pub const SGT_MASK: u32 = 0x007f0000;
+
+    /// Bit offset of [`sfilt`](CoolConf::sfilt)
+    pub const SFILT_SHIFT: u32 = 24;
+    /// Bits of [`sfilt`](CoolConf::sfilt) in the register value
+    pub const SFILT_MASK: u32 = 0x01000000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "semin",
+            self.semin as u32,
+            Self::SEMIN_MASK >> Self::SEMIN_SHIFT,
+        )?;
+        check_field(
+            "seup",
+            self.seup as u32,
+            Self::SEUP_MASK >> Self::SEUP_SHIFT,
+        )?;
+        check_field(
+            "semax",
+            self.semax as u32,
+            Self::SEMAX_MASK >> Self::SEMAX_SHIFT,
+        )?;
+        check_signed_field("sgt", self.sgt as i32, Self::SGT_MASK.count_ones())?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for CoolConf<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -429,12 +842,15 @@ impl<const M: u8> Default for CoolConf<M> {
 
 impl<const M: u8> From<u32> for CoolConf<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let sgt = read_from_bit(data, 16, 0x7f) as u8;
         Self {
             semin: read_from_bit(data, 0, 0x0f) as u8,
             seup: read_from_bit(data, 5, 0x03) as u8,
             semax: read_from_bit(data, 8, 0x0f) as u8,
-            sedn: read_from_bit(data, 13, 0x03) as u8,
+            // 2 bits, every value is defined
+            sedn: CurrentDownStep::try_from(read_from_bit(data, 13, 0x03) as u8)
+                .unwrap_or_default(),
             seimin: read_bool_from_bit(data, 15),
             sgt: if sgt >> 6 & 1 == 1 {
                 -(((!sgt) & 0x3f) as i8 + 1)
@@ -448,6 +864,7 @@ impl<const M: u8> From<u32> for CoolConf<M> {
 
 impl<const M: u8> From<CoolConf<M>> for u32 {
     fn from(data: CoolConf<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         let corrected_sgt = if data.sgt.is_negative() {
             ((!(-(data.sgt + 1)) & 0x3f) as u8) | 1 << 6
@@ -466,16 +883,41 @@ impl<const M: u8> From<CoolConf<M>> for u32 {
 }
 
 impl Register for CoolConf<0> {
+    const MASK: u32 = Self::SEMIN_MASK
+        | Self::SEUP_MASK
+        | Self::SEMAX_MASK
+        | Self::SEDN_MASK
+        | Self::SEIMIN_MASK
+        | Self::SGT_MASK
+        | Self::SFILT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x6D
     }
 }
+
+impl MotorRegister<0> for CoolConf<0> {}
 impl Register for CoolConf<1> {
+    const MASK: u32 = Self::SEMIN_MASK
+        | Self::SEUP_MASK
+        | Self::SEMAX_MASK
+        | Self::SEDN_MASK
+        | Self::SEIMIN_MASK
+        | Self::SGT_MASK
+        | Self::SFILT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x7D
     }
 }
 
+impl MotorRegister<1> for CoolConf<1> {}
+impl<const M: u8> WritableRegister for CoolConf<M> {}
+
 #[cfg(test)]
 mod cool_conf {
     use super::*;
@@ -510,6 +952,7 @@ mod cool_conf {
 /// DCCTRL: dcStep (DC) automatic commutation configuration
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DcCtrl<const M: u8> {
     /// DC_TIME: Upper PWM on time limit for commutation (DC_TIME * 1/fCLK).
     ///
@@ -523,6 +966,18 @@ pub struct DcCtrl<const M: u8> {
     pub dc_sg: u8,
 }
 
+impl<const M: u8> DcCtrl<M> {
+    /// Bit offset of [`dc_time`](DcCtrl::dc_time)
+    pub const DC_TIME_SHIFT: u32 = 0;
+    /// Bits of [`dc_time`](DcCtrl::dc_time) in the register value
+    pub const DC_TIME_MASK: u32 = 0x000000ff;
+
+    /// Bit offset of [`dc_sg`](DcCtrl::dc_sg)
+    pub const DC_SG_SHIFT: u32 = 8;
+    /// Bits of [`dc_sg`](DcCtrl::dc_sg) in the register value
+    pub const DC_SG_MASK: u32 = 0x0000ff00;
+}
+
 impl<const M: u8> Default for DcCtrl<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -531,6 +986,7 @@ impl<const M: u8> Default for DcCtrl<M> {
 
 impl<const M: u8> From<u32> for DcCtrl<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             dc_time: read_from_bit(data, 0, 0xff) as u8,
             dc_sg: read_from_bit(data, 8, 0xff) as u8,
@@ -540,6 +996,7 @@ impl<const M: u8> From<u32> for DcCtrl<M> {
 
 impl<const M: u8> From<DcCtrl<M>> for u32 {
     fn from(data: DcCtrl<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xff, data.dc_time as u32);
         write_from_bit(&mut value, 8, 0xff, data.dc_sg as u32);
@@ -548,16 +1005,29 @@ impl<const M: u8> From<DcCtrl<M>> for u32 {
 }
 
 impl Register for DcCtrl<0> {
+    const MASK: u32 = Self::DC_TIME_MASK | Self::DC_SG_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x6E
     }
 }
+
+impl MotorRegister<0> for DcCtrl<0> {}
 impl Register for DcCtrl<1> {
+    const MASK: u32 = Self::DC_TIME_MASK | Self::DC_SG_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x7E
     }
 }
 
+impl MotorRegister<1> for DcCtrl<1> {}
+impl<const M: u8> WritableRegister for DcCtrl<M> {}
+
 #[cfg(test)]
 mod dc_ctrl {
     use super::*;
@@ -588,6 +1058,7 @@ mod dc_ctrl {
 /// stallGuard2 value and driver error flags
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DrvStatus<const M: u8> {
     /// SG_ RESULT: stallGuard2 result respectively PWM on time for coil A in stand still for motor temperature detection
     ///
@@ -651,6 +1122,78 @@ pub struct DrvStatus<const M: u8> {
     pub stst: bool,
 }
 
+impl<const M: u8> DrvStatus<M> {
+    /// Bit offset of [`sg_result`](DrvStatus::sg_result)
+    pub const SG_RESULT_SHIFT: u32 = 0;
+    /// Bits of [`sg_result`](DrvStatus::sg_result) in the register value
+    pub const SG_RESULT_MASK: u32 = 0x000003ff;
+
+    /// Bit offset of [`fsactive`](DrvStatus::fsactive)
+    pub const FSACTIVE_SHIFT: u32 = 15;
+    /// Bits of [`fsactive`](DrvStatus::fsactive) in the register value
+    pub const FSACTIVE_MASK: u32 = 0x00008000;
+
+    /// Bit offset of [`cs_actual`](DrvStatus::cs_actual)
+    pub const CS_ACTUAL_SHIFT: u32 = 16;
+    /// Bits of [`cs_actual`](DrvStatus::cs_actual) in the register value
+    pub const CS_ACTUAL_MASK: u32 = 0x001f0000;
+
+    /// Bit offset of [`stall_guard`](DrvStatus::stall_guard)
+    pub const STALL_GUARD_SHIFT: u32 = 24;
+    /// Bits of [`stall_guard`](DrvStatus::stall_guard) in the register value
+    pub const STALL_GUARD_MASK: u32 = 0x01000000;
+
+    /// Bit offset of [`ot`](DrvStatus::ot)
+    pub const OT_SHIFT: u32 = 25;
+    /// Bits of [`ot`](DrvStatus::ot) in the register value
+    pub const OT_MASK: u32 = 0x02000000;
+
+    /// Bit offset of [`otpw`](DrvStatus::otpw)
+    pub const OTPW_SHIFT: u32 = 26;
+    /// Bits of [`otpw`](DrvStatus::otpw) in the register value
+    pub const OTPW_MASK: u32 = 0x04000000;
+
+    /// Bit offset of [`s2ga`](DrvStatus::s2ga)
+    pub const S2GA_SHIFT: u32 = 27;
+    /// Bits of [`s2ga`](DrvStatus::s2ga) in the register value
+    pub const S2GA_MASK: u32 = 0x08000000;
+
+    /// Bit offset of [`s2gb`](DrvStatus::s2gb)
+    pub const S2GB_SHIFT: u32 = 28;
+    /// Bits of [`s2gb`](DrvStatus::s2gb) in the register value
+    pub const S2GB_MASK: u32 = 0x10000000;
+
+    /// Bit offset of [`ola`](DrvStatus::ola)
+    pub const OLA_SHIFT: u32 = 29;
+    /// Bits of [`ola`](DrvStatus::ola) in the register value
+    pub const OLA_MASK: u32 = 0x20000000;
+
+    /// Bit offset of [`olb`](DrvStatus::olb)
+    pub const OLB_SHIFT: u32 = 30;
+    /// Bits of [`olb`](DrvStatus::olb) in the register value
+    pub const OLB_MASK: u32 = 0x40000000;
+
+    /// Bit offset of [`stst`](DrvStatus::stst)
+    pub const STST_SHIFT: u32 = 31;
+    /// Bits of [`stst`](DrvStatus::stst) in the register value
+    pub const STST_MASK: u32 = 0x80000000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "sg_result",
+            self.sg_result as u32,
+            Self::SG_RESULT_MASK >> Self::SG_RESULT_SHIFT,
+        )?;
+        check_field(
+            "cs_actual",
+            self.cs_actual as u32,
+            Self::CS_ACTUAL_MASK >> Self::CS_ACTUAL_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for DrvStatus<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -659,6 +1202,7 @@ impl<const M: u8> Default for DrvStatus<M> {
 
 impl<const M: u8> From<u32> for DrvStatus<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             sg_result: read_from_bit(data, 0, 0x3ff) as u16,
             fsactive: read_bool_from_bit(data, 15),
@@ -677,6 +1221,7 @@ impl<const M: u8> From<u32> for DrvStatus<M> {
 
 impl<const M: u8> From<DrvStatus<M>> for u32 {
     fn from(data: DrvStatus<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x3ff, data.sg_result as u32);
         write_bool_to_bit(&mut value, 15, data.fsactive);
@@ -694,16 +1239,73 @@ impl<const M: u8> From<DrvStatus<M>> for u32 {
 }
 
 impl Register for DrvStatus<0> {
+    const MASK: u32 = Self::SG_RESULT_MASK
+        | Self::FSACTIVE_MASK
+        | Self::CS_ACTUAL_MASK
+        | Self::STALL_GUARD_MASK
+        | Self::OT_MASK
+        | Self::OTPW_MASK
+        | Self::S2GA_MASK
+        | Self::S2GB_MASK
+        | Self::OLA_MASK
+        | Self::OLB_MASK
+        | Self::STST_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x6F
     }
 }
+
+impl MotorRegister<0> for DrvStatus<0> {}
 impl Register for DrvStatus<1> {
+    const MASK: u32 = Self::SG_RESULT_MASK
+        | Self::FSACTIVE_MASK
+        | Self::CS_ACTUAL_MASK
+        | Self::STALL_GUARD_MASK
+        | Self::OT_MASK
+        | Self::OTPW_MASK
+        | Self::S2GA_MASK
+        | Self::S2GB_MASK
+        | Self::OLA_MASK
+        | Self::OLB_MASK
+        | Self::STST_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x7F
     }
 }
 
+impl MotorRegister<1> for DrvStatus<1> {}
+impl<const M: u8> ReadableRegister for DrvStatus<M> {}
+
+impl<const M: u8> DrvStatus<M> {
+    /// Flags with their datasheet names
+    pub(crate) fn flags(&self) -> [(&'static str, bool); 9] {
+        [
+            ("fsactive", self.fsactive),
+            ("stallguard", self.stall_guard),
+            ("ot", self.ot),
+            ("otpw", self.otpw),
+            ("s2ga", self.s2ga),
+            ("s2gb", self.s2gb),
+            ("ola", self.ola),
+            ("olb", self.olb),
+            ("stst", self.stst),
+        ]
+    }
+}
+
+impl<const M: u8> fmt::Display for DrvStatus<M> {
+    /// Names of the set flags, e.g. `[fsactive stallguard]`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_flags(f, &self.flags())
+    }
+}
+
 #[cfg(test)]
 mod drv_status {
     use super::*;
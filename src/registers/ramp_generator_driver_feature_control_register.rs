@@ -8,14 +8,20 @@
 //! - reference switch and stallGuard2 event configuration
 //! - a ramp and reference switch status register
 
-use super::Register;
+use super::{
+    address::Access, check_field, ClearOnRead, FieldOutOfRange, MotorIndexCheck, MotorRegister,
+    ReadableRegister, Register, WritableRegister,
+};
 use crate::bits::{read_bool_from_bit, read_from_bit, write_bool_to_bit, write_from_bit};
+use crate::flags::fmt_flags;
+use core::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// IHOLD_IRUN: Driver current control
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IHoldIRun<const M: u8> {
     /// IHOLD: Standstill current (0=1/32…31=32/32)
     ///
@@ -32,6 +38,43 @@ pub struct IHoldIRun<const M: u8> {
     pub i_hold_delay: u8,
 }
 
+impl<const M: u8> IHoldIRun<M> {
+    /// Bit offset of [`i_hold`](IHoldIRun::i_hold)
+    pub const I_HOLD_SHIFT: u32 = 0;
+    /// Bits of [`i_hold`](IHoldIRun::i_hold) in the register value
+    pub const I_HOLD_MASK: u32 = 0x0000001f;
+
+    /// Bit offset of [`i_run`](IHoldIRun::i_run)
+    pub const I_RUN_SHIFT: u32 = 8;
+    /// Bits of [`i_run`](IHoldIRun::i_run) in the register value
+    pub const I_RUN_MASK: u32 = 0x00001f00;
+
+    /// Bit offset of [`i_hold_delay`](IHoldIRun::i_hold_delay)
+    pub const I_HOLD_DELAY_SHIFT: u32 = 16;
+    /// Bits of [`i_hold_delay`](IHoldIRun::i_hold_delay) in the register value
+    pub const I_HOLD_DELAY_MASK: u32 = 0x000f0000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "i_hold",
+            self.i_hold as u32,
+            Self::I_HOLD_MASK >> Self::I_HOLD_SHIFT,
+        )?;
+        check_field(
+            "i_run",
+            self.i_run as u32,
+            Self::I_RUN_MASK >> Self::I_RUN_SHIFT,
+        )?;
+        check_field(
+            "i_hold_delay",
+            self.i_hold_delay as u32,
+            Self::I_HOLD_DELAY_MASK >> Self::I_HOLD_DELAY_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for IHoldIRun<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -40,6 +83,7 @@ impl<const M: u8> Default for IHoldIRun<M> {
 
 impl<const M: u8> From<u32> for IHoldIRun<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             i_hold: read_from_bit(data, 0, 0x1f) as u8,
             i_run: read_from_bit(data, 8, 0x1f) as u8,
@@ -50,6 +94,7 @@ impl<const M: u8> From<u32> for IHoldIRun<M> {
 
 impl<const M: u8> From<IHoldIRun<M>> for u32 {
     fn from(data: IHoldIRun<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x1f, data.i_hold as u32);
         write_from_bit(&mut value, 8, 0x1f, data.i_run as u32);
@@ -59,16 +104,29 @@ impl<const M: u8> From<IHoldIRun<M>> for u32 {
 }
 
 impl Register for IHoldIRun<0> {
+    const MASK: u32 = Self::I_HOLD_MASK | Self::I_RUN_MASK | Self::I_HOLD_DELAY_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x30
     }
 }
+
+impl MotorRegister<0> for IHoldIRun<0> {}
 impl Register for IHoldIRun<1> {
+    const MASK: u32 = Self::I_HOLD_MASK | Self::I_RUN_MASK | Self::I_HOLD_DELAY_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x50
     }
 }
 
+impl MotorRegister<1> for IHoldIRun<1> {}
+impl<const M: u8> WritableRegister for IHoldIRun<M> {}
+
 #[cfg(test)]
 mod i_hold_i_run {
     use super::*;
@@ -101,6 +159,7 @@ mod i_hold_i_run {
 /// VCOOLTHRS: coolStep & stallGuard lower threshold velocity (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VCoolThrs<const M: u8> {
     /// coolStep & stallGuard lower threshold velocity (unsigned)
     ///
@@ -121,6 +180,23 @@ pub struct VCoolThrs<const M: u8> {
     pub v_cool_thrs: u32,
 }
 
+impl<const M: u8> VCoolThrs<M> {
+    /// Bit offset of [`v_cool_thrs`](VCoolThrs::v_cool_thrs)
+    pub const V_COOL_THRS_SHIFT: u32 = 0;
+    /// Bits of [`v_cool_thrs`](VCoolThrs::v_cool_thrs) in the register value
+    pub const V_COOL_THRS_MASK: u32 = 0x007fffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "v_cool_thrs",
+            self.v_cool_thrs,
+            Self::V_COOL_THRS_MASK >> Self::V_COOL_THRS_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VCoolThrs<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -129,6 +205,7 @@ impl<const M: u8> Default for VCoolThrs<M> {
 
 impl<const M: u8> From<u32> for VCoolThrs<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_cool_thrs: read_from_bit(data, 0, 0x7fffff) as u32,
         }
@@ -137,6 +214,7 @@ impl<const M: u8> From<u32> for VCoolThrs<M> {
 
 impl<const M: u8> From<VCoolThrs<M>> for u32 {
     fn from(data: VCoolThrs<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x7fffff, data.v_cool_thrs as u32);
         value
@@ -144,16 +222,29 @@ impl<const M: u8> From<VCoolThrs<M>> for u32 {
 }
 
 impl Register for VCoolThrs<0> {
+    const MASK: u32 = Self::V_COOL_THRS_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x31
     }
 }
+
+impl MotorRegister<0> for VCoolThrs<0> {}
 impl Register for VCoolThrs<1> {
+    const MASK: u32 = Self::V_COOL_THRS_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x51
     }
 }
 
+impl MotorRegister<1> for VCoolThrs<1> {}
+impl<const M: u8> WritableRegister for VCoolThrs<M> {}
+
 #[cfg(test)]
 mod v_cool_thrs {
     use super::*;
@@ -182,6 +273,7 @@ mod v_cool_thrs {
 /// VHIGH
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VHigh<const M: u8> {
     /// VHIGH
     ///
@@ -197,6 +289,23 @@ pub struct VHigh<const M: u8> {
     pub v_high: u32,
 }
 
+impl<const M: u8> VHigh<M> {
+    /// Bit offset of [`v_high`](VHigh::v_high)
+    pub const V_HIGH_SHIFT: u32 = 0;
+    /// Bits of [`v_high`](VHigh::v_high) in the register value
+    pub const V_HIGH_MASK: u32 = 0x007fffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "v_high",
+            self.v_high,
+            Self::V_HIGH_MASK >> Self::V_HIGH_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VHigh<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -205,6 +314,7 @@ impl<const M: u8> Default for VHigh<M> {
 
 impl<const M: u8> From<u32> for VHigh<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_high: read_from_bit(data, 0, 0x7fffff) as u32,
         }
@@ -213,6 +323,7 @@ impl<const M: u8> From<u32> for VHigh<M> {
 
 impl<const M: u8> From<VHigh<M>> for u32 {
     fn from(data: VHigh<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x7fffff, data.v_high as u32);
         value
@@ -220,16 +331,29 @@ impl<const M: u8> From<VHigh<M>> for u32 {
 }
 
 impl Register for VHigh<0> {
+    const MASK: u32 = Self::V_HIGH_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x32
     }
 }
+
+impl MotorRegister<0> for VHigh<0> {}
 impl Register for VHigh<1> {
+    const MASK: u32 = Self::V_HIGH_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x52
     }
 }
 
+impl MotorRegister<1> for VHigh<1> {}
+impl<const M: u8> WritableRegister for VHigh<M> {}
+
 #[cfg(test)]
 mod v_high {
     use super::*;
@@ -258,6 +382,7 @@ mod v_high {
 /// VDCMIN: dcStep minimum velocity (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VDcMin<const M: u8> {
     /// dcStep minimum velocity (unsigned)
     ///
@@ -276,6 +401,23 @@ pub struct VDcMin<const M: u8> {
     pub v_dc_min: u32,
 }
 
+impl<const M: u8> VDcMin<M> {
+    /// Bit offset of [`v_dc_min`](VDcMin::v_dc_min)
+    pub const V_DC_MIN_SHIFT: u32 = 0;
+    /// Bits of [`v_dc_min`](VDcMin::v_dc_min) in the register value
+    pub const V_DC_MIN_MASK: u32 = 0x007fffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "v_dc_min",
+            self.v_dc_min,
+            Self::V_DC_MIN_MASK >> Self::V_DC_MIN_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VDcMin<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -284,6 +426,7 @@ impl<const M: u8> Default for VDcMin<M> {
 
 impl<const M: u8> From<u32> for VDcMin<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_dc_min: read_from_bit(data, 0, 0x7fffff) as u32,
         }
@@ -292,6 +435,7 @@ impl<const M: u8> From<u32> for VDcMin<M> {
 
 impl<const M: u8> From<VDcMin<M>> for u32 {
     fn from(data: VDcMin<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x7fffff, data.v_dc_min as u32);
         value
@@ -299,16 +443,29 @@ impl<const M: u8> From<VDcMin<M>> for u32 {
 }
 
 impl Register for VDcMin<0> {
+    const MASK: u32 = Self::V_DC_MIN_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x33
     }
 }
+
+impl MotorRegister<0> for VDcMin<0> {}
 impl Register for VDcMin<1> {
+    const MASK: u32 = Self::V_DC_MIN_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x53
     }
 }
 
+impl MotorRegister<1> for VDcMin<1> {}
+impl<const M: u8> WritableRegister for VDcMin<M> {}
+
 #[cfg(test)]
 mod v_dc_min {
     use super::*;
@@ -337,6 +494,7 @@ mod v_dc_min {
 /// SW_MODE: Reference Switch & stallGuard2 Event Configuration
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SwMode<const M: u8> {
     /// stop_l_enable:
     /// - true: Enables automatic motor stop during active left reference switch input
@@ -400,6 +558,68 @@ pub struct SwMode<const M: u8> {
     pub en_softstop: bool,
 }
 
+impl<const M: u8> SwMode<M> {
+    /// Bit offset of [`stop_l_enable`](SwMode::stop_l_enable)
+    pub const STOP_L_ENABLE_SHIFT: u32 = 0;
+    /// Bits of [`stop_l_enable`](SwMode::stop_l_enable) in the register value
+    pub const STOP_L_ENABLE_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`stop_r_enable`](SwMode::stop_r_enable)
+    pub const STOP_R_ENABLE_SHIFT: u32 = 1;
+    /// Bits of [`stop_r_enable`](SwMode::stop_r_enable) in the register value
+    pub const STOP_R_ENABLE_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`pol_stop_l`](SwMode::pol_stop_l)
+    pub const POL_STOP_L_SHIFT: u32 = 2;
+    /// Bits of [`pol_stop_l`](SwMode::pol_stop_l) in the register value
+    pub const POL_STOP_L_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`pol_stop_r`](SwMode::pol_stop_r)
+    pub const POL_STOP_R_SHIFT: u32 = 3;
+    /// Bits of [`pol_stop_r`](SwMode::pol_stop_r) in the register value
+    pub const POL_STOP_R_MASK: u32 = 0x00000008;
+
+    /// Bit offset of [`swap_lr`](SwMode::swap_lr)
+    pub const SWAP_LR_SHIFT: u32 = 4;
+    /// Bits of [`swap_lr`](SwMode::swap_lr) in the register value
+    pub const SWAP_LR_MASK: u32 = 0x00000010;
+
+    /// Bit offset of [`latch_l_active`](SwMode::latch_l_active)
+    pub const LATCH_L_ACTIVE_SHIFT: u32 = 5;
+    /// Bits of [`latch_l_active`](SwMode::latch_l_active) in the register value
+    pub const LATCH_L_ACTIVE_MASK: u32 = 0x00000020;
+
+    /// Bit offset of [`latch_l_inactive`](SwMode::latch_l_inactive)
+    pub const LATCH_L_INACTIVE_SHIFT: u32 = 6;
+    /// Bits of [`latch_l_inactive`](SwMode::latch_l_inactive) in the register value
+    pub const LATCH_L_INACTIVE_MASK: u32 = 0x00000040;
+
+    /// Bit offset of [`latch_r_active`](SwMode::latch_r_active)
+    pub const LATCH_R_ACTIVE_SHIFT: u32 = 7;
+    /// Bits of [`latch_r_active`](SwMode::latch_r_active) in the register value
+    pub const LATCH_R_ACTIVE_MASK: u32 = 0x00000080;
+
+    /// Bit offset of [`latch_r_inactive`](SwMode::latch_r_inactive)
+    pub const LATCH_R_INACTIVE_SHIFT: u32 = 8;
+    /// Bits of [`latch_r_inactive`](SwMode::latch_r_inactive) in the register value
+    pub const LATCH_R_INACTIVE_MASK: u32 = 0x00000100;
+
+    /// Bit offset of [`en_latch_encoder`](SwMode::en_latch_encoder)
+    pub const EN_LATCH_ENCODER_SHIFT: u32 = 9;
+    /// Bits of [`en_latch_encoder`](SwMode::en_latch_encoder) in the register value
+    pub const EN_LATCH_ENCODER_MASK: u32 = 0x00000200;
+
+    /// Bit offset of [`sg_stop`](SwMode::sg_stop)
+    pub const SG_STOP_SHIFT: u32 = 10;
+    /// Bits of [`sg_stop`](SwMode::sg_stop) in the register value
+    pub const SG_STOP_MASK: u32 = 0x00000400;
+
+    /// Bit offset of [`en_softstop`](SwMode::en_softstop)
+    pub const EN_SOFTSTOP_SHIFT: u32 = 11;
+    /// Bits of [`en_softstop`](SwMode::en_softstop) in the register value
+    pub const EN_SOFTSTOP_MASK: u32 = 0x00000800;
+}
+
 impl<const M: u8> Default for SwMode<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -408,6 +628,7 @@ impl<const M: u8> Default for SwMode<M> {
 
 impl<const M: u8> From<u32> for SwMode<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             stop_l_enable: read_bool_from_bit(data, 0),
             stop_r_enable: read_bool_from_bit(data, 1),
@@ -427,6 +648,7 @@ impl<const M: u8> From<u32> for SwMode<M> {
 
 impl<const M: u8> From<SwMode<M>> for u32 {
     fn from(data: SwMode<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_bool_to_bit(&mut value, 0, data.stop_l_enable);
         write_bool_to_bit(&mut value, 1, data.stop_r_enable);
@@ -445,16 +667,52 @@ impl<const M: u8> From<SwMode<M>> for u32 {
 }
 
 impl Register for SwMode<0> {
+    const MASK: u32 = Self::STOP_L_ENABLE_MASK
+        | Self::STOP_R_ENABLE_MASK
+        | Self::POL_STOP_L_MASK
+        | Self::POL_STOP_R_MASK
+        | Self::SWAP_LR_MASK
+        | Self::LATCH_L_ACTIVE_MASK
+        | Self::LATCH_L_INACTIVE_MASK
+        | Self::LATCH_R_ACTIVE_MASK
+        | Self::LATCH_R_INACTIVE_MASK
+        | Self::EN_LATCH_ENCODER_MASK
+        | Self::SG_STOP_MASK
+        | Self::EN_SOFTSTOP_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x34
     }
 }
+
+impl MotorRegister<0> for SwMode<0> {}
 impl Register for SwMode<1> {
+    const MASK: u32 = Self::STOP_L_ENABLE_MASK
+        | Self::STOP_R_ENABLE_MASK
+        | Self::POL_STOP_L_MASK
+        | Self::POL_STOP_R_MASK
+        | Self::SWAP_LR_MASK
+        | Self::LATCH_L_ACTIVE_MASK
+        | Self::LATCH_L_INACTIVE_MASK
+        | Self::LATCH_R_ACTIVE_MASK
+        | Self::LATCH_R_INACTIVE_MASK
+        | Self::EN_LATCH_ENCODER_MASK
+        | Self::SG_STOP_MASK
+        | Self::EN_SOFTSTOP_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x54
     }
 }
 
+impl MotorRegister<1> for SwMode<1> {}
+impl<const M: u8> ReadableRegister for SwMode<M> {}
+impl<const M: u8> WritableRegister for SwMode<M> {}
+
 #[cfg(test)]
 mod sw_mode {
     use super::*;
@@ -487,6 +745,7 @@ mod sw_mode {
 /// RAMP_STAT: Ramp and Reference Switch Status
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RampStat<const M: u8> {
     /// status_stop_l: Reference switch left status (true=active)
     pub status_stop_l: bool,
@@ -566,6 +825,78 @@ pub struct RampStat<const M: u8> {
     pub status_sg: bool,
 }
 
+impl<const M: u8> RampStat<M> {
+    /// Bit offset of [`status_stop_l`](RampStat::status_stop_l)
+    pub const STATUS_STOP_L_SHIFT: u32 = 0;
+    /// Bits of [`status_stop_l`](RampStat::status_stop_l) in the register value
+    pub const STATUS_STOP_L_MASK: u32 = 0x00000001;
+
+    /// Bit offset of [`status_stop_r`](RampStat::status_stop_r)
+    pub const STATUS_STOP_R_SHIFT: u32 = 1;
+    /// Bits of [`status_stop_r`](RampStat::status_stop_r) in the register value
+    pub const STATUS_STOP_R_MASK: u32 = 0x00000002;
+
+    /// Bit offset of [`status_latch_l`](RampStat::status_latch_l)
+    pub const STATUS_LATCH_L_SHIFT: u32 = 2;
+    /// Bits of [`status_latch_l`](RampStat::status_latch_l) in the register value
+    pub const STATUS_LATCH_L_MASK: u32 = 0x00000004;
+
+    /// Bit offset of [`status_latch_r`](RampStat::status_latch_r)
+    pub const STATUS_LATCH_R_SHIFT: u32 = 3;
+    /// Bits of [`status_latch_r`](RampStat::status_latch_r) in the register value
+    pub const STATUS_LATCH_R_MASK: u32 = 0x00000008;
+
+    /// Bit offset of [`event_stop_l`](RampStat::event_stop_l)
+    pub const EVENT_STOP_L_SHIFT: u32 = 4;
+    /// Bits of [`event_stop_l`](RampStat::event_stop_l) in the register value
+    pub const EVENT_STOP_L_MASK: u32 = 0x00000010;
+
+    /// Bit offset of [`event_stop_r`](RampStat::event_stop_r)
+    pub const EVENT_STOP_R_SHIFT: u32 = 5;
+    /// Bits of [`event_stop_r`](RampStat::event_stop_r) in the register value
+    pub const EVENT_STOP_R_MASK: u32 = 0x00000020;
+
+    /// Bit offset of [`event_stop_sg`](RampStat::event_stop_sg)
+    pub const EVENT_STOP_SG_SHIFT: u32 = 6;
+    /// Bits of [`event_stop_sg`](RampStat::event_stop_sg) in the register value
+    pub const EVENT_STOP_SG_MASK: u32 = 0x00000040;
+
+    /// Bit offset of [`event_pos_reached`](RampStat::event_pos_reached)
+    pub const EVENT_POS_REACHED_SHIFT: u32 = 7;
+    /// Bits of [`event_pos_reached`](RampStat::event_pos_reached) in the register value
+    pub const EVENT_POS_REACHED_MASK: u32 = 0x00000080;
+
+    /// Bit offset of [`velocity_reached`](RampStat::velocity_reached)
+    pub const VELOCITY_REACHED_SHIFT: u32 = 8;
+    /// Bits of [`velocity_reached`](RampStat::velocity_reached) in the register value
+    pub const VELOCITY_REACHED_MASK: u32 = 0x00000100;
+
+    /// Bit offset of [`position_reached`](RampStat::position_reached)
+    pub const POSITION_REACHED_SHIFT: u32 = 9;
+    /// Bits of [`position_reached`](RampStat::position_reached) in the register value
+    pub const POSITION_REACHED_MASK: u32 = 0x00000200;
+
+    /// Bit offset of [`vzero`](RampStat::vzero)
+    pub const VZERO_SHIFT: u32 = 10;
+    /// Bits of [`vzero`](RampStat::vzero) in the register value
+    pub const VZERO_MASK: u32 = 0x00000400;
+
+    /// Bit offset of [`t_zerowait_active`](RampStat::t_zerowait_active)
+    pub const T_ZEROWAIT_ACTIVE_SHIFT: u32 = 11;
+    /// Bits of [`t_zerowait_active`](RampStat::t_zerowait_active) in the register value
+    pub const T_ZEROWAIT_ACTIVE_MASK: u32 = 0x00000800;
+
+    /// Bit offset of [`second_move`](RampStat::second_move)
+    pub const SECOND_MOVE_SHIFT: u32 = 12;
+    /// Bits of [`second_move`](RampStat::second_move) in the register value
+    pub const SECOND_MOVE_MASK: u32 = 0x00001000;
+
+    /// Bit offset of [`status_sg`](RampStat::status_sg)
+    pub const STATUS_SG_SHIFT: u32 = 13;
+    /// Bits of [`status_sg`](RampStat::status_sg) in the register value
+    pub const STATUS_SG_MASK: u32 = 0x00002000;
+}
+
 impl<const M: u8> Default for RampStat<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -574,6 +905,7 @@ impl<const M: u8> Default for RampStat<M> {
 
 impl<const M: u8> From<u32> for RampStat<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             status_stop_l: read_bool_from_bit(data, 0),
             status_stop_r: read_bool_from_bit(data, 1),
@@ -595,6 +927,7 @@ impl<const M: u8> From<u32> for RampStat<M> {
 
 impl<const M: u8> From<RampStat<M>> for u32 {
     fn from(data: RampStat<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_bool_to_bit(&mut value, 0, data.status_stop_l);
         write_bool_to_bit(&mut value, 1, data.status_stop_r);
@@ -615,16 +948,85 @@ impl<const M: u8> From<RampStat<M>> for u32 {
 }
 
 impl Register for RampStat<0> {
+    const MASK: u32 = Self::STATUS_STOP_L_MASK
+        | Self::STATUS_STOP_R_MASK
+        | Self::STATUS_LATCH_L_MASK
+        | Self::STATUS_LATCH_R_MASK
+        | Self::EVENT_STOP_L_MASK
+        | Self::EVENT_STOP_R_MASK
+        | Self::EVENT_STOP_SG_MASK
+        | Self::EVENT_POS_REACHED_MASK
+        | Self::VELOCITY_REACHED_MASK
+        | Self::POSITION_REACHED_MASK
+        | Self::VZERO_MASK
+        | Self::T_ZEROWAIT_ACTIVE_MASK
+        | Self::SECOND_MOVE_MASK
+        | Self::STATUS_SG_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadClear;
+
     fn addr() -> u8 {
         0x35
     }
 }
+
+impl MotorRegister<0> for RampStat<0> {}
 impl Register for RampStat<1> {
+    const MASK: u32 = Self::STATUS_STOP_L_MASK
+        | Self::STATUS_STOP_R_MASK
+        | Self::STATUS_LATCH_L_MASK
+        | Self::STATUS_LATCH_R_MASK
+        | Self::EVENT_STOP_L_MASK
+        | Self::EVENT_STOP_R_MASK
+        | Self::EVENT_STOP_SG_MASK
+        | Self::EVENT_POS_REACHED_MASK
+        | Self::VELOCITY_REACHED_MASK
+        | Self::POSITION_REACHED_MASK
+        | Self::VZERO_MASK
+        | Self::T_ZEROWAIT_ACTIVE_MASK
+        | Self::SECOND_MOVE_MASK
+        | Self::STATUS_SG_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadClear;
+
     fn addr() -> u8 {
         0x55
     }
 }
 
+impl MotorRegister<1> for RampStat<1> {}
+impl<const M: u8> ReadableRegister for RampStat<M> {}
+impl<const M: u8> ClearOnRead for RampStat<M> {}
+
+impl<const M: u8> RampStat<M> {
+    /// Flags with their datasheet names
+    pub(crate) fn flags(&self) -> [(&'static str, bool); 14] {
+        [
+            ("status_stop_l", self.status_stop_l),
+            ("status_stop_r", self.status_stop_r),
+            ("status_latch_l", self.status_latch_l),
+            ("status_latch_r", self.status_latch_r),
+            ("event_stop_l", self.event_stop_l),
+            ("event_stop_r", self.event_stop_r),
+            ("event_stop_sg", self.event_stop_sg),
+            ("event_pos_reached", self.event_pos_reached),
+            ("velocity_reached", self.velocity_reached),
+            ("position_reached", self.position_reached),
+            ("vzero", self.vzero),
+            ("t_zerowait_active", self.t_zerowait_active),
+            ("second_move", self.second_move),
+            ("status_sg", self.status_sg),
+        ]
+    }
+}
+
+impl<const M: u8> fmt::Display for RampStat<M> {
+    /// Names of the set flags, e.g. `[status_stop_l status_stop_r]`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_flags(f, &self.flags())
+    }
+}
+
 #[cfg(test)]
 mod ramp_stat {
     use super::*;
@@ -657,6 +1059,7 @@ mod ramp_stat {
 /// XLATCH: Ramp generator latch position
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct XLatch<const M: u8> {
     /// Ramp generator latch position
     ///
@@ -666,6 +1069,13 @@ pub struct XLatch<const M: u8> {
     pub x_latch: u32,
 }
 
+impl<const M: u8> XLatch<M> {
+    /// Bit offset of [`x_latch`](XLatch::x_latch)
+    pub const X_LATCH_SHIFT: u32 = 0;
+    /// Bits of [`x_latch`](XLatch::x_latch) in the register value
+    pub const X_LATCH_MASK: u32 = 0xffffffff;
+}
+
 impl<const M: u8> Default for XLatch<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -674,6 +1084,7 @@ impl<const M: u8> Default for XLatch<M> {
 
 impl<const M: u8> From<u32> for XLatch<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             x_latch: read_from_bit(data, 0, 0xffffffff) as u32,
         }
@@ -682,6 +1093,7 @@ impl<const M: u8> From<u32> for XLatch<M> {
 
 impl<const M: u8> From<XLatch<M>> for u32 {
     fn from(data: XLatch<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffffffff, data.x_latch as u32);
         value
@@ -689,16 +1101,29 @@ impl<const M: u8> From<XLatch<M>> for u32 {
 }
 
 impl Register for XLatch<0> {
+    const MASK: u32 = Self::X_LATCH_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x36
     }
 }
+
+impl MotorRegister<0> for XLatch<0> {}
 impl Register for XLatch<1> {
+    const MASK: u32 = Self::X_LATCH_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x56
     }
 }
 
+impl MotorRegister<1> for XLatch<1> {}
+impl<const M: u8> ReadableRegister for XLatch<M> {}
+
 #[cfg(test)]
 mod x_latch {
     use super::*;
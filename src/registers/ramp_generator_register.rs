@@ -7,21 +7,59 @@
 //! - acceleration and deceleration
 //! - target positioning
 
-use super::Register;
+use super::{
+    address::Access, check_field, check_signed_field, FieldOutOfRange, InvalidValue,
+    MotorIndexCheck, MotorRegister, ReadableRegister, Register, WritableRegister,
+};
 use crate::bits::{convert_from_signed_n, convert_to_signed_n, read_from_bit, write_from_bit};
+use core::convert::TryFrom;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// RAMPMODE value
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RampModeValue {
+    /// Positioning mode (using all A, D and V parameters)
+    #[default]
+    Positioning = 0,
+    /// Velocity mode to positive VMAX (using AMAX acceleration)
+    VelocityPos = 1,
+    /// Velocity mode to negative VMAX (using AMAX acceleration)
+    VelocityNeg = 2,
+    /// Hold mode (velocity remains unchanged, unless stop event occurs)
+    Hold = 3,
+}
+
+impl TryFrom<u8> for RampModeValue {
+    type Error = InvalidValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RampModeValue::Positioning),
+            1 => Ok(RampModeValue::VelocityPos),
+            2 => Ok(RampModeValue::VelocityNeg),
+            3 => Ok(RampModeValue::Hold),
+            _ => Err(InvalidValue(value)),
+        }
+    }
+}
+
 /// RAMPMODE
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RampMode<const M: u8> {
-    /// RAMPMODE:
-    /// - 0: Positioning mode (using all A, D and V parameters)
-    /// - 1: Velocity mode to positive VMAX (using AMAX acceleration)
-    /// - 2: Velocity mode to negative VMAX (using AMAX acceleration)
-    /// - 3: Hold mode (velocity remains unchanged, unless stop event occurs)
-    pub ramp_mode: u8,
+    /// RAMPMODE: positioning, velocity or hold mode
+    pub ramp_mode: RampModeValue,
+}
+
+impl<const M: u8> RampMode<M> {
+    /// Bit offset of [`ramp_mode`](RampMode::ramp_mode)
+    pub const RAMP_MODE_SHIFT: u32 = 0;
+    /// Bits of [`ramp_mode`](RampMode::ramp_mode) in the register value
+    pub const RAMP_MODE_MASK: u32 = 0x00000003;
 }
 
 impl<const M: u8> Default for RampMode<M> {
@@ -32,14 +70,18 @@ impl<const M: u8> Default for RampMode<M> {
 
 impl<const M: u8> From<u32> for RampMode<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
-            ramp_mode: read_from_bit(data, 0, 0x03) as u8,
+            // 2 bits, every value is defined
+            ramp_mode: RampModeValue::try_from(read_from_bit(data, 0, 0x03) as u8)
+                .unwrap_or_default(),
         }
     }
 }
 
 impl<const M: u8> From<RampMode<M>> for u32 {
     fn from(data: RampMode<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x03, data.ramp_mode as u32);
         value
@@ -47,16 +89,30 @@ impl<const M: u8> From<RampMode<M>> for u32 {
 }
 
 impl Register for RampMode<0> {
+    const MASK: u32 = Self::RAMP_MODE_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x20
     }
 }
+
+impl MotorRegister<0> for RampMode<0> {}
 impl Register for RampMode<1> {
+    const MASK: u32 = Self::RAMP_MODE_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x40
     }
 }
 
+impl MotorRegister<1> for RampMode<1> {}
+impl<const M: u8> ReadableRegister for RampMode<M> {}
+impl<const M: u8> WritableRegister for RampMode<M> {}
+
 #[cfg(test)]
 mod ramp_mode {
     use super::*;
@@ -64,7 +120,7 @@ mod ramp_mode {
     fn to_u32() {
         assert_eq!(
             u32::from(RampMode::<1> {
-                ramp_mode: 1,
+                ramp_mode: RampModeValue::VelocityPos,
                 ..Default::default()
             }),
             0x00000001
@@ -75,16 +131,22 @@ mod ramp_mode {
         assert_eq!(
             RampMode::<1>::from(0x00000001),
             RampMode::<1> {
-                ramp_mode: 1,
+                ramp_mode: RampModeValue::VelocityPos,
                 ..Default::default()
             },
         )
     }
+    #[test]
+    fn try_from_u8() {
+        assert_eq!(RampModeValue::try_from(3), Ok(RampModeValue::Hold));
+        assert_eq!(RampModeValue::try_from(4), Err(InvalidValue(4)));
+    }
 }
 
 /// XACTUAL: Actual motor position (signed)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct XActual<const M: u8> {
     /// Actual motor position (signed)
     ///
@@ -93,6 +155,13 @@ pub struct XActual<const M: u8> {
     pub x_actual: i32,
 }
 
+impl<const M: u8> XActual<M> {
+    /// Bit offset of [`x_actual`](XActual::x_actual)
+    pub const X_ACTUAL_SHIFT: u32 = 0;
+    /// Bits of [`x_actual`](XActual::x_actual) in the register value
+    pub const X_ACTUAL_MASK: u32 = 0xffffffff;
+}
+
 impl<const M: u8> Default for XActual<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -101,6 +170,7 @@ impl<const M: u8> Default for XActual<M> {
 
 impl<const M: u8> From<u32> for XActual<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             x_actual: read_from_bit(data, 0, 0xffffffff) as i32,
         }
@@ -109,6 +179,7 @@ impl<const M: u8> From<u32> for XActual<M> {
 
 impl<const M: u8> From<XActual<M>> for u32 {
     fn from(data: XActual<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffffffff, data.x_actual as u32);
         value
@@ -116,16 +187,30 @@ impl<const M: u8> From<XActual<M>> for u32 {
 }
 
 impl Register for XActual<0> {
+    const MASK: u32 = Self::X_ACTUAL_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x21
     }
 }
+
+impl MotorRegister<0> for XActual<0> {}
 impl Register for XActual<1> {
+    const MASK: u32 = Self::X_ACTUAL_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x41
     }
 }
 
+impl MotorRegister<1> for XActual<1> {}
+impl<const M: u8> ReadableRegister for XActual<M> {}
+impl<const M: u8> WritableRegister for XActual<M> {}
+
 #[cfg(test)]
 mod x_actual {
     use super::*;
@@ -154,6 +239,7 @@ mod x_actual {
 /// VACTUAL: Actual motor velocity from ramp generator (signed)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VActual<const M: u8> {
     /// Actual motor velocity from ramp generator (signed)
     ///
@@ -161,6 +247,19 @@ pub struct VActual<const M: u8> {
     pub v_actual: i32,
 }
 
+impl<const M: u8> VActual<M> {
+    /// Bit offset of [`v_actual`](VActual::v_actual)
+    pub const V_ACTUAL_SHIFT: u32 = 0;
+    /// Bits of [`v_actual`](VActual::v_actual) in the register value
+    pub const V_ACTUAL_MASK: u32 = 0x00ffffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_signed_field("v_actual", self.v_actual, Self::V_ACTUAL_MASK.count_ones())?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VActual<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -169,6 +268,7 @@ impl<const M: u8> Default for VActual<M> {
 
 impl<const M: u8> From<u32> for VActual<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_actual: convert_to_signed_n(read_from_bit(data, 0, 0xffffff) as u32, 24),
         }
@@ -177,6 +277,7 @@ impl<const M: u8> From<u32> for VActual<M> {
 
 impl<const M: u8> From<VActual<M>> for u32 {
     fn from(data: VActual<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(
             &mut value,
@@ -189,16 +290,29 @@ impl<const M: u8> From<VActual<M>> for u32 {
 }
 
 impl Register for VActual<0> {
+    const MASK: u32 = Self::V_ACTUAL_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x22
     }
 }
+
+impl MotorRegister<0> for VActual<0> {}
 impl Register for VActual<1> {
+    const MASK: u32 = Self::V_ACTUAL_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x42
     }
 }
 
+impl MotorRegister<1> for VActual<1> {}
+impl<const M: u8> ReadableRegister for VActual<M> {}
+
 #[cfg(test)]
 mod v_actual {
     use super::*;
@@ -227,6 +341,7 @@ mod v_actual {
 /// VSTART: Motor start velocity (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VStart<const M: u8> {
     /// Motor start velocity (unsigned)
     ///
@@ -234,6 +349,23 @@ pub struct VStart<const M: u8> {
     pub v_start: u32,
 }
 
+impl<const M: u8> VStart<M> {
+    /// Bit offset of [`v_start`](VStart::v_start)
+    pub const V_START_SHIFT: u32 = 0;
+    /// Bits of [`v_start`](VStart::v_start) in the register value
+    pub const V_START_MASK: u32 = 0x0003ffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "v_start",
+            self.v_start,
+            Self::V_START_MASK >> Self::V_START_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VStart<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -242,6 +374,7 @@ impl<const M: u8> Default for VStart<M> {
 
 impl<const M: u8> From<u32> for VStart<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_start: read_from_bit(data, 0, 0x3ffff) as u32,
         }
@@ -250,6 +383,7 @@ impl<const M: u8> From<u32> for VStart<M> {
 
 impl<const M: u8> From<VStart<M>> for u32 {
     fn from(data: VStart<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x3ffff, data.v_start as u32);
         value
@@ -257,16 +391,29 @@ impl<const M: u8> From<VStart<M>> for u32 {
 }
 
 impl Register for VStart<0> {
+    const MASK: u32 = Self::V_START_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x23
     }
 }
+
+impl MotorRegister<0> for VStart<0> {}
 impl Register for VStart<1> {
+    const MASK: u32 = Self::V_START_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x43
     }
 }
 
+impl MotorRegister<1> for VStart<1> {}
+impl<const M: u8> WritableRegister for VStart<M> {}
+
 #[cfg(test)]
 mod v_start {
     use super::*;
@@ -295,11 +442,19 @@ mod v_start {
 /// A1: First acceleration between VSTART and V1 (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct A1<const M: u8> {
     /// First acceleration between VSTART and V1 (unsigned)
     pub a1: u16,
 }
 
+impl<const M: u8> A1<M> {
+    /// Bit offset of [`a1`](A1::a1)
+    pub const A1_SHIFT: u32 = 0;
+    /// Bits of [`a1`](A1::a1) in the register value
+    pub const A1_MASK: u32 = 0x0000ffff;
+}
+
 impl<const M: u8> Default for A1<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -308,6 +463,7 @@ impl<const M: u8> Default for A1<M> {
 
 impl<const M: u8> From<u32> for A1<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             a1: read_from_bit(data, 0, 0xffff) as u16,
         }
@@ -316,6 +472,7 @@ impl<const M: u8> From<u32> for A1<M> {
 
 impl<const M: u8> From<A1<M>> for u32 {
     fn from(data: A1<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffff, data.a1 as u32);
         value
@@ -323,16 +480,29 @@ impl<const M: u8> From<A1<M>> for u32 {
 }
 
 impl Register for A1<0> {
+    const MASK: u32 = Self::A1_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x24
     }
 }
+
+impl MotorRegister<0> for A1<0> {}
 impl Register for A1<1> {
+    const MASK: u32 = Self::A1_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x44
     }
 }
 
+impl MotorRegister<1> for A1<1> {}
+impl<const M: u8> WritableRegister for A1<M> {}
+
 #[cfg(test)]
 mod a1 {
     use super::*;
@@ -361,6 +531,7 @@ mod a1 {
 /// V1: First acceleration / deceleration phase threshold velocity (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct V1<const M: u8> {
     /// First acceleration / deceleration phase threshold velocity (unsigned)
     ///
@@ -368,6 +539,19 @@ pub struct V1<const M: u8> {
     pub v1: u32,
 }
 
+impl<const M: u8> V1<M> {
+    /// Bit offset of [`v1`](V1::v1)
+    pub const V1_SHIFT: u32 = 0;
+    /// Bits of [`v1`](V1::v1) in the register value
+    pub const V1_MASK: u32 = 0x000fffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field("v1", self.v1, Self::V1_MASK >> Self::V1_SHIFT)?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for V1<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -376,6 +560,7 @@ impl<const M: u8> Default for V1<M> {
 
 impl<const M: u8> From<u32> for V1<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v1: read_from_bit(data, 0, 0xfffff) as u32,
         }
@@ -384,6 +569,7 @@ impl<const M: u8> From<u32> for V1<M> {
 
 impl<const M: u8> From<V1<M>> for u32 {
     fn from(data: V1<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xfffff, data.v1 as u32);
         value
@@ -391,16 +577,29 @@ impl<const M: u8> From<V1<M>> for u32 {
 }
 
 impl Register for V1<0> {
+    const MASK: u32 = Self::V1_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x25
     }
 }
+
+impl MotorRegister<0> for V1<0> {}
 impl Register for V1<1> {
+    const MASK: u32 = Self::V1_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x45
     }
 }
 
+impl MotorRegister<1> for V1<1> {}
+impl<const M: u8> WritableRegister for V1<M> {}
+
 #[cfg(test)]
 mod v1 {
     use super::*;
@@ -429,6 +628,7 @@ mod v1 {
 /// AMAX: Second acceleration between V1 and VMAX (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct AMax<const M: u8> {
     /// Second acceleration between V1 and VMAX (unsigned)
     ///
@@ -436,6 +636,13 @@ pub struct AMax<const M: u8> {
     pub a_max: u16,
 }
 
+impl<const M: u8> AMax<M> {
+    /// Bit offset of [`a_max`](AMax::a_max)
+    pub const A_MAX_SHIFT: u32 = 0;
+    /// Bits of [`a_max`](AMax::a_max) in the register value
+    pub const A_MAX_MASK: u32 = 0x0000ffff;
+}
+
 impl<const M: u8> Default for AMax<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -444,6 +651,7 @@ impl<const M: u8> Default for AMax<M> {
 
 impl<const M: u8> From<u32> for AMax<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             a_max: read_from_bit(data, 0, 0xffff) as u16,
         }
@@ -452,6 +660,7 @@ impl<const M: u8> From<u32> for AMax<M> {
 
 impl<const M: u8> From<AMax<M>> for u32 {
     fn from(data: AMax<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffff, data.a_max as u32);
         value
@@ -459,16 +668,29 @@ impl<const M: u8> From<AMax<M>> for u32 {
 }
 
 impl Register for AMax<0> {
+    const MASK: u32 = Self::A_MAX_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x26
     }
 }
+
+impl MotorRegister<0> for AMax<0> {}
 impl Register for AMax<1> {
+    const MASK: u32 = Self::A_MAX_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x46
     }
 }
 
+impl MotorRegister<1> for AMax<1> {}
+impl<const M: u8> WritableRegister for AMax<M> {}
+
 #[cfg(test)]
 mod a_max {
     use super::*;
@@ -497,6 +719,7 @@ mod a_max {
 /// VMAX: Motion ramp target velocity (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VMax<const M: u8> {
     /// Motion ramp target velocity (unsigned)
     ///
@@ -504,6 +727,19 @@ pub struct VMax<const M: u8> {
     pub v_max: u32,
 }
 
+impl<const M: u8> VMax<M> {
+    /// Bit offset of [`v_max`](VMax::v_max)
+    pub const V_MAX_SHIFT: u32 = 0;
+    /// Bits of [`v_max`](VMax::v_max) in the register value
+    pub const V_MAX_MASK: u32 = 0x007fffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field("v_max", self.v_max, Self::V_MAX_MASK >> Self::V_MAX_SHIFT)?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VMax<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -512,6 +748,7 @@ impl<const M: u8> Default for VMax<M> {
 
 impl<const M: u8> From<u32> for VMax<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_max: read_from_bit(data, 0, 0x7fffff) as u32,
         }
@@ -520,6 +757,7 @@ impl<const M: u8> From<u32> for VMax<M> {
 
 impl<const M: u8> From<VMax<M>> for u32 {
     fn from(data: VMax<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x7fffff, data.v_max as u32);
         value
@@ -527,16 +765,29 @@ impl<const M: u8> From<VMax<M>> for u32 {
 }
 
 impl Register for VMax<0> {
+    const MASK: u32 = Self::V_MAX_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x27
     }
 }
+
+impl MotorRegister<0> for VMax<0> {}
 impl Register for VMax<1> {
+    const MASK: u32 = Self::V_MAX_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x47
     }
 }
 
+impl MotorRegister<1> for VMax<1> {}
+impl<const M: u8> WritableRegister for VMax<M> {}
+
 #[cfg(test)]
 mod v_max {
     use super::*;
@@ -565,11 +816,19 @@ mod v_max {
 /// DMAX: Deceleration between VMAX and V1 (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DMax<const M: u8> {
     /// Deceleration between VMAX and V1 (unsigned)
     pub d_max: u16,
 }
 
+impl<const M: u8> DMax<M> {
+    /// Bit offset of [`d_max`](DMax::d_max)
+    pub const D_MAX_SHIFT: u32 = 0;
+    /// Bits of [`d_max`](DMax::d_max) in the register value
+    pub const D_MAX_MASK: u32 = 0x0000ffff;
+}
+
 impl<const M: u8> Default for DMax<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -578,6 +837,7 @@ impl<const M: u8> Default for DMax<M> {
 
 impl<const M: u8> From<u32> for DMax<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             d_max: read_from_bit(data, 0, 0xffff) as u16,
         }
@@ -586,6 +846,7 @@ impl<const M: u8> From<u32> for DMax<M> {
 
 impl<const M: u8> From<DMax<M>> for u32 {
     fn from(data: DMax<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffff, data.d_max as u32);
         value
@@ -593,16 +854,29 @@ impl<const M: u8> From<DMax<M>> for u32 {
 }
 
 impl Register for DMax<0> {
+    const MASK: u32 = Self::D_MAX_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x28
     }
 }
+
+impl MotorRegister<0> for DMax<0> {}
 impl Register for DMax<1> {
+    const MASK: u32 = Self::D_MAX_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x48
     }
 }
 
+impl MotorRegister<1> for DMax<1> {}
+impl<const M: u8> WritableRegister for DMax<M> {}
+
 #[cfg(test)]
 mod d_max {
     use super::*;
@@ -630,6 +904,7 @@ mod d_max {
 /// D1: Deceleration between V1 and VSTOP (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct D1<const M: u8> {
     /// Deceleration between V1 and VSTOP (unsigned)
     ///
@@ -637,6 +912,13 @@ pub struct D1<const M: u8> {
     pub d1: u16,
 }
 
+impl<const M: u8> D1<M> {
+    /// Bit offset of [`d1`](D1::d1)
+    pub const D1_SHIFT: u32 = 0;
+    /// Bits of [`d1`](D1::d1) in the register value
+    pub const D1_MASK: u32 = 0x0000ffff;
+}
+
 impl<const M: u8> Default for D1<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -645,6 +927,7 @@ impl<const M: u8> Default for D1<M> {
 
 impl<const M: u8> From<u32> for D1<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             d1: read_from_bit(data, 0, 0xffff) as u16,
         }
@@ -653,6 +936,7 @@ impl<const M: u8> From<u32> for D1<M> {
 
 impl<const M: u8> From<D1<M>> for u32 {
     fn from(data: D1<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffff, data.d1 as u32);
         value
@@ -660,16 +944,29 @@ impl<const M: u8> From<D1<M>> for u32 {
 }
 
 impl Register for D1<0> {
+    const MASK: u32 = Self::D1_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x2a
     }
 }
+
+impl MotorRegister<0> for D1<0> {}
 impl Register for D1<1> {
+    const MASK: u32 = Self::D1_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x4a
     }
 }
 
+impl MotorRegister<1> for D1<1> {}
+impl<const M: u8> WritableRegister for D1<M> {}
+
 #[cfg(test)]
 mod d1 {
     use super::*;
@@ -698,6 +995,7 @@ mod d1 {
 /// VSTOP: Motor stop velocity (unsigned)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct VStop<const M: u8> {
     ///  Motor stop velocity (unsigned)
     ///
@@ -707,6 +1005,23 @@ pub struct VStop<const M: u8> {
     pub v_stop: u32,
 }
 
+impl<const M: u8> VStop<M> {
+    /// Bit offset of [`v_stop`](VStop::v_stop)
+    pub const V_STOP_SHIFT: u32 = 0;
+    /// Bits of [`v_stop`](VStop::v_stop) in the register value
+    pub const V_STOP_MASK: u32 = 0x0003ffff;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "v_stop",
+            self.v_stop,
+            Self::V_STOP_MASK >> Self::V_STOP_SHIFT,
+        )?;
+        Ok(self)
+    }
+}
+
 impl<const M: u8> Default for VStop<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -715,6 +1030,7 @@ impl<const M: u8> Default for VStop<M> {
 
 impl<const M: u8> From<u32> for VStop<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             v_stop: read_from_bit(data, 0, 0x3ffff) as u32,
         }
@@ -723,6 +1039,7 @@ impl<const M: u8> From<u32> for VStop<M> {
 
 impl<const M: u8> From<VStop<M>> for u32 {
     fn from(data: VStop<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0x3ffff, data.v_stop as u32);
         value
@@ -730,16 +1047,29 @@ impl<const M: u8> From<VStop<M>> for u32 {
 }
 
 impl Register for VStop<0> {
+    const MASK: u32 = Self::V_STOP_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x2b
     }
 }
+
+impl MotorRegister<0> for VStop<0> {}
 impl Register for VStop<1> {
+    const MASK: u32 = Self::V_STOP_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x4b
     }
 }
 
+impl MotorRegister<1> for VStop<1> {}
+impl<const M: u8> WritableRegister for VStop<M> {}
+
 #[cfg(test)]
 mod v_stop {
     use super::*;
@@ -768,6 +1098,7 @@ mod v_stop {
 /// TZEROWAIT: Waiting time after ramping down to zero velocity
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TZeroWait<const M: u8> {
     /// Waiting time after ramping down to zero velocity before next movement or direction inversion can start and before motor power down starts.
     /// Time range is about 0 to 2 seconds.
@@ -776,6 +1107,13 @@ pub struct TZeroWait<const M: u8> {
     pub t_zero_wait: u16,
 }
 
+impl<const M: u8> TZeroWait<M> {
+    /// Bit offset of [`t_zero_wait`](TZeroWait::t_zero_wait)
+    pub const T_ZERO_WAIT_SHIFT: u32 = 0;
+    /// Bits of [`t_zero_wait`](TZeroWait::t_zero_wait) in the register value
+    pub const T_ZERO_WAIT_MASK: u32 = 0x0000ffff;
+}
+
 impl<const M: u8> Default for TZeroWait<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -784,6 +1122,7 @@ impl<const M: u8> Default for TZeroWait<M> {
 
 impl<const M: u8> From<u32> for TZeroWait<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             t_zero_wait: read_from_bit(data, 0, 0xffff) as u16,
         }
@@ -792,6 +1131,7 @@ impl<const M: u8> From<u32> for TZeroWait<M> {
 
 impl<const M: u8> From<TZeroWait<M>> for u32 {
     fn from(data: TZeroWait<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffff, data.t_zero_wait as u32);
         value
@@ -799,16 +1139,29 @@ impl<const M: u8> From<TZeroWait<M>> for u32 {
 }
 
 impl Register for TZeroWait<0> {
+    const MASK: u32 = Self::T_ZERO_WAIT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x2c
     }
 }
+
+impl MotorRegister<0> for TZeroWait<0> {}
 impl Register for TZeroWait<1> {
+    const MASK: u32 = Self::T_ZERO_WAIT_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x4c
     }
 }
 
+impl MotorRegister<1> for TZeroWait<1> {}
+impl<const M: u8> WritableRegister for TZeroWait<M> {}
+
 #[cfg(test)]
 mod t_zero_wait {
     use super::*;
@@ -837,6 +1190,7 @@ mod t_zero_wait {
 /// XTARGET: Target position for ramp mode (signed)
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct XTarget<const M: u8> {
     /// Target position for ramp mode (signed). Write a new target position to this register in order to activate the ramp generator positioning in RAMPMODE=0.
     /// Initialize all velocity, acceleration and deceleration parameters before.
@@ -849,6 +1203,13 @@ pub struct XTarget<const M: u8> {
     pub x_target: i32,
 }
 
+impl<const M: u8> XTarget<M> {
+    /// Bit offset of [`x_target`](XTarget::x_target)
+    pub const X_TARGET_SHIFT: u32 = 0;
+    /// Bits of [`x_target`](XTarget::x_target) in the register value
+    pub const X_TARGET_MASK: u32 = 0xffffffff;
+}
+
 impl<const M: u8> Default for XTarget<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -857,6 +1218,7 @@ impl<const M: u8> Default for XTarget<M> {
 
 impl<const M: u8> From<u32> for XTarget<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             x_target: read_from_bit(data, 0, 0xffffffff) as i32,
         }
@@ -865,6 +1227,7 @@ impl<const M: u8> From<u32> for XTarget<M> {
 
 impl<const M: u8> From<XTarget<M>> for u32 {
     fn from(data: XTarget<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xffffffff, data.x_target as u32);
         value
@@ -872,16 +1235,30 @@ impl<const M: u8> From<XTarget<M>> for u32 {
 }
 
 impl Register for XTarget<0> {
+    const MASK: u32 = Self::X_TARGET_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x2d
     }
 }
+
+impl MotorRegister<0> for XTarget<0> {}
 impl Register for XTarget<1> {
+    const MASK: u32 = Self::X_TARGET_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::ReadWrite;
+
     fn addr() -> u8 {
         0x4d
     }
 }
 
+impl MotorRegister<1> for XTarget<1> {}
+impl<const M: u8> ReadableRegister for XTarget<M> {}
+impl<const M: u8> WritableRegister for XTarget<M> {}
+
 #[cfg(test)]
 mod x_target {
     use super::*;
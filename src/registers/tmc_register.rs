@@ -0,0 +1,196 @@
+//! Chip generic register access (`tmc-register` feature)
+//!
+//! [`TmcRegister`] carries the address and the access mode of a register as associated constants, so the access core
+//! (frame building, read and write permission checks) does not depend on the TMC5072 register set. Other TMC chips
+//! sharing the SPI datagram format can implement it for their own register types, and user code can be written
+//! against the trait rather than against the TMC5072 structs:
+//! ```
+//! use tmc5072::registers::{tmc_register::{self, TmcRegister}, ramp_generator_register::XTarget};
+//!
+//! fn target_frame<R: TmcRegister>(r: R) -> Option<[u8; 5]> {
+//!     R::WRITABLE.then(|| tmc_register::write_frame(r))
+//! }
+//!
+//! assert_eq!(
+//!     target_frame(XTarget::<0> { x_target: 0x100 }),
+//!     Some([0xAD, 0x00, 0x00, 0x01, 0x00])
+//! );
+//! ```
+
+use crate::spi;
+
+use super::{
+    address::{Access, RegisterAddress},
+    encoder_registers::{EncConst, EncLatch, EncMode, EncStatus, XEnc},
+    general_configuration_register::{GConf, GStat, IfCnt, Input, Output, SlaveConf, XCompare},
+    microstep_table_register::{
+        MsLut0, MsLut1, MsLut2, MsLut3, MsLut4, MsLut5, MsLut6, MsLut7, MsLutSel, MsLutStart,
+    },
+    motor_driver_register::{ChopConf, CoolConf, DcCtrl, DrvStatus, MsCnt, MsCurAct},
+    ramp_generator_driver_feature_control_register::{
+        IHoldIRun, RampStat, SwMode, VCoolThrs, VDcMin, VHigh, XLatch,
+    },
+    ramp_generator_register::{
+        AMax, DMax, RampMode, TZeroWait, VActual, VMax, VStart, VStop, XActual, XTarget, A1, D1, V1,
+    },
+    voltage_pwm_mode_stealth_chop::{PwmConf, PwmStatus},
+};
+
+/// Register with a compile time address and access mode
+pub trait TmcRegister: Copy + From<u32> + Into<u32> {
+    /// Address
+    const ADDR: u8;
+    /// Access mode
+    const ACCESS: Access;
+    /// Whether reading returns the register value
+    const READABLE: bool = Self::ACCESS.is_readable();
+    /// Whether the register can be written
+    const WRITABLE: bool = Self::ACCESS.is_writable();
+}
+
+/// Builds the datagram reading `R`
+pub fn read_frame<R: TmcRegister>() -> [u8; 5] {
+    spi::read_frame(R::ADDR)
+}
+
+/// Builds the datagram writing `register`
+pub fn write_frame<R: TmcRegister>(register: R) -> [u8; 5] {
+    spi::write_frame(R::ADDR, register.into())
+}
+
+/// Implements [`TmcRegister`] from the [`RegisterAddress`] of each register, with an optional access override for
+/// registers sharing an address
+macro_rules! tmc_registers {
+    ($($register:ty => $address:ident $(as $access:ident)?,)*) => {
+        $(impl TmcRegister for $register {
+            const ADDR: u8 = RegisterAddress::$address.addr();
+            const ACCESS: Access = tmc_registers!(@access $address $($access)?);
+        })*
+
+        #[cfg(test)]
+        mod generated {
+            use super::*;
+            use crate::registers::Register;
+
+            #[test]
+            fn addresses() {
+                $(assert_eq!(<$register as TmcRegister>::ADDR, <$register as Register>::addr());)*
+            }
+        }
+    };
+    (@access $address:ident) => {
+        RegisterAddress::$address.access()
+    };
+    (@access $address:ident $access:ident) => {
+        Access::$access
+    };
+}
+
+tmc_registers! {
+    EncMode<0> => EncMode0,
+    EncMode<1> => EncMode1,
+    XEnc<0> => XEnc0,
+    XEnc<1> => XEnc1,
+    EncConst<0> => EncConst0,
+    EncConst<1> => EncConst1,
+    EncStatus<0> => EncStatus0,
+    EncStatus<1> => EncStatus1,
+    EncLatch<0> => EncLatch0,
+    EncLatch<1> => EncLatch1,
+    GConf => GConf,
+    GStat => GStat,
+    IfCnt => IfCnt,
+    SlaveConf => SlaveConf,
+    Input => InputOutput as Read,
+    Output => InputOutput as Write,
+    XCompare => XCompare,
+    MsLut0 => MsLut0,
+    MsLut1 => MsLut1,
+    MsLut2 => MsLut2,
+    MsLut3 => MsLut3,
+    MsLut4 => MsLut4,
+    MsLut5 => MsLut5,
+    MsLut6 => MsLut6,
+    MsLut7 => MsLut7,
+    MsLutSel => MsLutSel,
+    MsLutStart => MsLutStart,
+    MsCnt<0> => MsCnt0,
+    MsCnt<1> => MsCnt1,
+    MsCurAct<0> => MsCurAct0,
+    MsCurAct<1> => MsCurAct1,
+    ChopConf<0> => ChopConf0,
+    ChopConf<1> => ChopConf1,
+    CoolConf<0> => CoolConf0,
+    CoolConf<1> => CoolConf1,
+    DcCtrl<0> => DcCtrl0,
+    DcCtrl<1> => DcCtrl1,
+    DrvStatus<0> => DrvStatus0,
+    DrvStatus<1> => DrvStatus1,
+    IHoldIRun<0> => IHoldIRun0,
+    IHoldIRun<1> => IHoldIRun1,
+    VCoolThrs<0> => VCoolThrs0,
+    VCoolThrs<1> => VCoolThrs1,
+    VHigh<0> => VHigh0,
+    VHigh<1> => VHigh1,
+    VDcMin<0> => VDcMin0,
+    VDcMin<1> => VDcMin1,
+    SwMode<0> => SwMode0,
+    SwMode<1> => SwMode1,
+    RampStat<0> => RampStat0,
+    RampStat<1> => RampStat1,
+    XLatch<0> => XLatch0,
+    XLatch<1> => XLatch1,
+    RampMode<0> => RampMode0,
+    RampMode<1> => RampMode1,
+    XActual<0> => XActual0,
+    XActual<1> => XActual1,
+    VActual<0> => VActual0,
+    VActual<1> => VActual1,
+    VStart<0> => VStart0,
+    VStart<1> => VStart1,
+    A1<0> => A10,
+    A1<1> => A11,
+    V1<0> => V10,
+    V1<1> => V11,
+    AMax<0> => AMax0,
+    AMax<1> => AMax1,
+    VMax<0> => VMax0,
+    VMax<1> => VMax1,
+    DMax<0> => DMax0,
+    DMax<1> => DMax1,
+    D1<0> => D10,
+    D1<1> => D11,
+    VStop<0> => VStop0,
+    VStop<1> => VStop1,
+    TZeroWait<0> => TZeroWait0,
+    TZeroWait<1> => TZeroWait1,
+    XTarget<0> => XTarget0,
+    XTarget<1> => XTarget1,
+    PwmConf<0> => PwmConf0,
+    PwmConf<1> => PwmConf1,
+    PwmStatus<0> => PwmStatus0,
+    PwmStatus<1> => PwmStatus1,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn access() {
+        assert_eq!((GConf::READABLE, GConf::WRITABLE), (true, true));
+        assert_eq!((VMax::<1>::READABLE, VMax::<1>::WRITABLE), (false, true));
+        assert_eq!((GStat::READABLE, GStat::WRITABLE), (true, false));
+        assert_eq!((Input::READABLE, Input::WRITABLE), (true, false));
+        assert_eq!((Output::READABLE, Output::WRITABLE), (false, true));
+    }
+
+    #[test]
+    fn frames() {
+        assert_eq!(read_frame::<DrvStatus<1>>(), [0x7F, 0, 0, 0, 0]);
+        assert_eq!(
+            write_frame(XActual::<0> { x_actual: -1 }),
+            [0xA1, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+}
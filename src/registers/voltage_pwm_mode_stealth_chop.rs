@@ -1,13 +1,51 @@
 //! Voltage PWM mode stealthChop
 
-use super::Register;
+use super::{
+    address::Access, check_field, FieldOutOfRange, InvalidValue, MotorIndexCheck, MotorRegister,
+    ReadableRegister, Register, WritableRegister,
+};
 use crate::bits::{read_bool_from_bit, read_from_bit, write_bool_to_bit, write_from_bit};
+use core::convert::TryFrom;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// PWMCONF freewheel: standstill mode with IHOLD=0
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Freewheel {
+    /// Normal operation
+    #[default]
+    Normal = 0,
+    /// Freewheeling
+    Freewheel = 1,
+    /// Coil shorted using LS drivers
+    ShortLs = 2,
+    /// Coil shorted using HS drivers
+    ShortHs = 3,
+}
+
+impl TryFrom<u8> for Freewheel {
+    type Error = InvalidValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Freewheel::Normal),
+            1 => Ok(Freewheel::Freewheel),
+            2 => Ok(Freewheel::ShortLs),
+            3 => Ok(Freewheel::ShortHs),
+            _ => Err(InvalidValue(value)),
+        }
+    }
+}
+
+/// PWMCONF after a reset: amplitude 128 and gradient 4 at fPWM=2/683 fCLK with autoscale
+const PWM_CONF_RESET: u32 = 0x0005_0480;
+
 /// PWMCONF: Voltage PWM mode chopper configuration
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PwmConf<const M: u8> {
     /// PWM_ AMPL: User defined amplitude
     ///
@@ -54,29 +92,69 @@ pub struct PwmConf<const M: u8> {
     /// - %01: Freewheeling
     /// - %10: Coil shorted using LS drivers
     /// - %11: Coil shorted using HS drivers
-    pub freewheel: u8,
+    pub freewheel: Freewheel,
+}
+
+impl<const M: u8> PwmConf<M> {
+    /// Bit offset of [`pwm_ampl`](PwmConf::pwm_ampl)
+    pub const PWM_AMPL_SHIFT: u32 = 0;
+    /// Bits of [`pwm_ampl`](PwmConf::pwm_ampl) in the register value
+    pub const PWM_AMPL_MASK: u32 = 0x000000ff;
+
+    /// Bit offset of [`pwm_grad`](PwmConf::pwm_grad)
+    pub const PWM_GRAD_SHIFT: u32 = 8;
+    /// Bits of [`pwm_grad`](PwmConf::pwm_grad) in the register value
+    pub const PWM_GRAD_MASK: u32 = 0x0000ff00;
+
+    /// Bit offset of [`pwm_freq`](PwmConf::pwm_freq)
+    pub const PWM_FREQ_SHIFT: u32 = 16;
+    /// Bits of [`pwm_freq`](PwmConf::pwm_freq) in the register value
+    pub const PWM_FREQ_MASK: u32 = 0x00030000;
+
+    /// Bit offset of [`pwm_autoscale`](PwmConf::pwm_autoscale)
+    pub const PWM_AUTOSCALE_SHIFT: u32 = 18;
+    /// Bits of [`pwm_autoscale`](PwmConf::pwm_autoscale) in the register value
+    pub const PWM_AUTOSCALE_MASK: u32 = 0x00040000;
+
+    /// Bit offset of [`freewheel`](PwmConf::freewheel)
+    pub const FREEWHEEL_SHIFT: u32 = 20;
+    /// Bits of [`freewheel`](PwmConf::freewheel) in the register value
+    pub const FREEWHEEL_MASK: u32 = 0x00300000;
+
+    /// Checks that every field fits its bits, the u32 conversion silently truncating them
+    pub fn checked(self) -> Result<Self, FieldOutOfRange> {
+        check_field(
+            "pwm_freq",
+            self.pwm_freq as u32,
+            Self::PWM_FREQ_MASK >> Self::PWM_FREQ_SHIFT,
+        )?;
+        Ok(self)
+    }
 }
 
 impl<const M: u8> Default for PwmConf<M> {
     fn default() -> Self {
-        Self::from(0u32)
+        Self::from(PWM_CONF_RESET)
     }
 }
 
 impl<const M: u8> From<u32> for PwmConf<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             pwm_ampl: read_from_bit(data, 0, 0xff) as u8,
             pwm_grad: read_from_bit(data, 8, 0xff) as u8,
             pwm_freq: read_from_bit(data, 16, 0x03) as u8,
             pwm_autoscale: read_bool_from_bit(data, 18),
-            freewheel: read_from_bit(data, 20, 0x03) as u8,
+            // 2 bits, every value is defined
+            freewheel: Freewheel::try_from(read_from_bit(data, 20, 0x03) as u8).unwrap_or_default(),
         }
     }
 }
 
 impl<const M: u8> From<PwmConf<M>> for u32 {
     fn from(data: PwmConf<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xff, data.pwm_ampl as u32);
         write_from_bit(&mut value, 8, 0xff, data.pwm_grad as u32);
@@ -88,16 +166,37 @@ impl<const M: u8> From<PwmConf<M>> for u32 {
 }
 
 impl Register for PwmConf<0> {
+    const MASK: u32 = Self::PWM_AMPL_MASK
+        | Self::PWM_GRAD_MASK
+        | Self::PWM_FREQ_MASK
+        | Self::PWM_AUTOSCALE_MASK
+        | Self::FREEWHEEL_MASK;
+    const RESET_VALUE: u32 = PWM_CONF_RESET;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x10
     }
 }
+
+impl MotorRegister<0> for PwmConf<0> {}
 impl Register for PwmConf<1> {
+    const MASK: u32 = Self::PWM_AMPL_MASK
+        | Self::PWM_GRAD_MASK
+        | Self::PWM_FREQ_MASK
+        | Self::PWM_AUTOSCALE_MASK
+        | Self::FREEWHEEL_MASK;
+    const RESET_VALUE: u32 = PWM_CONF_RESET;
+    const ACCESS: Access = Access::Write;
+
     fn addr() -> u8 {
         0x18
     }
 }
 
+impl MotorRegister<1> for PwmConf<1> {}
+impl<const M: u8> WritableRegister for PwmConf<M> {}
+
 #[cfg(test)]
 mod pwm_conf {
     use super::*;
@@ -132,11 +231,19 @@ mod pwm_conf {
 /// PWM_STATUS: Actual PWM scaler
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PwmStatus<const M: u8> {
     /// Actual PWM scaler (255=max. Voltage)
     pub pwm_status: u8,
 }
 
+impl<const M: u8> PwmStatus<M> {
+    /// Bit offset of [`pwm_status`](PwmStatus::pwm_status)
+    pub const PWM_STATUS_SHIFT: u32 = 0;
+    /// Bits of [`pwm_status`](PwmStatus::pwm_status) in the register value
+    pub const PWM_STATUS_MASK: u32 = 0x000000ff;
+}
+
 impl<const M: u8> Default for PwmStatus<M> {
     fn default() -> Self {
         Self::from(0u32)
@@ -145,6 +252,7 @@ impl<const M: u8> Default for PwmStatus<M> {
 
 impl<const M: u8> From<u32> for PwmStatus<M> {
     fn from(data: u32) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         Self {
             pwm_status: read_from_bit(data, 0, 0xff) as u8,
         }
@@ -153,6 +261,7 @@ impl<const M: u8> From<u32> for PwmStatus<M> {
 
 impl<const M: u8> From<PwmStatus<M>> for u32 {
     fn from(data: PwmStatus<M>) -> Self {
+        let () = MotorIndexCheck::<M>::OK;
         let mut value = 0;
         write_from_bit(&mut value, 0, 0xff, data.pwm_status as u32);
         value
@@ -160,16 +269,29 @@ impl<const M: u8> From<PwmStatus<M>> for u32 {
 }
 
 impl Register for PwmStatus<0> {
+    const MASK: u32 = Self::PWM_STATUS_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x11
     }
 }
+
+impl MotorRegister<0> for PwmStatus<0> {}
 impl Register for PwmStatus<1> {
+    const MASK: u32 = Self::PWM_STATUS_MASK;
+    const RESET_VALUE: u32 = 0;
+    const ACCESS: Access = Access::Read;
+
     fn addr() -> u8 {
         0x19
     }
 }
 
+impl MotorRegister<1> for PwmStatus<1> {}
+impl<const M: u8> ReadableRegister for PwmStatus<M> {}
+
 #[cfg(test)]
 mod pwm_status {
     use super::*;
@@ -0,0 +1,102 @@
+//! Reset detection
+//!
+//! A reset of the TMC5072 (power cycle, supply undervoltage) clears all its registers. It is signaled by GSTAT
+//! reset, also reported in the status of every SPI datagram until GSTAT is read: the driver remembers a reset flag
+//! seen in any status ([`Tmc5072::reset_seen`]), so that polling it costs no bus access.
+//!
+//! [`Tmc5072::check_reset`] reads GSTAT and, with [`Tmc5072::set_reset_recovery`], re-applies the shadow
+//! configuration ([`Tmc5072::config`]) after a reset. The chip signals its power up reset too: the first check
+//! after initialization reports it.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::batch::BatchError;
+use crate::config::CONFIG_WRITES;
+use crate::registers::general_configuration_register::GStat;
+use crate::spi::SpiError;
+use crate::Tmc5072;
+
+/// Reset check error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResetError<SPI, CS> {
+    /// SPI bus error while reading GSTAT
+    SpiError(SpiError<SPI, CS>),
+    /// The configuration could only be partially re-applied
+    Recovery(BatchError<SPI, CS, CONFIG_WRITES>),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for ResetError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        ResetError::SpiError(e)
+    }
+}
+
+impl<SPI, CS> From<BatchError<SPI, CS, CONFIG_WRITES>> for ResetError<SPI, CS> {
+    fn from(e: BatchError<SPI, CS, CONFIG_WRITES>) -> Self {
+        ResetError::Recovery(e)
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Whether a reset flag was seen in the status of a datagram since the last [`check_reset`](Tmc5072::check_reset)
+    pub fn reset_seen(&self) -> bool {
+        self.reset_seen
+    }
+
+    /// Re-applies the shadow configuration when [`check_reset`](Tmc5072::check_reset) detects a reset
+    pub fn set_reset_recovery(&mut self, recovery: bool) {
+        self.reset_recovery = recovery;
+    }
+
+    /// Reads GSTAT (clearing its flags), returns whether the chip was reset
+    ///
+    /// After a reset, the shadow configuration is re-applied if [reset recovery](Tmc5072::set_reset_recovery) is
+    /// enabled.
+    pub fn check_reset<SPI: Transfer<u8>>(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<bool, ResetError<SPI::Error, CS::Error>> {
+        let reset = self.read_register::<GStat, _>(spi)?.data.reset || self.reset_seen;
+        self.reset_seen = false;
+        if reset && self.reset_recovery {
+            let config = self.shadow;
+            self.apply_config(&config, spi)?;
+        }
+        Ok(reset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        ramp_generator_register::{VMax, XActual},
+        Register,
+    };
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn recovery() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        // power up reset
+        assert!(tmc5072.check_reset(&mut sim).unwrap());
+        assert!(!tmc5072.check_reset(&mut sim).unwrap());
+        tmc5072
+            .write_register(VMax::<0> { v_max: 5000 }, &mut sim)
+            .unwrap();
+        sim = Simulator::new();
+        tmc5072.read_register::<XActual<1>, _>(&mut sim).unwrap();
+        assert!(tmc5072.reset_seen());
+        assert!(tmc5072.check_reset(&mut sim).unwrap());
+        assert!(!tmc5072.reset_seen());
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+
+        tmc5072.set_reset_recovery(true);
+        sim = Simulator::new();
+        assert!(tmc5072.check_reset(&mut sim).unwrap());
+        assert_eq!(sim.get(VMax::<0>::addr()), 5000);
+        assert!(!tmc5072.check_reset(&mut sim).unwrap());
+    }
+}
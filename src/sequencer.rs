@@ -0,0 +1,243 @@
+//! Motion sequencing
+//!
+//! A [`MotionSequencer`] runs a queue of [`Segment`]s on motor `M`, one after the other, without blocking nor
+//! allocating: the main loop or a timer interrupt calls [`MotionSequencer::poll`], which starts the next segment once
+//! the current one is over.
+//! - [`Segment::MoveTo`] moves in positioning mode and is over when the target is reached
+//! - [`Segment::Dwell`] waits, in the unit of the `now` argument of [`MotionSequencer::poll`]
+//! - [`Segment::Velocity`] rotates in velocity mode and is over when the velocity is reached (0 stops the motor)
+//!
+//! The queue holds up to `N` segments, more can be pushed while it runs.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    ramp_generator_driver_feature_control_register::RampStat,
+    ramp_generator_register::{RampMode, VMax, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Step of a motion sequence
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Segment {
+    /// Moves to `x_target` with a maximum velocity of `v_max`
+    MoveTo {
+        /// Target position
+        x_target: i32,
+        /// Maximum velocity
+        v_max: u32,
+    },
+    /// Waits for `duration`
+    Dwell {
+        /// Waiting time
+        duration: u32,
+    },
+    /// Rotates at `velocity` (signed)
+    Velocity {
+        /// Target velocity
+        velocity: i32,
+    },
+}
+
+/// Sequencer state after a poll
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SequencerStatus {
+    /// The queue is empty
+    Idle,
+    /// A segment is running
+    Running {
+        /// Running segment
+        segment: Segment,
+        /// Segments queued after it
+        queued: usize,
+    },
+}
+
+/// Poll driven queue of segments of motor `M`, holding up to `N` segments
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct MotionSequencer<const M: u8, const N: usize> {
+    queue: [Segment; N],
+    head: usize,
+    len: usize,
+    /// Start time of the running segment (the front of the queue)
+    started: Option<u32>,
+}
+
+impl<const M: u8, const N: usize> Default for MotionSequencer<M, N> {
+    fn default() -> Self {
+        Self {
+            queue: [Segment::Dwell { duration: 0 }; N],
+            head: 0,
+            len: 0,
+            started: None,
+        }
+    }
+}
+
+impl<const M: u8, const N: usize> MotionSequencer<M, N>
+where
+    RampMode<M>: Register,
+    RampStat<M>: Register,
+    VMax<M>: Register,
+    XTarget<M>: Register,
+{
+    /// Creates an empty sequencer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a segment, returns it back if the queue is full
+    pub fn push(&mut self, segment: Segment) -> Result<(), Segment> {
+        if self.len == N {
+            return Err(segment);
+        }
+        self.queue[(self.head + self.len) % N] = segment;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Number of queued segments, the running one included
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no segment is queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops the queued segments, the motor keeps executing the last command
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.started = None;
+    }
+
+    /// Checks whether the running segment is over, starts the next one
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<SequencerStatus, SpiError<SPI::Error, CS::Error>> {
+        loop {
+            if self.len == 0 {
+                return Ok(SequencerStatus::Idle);
+            }
+            let segment = self.queue[self.head];
+            let Some(started) = self.started else {
+                self.start(segment, tmc5072, spi)?;
+                self.started = Some(now);
+                return Ok(self.running(segment));
+            };
+            let done = match segment {
+                Segment::MoveTo { .. } => {
+                    tmc5072
+                        .read_register::<RampStat<M>, _>(spi)?
+                        .data
+                        .position_reached
+                }
+                Segment::Dwell { duration } => now.wrapping_sub(started) >= duration,
+                Segment::Velocity { .. } => {
+                    tmc5072
+                        .read_register::<RampStat<M>, _>(spi)?
+                        .data
+                        .velocity_reached
+                }
+            };
+            if !done {
+                return Ok(self.running(segment));
+            }
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.started = None;
+        }
+    }
+
+    fn start<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        segment: Segment,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        match segment {
+            Segment::MoveTo { x_target, v_max } => tmc5072.move_to::<M, _>(x_target, v_max, spi),
+            Segment::Dwell { .. } => Ok(()),
+            Segment::Velocity { velocity } => tmc5072.rotate::<M, _>(velocity, spi),
+        }
+    }
+
+    fn running(&self, segment: Segment) -> SequencerStatus {
+        SequencerStatus::Running {
+            segment,
+            queued: self.len - 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::ramp_generator_register::XActual;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn sequence() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut sequencer = MotionSequencer::<1, 3>::new();
+        let move_to = Segment::MoveTo {
+            x_target: 500,
+            v_max: 10_000,
+        };
+        let dwell = Segment::Dwell { duration: 100 };
+        let stop = Segment::Velocity { velocity: 0 };
+        sequencer.push(move_to).unwrap();
+        sequencer.push(dwell).unwrap();
+        sequencer.push(stop).unwrap();
+        assert_eq!(sequencer.push(dwell), Err(dwell));
+        assert_eq!(
+            sequencer.poll(&mut tmc5072, &mut sim, 0),
+            Ok(SequencerStatus::Running {
+                segment: move_to,
+                queued: 2,
+            })
+        );
+        assert_eq!(sim.get(XActual::<1>::addr()), 500);
+        // the move is over, the dwell starts
+        assert_eq!(
+            sequencer.poll(&mut tmc5072, &mut sim, 10),
+            Ok(SequencerStatus::Running {
+                segment: dwell,
+                queued: 1,
+            })
+        );
+        assert_eq!(
+            sequencer.poll(&mut tmc5072, &mut sim, 100),
+            Ok(SequencerStatus::Running {
+                segment: dwell,
+                queued: 1,
+            })
+        );
+        assert_eq!(
+            sequencer.poll(&mut tmc5072, &mut sim, 110),
+            Ok(SequencerStatus::Running {
+                segment: stop,
+                queued: 0,
+            })
+        );
+        assert_eq!(sim.get(RampMode::<1>::addr()), 1);
+        sequencer.push(dwell).unwrap();
+        assert_eq!(sequencer.len(), 2);
+        sequencer.clear();
+        assert_eq!(
+            sequencer.poll(&mut tmc5072, &mut sim, 120),
+            Ok(SequencerStatus::Idle)
+        );
+    }
+}
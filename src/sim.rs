@@ -0,0 +1,806 @@
+//! TMC5072 simulator
+//!
+//! Register file model answering SPI datagrams like the chip does:
+//! the reply to a datagram holds the SPI status and the data requested by the previous read access.
+//! The SPI status is computed on every datagram from GSTAT and both RAMP_STAT, as they were before the access.
+//!
+//! Motion is simplified to constant velocity moves at VMAX (no acceleration), with two [`Timing`] modes:
+//! - [`Timing::Instant`]: writing XTARGET in positioning mode moves XACTUAL (and X_ENC, MSCNT) to the target at once.
+//! - [`Timing::Scaled`]: motors only move when [`Simulator::advance`] is called, with simulated time running
+//!   faster than real time, so that state machines see every state transition in order.
+//!
+//! Virtual reference switches can be placed at given XACTUAL coordinates ([`Simulator::set_switches`]):
+//! they update the RAMP_STAT switch flags, stop the motor and latch XLATCH (and ENC_LATCH) according to SW_MODE.
+//! Switch polarity and swap_lr are not modeled. Writing ENCMODE with latch_now latches ENC_LATCH (and XLATCH with
+//! latch_x_act) at once.
+//!
+//! Mechanical stops ([`Simulator::set_stalls`]) stall the motor: with SW_MODE sg_stop enabled and VMAX at or above
+//! VCOOLTHRS, stallGuard stops the motor there and raises event_stop_sg. Otherwise the stall is ignored.
+//!
+//! The simulator also answers UART datagrams (`serial::Read`/`serial::Write`), without echo.
+//!
+//! Requires the `sim` feature.
+
+use core::convert::Infallible;
+use embedded_hal::{
+    blocking::spi::Transfer,
+    digital::v2::OutputPin,
+    serial::{Read, Write},
+};
+
+use crate::registers::{
+    encoder_registers::{EncLatch, EncMode, EncStatus, XEnc},
+    general_configuration_register::{GConf, GStat, IfCnt, Input, SlaveConf},
+    microstep_table_register::{
+        MsLut0, MsLut1, MsLut2, MsLut3, MsLut4, MsLut5, MsLut6, MsLut7, MsLutSel, MsLutStart,
+    },
+    motor_driver_register::{ChopConf, MsCnt},
+    ramp_generator_driver_feature_control_register::{RampStat, SwMode, VCoolThrs, XLatch},
+    ramp_generator_register::{RampMode, RampModeValue, VActual, VMax, XActual, XTarget},
+    voltage_pwm_mode_stealth_chop::PwmConf,
+    Register, IC_VERSION, WRITE_FLAG,
+};
+use crate::status::SpiStatus;
+use crate::uart::{crc8, MASTER_ADDR, SYNC};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Simulated clock frequency (Hz)
+pub const F_CLK: u64 = 13_200_000;
+
+/// Ramp timing of the simulator
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Timing {
+    /// Positioning moves complete as soon as XTARGET is written
+    Instant,
+    /// Motors move when time is advanced, simulated time being this many times faster than the advanced time
+    Scaled(u32),
+}
+
+/// Simulated TMC5072 on an SPI bus
+pub struct Simulator {
+    registers: [u32; 0x80],
+    input: u32,
+    reply: u32,
+    encoder_slip: [i32; 2],
+    timing: Timing,
+    /// Traveled distance not yet turned into microsteps, in microsteps * 2^24 * 10^6
+    remainder: [u128; 2],
+    /// Left and right switch positions of each motor
+    switches: [[Option<i32>; 2]; 2],
+    /// Left and right mechanical stop positions of each motor
+    stalls: [[Option<i32>; 2]; 2],
+    /// UART datagram being received
+    uart_rx: [u8; 8],
+    uart_rx_len: usize,
+    /// UART reply being sent
+    uart_tx: [u8; 8],
+    uart_tx_pos: usize,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    /// Creates a simulator in its reset state: registers at their datasheet reset value, GSTAT reset set
+    pub fn new() -> Self {
+        let mut sim = Self {
+            registers: [0; 0x80],
+            input: u32::from(Input {
+                version: IC_VERSION,
+                ..Default::default()
+            }),
+            reply: 0,
+            encoder_slip: [0; 2],
+            timing: Timing::Instant,
+            remainder: [0; 2],
+            switches: [[None; 2]; 2],
+            stalls: [[None; 2]; 2],
+            uart_rx: [0; 8],
+            uart_rx_len: 0,
+            uart_tx: [0; 8],
+            uart_tx_pos: 8,
+        };
+        sim.reset::<MsLut0>();
+        sim.reset::<MsLut1>();
+        sim.reset::<MsLut2>();
+        sim.reset::<MsLut3>();
+        sim.reset::<MsLut4>();
+        sim.reset::<MsLut5>();
+        sim.reset::<MsLut6>();
+        sim.reset::<MsLut7>();
+        sim.reset::<MsLutSel>();
+        sim.reset::<MsLutStart>();
+        sim.reset::<PwmConf<0>>();
+        sim.reset::<PwmConf<1>>();
+        sim.set(
+            GStat::addr(),
+            u32::from(GStat {
+                reset: true,
+                ..GStat::from(0)
+            }),
+        );
+        sim
+    }
+
+    /// Sets register `R` to its reset value
+    fn reset<R: Register>(&mut self)
+    where
+        u32: From<R>,
+    {
+        self.set(R::addr(), R::RESET_VALUE);
+    }
+
+    /// Current value of a register, without side effect
+    pub fn get(&self, addr: u8) -> u32 {
+        self.registers[addr as usize]
+    }
+
+    /// Sets a register value, without side effect
+    pub fn set(&mut self, addr: u8, value: u32) {
+        self.registers[addr as usize] = value;
+    }
+
+    /// SPI status returned with the next datagram
+    pub fn spi_status(&self) -> SpiStatus {
+        let g_stat = GStat::from(self.get(GStat::addr()));
+        let ramp_stat0 = RampStat::<0>::from(self.get(RampStat::<0>::addr()));
+        let ramp_stat1 = RampStat::<1>::from(self.get(RampStat::<1>::addr()));
+        SpiStatus {
+            reset_flag: g_stat.reset,
+            driver_error1: g_stat.drv_err1,
+            driver_error2: g_stat.drv_err2,
+            velocity_reached1: ramp_stat0.velocity_reached,
+            velocity_reached2: ramp_stat1.velocity_reached,
+            status_stop_l1: ramp_stat0.status_stop_l,
+            status_stop_l2: ramp_stat1.status_stop_l,
+        }
+    }
+
+    /// Offsets X_ENC from XACTUAL after each move of motor `m` to simulate lost steps
+    pub fn set_encoder_slip(&mut self, m: usize, slip: i32) {
+        self.encoder_slip[m] = slip;
+    }
+
+    /// Places the left (REFL) and right (REFR) switches of motor `m`, `None` to remove one
+    ///
+    /// The left switch is active at and below its position, the right switch at and above its position.
+    pub fn set_switches(&mut self, m: usize, left: Option<i32>, right: Option<i32>) {
+        self.switches[m] = [left, right];
+        match m {
+            0 => self.update_switches::<0>(0),
+            _ => self.update_switches::<1>(0),
+        }
+    }
+
+    /// Places the left and right mechanical stops of motor `m`, `None` to remove one
+    ///
+    /// The motor stalls when moving to or beyond a stop.
+    pub fn set_stalls(&mut self, m: usize, left: Option<i32>, right: Option<i32>) {
+        self.stalls[m] = [left, right];
+    }
+
+    /// Selects the ramp timing
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    /// Advances time by `micros` microseconds (scaled by the [`Timing::Scaled`] factor)
+    ///
+    /// Does nothing with [`Timing::Instant`].
+    pub fn advance(&mut self, micros: u32) {
+        if let Timing::Scaled(scale) = self.timing {
+            let micros = micros as u128 * scale as u128;
+            self.advance_motor::<0>(micros);
+            self.advance_motor::<1>(micros);
+        }
+    }
+
+    /// Advances time by a duration, like [`Simulator::advance`]
+    #[cfg(feature = "fugit")]
+    pub fn advance_by(&mut self, duration: fugit::MicrosDurationU32) {
+        self.advance(duration.ticks());
+    }
+
+    fn read(&mut self, addr: u8) -> u32 {
+        if addr == Input::addr() {
+            return self.input;
+        }
+        let value = self.get(addr);
+        // clear on read flags
+        if addr == GStat::addr() {
+            self.set(addr, 0);
+        } else if addr == RampStat::<0>::addr() || addr == RampStat::<1>::addr() {
+            let clear = u32::from(RampStat::<0> {
+                status_latch_l: true,
+                status_latch_r: true,
+                event_stop_sg: true,
+                event_pos_reached: true,
+                second_move: true,
+                ..Default::default()
+            });
+            self.set(addr, value & !clear);
+        } else if addr == EncStatus::<0>::addr() || addr == EncStatus::<1>::addr() {
+            self.set(addr, 0);
+        }
+        value
+    }
+
+    fn write(&mut self, addr: u8, value: u32) {
+        if addr == GConf::addr() && GConf::from(self.get(addr)).lock_gconf {
+            // locked until reset
+            return;
+        }
+        self.set(addr, value);
+        if addr == XTarget::<0>::addr() {
+            self.move_to::<0>(value);
+        } else if addr == XTarget::<1>::addr() {
+            self.move_to::<1>(value);
+        } else if addr == RampMode::<0>::addr() {
+            // entering positioning mode moves to the pending target
+            self.move_to::<0>(self.get(XTarget::<0>::addr()));
+        } else if addr == RampMode::<1>::addr() {
+            self.move_to::<1>(self.get(XTarget::<1>::addr()));
+        } else if addr == EncMode::<0>::addr() {
+            self.latch_now::<0>();
+        } else if addr == EncMode::<1>::addr() {
+            self.latch_now::<1>();
+        }
+    }
+
+    /// Latches X_ENC, and XACTUAL if selected, when ENCMODE latch_now is set
+    fn latch_now<const M: u8>(&mut self)
+    where
+        EncMode<M>: Register,
+        EncLatch<M>: Register,
+        XEnc<M>: Register,
+        XLatch<M>: Register,
+        XActual<M>: Register,
+    {
+        let enc_mode = EncMode::<M>::from(self.get(EncMode::<M>::addr()));
+        if enc_mode.latch_now {
+            self.set(EncLatch::<M>::addr(), self.get(XEnc::<M>::addr()));
+            if enc_mode.latch_x_act {
+                self.set(XLatch::<M>::addr(), self.get(XActual::<M>::addr()));
+            }
+        }
+    }
+
+    fn move_to<const M: u8>(&mut self, target: u32)
+    where
+        RampMode<M>: Register,
+        VActual<M>: Register,
+        XActual<M>: Register,
+        XEnc<M>: Register,
+        RampStat<M>: Register,
+        MsCnt<M>: Register,
+        ChopConf<M>: Register,
+        SwMode<M>: Register,
+        XLatch<M>: Register,
+        EncLatch<M>: Register,
+        VMax<M>: Register,
+        VCoolThrs<M>: Register,
+    {
+        if RampMode::<M>::from(self.get(RampMode::<M>::addr())).ramp_mode
+            != RampModeValue::Positioning
+        {
+            return;
+        }
+        let x_actual = XActual::<M>::from(self.get(XActual::<M>::addr())).x_actual;
+        let target = target as i32;
+        match self.timing {
+            Timing::Instant => {
+                if self.travel::<M>(target.wrapping_sub(x_actual)) {
+                    self.switch_stop::<M>();
+                } else {
+                    self.arrive::<M>(target != x_actual);
+                }
+            }
+            Timing::Scaled(_) if target == x_actual => self.arrive::<M>(false),
+            Timing::Scaled(_) => self.update_ramp_stat::<M>(|ramp_stat| {
+                ramp_stat.position_reached = false;
+                ramp_stat.vzero = false;
+            }),
+        }
+    }
+
+    fn advance_motor<const M: u8>(&mut self, micros: u128)
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+        VActual<M>: Register,
+        XActual<M>: Register,
+        XTarget<M>: Register,
+        XEnc<M>: Register,
+        RampStat<M>: Register,
+        MsCnt<M>: Register,
+        ChopConf<M>: Register,
+        SwMode<M>: Register,
+        XLatch<M>: Register,
+        EncLatch<M>: Register,
+        VCoolThrs<M>: Register,
+    {
+        let ramp_mode = RampMode::<M>::from(self.get(RampMode::<M>::addr())).ramp_mode;
+        let v_max = VMax::<M>::from(self.get(VMax::<M>::addr())).v_max;
+        // v[microsteps/s] = VMAX * fCLK / 2^24
+        let unit = (1u128 << 24) * 1_000_000;
+        let remainder = &mut self.remainder[M as usize];
+        *remainder += v_max as u128 * F_CLK as u128 * micros;
+        let steps = (*remainder / unit).min(i32::MAX as u128) as i32;
+        *remainder %= unit;
+        match ramp_mode {
+            RampModeValue::Positioning => {
+                let x_actual = XActual::<M>::from(self.get(XActual::<M>::addr())).x_actual;
+                let x_target = XTarget::<M>::from(self.get(XTarget::<M>::addr())).x_target;
+                let distance = x_target.wrapping_sub(x_actual);
+                if distance == 0 {
+                    return;
+                }
+                let arrived = steps as u32 >= distance.unsigned_abs();
+                let delta = if arrived {
+                    distance
+                } else {
+                    steps * distance.signum()
+                };
+                if self.travel::<M>(delta) {
+                    self.switch_stop::<M>();
+                } else if arrived {
+                    self.set_velocity::<M>(0);
+                    self.remainder[M as usize] = 0;
+                    self.arrive::<M>(true);
+                } else {
+                    self.set_velocity::<M>(v_max as i32 * distance.signum());
+                }
+            }
+            RampModeValue::VelocityPos | RampModeValue::VelocityNeg => {
+                let sign = if ramp_mode == RampModeValue::VelocityPos {
+                    1
+                } else {
+                    -1
+                };
+                if self.travel::<M>(steps * sign) {
+                    self.switch_stop::<M>();
+                } else {
+                    self.set_velocity::<M>(v_max as i32 * sign);
+                    self.update_ramp_stat::<M>(|ramp_stat| {
+                        ramp_stat.velocity_reached = true;
+                        ramp_stat.vzero = v_max == 0;
+                    });
+                }
+            }
+            RampModeValue::Hold => {}
+        }
+    }
+
+    /// Moves by `delta` microsteps unless an enabled stop switch is hit or stallGuard stops the motor,
+    /// returns whether the motor was stopped
+    fn travel<const M: u8>(&mut self, delta: i32) -> bool
+    where
+        XActual<M>: Register,
+        XEnc<M>: Register,
+        MsCnt<M>: Register,
+        ChopConf<M>: Register,
+        SwMode<M>: Register,
+        RampStat<M>: Register,
+        XLatch<M>: Register,
+        EncLatch<M>: Register,
+        VMax<M>: Register,
+        VCoolThrs<M>: Register,
+    {
+        let sw_mode = SwMode::<M>::from(self.get(SwMode::<M>::addr()));
+        let v_max = VMax::<M>::from(self.get(VMax::<M>::addr())).v_max;
+        let v_cool_thrs = VCoolThrs::<M>::from(self.get(VCoolThrs::<M>::addr())).v_cool_thrs;
+        let sg_stop = sw_mode.sg_stop && v_max >= v_cool_thrs;
+        let x_actual = XActual::<M>::from(self.get(XActual::<M>::addr())).x_actual;
+        let target = x_actual.wrapping_add(delta);
+        let limit = |[left, right]: [Option<i32>; 2], left_enable: bool, right_enable: bool| {
+            if delta < 0 && left_enable {
+                left.filter(|&p| target <= p)
+            } else if delta > 0 && right_enable {
+                right.filter(|&p| target >= p)
+            } else {
+                None
+            }
+        };
+        let switch = limit(
+            self.switches[M as usize],
+            sw_mode.stop_l_enable,
+            sw_mode.stop_r_enable,
+        );
+        let stall = limit(self.stalls[M as usize], sg_stop, sg_stop);
+        // the first one reached stops the motor
+        let stop = match (switch, stall) {
+            (Some(s), Some(g)) if delta < 0 => Some(s.max(g)),
+            (Some(s), Some(g)) => Some(s.min(g)),
+            (s, g) => s.or(g),
+        };
+        let moved = match stop {
+            // already stopped there, no motion
+            Some(p) if (delta < 0 && x_actual <= p) || (delta > 0 && x_actual >= p) => 0,
+            Some(p) => p.wrapping_sub(x_actual),
+            None => delta,
+        };
+        self.displace::<M>(moved);
+        self.update_switches::<M>(moved);
+        if stall.is_some() && stop == stall {
+            self.update_ramp_stat::<M>(|ramp_stat| {
+                ramp_stat.event_stop_sg = true;
+                ramp_stat.status_sg = true;
+            });
+        } else if moved != 0 {
+            self.update_ramp_stat::<M>(|ramp_stat| ramp_stat.status_sg = false);
+        }
+        stop.is_some()
+    }
+
+    /// Updates the switch flags and latches after a move of `moved` microsteps
+    fn update_switches<const M: u8>(&mut self, moved: i32)
+    where
+        XActual<M>: Register,
+        XEnc<M>: Register,
+        SwMode<M>: Register,
+        RampStat<M>: Register,
+        XLatch<M>: Register,
+        EncLatch<M>: Register,
+    {
+        let sw_mode = SwMode::<M>::from(self.get(SwMode::<M>::addr()));
+        let [left, right] = self.switches[M as usize];
+        let after = XActual::<M>::from(self.get(XActual::<M>::addr())).x_actual;
+        let before = after.wrapping_sub(moved);
+        let left_active = |x: i32| left.is_some_and(|p| x <= p);
+        let right_active = |x: i32| right.is_some_and(|p| x >= p);
+        let latch_l = (sw_mode.latch_l_active && !left_active(before) && left_active(after))
+            || (sw_mode.latch_l_inactive && left_active(before) && !left_active(after));
+        let latch_r = (sw_mode.latch_r_active && !right_active(before) && right_active(after))
+            || (sw_mode.latch_r_inactive && right_active(before) && !right_active(after));
+        let latch = match (latch_l, latch_r) {
+            (true, _) => left,
+            (_, true) => right,
+            _ => None,
+        };
+        if let Some(p) = latch {
+            self.set(XLatch::<M>::addr(), p as u32);
+            if sw_mode.en_latch_encoder {
+                let x_enc = XEnc::<M>::from(self.get(XEnc::<M>::addr())).x_enc;
+                self.set(
+                    EncLatch::<M>::addr(),
+                    x_enc.wrapping_sub(after.wrapping_sub(p)) as u32,
+                );
+            }
+        }
+        self.update_ramp_stat::<M>(|ramp_stat| {
+            ramp_stat.status_stop_l = left_active(after);
+            ramp_stat.status_stop_r = right_active(after);
+            ramp_stat.event_stop_l = sw_mode.stop_l_enable && left_active(after);
+            ramp_stat.event_stop_r = sw_mode.stop_r_enable && right_active(after);
+            ramp_stat.status_latch_l |= latch_l;
+            ramp_stat.status_latch_r |= latch_r;
+        });
+    }
+
+    /// Stops the motor on a switch, short of its target
+    fn switch_stop<const M: u8>(&mut self)
+    where
+        VActual<M>: Register,
+        RampStat<M>: Register,
+    {
+        self.set_velocity::<M>(0);
+        self.remainder[M as usize] = 0;
+        self.update_ramp_stat::<M>(|ramp_stat| {
+            ramp_stat.position_reached = false;
+            ramp_stat.velocity_reached = false;
+            ramp_stat.vzero = true;
+        });
+    }
+
+    /// Moves XACTUAL, X_ENC and MSCNT by `delta` microsteps
+    fn displace<const M: u8>(&mut self, delta: i32)
+    where
+        XActual<M>: Register,
+        XEnc<M>: Register,
+        MsCnt<M>: Register,
+        ChopConf<M>: Register,
+    {
+        let x_actual = XActual::<M>::from(self.get(XActual::<M>::addr())).x_actual;
+        let x_enc = XEnc::<M>::from(self.get(XEnc::<M>::addr())).x_enc;
+        self.set(XActual::<M>::addr(), x_actual.wrapping_add(delta) as u32);
+        self.set(XEnc::<M>::addr(), x_enc.wrapping_add(delta) as u32);
+        // each microstep advances the microstep table by 2^MRES entries
+        let mres = ChopConf::<M>::from(self.get(ChopConf::<M>::addr()))
+            .mres
+            .mres();
+        let ms_cnt = MsCnt::<M>::from(self.get(MsCnt::<M>::addr())).ms_cnt as i32;
+        self.set(
+            MsCnt::<M>::addr(),
+            u32::from(MsCnt::<M> {
+                ms_cnt: (ms_cnt.wrapping_add(delta << mres) & 0x3ff) as u16,
+            }),
+        );
+    }
+
+    /// Signals the end of a move, applying the encoder slip if the motor moved
+    fn arrive<const M: u8>(&mut self, moved: bool)
+    where
+        XEnc<M>: Register,
+        RampStat<M>: Register,
+    {
+        if moved {
+            let x_enc = XEnc::<M>::from(self.get(XEnc::<M>::addr())).x_enc;
+            self.set(
+                XEnc::<M>::addr(),
+                x_enc.wrapping_add(self.encoder_slip[M as usize]) as u32,
+            );
+        }
+        self.update_ramp_stat::<M>(|ramp_stat| {
+            ramp_stat.event_pos_reached = true;
+            ramp_stat.position_reached = true;
+            ramp_stat.vzero = true;
+        });
+    }
+
+    fn set_velocity<const M: u8>(&mut self, v_actual: i32)
+    where
+        VActual<M>: Register,
+    {
+        self.set(VActual::<M>::addr(), u32::from(VActual::<M> { v_actual }));
+    }
+
+    fn receive_uart(&mut self, byte: u8) {
+        if self.uart_rx_len == 0 && byte & 0x0F != SYNC {
+            // out of sync
+            return;
+        }
+        self.uart_rx[self.uart_rx_len] = byte;
+        self.uart_rx_len += 1;
+        let datagram = self.uart_rx;
+        let len = match self.uart_rx_len {
+            4 if datagram[2] & WRITE_FLAG == 0 => 4,
+            8 => 8,
+            _ => return,
+        };
+        self.uart_rx_len = 0;
+        let slave_addr = SlaveConf::from(self.get(SlaveConf::addr())).slave_addr;
+        if datagram[len - 1] != crc8(&datagram[..len - 1]) || datagram[1] != slave_addr {
+            return;
+        }
+        let addr = datagram[2] & !WRITE_FLAG;
+        if len == 8 {
+            self.write(
+                addr,
+                u32::from_be_bytes([datagram[3], datagram[4], datagram[5], datagram[6]]),
+            );
+            let if_cnt = IfCnt::from(self.get(IfCnt::addr())).if_cnt.wrapping_add(1);
+            self.set(IfCnt::addr(), u32::from(IfCnt { if_cnt }));
+        } else {
+            let data = self.read(addr);
+            self.uart_tx[..3].copy_from_slice(&[SYNC, MASTER_ADDR, addr]);
+            self.uart_tx[3..7].copy_from_slice(&data.to_be_bytes());
+            self.uart_tx[7] = crc8(&self.uart_tx[..7]);
+            self.uart_tx_pos = 0;
+        }
+    }
+
+    fn update_ramp_stat<const M: u8>(&mut self, f: impl FnOnce(&mut RampStat<M>))
+    where
+        RampStat<M>: Register,
+    {
+        let mut ramp_stat = RampStat::<M>::from(self.get(RampStat::<M>::addr()));
+        f(&mut ramp_stat);
+        self.set(RampStat::<M>::addr(), u32::from(ramp_stat));
+    }
+}
+
+impl Transfer<u8> for Simulator {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        let write = words[0] & WRITE_FLAG != 0;
+        let addr = words[0] & !WRITE_FLAG;
+        let data = u32::from_be_bytes([words[1], words[2], words[3], words[4]]);
+        words[0] = u8::from(self.spi_status());
+        words[1..].copy_from_slice(&self.reply.to_be_bytes());
+        if write {
+            self.write(addr, data);
+            self.reply = data;
+        } else {
+            self.reply = self.read(addr);
+        }
+        Ok(words)
+    }
+}
+
+impl Read<u8> for Simulator {
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.uart_tx.get(self.uart_tx_pos) {
+            Some(&byte) => {
+                self.uart_tx_pos += 1;
+                Ok(byte)
+            }
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+impl Write<u8> for Simulator {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.receive_uart(byte);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Simulated chip select pin
+pub struct CsPin;
+
+impl OutputPin for CsPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scaled() {
+        let mut sim = Simulator::new();
+        sim.set_timing(Timing::Scaled(1000));
+        // 13.2MHz / 2^24 * 1271 = 1000 microsteps/s
+        sim.write(VMax::<0>::addr(), 1271);
+        sim.write(XTarget::<0>::addr(), 2000);
+        assert!(!RampStat::<0>::from(sim.get(RampStat::<0>::addr())).position_reached);
+        // 1ms is 1s of simulated time
+        sim.advance(1000);
+        let x_actual = XActual::<0>::from(sim.get(XActual::<0>::addr())).x_actual;
+        assert!((999..=1000).contains(&x_actual));
+        assert_eq!(
+            VActual::<0>::from(sim.get(VActual::<0>::addr())).v_actual,
+            1271
+        );
+        sim.advance(2000);
+        assert_eq!(sim.get(XActual::<0>::addr()), 2000);
+        let ramp_stat = RampStat::<0>::from(sim.read(RampStat::<0>::addr()));
+        assert!(ramp_stat.position_reached && ramp_stat.event_pos_reached);
+        assert_eq!(sim.get(VActual::<0>::addr()), 0);
+    }
+
+    #[test]
+    fn reset_state() {
+        let sim = Simulator::new();
+        assert_eq!(sim.get(PwmConf::<1>::addr()), PwmConf::<1>::RESET_VALUE);
+        assert_eq!(sim.get(MsLutStart::addr()), MsLutStart::RESET_VALUE);
+        assert_eq!(sim.get(XActual::<0>::addr()), 0);
+        assert!(sim.spi_status().reset_flag);
+    }
+
+    #[test]
+    fn spi_status() {
+        use crate::Tmc5072;
+
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(
+            GStat::addr(),
+            u32::from(GStat {
+                reset: true,
+                drv_err2: true,
+                ..Default::default()
+            }),
+        );
+        sim.set_switches(1, Some(0), None);
+        let status = tmc5072
+            .read_register::<XActual<0>, _>(&mut sim)
+            .unwrap()
+            .status;
+        assert!(status.reset_flag && status.driver_error2 && status.status_stop_l2);
+        assert!(!status.driver_error1 && !status.status_stop_l1);
+        // GSTAT is cleared by the first datagram, its data comes with the second one
+        let g_stat = tmc5072.read_register::<GStat, _>(&mut sim).unwrap();
+        assert!(g_stat.data.reset && g_stat.data.drv_err2);
+        let status = g_stat.status;
+        assert!(!status.reset_flag && !status.driver_error2 && status.status_stop_l2);
+        assert_eq!(status, sim.spi_status());
+    }
+
+    #[test]
+    fn velocity_mode() {
+        let mut sim = Simulator::new();
+        sim.set_timing(Timing::Scaled(1));
+        sim.write(VMax::<1>::addr(), 1271);
+        sim.write(RampMode::<1>::addr(), 2);
+        sim.advance(500_000);
+        let x_actual = XActual::<1>::from(sim.get(XActual::<1>::addr())).x_actual;
+        assert!((-500..=-499).contains(&x_actual));
+        assert_eq!(sim.get(XActual::<0>::addr()), 0);
+    }
+
+    #[test]
+    fn switches() {
+        let mut sim = Simulator::new();
+        sim.set_switches(0, Some(-100), Some(100));
+        sim.write(
+            SwMode::<0>::addr(),
+            u32::from(SwMode::<0> {
+                stop_l_enable: true,
+                latch_l_active: true,
+                ..Default::default()
+            }),
+        );
+        sim.write(XTarget::<0>::addr(), -500i32 as u32);
+        assert_eq!(sim.get(XActual::<0>::addr()), -100i32 as u32);
+        assert_eq!(sim.get(XLatch::<0>::addr()), -100i32 as u32);
+        let ramp_stat = RampStat::<0>::from(sim.read(RampStat::<0>::addr()));
+        assert!(ramp_stat.status_stop_l && ramp_stat.event_stop_l && ramp_stat.status_latch_l);
+        assert!(!ramp_stat.position_reached);
+        // right switch is not enabled for stopping
+        sim.write(XTarget::<0>::addr(), 200);
+        assert_eq!(sim.get(XActual::<0>::addr()), 200);
+        let ramp_stat = RampStat::<0>::from(sim.read(RampStat::<0>::addr()));
+        assert!(ramp_stat.status_stop_r && !ramp_stat.event_stop_r && !ramp_stat.status_stop_l);
+    }
+
+    #[test]
+    fn scaled_switch() {
+        let mut sim = Simulator::new();
+        sim.set_timing(Timing::Scaled(1000));
+        sim.set_switches(1, None, Some(300));
+        sim.write(
+            SwMode::<1>::addr(),
+            u32::from(SwMode::<1> {
+                stop_r_enable: true,
+                ..Default::default()
+            }),
+        );
+        sim.write(VMax::<1>::addr(), 1271);
+        sim.write(RampMode::<1>::addr(), 1);
+        sim.advance(100);
+        assert!(!RampStat::<1>::from(sim.get(RampStat::<1>::addr())).event_stop_r);
+        sim.advance(1000);
+        assert_eq!(sim.get(XActual::<1>::addr()), 300);
+        assert_eq!(sim.get(VActual::<1>::addr()), 0);
+        let ramp_stat = RampStat::<1>::from(sim.get(RampStat::<1>::addr()));
+        assert!(ramp_stat.event_stop_r && ramp_stat.vzero);
+    }
+
+    #[test]
+    fn stall() {
+        let mut sim = Simulator::new();
+        sim.set_stalls(0, Some(-400), None);
+        sim.write(VMax::<0>::addr(), 1000);
+        sim.write(XTarget::<0>::addr(), -500i32 as u32);
+        // stallGuard stop disabled
+        assert_eq!(sim.get(XActual::<0>::addr()), -500i32 as u32);
+        sim.write(XTarget::<0>::addr(), 0);
+        sim.write(VCoolThrs::<0>::addr(), 500);
+        sim.write(
+            SwMode::<0>::addr(),
+            u32::from(SwMode::<0> {
+                sg_stop: true,
+                ..Default::default()
+            }),
+        );
+        sim.write(XTarget::<0>::addr(), -500i32 as u32);
+        assert_eq!(sim.get(XActual::<0>::addr()), -400i32 as u32);
+        let ramp_stat = RampStat::<0>::from(sim.read(RampStat::<0>::addr()));
+        assert!(ramp_stat.event_stop_sg && ramp_stat.status_sg);
+        assert!(!RampStat::<0>::from(sim.get(RampStat::<0>::addr())).event_stop_sg);
+    }
+}
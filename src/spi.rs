@@ -1,5 +1,8 @@
 //! SPI Error handling
 
+use core::ops::Deref;
+
+use crate::registers::{READ_FLAG, WRITE_FLAG};
 use crate::status::SpiStatus;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -8,6 +11,8 @@ use serde::{Deserialize, Serialize};
 pub type SpiResult<T, SPI, CS> = Result<SpiOk<T>, SpiError<SPI, CS>>;
 
 /// Bundles the SPI status register and the actual read data
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SpiOk<T> {
     /// Spi Status register
     pub status: SpiStatus,
@@ -16,6 +21,11 @@ pub struct SpiOk<T> {
 }
 
 impl<T> SpiOk<T> {
+    /// Data, dropping the status
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+
     /// Maps an SpiOk<T> to SpiOk<U> by applying a function to a contained value.
     pub fn map<U, F>(self, f: F) -> SpiOk<U>
     where
@@ -28,6 +38,14 @@ impl<T> SpiOk<T> {
     }
 }
 
+impl<T> Deref for SpiOk<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
 impl SpiOk<u32> {
     /// Parses TMC5072 SPI buffer into the SPI status and u32 data
     pub fn from_buffer(buffer: &[u8; 5]) -> Self {
@@ -51,12 +69,48 @@ impl SpiOk<()> {
     }
 }
 
+/// Builds the 40 bit datagram of a read access: the data comes with the reply to the next datagram
+pub fn read_frame(addr: u8) -> [u8; 5] {
+    [READ_FLAG | (addr & !WRITE_FLAG), 0, 0, 0, 0]
+}
+
+/// Builds the 40 bit datagram of a write access, data is sent MSB first
+pub fn write_frame(addr: u8, data: u32) -> [u8; 5] {
+    let data = data.to_be_bytes();
+    [WRITE_FLAG | addr, data[0], data[1], data[2], data[3]]
+}
+
+/// Parses a 40 bit reply into the SPI status and the data
+pub fn parse_frame(buffer: &[u8; 5]) -> SpiOk<u32> {
+    SpiOk::<u32>::from_buffer(buffer)
+}
+
 /// Errors that can occur while using SPI
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SpiError<SPI, CS> {
     /// SPI communication error
     SpiError(SPI),
     /// Chip Select pin error
     CSError(CS),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        assert_eq!(read_frame(0x21), [0x21, 0, 0, 0, 0]);
+        assert_eq!(
+            write_frame(0x2D, 0x1234_5678),
+            [0xAD, 0x12, 0x34, 0x56, 0x78]
+        );
+        let reply = parse_frame(&[0x01, 0xFF, 0xFF, 0xFF, 0x9C]);
+        assert!(reply.status.reset_flag);
+        assert_eq!(reply.data as i32, -100);
+        assert_eq!(*reply, 0xFFFF_FF9C);
+        assert_eq!(reply.into_inner(), 0xFFFF_FF9C);
+    }
+}
@@ -0,0 +1,351 @@
+//! Motor usage statistics
+//!
+//! Counters are updated from the register traffic going through the driver,
+//! no additional SPI transfer is ever issued to maintain them.
+//!
+//! With the `access-stats` feature, `AccessStats` also counts the reads and writes of each register address,
+//! to find the registers worth caching or batching, or to check that an init sequence wrote every register expected.
+
+use crate::registers::{
+    ramp_generator_driver_feature_control_register::RampStat,
+    ramp_generator_register::{XActual, XTarget},
+    MotorIndexCheck, Register,
+};
+use crate::status::SpiStatus;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Per motor usage counters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MotorStats {
+    /// Number of stallGuard2 stop events read from RAMP_STAT
+    pub stall_events: u32,
+    /// Number of reference switch stop events read from RAMP_STAT
+    pub switch_stops: u32,
+    /// Number of driver errors signaled by the SPI status
+    pub faults: u32,
+    /// Total distance traveled in microsteps, accumulated from successive XACTUAL reads
+    pub distance: u32,
+    /// Number of moves commanded by writing XTARGET
+    pub moves: u32,
+}
+
+/// Lifetime absolute travel of both motors in microsteps
+///
+/// Unlike [`MotorStats`] it is not cleared by [`Stats::reset`], store it in non volatile memory
+/// (using [`Odometer::to_bytes`] or serde) and restore it at startup to track mechanism wear across power cycles.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Odometer {
+    /// Motor 1 and motor 2 travel
+    pub travel: [u64; 2],
+}
+
+impl Odometer {
+    /// Size of the serialized odometer
+    pub const SIZE: usize = 16;
+
+    /// Travel of motor `M`
+    pub fn motor<const M: u8>(&self) -> u64 {
+        let () = MotorIndexCheck::<M>::OK;
+        self.travel[M as usize]
+    }
+
+    /// Serializes the odometer (little endian)
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..8].copy_from_slice(&self.travel[0].to_le_bytes());
+        bytes[8..].copy_from_slice(&self.travel[1].to_le_bytes());
+        bytes
+    }
+
+    /// Deserializes an odometer created with [`Odometer::to_bytes`]
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let mut travel = [[0; 8]; 2];
+        travel[0].copy_from_slice(&bytes[..8]);
+        travel[1].copy_from_slice(&bytes[8..]);
+        Self {
+            travel: [u64::from_le_bytes(travel[0]), u64::from_le_bytes(travel[1])],
+        }
+    }
+
+    fn add(&mut self, m: usize, delta: u32) {
+        self.travel[m] = self.travel[m].saturating_add(delta as u64);
+    }
+}
+
+/// Usage counters of both motors
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stats {
+    /// Motor 1 and motor 2 counters
+    pub motors: [MotorStats; 2],
+    /// Lifetime travel
+    pub odometer: Odometer,
+    /// Last known XACTUAL
+    last_x_actual: [Option<i32>; 2],
+    /// Last seen stop switch events (event_stop_l or event_stop_r)
+    last_event_stop: [bool; 2],
+    /// Last seen driver error status bits
+    last_driver_error: [bool; 2],
+}
+
+impl Stats {
+    /// Counters of motor `M`
+    pub fn motor<const M: u8>(&self) -> &MotorStats {
+        let () = MotorIndexCheck::<M>::OK;
+        &self.motors[M as usize]
+    }
+
+    /// Clears the motor counters, the odometer is kept
+    pub fn reset(&mut self) {
+        self.motors = Default::default();
+    }
+
+    /// Updates counters from the status byte of a datagram
+    pub(crate) fn observe_status(&mut self, status: &SpiStatus) {
+        let driver_error = [status.driver_error1, status.driver_error2];
+        for (m, &error) in driver_error.iter().enumerate() {
+            // status bits stay set until GSTAT is read, count rising edges only
+            if error && !self.last_driver_error[m] {
+                self.motors[m].faults = self.motors[m].faults.saturating_add(1);
+            }
+            self.last_driver_error[m] = error;
+        }
+    }
+
+    /// Updates counters from a register read
+    pub(crate) fn observe_read(&mut self, addr: u8, data: u32) {
+        if addr == RampStat::<0>::addr() {
+            self.observe_ramp_stat(0, RampStat::<0>::from(data));
+        } else if addr == RampStat::<1>::addr() {
+            self.observe_ramp_stat(1, RampStat::<1>::from(data));
+        } else if addr == XActual::<0>::addr() {
+            self.observe_x_actual(0, XActual::<0>::from(data).x_actual);
+        } else if addr == XActual::<1>::addr() {
+            self.observe_x_actual(1, XActual::<1>::from(data).x_actual);
+        }
+    }
+
+    /// Updates counters from a register write
+    pub(crate) fn observe_write(&mut self, addr: u8, data: u32) {
+        if addr == XTarget::<0>::addr() {
+            self.motors[0].moves = self.motors[0].moves.saturating_add(1);
+        } else if addr == XTarget::<1>::addr() {
+            self.motors[1].moves = self.motors[1].moves.saturating_add(1);
+        } else if addr == XActual::<0>::addr() {
+            // position is redefined (homing), this is not a travel
+            self.last_x_actual[0] = Some(XActual::<0>::from(data).x_actual);
+        } else if addr == XActual::<1>::addr() {
+            self.last_x_actual[1] = Some(XActual::<1>::from(data).x_actual);
+        }
+    }
+
+    fn observe_ramp_stat<const M: u8>(&mut self, m: usize, ramp_stat: RampStat<M>) {
+        let stats = &mut self.motors[m];
+        // event_stop_sg is cleared upon reading, every occurrence is a new event
+        if ramp_stat.event_stop_sg {
+            stats.stall_events = stats.stall_events.saturating_add(1);
+        }
+        // event_stop_l/r stay active until the stop condition is removed, count rising edges only
+        let event_stop = ramp_stat.event_stop_l || ramp_stat.event_stop_r;
+        if event_stop && !self.last_event_stop[m] {
+            stats.switch_stops = stats.switch_stops.saturating_add(1);
+        }
+        self.last_event_stop[m] = event_stop;
+    }
+
+    fn observe_x_actual(&mut self, m: usize, x_actual: i32) {
+        if let Some(last) = self.last_x_actual[m] {
+            // XACTUAL wraps around, so does the difference
+            let delta = x_actual.wrapping_sub(last).unsigned_abs();
+            self.motors[m].distance = self.motors[m].distance.saturating_add(delta);
+            self.odometer.add(m, delta);
+        }
+        self.last_x_actual[m] = Some(x_actual);
+    }
+}
+
+/// Number of register addresses
+#[cfg(feature = "access-stats")]
+const ADDRESSES: usize = 0x80;
+
+/// Read and write counts of each register address (`access-stats` feature)
+#[cfg(feature = "access-stats")]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct AccessStats {
+    /// Reads, indexed by address
+    pub reads: [u32; ADDRESSES],
+    /// Writes, indexed by address
+    pub writes: [u32; ADDRESSES],
+}
+
+#[cfg(feature = "access-stats")]
+impl Default for AccessStats {
+    fn default() -> Self {
+        Self {
+            reads: [0; ADDRESSES],
+            writes: [0; ADDRESSES],
+        }
+    }
+}
+
+#[cfg(feature = "access-stats")]
+impl AccessStats {
+    /// Reads of register `R`
+    pub fn reads_of<R: Register>(&self) -> u32
+    where
+        u32: From<R>,
+    {
+        self.reads[R::addr() as usize]
+    }
+
+    /// Writes of register `R`
+    pub fn writes_of<R: Register>(&self) -> u32
+    where
+        u32: From<R>,
+    {
+        self.writes[R::addr() as usize]
+    }
+
+    /// Addresses accessed at least once, reads or writes
+    pub fn touched(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..ADDRESSES as u8)
+            .filter(move |&addr| self.reads[addr as usize] != 0 || self.writes[addr as usize] != 0)
+    }
+
+    /// Clears the counters
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn observe_read(&mut self, addr: u8) {
+        let count = &mut self.reads[(addr & 0x7f) as usize];
+        *count = count.saturating_add(1);
+    }
+
+    pub(crate) fn observe_write(&mut self, addr: u8) {
+        let count = &mut self.writes[(addr & 0x7f) as usize];
+        *count = count.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "access-stats")]
+    #[test]
+    fn access_stats() {
+        let mut access = AccessStats::default();
+        access.observe_read(XActual::<1>::addr());
+        access.observe_read(XActual::<1>::addr());
+        access.observe_write(XTarget::<0>::addr());
+        assert_eq!(access.reads_of::<XActual<1>>(), 2);
+        assert_eq!(access.writes_of::<XActual<1>>(), 0);
+        assert_eq!(access.writes_of::<XTarget<0>>(), 1);
+        let touched: [Option<u8>; 3] = {
+            let mut touched = access.touched();
+            [touched.next(), touched.next(), touched.next()]
+        };
+        assert_eq!(
+            touched,
+            [Some(XTarget::<0>::addr()), Some(XActual::<1>::addr()), None]
+        );
+        access.reset();
+        assert_eq!(access.touched().count(), 0);
+    }
+
+    #[test]
+    fn stall_events() {
+        let mut stats = Stats::default();
+        let stall = u32::from(RampStat::<0> {
+            event_stop_sg: true,
+            ..Default::default()
+        });
+        stats.observe_read(RampStat::<0>::addr(), stall);
+        stats.observe_read(RampStat::<0>::addr(), stall);
+        stats.observe_read(RampStat::<0>::addr(), 0);
+        assert_eq!(stats.motor::<0>().stall_events, 2);
+        assert_eq!(stats.motor::<1>().stall_events, 0);
+    }
+
+    #[test]
+    fn switch_stops() {
+        let mut stats = Stats::default();
+        let stop = u32::from(RampStat::<1> {
+            event_stop_l: true,
+            ..Default::default()
+        });
+        stats.observe_read(RampStat::<1>::addr(), stop);
+        stats.observe_read(RampStat::<1>::addr(), stop);
+        stats.observe_read(RampStat::<1>::addr(), 0);
+        stats.observe_read(RampStat::<1>::addr(), stop);
+        assert_eq!(stats.motor::<1>().switch_stops, 2);
+    }
+
+    #[test]
+    fn faults() {
+        let mut stats = Stats::default();
+        let error = SpiStatus {
+            driver_error2: true,
+            ..Default::default()
+        };
+        stats.observe_status(&error);
+        stats.observe_status(&error);
+        stats.observe_status(&SpiStatus::default());
+        stats.observe_status(&error);
+        assert_eq!(stats.motor::<0>().faults, 0);
+        assert_eq!(stats.motor::<1>().faults, 2);
+    }
+
+    #[test]
+    fn distance() {
+        let mut stats = Stats::default();
+        stats.observe_read(XActual::<0>::addr(), 100);
+        stats.observe_read(XActual::<0>::addr(), 250);
+        stats.observe_read(XActual::<0>::addr(), -50i32 as u32);
+        // homing redefines the position without traveling
+        stats.observe_write(XActual::<0>::addr(), 1000);
+        stats.observe_read(XActual::<0>::addr(), 1010);
+        assert_eq!(stats.motor::<0>().distance, 150 + 300 + 10);
+    }
+
+    #[test]
+    fn odometer() {
+        let mut stats = Stats {
+            odometer: Odometer {
+                travel: [u64::MAX - 10, 1 << 40],
+            },
+            ..Default::default()
+        };
+        stats.observe_read(XActual::<0>::addr(), 0);
+        stats.observe_read(XActual::<0>::addr(), 100);
+        stats.observe_read(XActual::<1>::addr(), 0);
+        stats.observe_read(XActual::<1>::addr(), -100i32 as u32);
+        stats.reset();
+        assert_eq!(stats.motor::<1>().distance, 0);
+        assert_eq!(stats.odometer.motor::<0>(), u64::MAX);
+        assert_eq!(stats.odometer.motor::<1>(), (1 << 40) + 100);
+    }
+
+    #[test]
+    fn odometer_bytes() {
+        let odometer = Odometer {
+            travel: [0x0102030405060708, u64::MAX],
+        };
+        let bytes = odometer.to_bytes();
+        assert_eq!(bytes[..8], [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(Odometer::from_bytes(&bytes), odometer);
+    }
+
+    #[test]
+    fn moves() {
+        let mut stats = Stats::default();
+        stats.observe_write(XTarget::<1>::addr(), 1000);
+        stats.observe_write(XTarget::<1>::addr(), 0);
+        assert_eq!(stats.motor::<1>().moves, 2);
+        assert_eq!(stats.motor::<0>().moves, 0);
+    }
+}
@@ -1,11 +1,18 @@
 //! TMC5072 status
+//!
+//! Every SPI reply carries a [`SpiStatus`]. A [`StatusTracker`] ORs them together so that flags raised by any
+//! transfer (reset, driver errors) stay visible until taken, [`Tmc5072::take_status_summary`](crate::Tmc5072::take_status_summary)
+//! returning those of the driver.
 use crate::bits::{read_bool_from_bit, write_bool_to_bit};
+use crate::flags::fmt_flags;
+use core::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// SPI Status Bits `SPI_STATUS`
-#[derive(Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SpiStatus {
     /// GSTAT\[0\] – 1: Signals, that a reset has occurred (clear by reading GSTAT)
     pub reset_flag: bool,
@@ -56,3 +63,74 @@ impl From<SpiStatus> for u8 {
         value
     }
 }
+
+impl SpiStatus {
+    /// Flags with their datasheet names
+    pub(crate) fn flags(&self) -> [(&'static str, bool); 7] {
+        [
+            ("reset_flag", self.reset_flag),
+            ("driver_error1", self.driver_error1),
+            ("driver_error2", self.driver_error2),
+            ("velocity_reached1", self.velocity_reached1),
+            ("velocity_reached2", self.velocity_reached2),
+            ("status_stop_l1", self.status_stop_l1),
+            ("status_stop_l2", self.status_stop_l2),
+        ]
+    }
+}
+
+impl fmt::Display for SpiStatus {
+    /// Names of the set flags, e.g. `[reset_flag driver_error1]`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_flags(f, &self.flags())
+    }
+}
+
+/// Sticky accumulation of the status bits of several replies
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StatusTracker {
+    seen: u8,
+}
+
+impl StatusTracker {
+    /// Adds the bits of a reply
+    pub fn observe(&mut self, status: SpiStatus) {
+        self.seen |= u8::from(status);
+    }
+
+    /// Bits seen since the last [`take`](StatusTracker::take)
+    pub fn summary(&self) -> SpiStatus {
+        SpiStatus::from(self.seen)
+    }
+
+    /// Returns the bits seen and clears them
+    pub fn take(&mut self) -> SpiStatus {
+        let summary = self.summary();
+        self.seen = 0;
+        summary
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tracker() {
+        let mut tracker = StatusTracker::default();
+        tracker.observe(SpiStatus {
+            driver_error1: true,
+            ..Default::default()
+        });
+        tracker.observe(SpiStatus {
+            velocity_reached2: true,
+            ..Default::default()
+        });
+        tracker.observe(SpiStatus::default());
+        let summary = tracker.take();
+        assert!(summary.driver_error1 && summary.velocity_reached2);
+        assert!(!summary.reset_flag);
+        assert_eq!(tracker.summary(), SpiStatus::default());
+    }
+}
@@ -0,0 +1,500 @@
+//! stealthChop setup
+//!
+//! stealthChop is enabled by a non zero PWM_GRAD. With pwm_autoscale, the chip regulates the PWM amplitude to the
+//! motor current: the regulation needs a measurable current, and should settle at standstill with the motor
+//! energized before the first move. [`enable`] checks the current settings and writes IHOLD_IRUN and PWMCONF,
+//! then [`StealthChopTuning::poll`] waits at standstill until PWM_STATUS stopped changing.
+//!
+//! PWMCONF freewheel only takes effect with IHOLD=0 and stealthChop on: [`set_standstill_mode`] writes both
+//! registers consistently.
+//!
+//! ```
+//! # use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+//! # use tmc5072::stealthchop::{enable, StealthChopConfig, TuningStatus};
+//! # use tmc5072::Tmc5072;
+//! # fn example<CS: OutputPin, SPI: Transfer<u8>>(tmc5072: &mut Tmc5072<CS>, spi: &mut SPI, now: impl Fn() -> u32) {
+//! let mut motor = tmc5072.motor::<0, _>(spi);
+//! if let Ok(mut tuning) = enable(&mut motor, StealthChopConfig::default()) {
+//!     while let Ok(TuningStatus::Tuning) = tuning.poll(&mut motor, now()) {}
+//! }
+//! # }
+//! ```
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::motor::Motor;
+use crate::registers::{
+    motor_driver_register::DrvStatus,
+    ramp_generator_driver_feature_control_register::IHoldIRun,
+    voltage_pwm_mode_stealth_chop::{Freewheel, PwmConf, PwmStatus},
+    Register,
+};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Lowest IRUN for automatic scaling, below it the regulation cannot measure the current
+pub const MIN_AUTOSCALE_I_RUN: u8 = 8;
+
+/// stealthChop settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StealthChopConfig {
+    /// PWM_AMPL: amplitude, or maximum amplitude with automatic scaling (above 0x40 recommended)
+    pub pwm_ampl: u8,
+    /// PWM_GRAD: amplitude change per half wave with automatic scaling, 1..15
+    pub pwm_grad: u8,
+    /// pwm_freq: PWM frequency, 0..3 for 2/1024, 2/683, 2/512, 2/410 fCLK
+    pub pwm_freq: u8,
+    /// pwm_autoscale: automatic amplitude scaling
+    pub pwm_autoscale: bool,
+    /// freewheel: standstill option with IHOLD=0
+    pub freewheel: Freewheel,
+    /// IRUN: run current, 0..31
+    pub i_run: u8,
+    /// IHOLD: standstill current, 0..31
+    pub i_hold: u8,
+    /// IHOLDDELAY: power down delay, 0..15
+    pub i_hold_delay: u8,
+    /// Time PWM_STATUS must stay unchanged at standstill, in the unit of the `now` argument of
+    /// [`StealthChopTuning::poll`]
+    pub settle_time: u32,
+}
+
+impl Default for StealthChopConfig {
+    fn default() -> Self {
+        Self {
+            pwm_ampl: 200,
+            pwm_grad: 1,
+            pwm_freq: 1,
+            pwm_autoscale: true,
+            freewheel: Freewheel::Normal,
+            i_run: 16,
+            i_hold: 8,
+            i_hold_delay: 6,
+            settle_time: 100,
+        }
+    }
+}
+
+impl StealthChopConfig {
+    /// Checks the settings
+    pub fn validate(&self) -> Result<(), StealthChopConfigError> {
+        if self.pwm_grad == 0 {
+            Err(StealthChopConfigError::Disabled)
+        } else if self.pwm_grad > 15
+            || self.pwm_freq > 3
+            || self.i_run > 31
+            || self.i_hold > 31
+            || self.i_hold_delay > 15
+        {
+            Err(StealthChopConfigError::OutOfRange)
+        } else if self.pwm_autoscale && self.i_run < MIN_AUTOSCALE_I_RUN {
+            Err(StealthChopConfigError::RunCurrentTooLow)
+        } else if self.pwm_autoscale && self.i_hold == 0 {
+            Err(StealthChopConfigError::ZeroHoldCurrent)
+        } else if self.i_hold > self.i_run {
+            Err(StealthChopConfigError::HoldAboveRun)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// PWMCONF of motor `M`
+    pub fn pwm_conf<const M: u8>(&self) -> PwmConf<M> {
+        PwmConf {
+            pwm_ampl: self.pwm_ampl,
+            pwm_grad: self.pwm_grad,
+            pwm_freq: self.pwm_freq,
+            pwm_autoscale: self.pwm_autoscale,
+            freewheel: self.freewheel,
+        }
+    }
+
+    /// IHOLD_IRUN of motor `M`
+    pub fn i_hold_i_run<const M: u8>(&self) -> IHoldIRun<M> {
+        IHoldIRun {
+            i_hold: self.i_hold,
+            i_run: self.i_run,
+            i_hold_delay: self.i_hold_delay,
+        }
+    }
+}
+
+/// Invalid stealthChop settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StealthChopConfigError {
+    /// PWM_GRAD is 0, which turns stealthChop off
+    Disabled,
+    /// A value does not fit its field
+    OutOfRange,
+    /// IRUN is below [`MIN_AUTOSCALE_I_RUN`] with automatic scaling
+    RunCurrentTooLow,
+    /// IHOLD is 0 with automatic scaling, the standstill tuning needs current
+    ZeroHoldCurrent,
+    /// IHOLD is above IRUN
+    HoldAboveRun,
+    /// The motor is [position critical](crate::Tmc5072::set_position_critical), it must stay energized
+    PositionCritical,
+}
+
+/// stealthChop error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StealthChopError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// Invalid settings, nothing was written
+    Invalid(StealthChopConfigError),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for StealthChopError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        StealthChopError::SpiError(e)
+    }
+}
+
+/// Tuning progress
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TuningStatus {
+    /// Waiting for PWM_STATUS to settle at standstill
+    Tuning,
+    /// PWM_STATUS settled at this value, the motor may move
+    Tuned(u8),
+}
+
+/// Standstill tuning of motor `M`, started by [`enable`]
+pub struct StealthChopTuning<const M: u8> {
+    settle_time: u32,
+    /// Last PWM_STATUS and the time it was first read
+    last: Option<(u8, u32)>,
+    status: TuningStatus,
+}
+
+/// Validates the settings, writes IHOLD_IRUN then PWMCONF of the motor
+///
+/// Without automatic scaling there is nothing to tune: the first poll reports the PWM_STATUS.
+pub fn enable<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    config: StealthChopConfig,
+) -> Result<StealthChopTuning<M>, StealthChopError<SPI::Error, CS::Error>>
+where
+    IHoldIRun<M>: Register,
+    PwmConf<M>: Register,
+{
+    config.validate().map_err(StealthChopError::Invalid)?;
+    let (tmc5072, spi) = motor.parts();
+    tmc5072.write_register(config.i_hold_i_run::<M>(), spi)?;
+    tmc5072.write_register(config.pwm_conf::<M>(), spi)?;
+    Ok(StealthChopTuning {
+        settle_time: if config.pwm_autoscale {
+            config.settle_time
+        } else {
+            0
+        },
+        last: None,
+        status: TuningStatus::Tuning,
+    })
+}
+
+impl<const M: u8> StealthChopTuning<M>
+where
+    DrvStatus<M>: Register,
+    PwmStatus<M>: Register,
+{
+    /// Actual status
+    pub fn status(&self) -> TuningStatus {
+        self.status
+    }
+
+    /// Reads PWM_STATUS at standstill, tuned once it did not change for the settle time
+    ///
+    /// Motion restarts the wait.
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        motor: &mut Motor<'_, CS, SPI, M>,
+        now: u32,
+    ) -> Result<TuningStatus, SpiError<SPI::Error, CS::Error>> {
+        if self.status != TuningStatus::Tuning {
+            return Ok(self.status);
+        }
+        let (tmc5072, spi) = motor.parts();
+        let [drv_status, pwm_status] = tmc5072
+            .read_many(&[DrvStatus::<M>::addr(), PwmStatus::<M>::addr()], spi)?
+            .data;
+        let pwm_status = PwmStatus::<M>::from(pwm_status).pwm_status;
+        if !DrvStatus::<M>::from(drv_status).stst {
+            self.last = None;
+            return Ok(self.status);
+        }
+        match self.last {
+            Some((last, since)) if last == pwm_status => {
+                if now.wrapping_sub(since) >= self.settle_time {
+                    self.status = TuningStatus::Tuned(pwm_status);
+                }
+            }
+            _ => {
+                self.last = Some((pwm_status, now));
+                if self.settle_time == 0 {
+                    self.status = TuningStatus::Tuned(pwm_status);
+                }
+            }
+        }
+        Ok(self.status)
+    }
+}
+
+/// Motor behavior at standstill
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StandstillMode {
+    /// Energized with the IHOLD current scale, 0..31
+    Hold(u8),
+    /// Not energized, the motor turns freely
+    Freewheel,
+    /// Coils shorted through the low side drivers (passive braking)
+    ShortLs,
+    /// Coils shorted through the high side drivers (passive braking)
+    ShortHs,
+}
+
+/// Sets the standstill mode of the motor, writing IHOLD and PWMCONF freewheel from the shadow registers
+///
+/// Freewheeling and coil shorting require stealthChop (PWM_GRAD not 0): IHOLD is set to 0 once PWMCONF selects
+/// the mode. Holding restores freewheel to normal once IHOLD is set. Both are refused for a
+/// [position critical](crate::Tmc5072::set_position_critical) motor, whose IHOLD=0 write would be replaced.
+pub fn set_standstill_mode<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    mode: StandstillMode,
+) -> Result<(), StealthChopError<SPI::Error, CS::Error>>
+where
+    IHoldIRun<M>: Register,
+    PwmConf<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let config = tmc5072.config();
+    let (i_hold_i_run, pwm_conf) = match M {
+        0 => (
+            u32::from(config.motor0.i_hold_i_run),
+            u32::from(config.motor0.pwm_conf),
+        ),
+        _ => (
+            u32::from(config.motor1.i_hold_i_run),
+            u32::from(config.motor1.pwm_conf),
+        ),
+    };
+    let i_hold_i_run = IHoldIRun::<M>::from(i_hold_i_run);
+    let pwm_conf = PwmConf::<M>::from(pwm_conf);
+    let freewheel = match mode {
+        StandstillMode::Hold(i_hold) => {
+            if i_hold > 31 {
+                return Err(StealthChopError::Invalid(
+                    StealthChopConfigError::OutOfRange,
+                ));
+            }
+            tmc5072.write_register(
+                IHoldIRun::<M> {
+                    i_hold,
+                    ..i_hold_i_run
+                },
+                spi,
+            )?;
+            tmc5072.write_register(
+                PwmConf::<M> {
+                    freewheel: Freewheel::Normal,
+                    ..pwm_conf
+                },
+                spi,
+            )?;
+            return Ok(());
+        }
+        StandstillMode::Freewheel => Freewheel::Freewheel,
+        StandstillMode::ShortLs => Freewheel::ShortLs,
+        StandstillMode::ShortHs => Freewheel::ShortHs,
+    };
+    if tmc5072.is_position_critical::<M>() {
+        return Err(StealthChopError::Invalid(
+            StealthChopConfigError::PositionCritical,
+        ));
+    }
+    if pwm_conf.pwm_grad == 0 {
+        return Err(StealthChopError::Invalid(StealthChopConfigError::Disabled));
+    }
+    tmc5072.write_register(
+        PwmConf::<M> {
+            freewheel,
+            ..pwm_conf
+        },
+        spi,
+    )?;
+    tmc5072.write_register(
+        IHoldIRun::<M> {
+            i_hold: 0,
+            ..i_hold_i_run
+        },
+        spi,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+
+    #[test]
+    fn validate() {
+        let config = StealthChopConfig::default();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            StealthChopConfig {
+                pwm_grad: 0,
+                ..config
+            }
+            .validate(),
+            Err(StealthChopConfigError::Disabled)
+        );
+        assert_eq!(
+            StealthChopConfig { i_run: 4, ..config }.validate(),
+            Err(StealthChopConfigError::RunCurrentTooLow)
+        );
+        assert_eq!(
+            StealthChopConfig {
+                i_hold: 0,
+                ..config
+            }
+            .validate(),
+            Err(StealthChopConfigError::ZeroHoldCurrent)
+        );
+        assert_eq!(
+            StealthChopConfig {
+                i_hold: 0,
+                pwm_autoscale: false,
+                ..config
+            }
+            .validate(),
+            Ok(())
+        );
+        assert_eq!(
+            StealthChopConfig {
+                i_hold: 20,
+                ..config
+            }
+            .validate(),
+            Err(StealthChopConfigError::HoldAboveRun)
+        );
+    }
+
+    #[test]
+    fn tuning() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let config = StealthChopConfig::default();
+        let mut tuning = enable(&mut tmc5072.motor::<1, _>(&mut sim), config).unwrap();
+        assert_eq!(
+            PwmConf::<1>::from(sim.get(PwmConf::<1>::addr())),
+            config.pwm_conf()
+        );
+        let standstill = u32::from(DrvStatus::<1> {
+            stst: true,
+            ..Default::default()
+        });
+        // moving: no tuning
+        sim.set(PwmStatus::<1>::addr(), 40);
+        let mut poll = |sim: &mut Simulator, now| tuning.poll(&mut tmc5072.motor(sim), now);
+        assert_eq!(poll(&mut sim, 0).unwrap(), TuningStatus::Tuning);
+        assert_eq!(poll(&mut sim, 200).unwrap(), TuningStatus::Tuning);
+        sim.set(DrvStatus::<1>::addr(), standstill);
+        assert_eq!(poll(&mut sim, 300).unwrap(), TuningStatus::Tuning);
+        sim.set(PwmStatus::<1>::addr(), 52);
+        assert_eq!(poll(&mut sim, 350).unwrap(), TuningStatus::Tuning);
+        assert_eq!(poll(&mut sim, 420).unwrap(), TuningStatus::Tuning);
+        assert_eq!(poll(&mut sim, 450).unwrap(), TuningStatus::Tuned(52));
+        assert_eq!(poll(&mut sim, 460).unwrap(), TuningStatus::Tuned(52));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let config = StealthChopConfig {
+            i_run: 2,
+            i_hold: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            enable(&mut tmc5072.motor::<0, _>(&mut sim), config),
+            Err(StealthChopError::Invalid(
+                StealthChopConfigError::RunCurrentTooLow
+            ))
+        ));
+        assert_eq!(sim.get(PwmConf::<0>::addr()), PwmConf::<0>::RESET_VALUE);
+    }
+
+    #[test]
+    fn standstill_mode() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        // stealthChop is configured out of reset
+        tmc5072
+            .write_register(PwmConf::<1>::from(0), &mut sim)
+            .unwrap();
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        assert!(matches!(
+            set_standstill_mode(&mut motor, StandstillMode::Freewheel),
+            Err(StealthChopError::Invalid(StealthChopConfigError::Disabled))
+        ));
+        let config = StealthChopConfig {
+            pwm_autoscale: false,
+            ..Default::default()
+        };
+        enable(&mut motor, config).unwrap();
+        tmc5072.set_position_critical::<1>(true);
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        assert!(matches!(
+            set_standstill_mode(&mut motor, StandstillMode::Freewheel),
+            Err(StealthChopError::Invalid(
+                StealthChopConfigError::PositionCritical
+            ))
+        ));
+        assert_eq!(
+            PwmConf::<1>::from(sim.get(PwmConf::<1>::addr())),
+            config.pwm_conf()
+        );
+        tmc5072.set_position_critical::<1>(false);
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        set_standstill_mode(&mut motor, StandstillMode::ShortLs).unwrap();
+        assert_eq!(
+            PwmConf::<1>::from(sim.get(PwmConf::<1>::addr())),
+            PwmConf {
+                freewheel: Freewheel::ShortLs,
+                ..config.pwm_conf()
+            }
+        );
+        assert_eq!(
+            IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())),
+            IHoldIRun {
+                i_hold: 0,
+                ..config.i_hold_i_run()
+            }
+        );
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        set_standstill_mode(&mut motor, StandstillMode::Hold(4)).unwrap();
+        assert_eq!(
+            PwmConf::<1>::from(sim.get(PwmConf::<1>::addr())),
+            config.pwm_conf()
+        );
+        assert_eq!(
+            IHoldIRun::<1>::from(sim.get(IHoldIRun::<1>::addr())),
+            IHoldIRun {
+                i_hold: 4,
+                ..config.i_hold_i_run()
+            }
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Step/Dir mode
+//!
+//! With GCONF stepdir_enable, a motor is driven by its STEP and DIR inputs instead of its ramp generator.
+//! [`enable_step_dir`] writes the Step/Dir related CHOPCONF bits (microstep resolution, interpolation, double edge)
+//! before handing the motor over. [`disable_step_dir`] first aims the ramp generator at XACTUAL in positioning mode,
+//! so that it holds the position once it gets the motor back, then restores the native 256 microstep resolution.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::gconf::GConfError;
+use crate::motor::Motor;
+use crate::registers::{
+    general_configuration_register::GConf,
+    motor_driver_register::{ChopConf, MicrostepResolution},
+    ramp_generator_register::{RampMode, RampModeValue, XActual, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Step/Dir settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StepDirConfig {
+    /// dedge: a step at each STEP edge
+    pub dedge: bool,
+    /// intpol16: interpolation of 16 microsteps to 256
+    pub intpol16: bool,
+    /// MRES: microsteps per STEP pulse
+    pub mres: MicrostepResolution,
+}
+
+impl Default for StepDirConfig {
+    fn default() -> Self {
+        Self {
+            dedge: false,
+            intpol16: true,
+            mres: MicrostepResolution::M16,
+        }
+    }
+}
+
+impl StepDirConfig {
+    /// Checks the settings
+    pub fn validate(&self) -> Result<(), StepDirConfigError> {
+        if self.intpol16 && self.mres != MicrostepResolution::M16 {
+            Err(StepDirConfigError::InterpolationWithoutM16)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Invalid Step/Dir settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StepDirConfigError {
+    /// intpol16 only applies to a 16 microstep resolution
+    InterpolationWithoutM16,
+}
+
+/// Step/Dir mode error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StepDirError<SPI, CS> {
+    /// GCONF access error
+    GConf(GConfError<SPI, CS>),
+    /// Invalid settings, nothing was written
+    Invalid(StepDirConfigError),
+}
+
+impl<SPI, CS> From<GConfError<SPI, CS>> for StepDirError<SPI, CS> {
+    fn from(e: GConfError<SPI, CS>) -> Self {
+        StepDirError::GConf(e)
+    }
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for StepDirError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        StepDirError::GConf(GConfError::SpiError(e))
+    }
+}
+
+/// GCONF stepdir_enable of motor `M`
+fn step_dir_enable<const M: u8>(g_conf: &mut GConf) -> &mut bool {
+    match M {
+        0 => &mut g_conf.stepdir1_enable,
+        _ => &mut g_conf.stepdir2_enable,
+    }
+}
+
+/// Validates the settings, updates CHOPCONF then sets GCONF stepdir_enable of the motor
+pub fn enable_step_dir<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    config: StepDirConfig,
+) -> Result<(), StepDirError<SPI::Error, CS::Error>>
+where
+    ChopConf<M>: Register,
+{
+    config.validate().map_err(StepDirError::Invalid)?;
+    let (tmc5072, spi) = motor.parts();
+    let chop_conf = tmc5072.read_register::<ChopConf<M>, _>(spi)?.data;
+    tmc5072.write_register(
+        ChopConf::<M> {
+            dedge: config.dedge,
+            intpol16: config.intpol16,
+            mres: config.mres,
+            ..chop_conf
+        },
+        spi,
+    )?;
+    tmc5072.update_gconf(|g_conf| *step_dir_enable::<M>(g_conf) = true, spi)?;
+    Ok(())
+}
+
+/// Returns the motor to its ramp generator, holding the actual position
+///
+/// Sets positioning mode with XTARGET=XACTUAL, clears GCONF stepdir_enable, then restores the native 256 microstep
+/// resolution without double edge steps. Returns XACTUAL.
+pub fn disable_step_dir<CS: OutputPin, SPI: Transfer<u8>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+) -> Result<i32, GConfError<SPI::Error, CS::Error>>
+where
+    ChopConf<M>: Register,
+    RampMode<M>: Register,
+    XActual<M>: Register,
+    XTarget<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let x_actual = tmc5072.read_register::<XActual<M>, _>(spi)?.data.x_actual;
+    tmc5072.write_register(
+        RampMode::<M> {
+            ramp_mode: RampModeValue::Positioning,
+        },
+        spi,
+    )?;
+    tmc5072.write_register(XTarget::<M> { x_target: x_actual }, spi)?;
+    tmc5072.update_gconf(|g_conf| *step_dir_enable::<M>(g_conf) = false, spi)?;
+    let chop_conf = tmc5072.read_register::<ChopConf<M>, _>(spi)?.data;
+    tmc5072.write_register(
+        ChopConf::<M> {
+            dedge: false,
+            mres: MicrostepResolution::M256,
+            ..chop_conf
+        },
+        spi,
+    )?;
+    Ok(x_actual)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+
+    #[test]
+    fn enable_and_disable() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let config = StepDirConfig {
+            intpol16: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            StepDirConfig {
+                mres: MicrostepResolution::M8,
+                ..Default::default()
+            }
+            .validate(),
+            Err(StepDirConfigError::InterpolationWithoutM16)
+        );
+        enable_step_dir(
+            &mut tmc5072.motor::<1, _>(&mut sim),
+            StepDirConfig {
+                dedge: true,
+                ..config
+            },
+        )
+        .unwrap();
+        let g_conf = GConf::from(sim.get(GConf::addr()));
+        assert!(g_conf.stepdir2_enable && !g_conf.stepdir1_enable);
+        let chop_conf = ChopConf::<1>::from(sim.get(ChopConf::<1>::addr()));
+        assert!(chop_conf.dedge && !chop_conf.intpol16);
+        assert_eq!(chop_conf.mres, MicrostepResolution::M16);
+        // moved by STEP pulses
+        sim.set(XActual::<1>::addr(), 1234);
+        sim.set(XTarget::<1>::addr(), 0);
+        assert_eq!(
+            disable_step_dir(&mut tmc5072.motor::<1, _>(&mut sim)),
+            Ok(1234)
+        );
+        assert_eq!(sim.get(XActual::<1>::addr()), 1234);
+        assert_eq!(sim.get(XTarget::<1>::addr()), 1234);
+        assert!(!GConf::from(sim.get(GConf::addr())).stepdir2_enable);
+        let chop_conf = ChopConf::<1>::from(sim.get(ChopConf::<1>::addr()));
+        assert!(!chop_conf.dedge);
+        assert_eq!(chop_conf.mres, MicrostepResolution::M256);
+    }
+}
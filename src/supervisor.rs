@@ -0,0 +1,326 @@
+//! Driver fault supervision
+//!
+//! A [`Supervisor`] reads GSTAT and both DRV_STATUS registers into a [`DriverHealth`] report. With the
+//! [`SupervisorPolicy::DisableOnCritical`] policy it also switches off the drivers (CHOPCONF TOFF=0) on critical
+//! faults: overtemperature and charge pump undervoltage switch off both drivers, a short to GND only the shorted one.
+//! Disabled drivers stay off until [`Supervisor::clear_and_reenable`] restores their TOFF.
+//!
+//! Reading GSTAT clears it: do not read GSTAT elsewhere while a supervisor is in use.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::motor::MotorId;
+use crate::registers::{
+    general_configuration_register::GStat,
+    motor_driver_register::{ChopConf, DrvStatus},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Fault flags of a driver
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MotorHealth {
+    /// GSTAT drv_err: the driver has been shut down since the last read
+    pub drv_err: bool,
+    /// DRV_STATUS s2ga: short to GND on phase A
+    pub s2ga: bool,
+    /// DRV_STATUS s2gb: short to GND on phase B
+    pub s2gb: bool,
+    /// DRV_STATUS ola: open load on phase A (informative)
+    pub ola: bool,
+    /// DRV_STATUS olb: open load on phase B (informative)
+    pub olb: bool,
+}
+
+impl MotorHealth {
+    /// Whether a short to GND is detected
+    pub fn is_short(&self) -> bool {
+        self.s2ga || self.s2gb
+    }
+}
+
+/// Health report of both drivers
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DriverHealth {
+    /// GSTAT reset: the IC has been reset since the last read
+    pub reset: bool,
+    /// DRV_STATUS ot: overtemperature, the drivers are disabled
+    pub ot: bool,
+    /// DRV_STATUS otpw: overtemperature prewarning
+    pub otpw: bool,
+    /// GSTAT uv_cp: charge pump undervoltage, the drivers are disabled
+    pub uv_cp: bool,
+    /// Flags of each driver
+    pub motors: [MotorHealth; 2],
+}
+
+impl DriverHealth {
+    /// Builds the report from GSTAT and the DRV_STATUS of both motors
+    pub fn new(g_stat: GStat, drv_status0: DrvStatus<0>, drv_status1: DrvStatus<1>) -> Self {
+        Self {
+            reset: g_stat.reset,
+            ot: drv_status0.ot || drv_status1.ot,
+            otpw: drv_status0.otpw || drv_status1.otpw,
+            uv_cp: g_stat.uv_cp,
+            motors: [
+                MotorHealth {
+                    drv_err: g_stat.drv_err1,
+                    s2ga: drv_status0.s2ga,
+                    s2gb: drv_status0.s2gb,
+                    ola: drv_status0.ola,
+                    olb: drv_status0.olb,
+                },
+                MotorHealth {
+                    drv_err: g_stat.drv_err2,
+                    s2ga: drv_status1.s2ga,
+                    s2gb: drv_status1.s2gb,
+                    ola: drv_status1.ola,
+                    olb: drv_status1.olb,
+                },
+            ],
+        }
+    }
+
+    /// Whether a fault affecting both drivers is active (overtemperature or charge pump undervoltage)
+    pub fn is_global_critical(&self) -> bool {
+        self.ot || self.uv_cp
+    }
+
+    /// Whether driver `motor` has a critical fault
+    pub fn is_critical(&self, motor: MotorId) -> bool {
+        let health = &self.motors[motor.index() as usize];
+        self.is_global_critical() || health.is_short() || health.drv_err
+    }
+
+    /// Whether any flag, warnings and informative ones included, is active
+    pub fn has_flags(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+/// Reaction to critical faults
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SupervisorPolicy {
+    /// Faults are only reported
+    #[default]
+    ReportOnly,
+    /// Drivers with a critical fault are switched off (TOFF=0)
+    DisableOnCritical,
+}
+
+/// Supervisor error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SupervisorError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// A fault affecting both drivers is still active, nothing was re-enabled
+    Faulty(DriverHealth),
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for SupervisorError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        SupervisorError::SpiError(e)
+    }
+}
+
+/// Polls the driver health, disabling faulty drivers according to its policy
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Supervisor {
+    policy: SupervisorPolicy,
+    /// TOFF of the drivers switched off by the supervisor
+    saved_toff: [Option<u8>; 2],
+}
+
+impl Supervisor {
+    /// Creates a supervisor
+    pub fn new(policy: SupervisorPolicy) -> Self {
+        Self {
+            policy,
+            saved_toff: [None; 2],
+        }
+    }
+
+    /// Whether driver `motor` has been switched off by the supervisor
+    pub fn is_disabled(&self, motor: MotorId) -> bool {
+        self.saved_toff[motor.index() as usize].is_some()
+    }
+
+    /// Reads the driver health, switches off faulty drivers with [`SupervisorPolicy::DisableOnCritical`]
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<DriverHealth, SpiError<SPI::Error, CS::Error>> {
+        let health = read_health(tmc5072, spi)?;
+        if self.policy == SupervisorPolicy::DisableOnCritical {
+            if health.is_critical(MotorId::Motor0) && self.saved_toff[0].is_none() {
+                self.saved_toff[0] = Some(set_toff::<0, _, _>(tmc5072, 0, spi)?);
+            }
+            if health.is_critical(MotorId::Motor1) && self.saved_toff[1].is_none() {
+                self.saved_toff[1] = Some(set_toff::<1, _, _>(tmc5072, 0, spi)?);
+            }
+        }
+        Ok(health)
+    }
+
+    /// Clears the latched flags and switches the disabled drivers on again
+    ///
+    /// Fails while overtemperature or charge pump undervoltage is active. Short to GND flags are cleared by
+    /// switching the driver off, a persisting short is detected again once the driver is back on.
+    pub fn clear_and_reenable<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<DriverHealth, SupervisorError<SPI::Error, CS::Error>> {
+        let health = read_health(tmc5072, spi)?;
+        if health.is_global_critical() {
+            return Err(SupervisorError::Faulty(health));
+        }
+        if let Some(toff) = self.saved_toff[0].take() {
+            set_toff::<0, _, _>(tmc5072, toff, spi)?;
+        }
+        if let Some(toff) = self.saved_toff[1].take() {
+            set_toff::<1, _, _>(tmc5072, toff, spi)?;
+        }
+        Ok(health)
+    }
+}
+
+fn read_health<CS: OutputPin, SPI: Transfer<u8>>(
+    tmc5072: &mut Tmc5072<CS>,
+    spi: &mut SPI,
+) -> Result<DriverHealth, SpiError<SPI::Error, CS::Error>> {
+    let [g_stat, drv_status0, drv_status1] = tmc5072
+        .read_many(
+            &[
+                GStat::addr(),
+                DrvStatus::<0>::addr(),
+                DrvStatus::<1>::addr(),
+            ],
+            spi,
+        )?
+        .data;
+    Ok(DriverHealth::new(
+        g_stat.into(),
+        drv_status0.into(),
+        drv_status1.into(),
+    ))
+}
+
+/// Writes TOFF of motor `M`, returns the previous value
+fn set_toff<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+    tmc5072: &mut Tmc5072<CS>,
+    toff: u8,
+    spi: &mut SPI,
+) -> Result<u8, SpiError<SPI::Error, CS::Error>>
+where
+    ChopConf<M>: Register,
+{
+    let chop_conf = tmc5072.read_register::<ChopConf<M>, _>(spi)?.data;
+    tmc5072.write_register(ChopConf::<M> { toff, ..chop_conf }, spi)?;
+    Ok(chop_conf.toff)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    fn toff<const M: u8>(sim: &mut Simulator) -> u8
+    where
+        ChopConf<M>: Register,
+    {
+        ChopConf::<M>::from(sim.get(ChopConf::<M>::addr())).toff
+    }
+
+    #[test]
+    fn short_disables_one_driver() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut supervisor = Supervisor::new(SupervisorPolicy::DisableOnCritical);
+        supervisor.poll(&mut tmc5072, &mut sim).unwrap();
+        let chop_conf = ChopConf::<1> {
+            toff: 5,
+            ..Default::default()
+        };
+        tmc5072.write_register(chop_conf, &mut sim).unwrap();
+        assert!(!supervisor.poll(&mut tmc5072, &mut sim).unwrap().has_flags());
+        sim.set(
+            GStat::addr(),
+            u32::from(GStat {
+                drv_err2: true,
+                ..Default::default()
+            }),
+        );
+        let drv_status = DrvStatus::<1> {
+            s2gb: true,
+            olb: true,
+            ..Default::default()
+        };
+        sim.set(DrvStatus::<1>::addr(), drv_status.into());
+        let health = supervisor.poll(&mut tmc5072, &mut sim).unwrap();
+        assert!(health.motors[1].drv_err && health.motors[1].is_short() && health.motors[1].olb);
+        assert!(health.is_critical(MotorId::Motor1) && !health.is_critical(MotorId::Motor0));
+        assert!(
+            supervisor.is_disabled(MotorId::Motor1) && !supervisor.is_disabled(MotorId::Motor0)
+        );
+        assert_eq!(toff::<1>(&mut sim), 0);
+        // TOFF=0 clears the short flags
+        sim.set(DrvStatus::<1>::addr(), 0);
+        assert_eq!(
+            supervisor.clear_and_reenable(&mut tmc5072, &mut sim),
+            Ok(DriverHealth::default())
+        );
+        assert!(!supervisor.is_disabled(MotorId::Motor1));
+        assert_eq!(toff::<1>(&mut sim), 5);
+    }
+
+    #[test]
+    fn overtemperature() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut supervisor = Supervisor::new(SupervisorPolicy::ReportOnly);
+        let drv_status = DrvStatus::<0> {
+            ot: true,
+            otpw: true,
+            ..Default::default()
+        };
+        sim.set(DrvStatus::<0>::addr(), drv_status.into());
+        let health = supervisor.poll(&mut tmc5072, &mut sim).unwrap();
+        assert!(
+            health.ot
+                && health.otpw
+                && health.is_critical(MotorId::Motor0)
+                && health.is_critical(MotorId::Motor1)
+        );
+        assert!(
+            !supervisor.is_disabled(MotorId::Motor0) && !supervisor.is_disabled(MotorId::Motor1)
+        );
+        let mut supervisor = Supervisor::new(SupervisorPolicy::DisableOnCritical);
+        supervisor.poll(&mut tmc5072, &mut sim).unwrap();
+        assert!(supervisor.is_disabled(MotorId::Motor0) && supervisor.is_disabled(MotorId::Motor1));
+        assert!(matches!(
+            supervisor.clear_and_reenable(&mut tmc5072, &mut sim),
+            Err(SupervisorError::Faulty(DriverHealth { ot: true, .. }))
+        ));
+        assert!(supervisor.is_disabled(MotorId::Motor0));
+        sim.set(DrvStatus::<0>::addr(), 0);
+        supervisor
+            .clear_and_reenable(&mut tmc5072, &mut sim)
+            .unwrap();
+        assert!(
+            !supervisor.is_disabled(MotorId::Motor0) && !supervisor.is_disabled(MotorId::Motor1)
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Synchronized start of both motors
+//!
+//! In hold mode (RAMPMODE=3) the ramp generator ignores XTARGET, so both targets (and any ramp parameter)
+//! can be written in advance. [`Tmc5072::trigger`] then only has to switch both motors to positioning mode,
+//! two back to back datagrams, which keeps the start skew to a single SPI transfer.
+//!
+//! Hold mode keeps the actual velocity: arm the moves while both motors stand still.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::ramp_generator_register::{RampMode, RampModeValue, XTarget};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Moves of both motors waiting for [`Tmc5072::trigger`]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[must_use = "armed moves only start when triggered"]
+pub struct ArmedMoves {
+    targets: [i32; 2],
+}
+
+impl ArmedMoves {
+    /// XTARGET of each motor
+    pub fn targets(&self) -> [i32; 2] {
+        self.targets
+    }
+}
+
+impl<CS: OutputPin> Tmc5072<CS> {
+    /// Puts both motors in hold mode and writes their targets
+    ///
+    /// Ramp parameters (VMAX, AMAX...) can still be written before triggering the moves.
+    pub fn arm_moves<SPI: Transfer<u8>>(
+        &mut self,
+        target0: i32,
+        target1: i32,
+        spi: &mut SPI,
+    ) -> Result<ArmedMoves, SpiError<SPI::Error, CS::Error>> {
+        self.write_register(
+            RampMode::<0> {
+                ramp_mode: RampModeValue::Hold,
+            },
+            spi,
+        )?;
+        self.write_register(
+            RampMode::<1> {
+                ramp_mode: RampModeValue::Hold,
+            },
+            spi,
+        )?;
+        self.write_register(XTarget::<0> { x_target: target0 }, spi)?;
+        self.write_register(XTarget::<1> { x_target: target1 }, spi)?;
+        Ok(ArmedMoves {
+            targets: [target0, target1],
+        })
+    }
+
+    /// Starts armed moves, switching both motors to positioning mode in consecutive datagrams
+    pub fn trigger<SPI: Transfer<u8>>(
+        &mut self,
+        _moves: ArmedMoves,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        self.write_register(
+            RampMode::<0> {
+                ramp_mode: RampModeValue::Positioning,
+            },
+            spi,
+        )?;
+        self.write_register(
+            RampMode::<1> {
+                ramp_mode: RampModeValue::Positioning,
+            },
+            spi,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        ramp_generator_register::{VMax, XActual},
+        Register,
+    };
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn arm_and_trigger() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let moves = tmc5072.arm_moves(1000, -250, &mut sim).unwrap();
+        tmc5072
+            .write_register(VMax::<0> { v_max: 2000 }, &mut sim)
+            .unwrap();
+        // held
+        assert_eq!(sim.get(XActual::<0>::addr()), 0);
+        assert_eq!(sim.get(XActual::<1>::addr()), 0);
+        assert_eq!(moves.targets(), [1000, -250]);
+        tmc5072.trigger(moves, &mut sim).unwrap();
+        assert_eq!(sim.get(XActual::<0>::addr()), 1000);
+        assert_eq!(sim.get(XActual::<1>::addr()) as i32, -250);
+    }
+}
@@ -0,0 +1,294 @@
+//! Motor telemetry polling
+//!
+//! A [`TelemetryPoller`] reads the state of the motors (XACTUAL, VACTUAL, DRV_STATUS) with a single pipelined
+//! [`Tmc5072::read_many`] per cycle. The shared status (reset, driver errors, velocity reached and stop switch
+//! of both motors) comes with the SPI status of every datagram, so it is refreshed on every cycle for free.
+//!
+//! [`Schedule::Interleaved`] alternates the motor whose registers are read each cycle: every cycle takes half the
+//! bus time of [`Schedule::Both`], each motor being refreshed every other cycle.
+//!
+//! With a [`SgtTable`] set for a motor, the poller also rewrites its COOLCONF when the refreshed VACTUAL enters
+//! another velocity range, applying the stallGuard2 threshold of that range.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::coolstep::{SgtTable, SgtTableError};
+use crate::registers::{
+    motor_driver_register::{CoolConf, DrvStatus},
+    ramp_generator_register::{VActual, XActual},
+    MotorIndexCheck, Register,
+};
+use crate::spi::SpiError;
+use crate::status::SpiStatus;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Registers read each cycle
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Schedule {
+    /// Both motors every cycle
+    Both,
+    /// Motor 0 and motor 1 on alternate cycles
+    Interleaved,
+}
+
+/// State of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MotorTelemetry<const M: u8> {
+    /// Actual position
+    pub x_actual: XActual<M>,
+    /// Actual velocity
+    pub v_actual: VActual<M>,
+    /// Driver status
+    pub drv_status: DrvStatus<M>,
+}
+
+impl<const M: u8> MotorTelemetry<M>
+where
+    XActual<M>: Register,
+    VActual<M>: Register,
+    DrvStatus<M>: Register,
+{
+    fn addrs() -> [u8; 3] {
+        [
+            XActual::<M>::addr(),
+            VActual::<M>::addr(),
+            DrvStatus::<M>::addr(),
+        ]
+    }
+
+    fn from_values([x_actual, v_actual, drv_status]: [u32; 3]) -> Self {
+        Self {
+            x_actual: x_actual.into(),
+            v_actual: v_actual.into(),
+            drv_status: drv_status.into(),
+        }
+    }
+}
+
+/// Latest telemetry of both motors
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Telemetry {
+    /// Motor 0 state
+    pub motor0: MotorTelemetry<0>,
+    /// Motor 1 state
+    pub motor1: MotorTelemetry<1>,
+    /// SPI status of the last cycle
+    pub status: SpiStatus,
+}
+
+/// stallGuard2 threshold table of a motor
+#[derive(Debug)]
+struct SgtState {
+    table: SgtTable,
+    /// COOLCONF without the threshold, the register being write only
+    cool_conf: u32,
+    range: Option<usize>,
+}
+
+/// Periodic telemetry reads
+#[derive(Debug)]
+pub struct TelemetryPoller {
+    schedule: Schedule,
+    next: u8,
+    telemetry: Telemetry,
+    sgt: [Option<SgtState>; 2],
+}
+
+impl TelemetryPoller {
+    /// Creates a poller, interleaved schedules start with motor 0
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            next: 0,
+            telemetry: Telemetry::default(),
+            sgt: [None, None],
+        }
+    }
+
+    /// Applies the stallGuard2 thresholds of `table` to motor `M` from the next cycle refreshing it
+    ///
+    /// `cool_conf` holds the other COOLCONF settings, its sgt being replaced by the threshold of each range.
+    pub fn set_sgt_table<const M: u8>(
+        &mut self,
+        table: SgtTable,
+        cool_conf: CoolConf<M>,
+    ) -> Result<(), SgtTableError> {
+        let () = MotorIndexCheck::<M>::OK;
+        table.validate()?;
+        self.sgt[M as usize] = Some(SgtState {
+            table,
+            cool_conf: u32::from(cool_conf),
+            range: None,
+        });
+        Ok(())
+    }
+
+    /// Stops applying stallGuard2 thresholds to motor `M`, leaving COOLCONF as is
+    pub fn clear_sgt_table<const M: u8>(&mut self) {
+        let () = MotorIndexCheck::<M>::OK;
+        self.sgt[M as usize] = None;
+    }
+
+    /// Velocity range whose stallGuard2 threshold is applied to motor `M`
+    pub fn sgt_range<const M: u8>(&self) -> Option<usize> {
+        let () = MotorIndexCheck::<M>::OK;
+        self.sgt[M as usize].as_ref().and_then(|state| state.range)
+    }
+
+    /// Selects the schedule of the next cycles
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    /// Latest telemetry
+    pub fn telemetry(&self) -> &Telemetry {
+        &self.telemetry
+    }
+
+    /// Runs a cycle, returns which motors were refreshed
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<[bool; 2], SpiError<SPI::Error, CS::Error>> {
+        let updated = match self.schedule {
+            Schedule::Both => {
+                let [a0, a1, a2] = MotorTelemetry::<0>::addrs();
+                let [b0, b1, b2] = MotorTelemetry::<1>::addrs();
+                let values = tmc5072.read_many(&[a0, a1, a2, b0, b1, b2], spi)?;
+                let [x0, v0, d0, x1, v1, d1] = values.data;
+                self.telemetry.motor0 = MotorTelemetry::from_values([x0, v0, d0]);
+                self.telemetry.motor1 = MotorTelemetry::from_values([x1, v1, d1]);
+                self.telemetry.status = values.status;
+                [true, true]
+            }
+            Schedule::Interleaved if self.next == 0 => {
+                let values = tmc5072.read_many(&MotorTelemetry::<0>::addrs(), spi)?;
+                self.telemetry.motor0 = MotorTelemetry::from_values(values.data);
+                self.telemetry.status = values.status;
+                [true, false]
+            }
+            Schedule::Interleaved => {
+                let values = tmc5072.read_many(&MotorTelemetry::<1>::addrs(), spi)?;
+                self.telemetry.motor1 = MotorTelemetry::from_values(values.data);
+                self.telemetry.status = values.status;
+                [false, true]
+            }
+        };
+        self.next ^= 1;
+        if updated[0] {
+            let v_actual = self.telemetry.motor0.v_actual.v_actual;
+            self.apply_sgt::<0, _, _>(v_actual, tmc5072, spi)?;
+        }
+        if updated[1] {
+            let v_actual = self.telemetry.motor1.v_actual.v_actual;
+            self.apply_sgt::<1, _, _>(v_actual, tmc5072, spi)?;
+        }
+        Ok(updated)
+    }
+
+    /// Writes COOLCONF of motor `M` if `v_actual` entered another velocity range
+    fn apply_sgt<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        v_actual: i32,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        CoolConf<M>: Register,
+    {
+        let Some(state) = self.sgt[M as usize].as_mut() else {
+            return Ok(());
+        };
+        let range = state.table.range(state.range, v_actual);
+        if state.range != Some(range) {
+            let cool_conf = CoolConf::<M> {
+                sgt: state.table.ranges[range].sgt,
+                ..CoolConf::from(state.cool_conf)
+            };
+            tmc5072.write_register(cool_conf, spi)?;
+            state.range = Some(range);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn schedules() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        sim.set(XActual::<0>::addr(), 100);
+        sim.set(XActual::<1>::addr(), 200);
+        let mut poller = TelemetryPoller::new(Schedule::Interleaved);
+        assert_eq!(poller.poll(&mut tmc5072, &mut sim).unwrap(), [true, false]);
+        assert_eq!(poller.telemetry().motor0.x_actual.x_actual, 100);
+        assert_eq!(poller.telemetry().motor1.x_actual.x_actual, 0);
+        sim.set(XActual::<0>::addr(), 150);
+        assert_eq!(poller.poll(&mut tmc5072, &mut sim).unwrap(), [false, true]);
+        assert_eq!(poller.telemetry().motor0.x_actual.x_actual, 100);
+        assert_eq!(poller.telemetry().motor1.x_actual.x_actual, 200);
+        assert_eq!(poller.poll(&mut tmc5072, &mut sim).unwrap(), [true, false]);
+        assert_eq!(poller.telemetry().motor0.x_actual.x_actual, 150);
+        poller.set_schedule(Schedule::Both);
+        sim.set(XActual::<1>::addr(), 250);
+        assert_eq!(poller.poll(&mut tmc5072, &mut sim).unwrap(), [true, true]);
+        assert_eq!(poller.telemetry().motor1.x_actual.x_actual, 250);
+    }
+
+    #[test]
+    fn sgt_table() {
+        use crate::coolstep::SgtRange;
+
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut poller = TelemetryPoller::new(Schedule::Both);
+        let range = |v_max, sgt| SgtRange { v_max, sgt };
+        let table = SgtTable {
+            ranges: [
+                range(10_000, 2),
+                range(50_000, 5),
+                range(200_000, 10),
+                range(200_000, 10),
+            ],
+            hysteresis: 1000,
+        };
+        let cool_conf = CoolConf::<1> {
+            semin: 5,
+            sgt: 0,
+            ..Default::default()
+        };
+        poller.set_sgt_table(table, cool_conf).unwrap();
+        poller.poll(&mut tmc5072, &mut sim).unwrap();
+        assert_eq!(poller.sgt_range::<0>(), None);
+        assert_eq!(poller.sgt_range::<1>(), Some(0));
+        let written = CoolConf::<1>::from(sim.get(CoolConf::<1>::addr()));
+        assert_eq!(
+            written,
+            CoolConf {
+                sgt: 2,
+                ..cool_conf
+            }
+        );
+        sim.set(VActual::<1>::addr(), 10_500);
+        poller.poll(&mut tmc5072, &mut sim).unwrap();
+        assert_eq!(poller.sgt_range::<1>(), Some(0));
+        sim.set(VActual::<1>::addr(), (-60_000i32) as u32);
+        poller.poll(&mut tmc5072, &mut sim).unwrap();
+        assert_eq!(poller.sgt_range::<1>(), Some(2));
+        let written = CoolConf::<1>::from(sim.get(CoolConf::<1>::addr()));
+        assert_eq!(written.sgt, 10);
+        assert_eq!(written.semin, 5);
+        poller.clear_sgt_table::<1>();
+        assert_eq!(poller.sgt_range::<1>(), None);
+    }
+}
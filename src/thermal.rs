@@ -0,0 +1,236 @@
+//! Duty cycle limiter
+//!
+//! Motors sized for intermittent operation overheat when run continuously at full current.
+//! [`DutyCycleLimiter`] integrates the run current over time (a leaky bucket: heat is added while the motor moves,
+//! the allowed duty cycle is continuously drained) and, once the budget is exhausted,
+//! either derates IRUN or requests a cool-down hold until the load dropped back to half the budget.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    ramp_generator_driver_feature_control_register::IHoldIRun, ramp_generator_register::VActual,
+    Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Action taken when the duty cycle budget is exhausted
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DutyCyclePolicy {
+    /// Lower IRUN to this value
+    Derate(u8),
+    /// Report a cool-down, new moves should wait for [`DutyCycleStatus::Normal`]
+    CoolDown,
+}
+
+/// Duty cycle limiter parameters
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DutyCycleConfig<const M: u8> {
+    /// Nominal IHOLD_IRUN
+    pub current: IHoldIRun<M>,
+    /// Allowed duty cycle at full IRUN (percent)
+    pub duty_percent: u8,
+    /// Time the motor may run at full current from cold before the limiter kicks in,
+    /// in the unit of the `now` argument of [`DutyCycleLimiter::poll`]
+    pub burst: u32,
+    /// Action when the budget is exhausted
+    pub policy: DutyCyclePolicy,
+}
+
+/// Duty cycle limiter state
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DutyCycleStatus {
+    /// Within budget
+    Normal,
+    /// IRUN is derated
+    Derated,
+    /// The motor should stay at standstill
+    CoolDown,
+}
+
+/// Duty cycle limiter of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct DutyCycleLimiter<const M: u8> {
+    config: DutyCycleConfig<M>,
+    /// Accumulated heat, in current (1/32 of full scale) * time
+    load: u64,
+    last: Option<u32>,
+    status: DutyCycleStatus,
+}
+
+impl<const M: u8> DutyCycleLimiter<M>
+where
+    IHoldIRun<M>: Register,
+    VActual<M>: Register,
+{
+    /// Creates a limiter of a cold motor
+    pub fn new(config: DutyCycleConfig<M>) -> Self {
+        Self {
+            config,
+            load: 0,
+            last: None,
+            status: DutyCycleStatus::Normal,
+        }
+    }
+
+    /// Current state
+    pub fn status(&self) -> DutyCycleStatus {
+        self.status
+    }
+
+    /// Whether a new move may be started
+    pub fn may_move(&self) -> bool {
+        self.status != DutyCycleStatus::CoolDown
+    }
+
+    /// Load relative to the budget (percent)
+    pub fn load_percent(&self) -> u32 {
+        (self.load * 100 / self.capacity().max(1)).min(u32::MAX as u64) as u32
+    }
+
+    /// Allowed heat rate
+    fn allowed(&self) -> u64 {
+        32 * self.config.duty_percent.min(100) as u64 / 100
+    }
+
+    /// Budget of the leaky bucket, filled in `burst` at full current
+    fn capacity(&self) -> u64 {
+        (32 - self.allowed()).max(1) * self.config.burst as u64
+    }
+
+    /// Integrates the load since the previous poll and applies the policy
+    ///
+    /// `now` is a monotonic (wrapping) time, poll often compared to `burst`.
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<DutyCycleStatus, SpiError<SPI::Error, CS::Error>> {
+        // VACTUAL rather than RAMP_STAT vzero, whose read would clear the ramp events
+        let moving = tmc5072.read_register::<VActual<M>, _>(spi)?.data.v_actual != 0;
+        let elapsed = self.last.map_or(0, |last| now.wrapping_sub(last)) as u64;
+        self.last = Some(now);
+        let current = if moving {
+            match (self.status, self.config.policy) {
+                (DutyCycleStatus::Derated, DutyCyclePolicy::Derate(i_run)) => i_run,
+                _ => self.config.current.i_run,
+            }
+        } else {
+            self.config.current.i_hold
+        };
+        let heat = (current.min(31) as u64 + 1) * elapsed;
+        self.load = (self.load + heat).saturating_sub(self.allowed() * elapsed);
+        let status = if self.load >= self.capacity() {
+            match self.config.policy {
+                DutyCyclePolicy::Derate(_) => DutyCycleStatus::Derated,
+                DutyCyclePolicy::CoolDown => DutyCycleStatus::CoolDown,
+            }
+        } else if self.load <= self.capacity() / 2 {
+            DutyCycleStatus::Normal
+        } else {
+            // hysteresis
+            self.status
+        };
+        if status != self.status {
+            match (status, self.config.policy) {
+                (DutyCycleStatus::Derated, DutyCyclePolicy::Derate(i_run)) => {
+                    tmc5072.write_register(
+                        IHoldIRun::<M> {
+                            i_run: i_run.min(self.config.current.i_run),
+                            ..self.config.current
+                        },
+                        spi,
+                    )?;
+                }
+                (DutyCycleStatus::Normal, DutyCyclePolicy::Derate(_)) => {
+                    tmc5072.write_register(self.config.current, spi)?;
+                }
+                _ => {}
+            }
+            self.status = status;
+        }
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    fn set_moving(sim: &mut Simulator, moving: bool) {
+        sim.set(VActual::<0>::addr(), if moving { 1000 } else { 0 });
+    }
+
+    #[test]
+    fn derate() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let current = IHoldIRun::<0> {
+            i_hold: 3,
+            i_run: 31,
+            i_hold_delay: 0,
+        };
+        let mut limiter = DutyCycleLimiter::new(DutyCycleConfig {
+            current,
+            duty_percent: 50,
+            burst: 100,
+            policy: DutyCyclePolicy::Derate(15),
+        });
+        set_moving(&mut sim, true);
+        let mut now = 0;
+        // 32 - 16 = 16 per time unit, budget of 1600
+        while limiter.poll(&mut tmc5072, &mut sim, now).unwrap() == DutyCycleStatus::Normal {
+            now += 10;
+        }
+        assert_eq!(now, 100);
+        assert_eq!(
+            IHoldIRun::<0>::from(sim.get(IHoldIRun::<0>::addr())).i_run,
+            15
+        );
+        // derated current is sustainable
+        now += 1000;
+        limiter.poll(&mut tmc5072, &mut sim, now).unwrap();
+        assert_eq!(limiter.status(), DutyCycleStatus::Derated);
+        // standstill: 16 - 4 = 12 drained per time unit, down to half the budget
+        set_moving(&mut sim, false);
+        let mut polls = 1;
+        while limiter.poll(&mut tmc5072, &mut sim, now).unwrap() != DutyCycleStatus::Normal {
+            now += 10;
+            polls += 1;
+        }
+        assert_eq!(polls, 8);
+        assert_eq!(
+            IHoldIRun::<0>::from(sim.get(IHoldIRun::<0>::addr())),
+            current
+        );
+    }
+
+    #[test]
+    fn cool_down() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut limiter = DutyCycleLimiter::new(DutyCycleConfig {
+            current: IHoldIRun::<0> {
+                i_hold: 0,
+                i_run: 31,
+                i_hold_delay: 0,
+            },
+            duty_percent: 0,
+            burst: 10,
+            policy: DutyCyclePolicy::CoolDown,
+        });
+        set_moving(&mut sim, true);
+        limiter.poll(&mut tmc5072, &mut sim, 0).unwrap();
+        assert!(limiter.may_move());
+        limiter.poll(&mut tmc5072, &mut sim, 10).unwrap();
+        assert!(!limiter.may_move());
+        assert_eq!(limiter.load_percent(), 100);
+    }
+}
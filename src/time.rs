@@ -0,0 +1,78 @@
+//! `fugit` time types
+//!
+//! The TMC5072 counts time and velocity in clock cycles: conversions between register values and
+//! `fugit` rates and durations for a given clock frequency fCLK.
+//! Velocities are rates in microsteps per second.
+//!
+//! Requires the `fugit` feature.
+
+use fugit::{HertzU32, MicrosDurationU32};
+
+/// Typical frequency of the internal clock
+pub const F_CLK_INTERNAL: HertzU32 = HertzU32::from_raw(13_200_000);
+
+/// Largest VMAX value
+const V_MAX: u64 = (1 << 23) - 512;
+
+/// Velocity (microsteps/s) of a VMAX or VACTUAL value: v * fCLK / 2^24
+pub fn velocity_to_rate(v: u32, f_clk: HertzU32) -> HertzU32 {
+    HertzU32::from_raw(((v as u64 * f_clk.raw() as u64) >> 24) as u32)
+}
+
+/// VMAX value of a velocity (microsteps/s), saturated to the largest VMAX
+pub fn rate_to_velocity(rate: HertzU32, f_clk: HertzU32) -> u32 {
+    let v = ((rate.raw() as u64) << 24) / (f_clk.raw() as u64).max(1);
+    v.min(V_MAX) as u32
+}
+
+/// Clock cycles of a duration
+fn cycles(duration: MicrosDurationU32, f_clk: HertzU32) -> u64 {
+    duration.ticks() as u64 * f_clk.raw() as u64 / 1_000_000
+}
+
+/// TZEROWAIT value of a wait time (multiples of 512 clocks), saturated
+pub fn zero_wait(wait: MicrosDurationU32, f_clk: HertzU32) -> u16 {
+    (cycles(wait, f_clk) >> 9).min(u16::MAX as u64) as u16
+}
+
+/// IHOLDDELAY value of a delay per current reduction step (multiples of 2^18 clocks), saturated
+pub fn hold_delay(delay: MicrosDurationU32, f_clk: HertzU32) -> u8 {
+    (cycles(delay, f_clk) >> 18).min(15) as u8
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn velocity() {
+        // 13.2MHz / 2^24 * 1272 = 1000.8 microsteps/s
+        assert_eq!(velocity_to_rate(1272, F_CLK_INTERNAL).raw(), 1000);
+        assert_eq!(
+            rate_to_velocity(HertzU32::from_raw(1000), F_CLK_INTERNAL),
+            1271
+        );
+        assert_eq!(
+            rate_to_velocity(HertzU32::from_raw(u32::MAX), F_CLK_INTERNAL),
+            (1 << 23) - 512
+        );
+    }
+
+    #[test]
+    fn durations() {
+        // 512 / 13.2MHz = 38.8µs
+        assert_eq!(
+            zero_wait(MicrosDurationU32::from_ticks(1000), F_CLK_INTERNAL),
+            25
+        );
+        // 2^18 / 13.2MHz = 19.9ms
+        assert_eq!(
+            hold_delay(MicrosDurationU32::from_ticks(100_000), F_CLK_INTERNAL),
+            5
+        );
+        assert_eq!(
+            hold_delay(MicrosDurationU32::from_ticks(1_000_000), F_CLK_INTERNAL),
+            15
+        );
+    }
+}
@@ -0,0 +1,699 @@
+//! TMCL command server
+//!
+//! A minimal interpreter of Trinamic's TMCL binary commands, so a host tool (TMCL-IDE or a custom GUI) can drive the
+//! chip through a firmware using this crate. Commands and replies are 9 byte frames:
+//! - command: module address, instruction, type, motor, value (i32, big endian), checksum
+//! - reply: reply address, module address, status, instruction, value (i32, big endian), checksum
+//!
+//! The checksum is the 8 bit sum of the preceding bytes. Supported instructions:
+//! - ROR, ROL: rotate right (positive) or left at the velocity given as value
+//! - MST: stop
+//! - MVP: move to an absolute (type 0) or relative (type 1) position, at the maximum positioning speed (parameter 4)
+//! - SAP, GAP: set and get an axis parameter, see [`TmclServer::execute`]
+//! - RFS: reference search (type 0 start, 1 stop, 2 status) with the switch homing of [`TmclConfig::homing`]
+//!
+//! Axis parameters map to register fields ([`fields`](crate::fields)): write only registers are read back from the
+//! shadow configuration. Reference search progresses on RFS status requests, as TMCL hosts poll it.
+//!
+//! RAMP_STAT is clear on read: position reached (parameter 8) compares XACTUAL and XTARGET instead of reading it, and
+//! the events cleared by the end switch parameters (10 and 11) are kept for [`TmclServer::take_events`].
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::events::MotorEvents;
+use crate::fields::{Field, FieldError, FieldId};
+use crate::homing::{switch_home, HomingConfig, HomingStatus, SwitchHoming};
+use crate::registers::{
+    address::RegisterAddress,
+    encoder_registers::EncLatch,
+    motor_driver_register::MicrostepResolution,
+    ramp_generator_driver_feature_control_register::{RampStat, SwMode, XLatch},
+    ramp_generator_register::{RampMode, VActual, VMax, XActual, XTarget},
+    MotorIndexCheck, Register,
+};
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Command or reply frame
+pub type TmclFrame = [u8; 9];
+
+/// ROR: rotate right
+pub const ROR: u8 = 1;
+/// ROL: rotate left
+pub const ROL: u8 = 2;
+/// MST: motor stop
+pub const MST: u8 = 3;
+/// MVP: move to position
+pub const MVP: u8 = 4;
+/// SAP: set axis parameter
+pub const SAP: u8 = 5;
+/// GAP: get axis parameter
+pub const GAP: u8 = 6;
+/// RFS: reference search
+pub const RFS: u8 = 13;
+
+/// Reply status
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u8)]
+pub enum TmclStatus {
+    /// Command executed
+    Ok = 100,
+    /// Wrong checksum
+    WrongChecksum = 1,
+    /// Invalid instruction
+    InvalidCommand = 2,
+    /// Wrong type
+    WrongType = 3,
+    /// Invalid value, or motor
+    InvalidValue = 4,
+}
+
+/// TMCL command
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TmclCommand {
+    /// Module address
+    pub address: u8,
+    /// Instruction number
+    pub instruction: u8,
+    /// Type, or parameter number
+    pub kind: u8,
+    /// Motor or bank
+    pub motor: u8,
+    /// Value
+    pub value: i32,
+}
+
+impl TmclCommand {
+    /// Decodes a command frame, rejecting a wrong checksum
+    pub fn parse(frame: &TmclFrame) -> Result<Self, TmclStatus> {
+        if checksum(&frame[..8]) != frame[8] {
+            return Err(TmclStatus::WrongChecksum);
+        }
+        Ok(Self {
+            address: frame[0],
+            instruction: frame[1],
+            kind: frame[2],
+            motor: frame[3],
+            value: i32::from_be_bytes([frame[4], frame[5], frame[6], frame[7]]),
+        })
+    }
+
+    /// Command frame
+    pub fn frame(&self) -> TmclFrame {
+        let v = self.value.to_be_bytes();
+        with_checksum([
+            self.address,
+            self.instruction,
+            self.kind,
+            self.motor,
+            v[0],
+            v[1],
+            v[2],
+            v[3],
+        ])
+    }
+}
+
+/// TMCL reply
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TmclReply {
+    /// Host address
+    pub reply_address: u8,
+    /// Module address
+    pub module_address: u8,
+    /// Status
+    pub status: TmclStatus,
+    /// Instruction number of the command
+    pub instruction: u8,
+    /// Value
+    pub value: i32,
+}
+
+impl TmclReply {
+    /// Reply frame
+    pub fn frame(&self) -> TmclFrame {
+        let v = self.value.to_be_bytes();
+        with_checksum([
+            self.reply_address,
+            self.module_address,
+            self.status as u8,
+            self.instruction,
+            v[0],
+            v[1],
+            v[2],
+            v[3],
+        ])
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+fn with_checksum(bytes: [u8; 8]) -> TmclFrame {
+    let mut frame = [0; 9];
+    frame[..8].copy_from_slice(&bytes);
+    frame[8] = checksum(&bytes);
+    frame
+}
+
+/// Server settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TmclConfig {
+    /// Address of the module, commands to other addresses are ignored
+    pub module_address: u8,
+    /// Address of the host in the replies
+    pub reply_address: u8,
+    /// Maximum positioning speed (axis parameter 4) of MVP, per motor
+    pub v_max: [u32; 2],
+    /// Reference search of RFS, per motor
+    pub homing: [HomingConfig; 2],
+}
+
+/// Register field of axis parameter `number` of motor `M`: address, field, signed, settable
+fn parameter<const M: u8>(number: u8) -> Option<(RegisterAddress, FieldId, bool, bool)> {
+    use RegisterAddress as A;
+    let motor = |addrs: [RegisterAddress; 2]| addrs[M as usize];
+    Some(match number {
+        0 => (
+            motor([A::XTarget0, A::XTarget1]),
+            FieldId::XTarget,
+            true,
+            true,
+        ),
+        1 => (
+            motor([A::XActual0, A::XActual1]),
+            FieldId::XActual,
+            true,
+            true,
+        ),
+        2 => (motor([A::VMax0, A::VMax1]), FieldId::VMax, false, true),
+        3 => (
+            motor([A::VActual0, A::VActual1]),
+            FieldId::VActual,
+            true,
+            false,
+        ),
+        5 => (motor([A::AMax0, A::AMax1]), FieldId::AMax, false, true),
+        6 => (
+            motor([A::IHoldIRun0, A::IHoldIRun1]),
+            FieldId::IRun,
+            false,
+            true,
+        ),
+        7 => (
+            motor([A::IHoldIRun0, A::IHoldIRun1]),
+            FieldId::IHold,
+            false,
+            true,
+        ),
+        15 => (motor([A::A10, A::A11]), FieldId::A1, false, true),
+        16 => (motor([A::V10, A::V11]), FieldId::V1, false, true),
+        17 => (motor([A::DMax0, A::DMax1]), FieldId::DMax, false, true),
+        18 => (motor([A::D10, A::D11]), FieldId::D1, false, true),
+        19 => (
+            motor([A::VStart0, A::VStart1]),
+            FieldId::VStart,
+            false,
+            true,
+        ),
+        20 => (motor([A::VStop0, A::VStop1]), FieldId::VStop, false, true),
+        140 => (
+            motor([A::ChopConf0, A::ChopConf1]),
+            FieldId::Mres,
+            false,
+            true,
+        ),
+        _ => return None,
+    })
+}
+
+/// Maximum positioning speed
+const MAX_POSITIONING_SPEED: u8 = 4;
+/// Position reached
+const POSITION_REACHED: u8 = 8;
+/// Right end switch
+const RIGHT_SWITCH: u8 = 10;
+/// Left end switch
+const LEFT_SWITCH: u8 = 11;
+/// Microstep resolution, 0 (full step) to 8 (256 microsteps)
+const MICROSTEP_RESOLUTION: u8 = 140;
+
+/// TMCL interpreter driving both motors
+pub struct TmclServer {
+    config: TmclConfig,
+    homing0: Option<SwitchHoming<0>>,
+    homing1: Option<SwitchHoming<1>>,
+    /// RAMP_STAT events cleared by end switch reads
+    events: [MotorEvents; 2],
+}
+
+impl TmclServer {
+    /// Creates a server
+    pub fn new(config: TmclConfig) -> Self {
+        Self {
+            config,
+            homing0: None,
+            homing1: None,
+            events: [MotorEvents::NONE; 2],
+        }
+    }
+
+    /// Actual settings, the maximum positioning speeds included
+    pub fn config(&self) -> &TmclConfig {
+        &self.config
+    }
+
+    /// Returns and clears the RAMP_STAT events of motor `M` cleared on the chip by end switch reads
+    #[must_use = "the pending events are cleared"]
+    pub fn take_events<const M: u8>(&mut self) -> MotorEvents {
+        let () = MotorIndexCheck::<M>::OK;
+        core::mem::take(&mut self.events[M as usize])
+    }
+
+    /// Executes a command frame, returns the reply frame or `None` if the command is for another module
+    pub fn handle<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        frame: &TmclFrame,
+    ) -> Result<Option<TmclFrame>, SpiError<SPI::Error, CS::Error>> {
+        if frame[0] != self.config.module_address {
+            return Ok(None);
+        }
+        let reply = match TmclCommand::parse(frame) {
+            Ok(command) => self.execute(tmc5072, spi, command)?,
+            Err(status) => self.reply(frame[1], status, 0),
+        };
+        Ok(Some(reply.frame()))
+    }
+
+    /// Executes a command
+    ///
+    /// Axis parameters: 0 target position (XTARGET), 1 actual position (XACTUAL), 2 target speed (VMAX), 3 actual
+    /// speed (VACTUAL, read only), 4 maximum positioning speed, 5 maximum acceleration (AMAX), 6 run current (IRUN),
+    /// 7 standby current (IHOLD), 8 position reached, 10 right and 11 left end switch (read only), 15 A1, 16 V1,
+    /// 17 DMAX, 18 D1, 19 VSTART, 20 VSTOP, 140 microstep resolution (0 full step to 8 for 256 microsteps).
+    pub fn execute<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        command: TmclCommand,
+    ) -> Result<TmclReply, SpiError<SPI::Error, CS::Error>> {
+        let homing = self.config.homing;
+        let result = match (command.instruction, command.motor) {
+            (RFS, 0) => reference_search(&mut self.homing0, homing[0], tmc5072, spi, command.kind),
+            (RFS, 1) => reference_search(&mut self.homing1, homing[1], tmc5072, spi, command.kind),
+            (_, 0) => self.execute_for::<0, _, _>(tmc5072, spi, command),
+            (_, 1) => self.execute_for::<1, _, _>(tmc5072, spi, command),
+            _ => Ok(Err(TmclStatus::InvalidValue)),
+        };
+        Ok(match result? {
+            Ok(value) => self.reply(command.instruction, TmclStatus::Ok, value),
+            Err(status) => self.reply(command.instruction, status, command.value),
+        })
+    }
+
+    fn reply(&self, instruction: u8, status: TmclStatus, value: i32) -> TmclReply {
+        TmclReply {
+            reply_address: self.config.reply_address,
+            module_address: self.config.module_address,
+            status,
+            instruction,
+            value,
+        }
+    }
+
+    fn execute_for<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        command: TmclCommand,
+    ) -> Result<Result<i32, TmclStatus>, SpiError<SPI::Error, CS::Error>>
+    where
+        RampMode<M>: Register,
+        VMax<M>: Register,
+        XActual<M>: Register,
+        XTarget<M>: Register,
+        RampStat<M>: Register,
+    {
+        let motor = M as usize;
+        let value = command.value;
+        match command.instruction {
+            ROR => tmc5072.rotate::<M, _>(value, spi)?,
+            ROL => tmc5072.rotate::<M, _>(value.wrapping_neg(), spi)?,
+            MST => tmc5072.stop::<M, _>(spi)?,
+            MVP => {
+                let v_max = self.config.v_max[motor];
+                match command.kind {
+                    0 => tmc5072.move_to::<M, _>(value, v_max, spi)?,
+                    1 => return Ok(Ok(tmc5072.move_by::<M, _>(value, v_max, spi)?)),
+                    _ => return Ok(Err(TmclStatus::WrongType)),
+                }
+            }
+            SAP => return self.set_parameter::<M, _, _>(tmc5072, spi, command.kind, value),
+            GAP => return self.get_parameter::<M, _, _>(tmc5072, spi, command.kind),
+            _ => return Ok(Err(TmclStatus::InvalidCommand)),
+        }
+        Ok(Ok(value))
+    }
+
+    fn get_parameter<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        number: u8,
+    ) -> Result<Result<i32, TmclStatus>, SpiError<SPI::Error, CS::Error>>
+    where
+        XActual<M>: Register,
+        XTarget<M>: Register,
+        RampStat<M>: Register,
+    {
+        match number {
+            MAX_POSITIONING_SPEED => return Ok(Ok(self.config.v_max[M as usize] as i32)),
+            POSITION_REACHED => {
+                let [x_actual, x_target] = tmc5072
+                    .read_many(&[XActual::<M>::addr(), XTarget::<M>::addr()], spi)?
+                    .data;
+                return Ok(Ok((x_actual == x_target) as i32));
+            }
+            RIGHT_SWITCH | LEFT_SWITCH => {
+                let ramp_stat = tmc5072.read_register::<RampStat<M>, _>(spi)?.data;
+                self.events[M as usize] |= MotorEvents::from(ramp_stat);
+                let active = if number == RIGHT_SWITCH {
+                    ramp_stat.status_stop_r
+                } else {
+                    ramp_stat.status_stop_l
+                };
+                return Ok(Ok(active as i32));
+            }
+            _ => {}
+        }
+        let Some((addr, id, signed, _)) = parameter::<M>(number) else {
+            return Ok(Err(TmclStatus::WrongType));
+        };
+        let Some(field) = Field::of(addr, id) else {
+            return Ok(Err(TmclStatus::WrongType));
+        };
+        let value = match tmc5072.read_field(addr, id, spi) {
+            Ok(value) => value,
+            Err(e) => return field_error(e),
+        };
+        let value = if number == MICROSTEP_RESOLUTION {
+            MicrostepResolution::Fullstep.mres() as i32 - value as i32
+        } else if signed {
+            sign_extend(value, field.mask.count_ones())
+        } else {
+            value as i32
+        };
+        Ok(Ok(value))
+    }
+
+    fn set_parameter<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        number: u8,
+        value: i32,
+    ) -> Result<Result<i32, TmclStatus>, SpiError<SPI::Error, CS::Error>> {
+        if number == MAX_POSITIONING_SPEED {
+            if !(0..=VMax::<0>::MASK as i32).contains(&value) {
+                return Ok(Err(TmclStatus::InvalidValue));
+            }
+            self.config.v_max[M as usize] = value as u32;
+            return Ok(Ok(value));
+        }
+        let Some((addr, id, signed, settable)) = parameter::<M>(number) else {
+            return Ok(Err(TmclStatus::WrongType));
+        };
+        if !settable {
+            return Ok(Err(TmclStatus::WrongType));
+        }
+        let Some(field) = Field::of(addr, id) else {
+            return Ok(Err(TmclStatus::WrongType));
+        };
+        let raw = if number == MICROSTEP_RESOLUTION {
+            let fullstep = MicrostepResolution::Fullstep.mres() as i32;
+            if !(0..=fullstep).contains(&value) {
+                return Ok(Err(TmclStatus::InvalidValue));
+            }
+            (fullstep - value) as u32
+        } else {
+            let bits = field.mask.count_ones();
+            let (min, max) = if signed {
+                (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1)
+            } else {
+                (0, (1i64 << bits) - 1)
+            };
+            if !(min..=max).contains(&(value as i64)) {
+                return Ok(Err(TmclStatus::InvalidValue));
+            }
+            value as u32
+        };
+        match tmc5072.write_field(addr, id, raw, spi) {
+            Ok(()) => Ok(Ok(value)),
+            Err(e) => field_error(e),
+        }
+    }
+}
+
+/// RFS: start (0), stop (1) or status (2), the status being 0 once the search is over
+fn reference_search<const M: u8, CS: OutputPin, SPI: Transfer<u8>>(
+    homing: &mut Option<SwitchHoming<M>>,
+    config: HomingConfig,
+    tmc5072: &mut Tmc5072<CS>,
+    spi: &mut SPI,
+    kind: u8,
+) -> Result<Result<i32, TmclStatus>, SpiError<SPI::Error, CS::Error>>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XActual<M>: Register,
+    XTarget<M>: Register,
+    XLatch<M>: Register,
+    EncLatch<M>: Register,
+    SwMode<M>: Register,
+    RampStat<M>: Register,
+    VActual<M>: Register,
+{
+    let mut motor = tmc5072.motor::<M, _>(spi);
+    match kind {
+        0 => *homing = Some(switch_home(&mut motor, config)?),
+        1 => {
+            if homing.take().is_some() {
+                let (tmc5072, spi) = motor.parts();
+                tmc5072.stop::<M, _>(spi)?;
+            }
+        }
+        2 => {
+            let active = match homing {
+                Some(search) => search.poll(&mut motor)? == HomingStatus::Homing,
+                None => false,
+            };
+            return Ok(Ok(active as i32));
+        }
+        _ => return Ok(Err(TmclStatus::WrongType)),
+    }
+    Ok(Ok(0))
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let unused = 32 - bits.clamp(1, 32);
+    ((value << unused) as i32) >> unused
+}
+
+fn field_error<SPI, CS>(
+    e: FieldError<SPI, CS>,
+) -> Result<Result<i32, TmclStatus>, SpiError<SPI, CS>> {
+    match e {
+        FieldError::SpiError(e) => Err(e),
+        FieldError::NoField(..) | FieldError::ReadOnly(..) | FieldError::NoShadow(..) => {
+            Ok(Err(TmclStatus::WrongType))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::homing::ReferenceSwitch;
+    use crate::registers::motor_driver_register::ChopConf;
+    use crate::registers::ramp_generator_register::RampModeValue;
+    use crate::sim::{CsPin, Simulator};
+
+    fn server() -> TmclServer {
+        let homing = HomingConfig {
+            switch: ReferenceSwitch::Left,
+            active_high: true,
+            soft_stop: false,
+            velocity: 10_000,
+            back_off: 100,
+            max_travel: 100_000,
+            encoder: false,
+            repeat: 0,
+        };
+        TmclServer::new(TmclConfig {
+            module_address: 1,
+            reply_address: 2,
+            v_max: [50_000; 2],
+            homing: [homing; 2],
+        })
+    }
+
+    fn command(instruction: u8, kind: u8, motor: u8, value: i32) -> TmclFrame {
+        TmclCommand {
+            address: 1,
+            instruction,
+            kind,
+            motor,
+            value,
+        }
+        .frame()
+    }
+
+    fn reply(frame: Option<TmclFrame>) -> (TmclStatus, i32) {
+        let frame = frame.unwrap();
+        assert_eq!(frame[8], checksum(&frame[..8]));
+        let command = TmclCommand::parse(&frame).unwrap();
+        let status = match frame[2] {
+            100 => TmclStatus::Ok,
+            1 => TmclStatus::WrongChecksum,
+            2 => TmclStatus::InvalidCommand,
+            3 => TmclStatus::WrongType,
+            _ => TmclStatus::InvalidValue,
+        };
+        (status, command.value)
+    }
+
+    #[test]
+    fn frames() {
+        // TMCL-IDE: MVP ABS, 0, 1000
+        let frame = command(MVP, 0, 0, 1000);
+        assert_eq!(frame, [1, 4, 0, 0, 0, 0, 0x03, 0xE8, 0xF0]);
+        let mut corrupted = frame;
+        corrupted[7] ^= 1;
+        assert_eq!(
+            TmclCommand::parse(&corrupted),
+            Err(TmclStatus::WrongChecksum)
+        );
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut server = server();
+        assert_eq!(
+            reply(server.handle(&mut tmc5072, &mut sim, &corrupted).unwrap()),
+            (TmclStatus::WrongChecksum, 0)
+        );
+        let other = TmclCommand {
+            address: 3,
+            instruction: MST,
+            kind: 0,
+            motor: 0,
+            value: 0,
+        };
+        assert_eq!(
+            server.handle(&mut tmc5072, &mut sim, &other.frame()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn motion() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut server = server();
+        let mut run = |frame| reply(server.handle(&mut tmc5072, &mut sim, &frame).unwrap());
+        assert_eq!(run(command(ROL, 0, 1, 2000)), (TmclStatus::Ok, 2000));
+        assert_eq!(run(command(MVP, 0, 0, 1000)), (TmclStatus::Ok, 1000));
+        assert_eq!(run(command(MVP, 2, 0, 1000)), (TmclStatus::WrongType, 1000));
+        assert_eq!(run(command(MST, 0, 2, 0)), (TmclStatus::InvalidValue, 0));
+        assert_eq!(run(command(9, 0, 0, 0)), (TmclStatus::InvalidCommand, 0));
+        assert_eq!(
+            RampMode::<1>::from(sim.get(RampMode::<1>::addr())).ramp_mode,
+            RampModeValue::VelocityNeg
+        );
+        assert_eq!(sim.get(VMax::<1>::addr()), 2000);
+        assert_eq!(sim.get(XTarget::<0>::addr()), 1000);
+        assert_eq!(sim.get(VMax::<0>::addr()), 50_000);
+    }
+
+    #[test]
+    fn parameters() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut server = server();
+        let mut run = |frame| reply(server.handle(&mut tmc5072, &mut sim, &frame).unwrap());
+        assert_eq!(run(command(SAP, 6, 0, 20)), (TmclStatus::Ok, 20));
+        assert_eq!(run(command(GAP, 6, 0, 0)), (TmclStatus::Ok, 20));
+        assert_eq!(run(command(SAP, 6, 0, 32)), (TmclStatus::InvalidValue, 32));
+        assert_eq!(run(command(SAP, 1, 1, -5)), (TmclStatus::Ok, -5));
+        assert_eq!(run(command(GAP, 1, 1, 0)), (TmclStatus::Ok, -5));
+        assert_eq!(run(command(SAP, 3, 0, 0)), (TmclStatus::WrongType, 0));
+        assert_eq!(run(command(SAP, 140, 0, 4)), (TmclStatus::Ok, 4));
+        assert_eq!(run(command(GAP, 140, 0, 0)), (TmclStatus::Ok, 4));
+        assert_eq!(run(command(SAP, 4, 0, 1000)), (TmclStatus::Ok, 1000));
+        assert_eq!(run(command(GAP, 4, 0, 0)), (TmclStatus::Ok, 1000));
+        assert_eq!(run(command(GAP, 99, 0, 0)), (TmclStatus::WrongType, 0));
+        assert_eq!(
+            ChopConf::<0>::from(sim.get(ChopConf::<0>::addr())).mres,
+            MicrostepResolution::M16
+        );
+        sim.set(VActual::<0>::addr(), 0x00FF_FFFF);
+        let mut run = |frame| reply(server.handle(&mut tmc5072, &mut sim, &frame).unwrap());
+        assert_eq!(run(command(GAP, 3, 0, 0)), (TmclStatus::Ok, -1));
+    }
+
+    #[test]
+    fn ramp_stat_events() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut server = server();
+        let ramp_stat = RampStat::<1> {
+            status_stop_l: true,
+            event_stop_sg: true,
+            event_pos_reached: true,
+            ..Default::default()
+        };
+        sim.set(RampStat::<1>::addr(), u32::from(ramp_stat));
+        sim.set(XTarget::<1>::addr(), 100);
+        let mut run = |frame| reply(server.handle(&mut tmc5072, &mut sim, &frame).unwrap());
+        assert_eq!(run(command(GAP, 8, 1, 0)), (TmclStatus::Ok, 0));
+        assert_eq!(run(command(SAP, 8, 1, 1)), (TmclStatus::WrongType, 1));
+        // polling position reached leaves the events pending
+        assert_eq!(sim.get(RampStat::<1>::addr()), u32::from(ramp_stat));
+        sim.set(XActual::<1>::addr(), 100);
+        let mut run = |frame| reply(server.handle(&mut tmc5072, &mut sim, &frame).unwrap());
+        assert_eq!(run(command(GAP, 8, 1, 0)), (TmclStatus::Ok, 1));
+        assert_eq!(run(command(GAP, 11, 1, 0)), (TmclStatus::Ok, 1));
+        assert_eq!(run(command(GAP, 10, 1, 0)), (TmclStatus::Ok, 0));
+        assert_eq!(
+            server.take_events::<1>(),
+            MotorEvents {
+                stall: true,
+                position_reached: true,
+                ..Default::default()
+            }
+        );
+        assert!(server.take_events::<1>().is_empty());
+        assert!(server.take_events::<0>().is_empty());
+    }
+
+    #[test]
+    fn reference_search() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut server = server();
+        let mut run = |frame| reply(server.handle(&mut tmc5072, &mut sim, &frame).unwrap());
+        assert_eq!(run(command(RFS, 2, 0, 0)), (TmclStatus::Ok, 0));
+        assert_eq!(run(command(RFS, 0, 0, 0)), (TmclStatus::Ok, 0));
+        assert_eq!(run(command(RFS, 2, 0, 0)), (TmclStatus::Ok, 1));
+        assert_eq!(run(command(RFS, 1, 0, 0)), (TmclStatus::Ok, 0));
+        assert_eq!(run(command(RFS, 2, 0, 0)), (TmclStatus::Ok, 0));
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+    }
+}
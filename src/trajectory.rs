@@ -0,0 +1,330 @@
+//! Trajectory following
+//!
+//! A [`Trajectory`] follows a stream of timestamped target positions of motor `M` on top of the hardware ramp:
+//! the datasheet allows rewriting XTARGET (and VMAX) at any time in positioning mode, the ramp generator then
+//! smoothly heads for the new target. [`Trajectory::poll`], called from the main loop or a timer, updates both at the
+//! control period of the [`TrajectoryConfig`]:
+//! - XTARGET is the next point of the stream
+//! - VMAX is the velocity reaching it at its time from the position interpolated at `now`
+//!
+//! The first point is the start of the trajectory: the motor is expected to stand there at its time. Times are in the
+//! unit of the `now` argument of [`Trajectory::poll`], wrapping around like `now`. The stream holds up to `N` points,
+//! more can be pushed while it runs.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::{
+    ramp_generator_register::{RampMode, VMax, XTarget},
+    Register,
+};
+use crate::spi::SpiError;
+use crate::units::{F_CLK_INTERNAL, V_MAX};
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Target position at a point in time
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrajectoryPoint {
+    /// Time, in the unit of `now`
+    pub time: u32,
+    /// Target position (microsteps)
+    pub position: i32,
+}
+
+/// Control rate and limits of a [`Trajectory`]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrajectoryConfig {
+    /// Time between two updates of XTARGET and VMAX, in the unit of `now`
+    pub period: u32,
+    /// Units of `now` per second
+    pub ticks_per_sec: u32,
+    /// Clock frequency fCLK (Hz)
+    pub f_clk: u32,
+    /// Largest VMAX written
+    pub v_limit: u32,
+}
+
+impl Default for TrajectoryConfig {
+    /// 1 kHz control rate with a millisecond clock, internal clock, no velocity limit
+    fn default() -> Self {
+        Self {
+            period: 1,
+            ticks_per_sec: 1000,
+            f_clk: F_CLK_INTERNAL,
+            v_limit: V_MAX,
+        }
+    }
+}
+
+impl TrajectoryConfig {
+    /// VMAX covering `distance` microsteps in `duration` units of `now`, saturated to `v_limit`
+    pub fn velocity(&self, distance: u32, duration: u32) -> u32 {
+        if duration == 0 {
+            return self.v_limit.min(V_MAX);
+        }
+        // v = steps/s * 2^24 / fCLK
+        let v = (distance as u128 * self.ticks_per_sec as u128) << 24;
+        let v = v / (duration as u128 * self.f_clk as u128);
+        v.min(self.v_limit.min(V_MAX) as u128) as u32
+    }
+}
+
+/// Trajectory state after a poll
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TrajectoryStatus {
+    /// No point is queued
+    Idle,
+    /// The first point is in the future
+    Waiting,
+    /// The motor heads for `x_target` at up to `v_max`
+    Following {
+        /// Written XTARGET
+        x_target: i32,
+        /// Written VMAX
+        v_max: u32,
+    },
+}
+
+/// Poll driven follower of a stream of up to `N` points of motor `M`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Trajectory<const M: u8, const N: usize> {
+    config: TrajectoryConfig,
+    points: [TrajectoryPoint; N],
+    head: usize,
+    len: usize,
+    /// Time of the last update
+    updated: Option<u32>,
+    /// Last written XTARGET and VMAX, `None` before the positioning mode is set
+    written: Option<(i32, u32)>,
+}
+
+impl<const M: u8, const N: usize> Trajectory<M, N>
+where
+    RampMode<M>: Register,
+    VMax<M>: Register,
+    XTarget<M>: Register,
+{
+    /// Creates an empty trajectory
+    pub fn new(config: TrajectoryConfig) -> Self {
+        Self {
+            config,
+            points: [TrajectoryPoint::default(); N],
+            head: 0,
+            len: 0,
+            updated: None,
+            written: None,
+        }
+    }
+
+    /// Control rate and limits
+    pub fn config(&self) -> &TrajectoryConfig {
+        &self.config
+    }
+
+    /// Queues a point, returns it back if the queue is full or if the point is not later than the last one
+    pub fn push(&mut self, point: TrajectoryPoint) -> Result<(), TrajectoryPoint> {
+        if self.len == N {
+            return Err(point);
+        }
+        if let Some(last) = self.last() {
+            if point.time.wrapping_sub(last.time) as i32 <= 0 {
+                return Err(point);
+            }
+        }
+        self.points[(self.head + self.len) % N] = point;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Number of queued points, the current segment included
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no point is queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops the queued points, the motor keeps heading for the last written target
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.updated = None;
+        self.written = None;
+    }
+
+    /// Updates XTARGET and VMAX if a control period elapsed since the last update
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+        now: u32,
+    ) -> Result<TrajectoryStatus, SpiError<SPI::Error, CS::Error>> {
+        if let (Some(updated), Some((x_target, v_max))) = (self.updated, self.written) {
+            if now.wrapping_sub(updated) < self.config.period {
+                return Ok(TrajectoryStatus::Following { x_target, v_max });
+            }
+        }
+        // drop the segments over
+        while self.len >= 2 && Self::reached(self.point(1), now) {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        }
+        let Some(from) = (self.len > 0).then(|| self.point(0)) else {
+            return Ok(TrajectoryStatus::Idle);
+        };
+        if !Self::reached(from, now) {
+            return Ok(TrajectoryStatus::Waiting);
+        }
+        let (x_target, v_max) = if self.len == 1 {
+            // last point: stay there
+            self.len = 0;
+            let v_max = self.written.map_or(0, |(_, v_max)| v_max);
+            (from.position, v_max)
+        } else {
+            let to = self.point(1);
+            let elapsed = now.wrapping_sub(from.time) as i64;
+            let duration = to.time.wrapping_sub(from.time) as i64;
+            // positions wrap around like XACTUAL
+            let distance = to.position.wrapping_sub(from.position) as i64;
+            let left = distance - distance * elapsed / duration;
+            let v_max = self
+                .config
+                .velocity(left.unsigned_abs() as u32, to.time.wrapping_sub(now));
+            (to.position, v_max)
+        };
+        self.write(x_target, v_max, tmc5072, spi)?;
+        self.updated = Some(now);
+        Ok(if self.len == 0 {
+            self.updated = None;
+            self.written = None;
+            TrajectoryStatus::Idle
+        } else {
+            TrajectoryStatus::Following { x_target, v_max }
+        })
+    }
+
+    fn write<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        x_target: i32,
+        v_max: u32,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        match self.written {
+            None => tmc5072.move_to::<M, _>(x_target, v_max, spi)?,
+            Some((written_target, written_v_max)) => {
+                // VMAX first, so the new target is not approached at the previous velocity
+                if v_max != written_v_max {
+                    tmc5072.write_register(VMax::<M> { v_max }, spi)?;
+                }
+                if x_target != written_target {
+                    tmc5072.write_register(XTarget::<M> { x_target }, spi)?;
+                }
+            }
+        }
+        self.written = Some((x_target, v_max));
+        Ok(())
+    }
+
+    fn point(&self, i: usize) -> TrajectoryPoint {
+        self.points[(self.head + i) % N]
+    }
+
+    fn last(&self) -> Option<TrajectoryPoint> {
+        (self.len > 0).then(|| self.point(self.len - 1))
+    }
+
+    fn reached(point: TrajectoryPoint, now: u32) -> bool {
+        now.wrapping_sub(point.time) as i32 >= 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn velocity() {
+        let config = TrajectoryConfig {
+            f_clk: 1 << 24,
+            ..Default::default()
+        };
+        // 1000 µsteps in 100 ms
+        assert_eq!(config.velocity(1000, 100), 10_000);
+        assert_eq!(config.velocity(1000, 0), V_MAX);
+        let limited = TrajectoryConfig {
+            v_limit: 5000,
+            ..config
+        };
+        assert_eq!(limited.velocity(1000, 100), 5000);
+    }
+
+    #[test]
+    fn follow() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut trajectory = Trajectory::<0, 3>::new(TrajectoryConfig {
+            period: 10,
+            f_clk: 1 << 24,
+            ..Default::default()
+        });
+        let start = TrajectoryPoint {
+            time: 100,
+            position: 0,
+        };
+        trajectory.push(start).unwrap();
+        trajectory
+            .push(TrajectoryPoint {
+                time: 200,
+                position: 1000,
+            })
+            .unwrap();
+        assert!(trajectory.push(start).is_err());
+        assert_eq!(
+            trajectory.poll(&mut tmc5072, &mut sim, 0),
+            Ok(TrajectoryStatus::Waiting)
+        );
+        assert_eq!(
+            trajectory.poll(&mut tmc5072, &mut sim, 100),
+            Ok(TrajectoryStatus::Following {
+                x_target: 1000,
+                v_max: 10_000
+            })
+        );
+        assert_eq!(sim.get(RampMode::<0>::addr()), 0);
+        assert_eq!(sim.get(XTarget::<0>::addr()), 1000);
+        // within the control period, nothing is written
+        sim.set(VMax::<0>::addr(), 0);
+        trajectory.poll(&mut tmc5072, &mut sim, 105).unwrap();
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+        // on time: same velocity, no write
+        trajectory.poll(&mut tmc5072, &mut sim, 150).unwrap();
+        assert_eq!(sim.get(VMax::<0>::addr()), 0);
+        trajectory
+            .push(TrajectoryPoint {
+                time: 300,
+                position: 500,
+            })
+            .unwrap();
+        assert_eq!(
+            trajectory.poll(&mut tmc5072, &mut sim, 250),
+            Ok(TrajectoryStatus::Following {
+                x_target: 500,
+                v_max: 5000
+            })
+        );
+        assert_eq!(sim.get(VMax::<0>::addr()), 5000);
+        assert_eq!(sim.get(XTarget::<0>::addr()), 500);
+        assert_eq!(
+            trajectory.poll(&mut tmc5072, &mut sim, 300),
+            Ok(TrajectoryStatus::Idle)
+        );
+        assert!(trajectory.is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! Position triggered pulse trains
+//!
+//! With GCONF poscmp_enable set, the PP output goes high while XACTUAL of motor 0 (motor 1 of the datasheet)
+//! equals X_COMPARE. [`TriggerTrain`] steps X_COMPARE through a list of positions, one pulse after the other,
+//! to fire a camera or laser at programmed positions with hardware timing.
+//!
+//! [`TriggerTrain::on_pulse`] is meant to be called from the PP (or INT) pin interrupt handler:
+//! it writes the next position only, a single register write.
+//! The next position must be written before the motor reaches it: keep consecutive positions far enough apart
+//! for the handler latency at the move velocity.
+//!
+//! poscmp_enable makes encoder 1 unavailable.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::general_configuration_register::XCompare;
+use crate::spi::SpiError;
+use crate::Tmc5072;
+
+/// Pulses at a list of positions of motor 0
+pub struct TriggerTrain<'a> {
+    positions: &'a [i32],
+    next: usize,
+}
+
+impl<'a> TriggerTrain<'a> {
+    /// Creates a train firing at `positions`, in order
+    pub fn new(positions: &'a [i32]) -> Self {
+        Self { positions, next: 0 }
+    }
+
+    /// Number of pulses fired
+    pub fn fired(&self) -> usize {
+        self.next.min(self.positions.len())
+    }
+
+    /// Whether all pulses were fired
+    pub fn is_done(&self) -> bool {
+        self.next >= self.positions.len()
+    }
+
+    /// Position of the next pulse
+    pub fn next_position(&self) -> Option<i32> {
+        self.positions.get(self.next).copied()
+    }
+
+    /// Writes the first position to X_COMPARE, restarting the train
+    pub fn arm<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        self.next = 0;
+        self.write_next(tmc5072, spi)
+    }
+
+    /// Steps X_COMPARE to the next position after a pulse, returns whether the train is done
+    pub fn on_pulse<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<bool, SpiError<SPI::Error, CS::Error>> {
+        if !self.is_done() {
+            self.next += 1;
+            self.write_next(tmc5072, spi)?;
+        }
+        Ok(self.is_done())
+    }
+
+    fn write_next<CS: OutputPin, SPI: Transfer<u8>>(
+        &self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<(), SpiError<SPI::Error, CS::Error>> {
+        if let Some(x) = self.next_position() {
+            tmc5072.write_register(
+                XCompare {
+                    x_compare: x as u32,
+                },
+                spi,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::Register;
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn train() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let positions = [100, 250, -40];
+        let mut train = TriggerTrain::new(&positions);
+        train.arm(&mut tmc5072, &mut sim).unwrap();
+        assert_eq!(sim.get(XCompare::addr()), 100);
+        assert!(!train.on_pulse(&mut tmc5072, &mut sim).unwrap());
+        assert_eq!(sim.get(XCompare::addr()), 250);
+        assert!(!train.on_pulse(&mut tmc5072, &mut sim).unwrap());
+        assert_eq!(sim.get(XCompare::addr()) as i32, -40);
+        assert_eq!(train.fired(), 2);
+        assert!(train.on_pulse(&mut tmc5072, &mut sim).unwrap());
+        assert!(train.on_pulse(&mut tmc5072, &mut sim).unwrap());
+        assert_eq!(train.fired(), 3);
+        assert_eq!(train.next_position(), None);
+        assert_eq!(sim.get(XCompare::addr()) as i32, -40);
+    }
+}
@@ -0,0 +1,412 @@
+//! UART (single wire) interface
+//!
+//! With SWSEL high the TMC5072 is accessed through a single wire UART instead of SPI.
+//! Datagrams start with a sync nibble and the slave address, and end with a CRC8:
+//! - write access: sync, slave address, register address | 0x80, 4 data bytes, CRC (8 bytes)
+//! - read request: sync, slave address, register address, CRC (4 bytes)
+//! - read reply: sync, 0xFF (master address), register address, 4 data bytes, CRC (8 bytes)
+//!
+//! UART replies carry no status, [`Tmc5072Uart`] returns plain register values.
+//! Successful writes increment IFCNT, which can be read back to detect lost datagrams.
+//!
+//! Several chips can share one UART, each one only answering datagrams with its address: SLAVEADDR (SLAVECONF),
+//! plus 1 while its NEXTADDR input is active. All chips answer the same address after reset, so give them distinct
+//! addresses before sharing the bus: two chips on one bus start at 0 and 1 when NEXTADDR is wired active on one of
+//! them, [`Tmc5072Uart::configure_slave`] then moves each one to its final address (SLAVEADDR values two apart
+//! stay distinct whatever the NEXTADDR inputs). With several slaves, SENDDELAY must be at least
+//! [`MIN_MULTI_SLAVE_SEND_DELAY`], so that the master has switched its line back to receive.
+//!
+//! In a ring topology (TX of each chip wired to RX of the next one, the last one to the master) chips forward the
+//! datagrams of other addresses: with [`UartConfig::ring`], the datagrams coming back to the master are checked
+//! against the sent ones, a difference meaning a broken ring. SLAVEADDR 0 disables forwarding in ring mode.
+
+use embedded_hal::serial::{Read, Write};
+
+use crate::registers::{
+    general_configuration_register::{IfCnt, SlaveConf},
+    ReadableRegister, Register, WritableRegister, READ_FLAG, WRITE_FLAG,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Sync nibble and reserved bits starting every datagram
+pub const SYNC: u8 = 0x05;
+/// Address of the master in read replies
+pub const MASTER_ADDR: u8 = 0xFF;
+/// Highest SLAVEADDR, NEXTADDR adds 1 to it
+pub const MAX_SLAVE_ADDR: u8 = 253;
+/// Lowest SENDDELAY with several slaves on one bus (3*8 bit times)
+pub const MIN_MULTI_SLAVE_SEND_DELAY: u8 = 2;
+
+/// CRC8 of a datagram (polynomial x^8 + x^2 + x + 1, bytes sent LSB first)
+pub fn crc8(datagram: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in datagram {
+        let mut byte = byte;
+        for _ in 0..8 {
+            if (crc >> 7) ^ (byte & 0x01) != 0 {
+                crc = (crc << 1) ^ 0x07;
+            } else {
+                crc <<= 1;
+            }
+            byte >>= 1;
+        }
+    }
+    crc
+}
+
+/// Errors that can occur while using the UART interface
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UartError<E> {
+    /// Serial communication error
+    SerialError(E),
+    /// No reply within the configured number of polls
+    TimeoutError,
+    /// The reply CRC does not match its content
+    CrcError,
+    /// The reply does not start with the sync nibble and the master address
+    FrameError,
+    /// The reply holds another register (its address)
+    AddressError(u8),
+    /// A datagram forwarded along the ring differs from the sent one
+    ForwardError,
+    /// SLAVEADDR or SENDDELAY out of range, nothing was written
+    InvalidSlaveConf,
+}
+
+/// UART interface settings
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UartConfig {
+    /// SLAVEADDR of the chip (0 after reset, 1 with NEXTADDR active)
+    pub slave_addr: u8,
+    /// TX and RX are tied together: every sent byte is received back and must be skipped
+    pub echo: bool,
+    /// Ring topology: every sent datagram comes back through the ring before the reply, and is checked
+    pub ring: bool,
+    /// Reads give up after this many polls without data, `None` to wait forever
+    pub max_polls: Option<u32>,
+}
+
+/// TMC5072 driver over UART
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Tmc5072Uart {
+    config: UartConfig,
+}
+
+impl Tmc5072Uart {
+    /// Creates a new Tmc5072 UART driver, the serial interface is passed to every access
+    pub fn new(config: UartConfig) -> Self {
+        Self { config }
+    }
+    /// Creates a new Tmc5072 UART driver of the chip at `slave_addr`, with default settings
+    pub fn with_address(slave_addr: u8) -> Self {
+        Self::new(UartConfig {
+            slave_addr,
+            ..Default::default()
+        })
+    }
+    /// Interface settings
+    pub fn config(&self) -> &UartConfig {
+        &self.config
+    }
+    /// Writes SLAVECONF, then addresses the chip at its new address
+    ///
+    /// `next_addr` is the state of the NEXTADDR input of the chip, which adds 1 to SLAVEADDR.
+    pub fn configure_slave<UART, E>(
+        &mut self,
+        slave_conf: SlaveConf,
+        next_addr: bool,
+        uart: &mut UART,
+    ) -> Result<(), UartError<E>>
+    where
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        if slave_conf.slave_addr > MAX_SLAVE_ADDR || slave_conf.send_delay > 15 {
+            return Err(UartError::InvalidSlaveConf);
+        }
+        self.write_register(slave_conf, uart)?;
+        self.config.slave_addr = slave_conf.slave_addr + next_addr as u8;
+        Ok(())
+    }
+    /// Read a typed register from the Tmc5072
+    pub fn read_register<R, UART, E>(&mut self, uart: &mut UART) -> Result<R, UartError<E>>
+    where
+        R: Register + ReadableRegister,
+        u32: From<R>,
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        self.read_raw(R::addr(), uart).map(R::from)
+    }
+    /// Write a typed register from the Tmc5072
+    pub fn write_register<R, UART, E>(&mut self, r: R, uart: &mut UART) -> Result<(), UartError<E>>
+    where
+        R: Register + WritableRegister,
+        u32: From<R>,
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        self.write_raw(R::addr(), u32::from(r), uart)
+    }
+    /// Reads IFCNT, the number of successful write accesses (wrapping at 256)
+    pub fn if_cnt<UART, E>(&mut self, uart: &mut UART) -> Result<u8, UartError<E>>
+    where
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        Ok(self.read_register::<IfCnt, _, _>(uart)?.if_cnt)
+    }
+    /// Read a raw register from the Tmc5072
+    pub fn read_raw<UART, E>(&mut self, addr: u8, uart: &mut UART) -> Result<u32, UartError<E>>
+    where
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        let mut request = [SYNC, self.config.slave_addr, READ_FLAG | addr, 0];
+        request[3] = crc8(&request[..3]);
+        self.send(&request, uart)?;
+        let mut reply = [0; 8];
+        for byte in reply.iter_mut() {
+            *byte = self.receive(uart)?;
+        }
+        if reply[7] != crc8(&reply[..7]) {
+            return Err(UartError::CrcError);
+        }
+        if reply[0] & 0x0F != SYNC || reply[1] != MASTER_ADDR {
+            return Err(UartError::FrameError);
+        }
+        if reply[2] != addr {
+            return Err(UartError::AddressError(reply[2]));
+        }
+        Ok(u32::from_be_bytes([reply[3], reply[4], reply[5], reply[6]]))
+    }
+    /// Write a raw register from the Tmc5072
+    pub fn write_raw<UART, E>(
+        &mut self,
+        addr: u8,
+        data: u32,
+        uart: &mut UART,
+    ) -> Result<(), UartError<E>>
+    where
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        let mut datagram = [0; 8];
+        datagram[0] = SYNC;
+        datagram[1] = self.config.slave_addr;
+        datagram[2] = WRITE_FLAG | addr;
+        datagram[3..7].copy_from_slice(&data.to_be_bytes());
+        datagram[7] = crc8(&datagram[..7]);
+        self.send(&datagram, uart)
+    }
+
+    fn send<UART, E>(&mut self, datagram: &[u8], uart: &mut UART) -> Result<(), UartError<E>>
+    where
+        UART: Read<u8, Error = E> + Write<u8, Error = E>,
+    {
+        for &byte in datagram {
+            nb_block(|| uart.write(byte))?;
+        }
+        nb_block(|| uart.flush())?;
+        if self.config.echo {
+            for _ in datagram {
+                self.receive(uart)?;
+            }
+        }
+        if self.config.ring {
+            let mut forwarded = true;
+            for &byte in datagram {
+                forwarded &= self.receive(uart)? == byte;
+            }
+            if !forwarded {
+                return Err(UartError::ForwardError);
+            }
+        }
+        Ok(())
+    }
+
+    fn receive<UART, E>(&mut self, uart: &mut UART) -> Result<u8, UartError<E>>
+    where
+        UART: Read<u8, Error = E>,
+    {
+        let mut polls = 0u32;
+        loop {
+            match uart.read() {
+                Ok(byte) => return Ok(byte),
+                Err(nb::Error::Other(e)) => return Err(UartError::SerialError(e)),
+                Err(nb::Error::WouldBlock) => {
+                    polls += 1;
+                    if self.config.max_polls.is_some_and(|max| polls >= max) {
+                        return Err(UartError::TimeoutError);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Blocks on a non-blocking serial operation
+fn nb_block<T, E>(mut f: impl FnMut() -> nb::Result<T, E>) -> Result<T, UartError<E>> {
+    loop {
+        match f() {
+            Ok(t) => return Ok(t),
+            Err(nb::Error::Other(e)) => return Err(UartError::SerialError(e)),
+            Err(nb::Error::WouldBlock) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::ramp_generator_register::XTarget;
+    use crate::sim::Simulator;
+
+    /// Ring of a single chip: the datagrams written by the master come back before the reply
+    struct Ring {
+        sim: Simulator,
+        forwarded: [u8; 8],
+        len: usize,
+        pos: usize,
+        /// Corrupts the forwarded datagrams
+        broken: bool,
+    }
+
+    impl Read<u8> for Ring {
+        type Error = core::convert::Infallible;
+
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            if self.pos < self.len {
+                self.pos += 1;
+                Ok(self.forwarded[self.pos - 1] ^ self.broken as u8)
+            } else {
+                self.sim.read()
+            }
+        }
+    }
+
+    impl Write<u8> for Ring {
+        type Error = core::convert::Infallible;
+
+        fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+            if self.pos == self.len {
+                self.len = 0;
+                self.pos = 0;
+            }
+            self.forwarded[self.len] = byte;
+            self.len += 1;
+            self.sim.write(byte)
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn crc() {
+        // read request of GCONF, slave 0
+        assert_eq!(crc8(&[0x05, 0x00, 0x00]), 0x48);
+        // any bit error changes the CRC
+        assert_ne!(crc8(&[0x05, 0x00, 0x01]), 0x48);
+    }
+
+    #[test]
+    fn registers() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072Uart::new(UartConfig::default());
+        tmc5072
+            .write_register(XTarget::<1> { x_target: -1200 }, &mut sim)
+            .unwrap();
+        assert_eq!(
+            tmc5072.read_register::<XTarget<1>, _, _>(&mut sim).unwrap(),
+            XTarget::<1> { x_target: -1200 }
+        );
+        assert_eq!(tmc5072.if_cnt(&mut sim).unwrap(), 1);
+    }
+
+    #[test]
+    fn slave_addr() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072Uart::new(UartConfig::default());
+        tmc5072
+            .write_register(
+                SlaveConf {
+                    slave_addr: 3,
+                    ..Default::default()
+                },
+                &mut sim,
+            )
+            .unwrap();
+        // the chip no longer answers address 0
+        let mut tmc5072 = Tmc5072Uart::new(UartConfig {
+            max_polls: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(
+            tmc5072.read_register::<IfCnt, _, _>(&mut sim),
+            Err(UartError::TimeoutError)
+        );
+        let mut tmc5072 = Tmc5072Uart::new(UartConfig {
+            slave_addr: 3,
+            ..Default::default()
+        });
+        assert_eq!(tmc5072.if_cnt(&mut sim).unwrap(), 1);
+    }
+
+    #[test]
+    fn configure_slave() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072Uart::with_address(0);
+        let slave_conf = SlaveConf {
+            slave_addr: 4,
+            send_delay: MIN_MULTI_SLAVE_SEND_DELAY,
+        };
+        assert_eq!(
+            tmc5072.configure_slave(
+                SlaveConf {
+                    slave_addr: 254,
+                    ..slave_conf
+                },
+                false,
+                &mut sim
+            ),
+            Err(UartError::InvalidSlaveConf)
+        );
+        tmc5072
+            .configure_slave(slave_conf, false, &mut sim)
+            .unwrap();
+        assert_eq!(tmc5072.config().slave_addr, 4);
+        assert_eq!(tmc5072.if_cnt(&mut sim).unwrap(), 1);
+        assert_eq!(
+            Tmc5072Uart::with_address(4).read_register::<XTarget<0>, _, _>(&mut sim),
+            Ok(XTarget::<0> { x_target: 0 })
+        );
+    }
+
+    #[test]
+    fn ring() {
+        let mut ring = Ring {
+            sim: Simulator::new(),
+            forwarded: [0; 8],
+            len: 0,
+            pos: 0,
+            broken: false,
+        };
+        let mut tmc5072 = Tmc5072Uart::new(UartConfig {
+            ring: true,
+            max_polls: Some(10),
+            ..Default::default()
+        });
+        tmc5072
+            .write_register(XTarget::<0> { x_target: 77 }, &mut ring)
+            .unwrap();
+        assert_eq!(
+            tmc5072.read_register::<XTarget<0>, _, _>(&mut ring),
+            Ok(XTarget::<0> { x_target: 77 })
+        );
+        ring.broken = true;
+        assert_eq!(
+            tmc5072.write_register(XTarget::<0> { x_target: 78 }, &mut ring),
+            Err(UartError::ForwardError)
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Physical units
+//!
+//! The ramp generator counts velocities in microsteps per 2^24 clock cycles and accelerations in microsteps per
+//! 2^41 clock cycles squared:
+//! - v[µsteps/s] = v * fCLK / 2^24
+//! - a[µsteps/s²] = a * fCLK² / 2^41
+//!
+//! [`Units`] converts register values from and to microsteps per second, revolutions per minute and millimeters
+//! per second for a given clock frequency and mechanics. Conversions to register values are rounded and
+//! saturated to the register range.
+//!
+//! ```
+//! # use tmc5072::registers::ramp_generator_register::{AMax, VMax};
+//! # use tmc5072::units::{Units, F_CLK_INTERNAL};
+//! let units = Units {
+//!     f_clk: F_CLK_INTERNAL,
+//!     microsteps_per_rev: 200 * 256,
+//!     microsteps_per_mm: 200.0 * 256.0 / 8.0,
+//! };
+//! let v_max = VMax::<0>::from_rpm(60.0, &units);
+//! let a_max = AMax::<0>::from_steps_per_sec2(100_000.0, &units);
+//! assert_eq!(v_max.v_max, 65075);
+//! assert!((units.velocity_to_mm_per_sec(v_max.v_max) - 8.0).abs() < 0.01);
+//! # let _ = a_max;
+//! ```
+
+use crate::registers::ramp_generator_register::{AMax, DMax, VMax, VStart, VStop, A1, D1, V1};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Typical frequency of the internal clock (Hz)
+pub const F_CLK_INTERNAL: u32 = 13_200_000;
+
+/// Largest VMAX value
+pub(crate) const V_MAX: u32 = (1 << 23) - 512;
+
+/// Clock frequency and mechanics of an axis
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Units {
+    /// Clock frequency fCLK (Hz)
+    pub f_clk: u32,
+    /// Microsteps per motor revolution (full steps times microstep resolution)
+    pub microsteps_per_rev: u32,
+    /// Microsteps per millimeter of travel of the axis
+    pub microsteps_per_mm: f32,
+}
+
+impl Units {
+    /// Microsteps/s of a velocity register value
+    pub fn velocity_to_steps_per_sec(&self, v: u32) -> f32 {
+        v as f32 * self.f_clk as f32 / (1u64 << 24) as f32
+    }
+
+    /// Velocity register value of microsteps/s, saturated to the VMAX range
+    pub fn velocity_from_steps_per_sec(&self, steps_per_sec: f32) -> u32 {
+        let v = steps_per_sec * (1u64 << 24) as f32 / self.f_clk as f32;
+        saturate(v, V_MAX)
+    }
+
+    /// Revolutions per minute of a velocity register value
+    pub fn velocity_to_rpm(&self, v: u32) -> f32 {
+        self.velocity_to_steps_per_sec(v) * 60.0 / self.microsteps_per_rev as f32
+    }
+
+    /// Velocity register value of revolutions per minute
+    pub fn velocity_from_rpm(&self, rpm: f32) -> u32 {
+        self.velocity_from_steps_per_sec(rpm * self.microsteps_per_rev as f32 / 60.0)
+    }
+
+    /// Millimeters per second of a velocity register value
+    pub fn velocity_to_mm_per_sec(&self, v: u32) -> f32 {
+        self.velocity_to_steps_per_sec(v) / self.microsteps_per_mm
+    }
+
+    /// Velocity register value of millimeters per second
+    pub fn velocity_from_mm_per_sec(&self, mm_per_sec: f32) -> u32 {
+        self.velocity_from_steps_per_sec(mm_per_sec * self.microsteps_per_mm)
+    }
+
+    /// Microsteps/s² of an acceleration register value
+    pub fn acceleration_to_steps_per_sec2(&self, a: u16) -> f32 {
+        let f_clk = self.f_clk as f32;
+        a as f32 * f_clk * f_clk / (1u64 << 41) as f32
+    }
+
+    /// Acceleration register value of microsteps/s², saturated
+    pub fn acceleration_from_steps_per_sec2(&self, steps_per_sec2: f32) -> u16 {
+        let f_clk = self.f_clk as f32;
+        let a = steps_per_sec2 * (1u64 << 41) as f32 / (f_clk * f_clk);
+        saturate(a, u16::MAX as u32) as u16
+    }
+
+    /// Millimeters per second² of an acceleration register value
+    pub fn acceleration_to_mm_per_sec2(&self, a: u16) -> f32 {
+        self.acceleration_to_steps_per_sec2(a) / self.microsteps_per_mm
+    }
+
+    /// Acceleration register value of millimeters per second²
+    pub fn acceleration_from_mm_per_sec2(&self, mm_per_sec2: f32) -> u16 {
+        self.acceleration_from_steps_per_sec2(mm_per_sec2 * self.microsteps_per_mm)
+    }
+}
+
+/// Rounds a non negative value, saturated to `max`
+fn saturate(value: f32, max: u32) -> u32 {
+    if value.is_nan() || value <= 0.0 {
+        0
+    } else if value >= max as f32 {
+        max
+    } else {
+        ((value + 0.5) as u32).min(max)
+    }
+}
+
+impl<const M: u8> VStart<M> {
+    /// VSTART of microsteps/s
+    pub fn from_steps_per_sec(steps_per_sec: f32, units: &Units) -> Self {
+        let v_start = units.velocity_from_steps_per_sec(steps_per_sec);
+        Self {
+            v_start: v_start.min((1 << 18) - 1),
+        }
+    }
+}
+
+impl<const M: u8> V1<M> {
+    /// V1 of microsteps/s
+    pub fn from_steps_per_sec(steps_per_sec: f32, units: &Units) -> Self {
+        let v1 = units.velocity_from_steps_per_sec(steps_per_sec);
+        Self {
+            v1: v1.min((1 << 20) - 1),
+        }
+    }
+}
+
+impl<const M: u8> VMax<M> {
+    /// VMAX of microsteps/s
+    pub fn from_steps_per_sec(steps_per_sec: f32, units: &Units) -> Self {
+        Self {
+            v_max: units.velocity_from_steps_per_sec(steps_per_sec),
+        }
+    }
+
+    /// VMAX of revolutions per minute
+    pub fn from_rpm(rpm: f32, units: &Units) -> Self {
+        Self {
+            v_max: units.velocity_from_rpm(rpm),
+        }
+    }
+
+    /// VMAX of millimeters per second
+    pub fn from_mm_per_sec(mm_per_sec: f32, units: &Units) -> Self {
+        Self {
+            v_max: units.velocity_from_mm_per_sec(mm_per_sec),
+        }
+    }
+}
+
+impl<const M: u8> VStop<M> {
+    /// VSTOP of microsteps/s
+    pub fn from_steps_per_sec(steps_per_sec: f32, units: &Units) -> Self {
+        let v_stop = units.velocity_from_steps_per_sec(steps_per_sec);
+        Self {
+            v_stop: v_stop.min((1 << 18) - 1),
+        }
+    }
+}
+
+impl<const M: u8> A1<M> {
+    /// A1 of microsteps/s²
+    pub fn from_steps_per_sec2(steps_per_sec2: f32, units: &Units) -> Self {
+        Self {
+            a1: units.acceleration_from_steps_per_sec2(steps_per_sec2),
+        }
+    }
+}
+
+impl<const M: u8> AMax<M> {
+    /// AMAX of microsteps/s²
+    pub fn from_steps_per_sec2(steps_per_sec2: f32, units: &Units) -> Self {
+        Self {
+            a_max: units.acceleration_from_steps_per_sec2(steps_per_sec2),
+        }
+    }
+
+    /// AMAX of millimeters per second²
+    pub fn from_mm_per_sec2(mm_per_sec2: f32, units: &Units) -> Self {
+        Self {
+            a_max: units.acceleration_from_mm_per_sec2(mm_per_sec2),
+        }
+    }
+}
+
+impl<const M: u8> DMax<M> {
+    /// DMAX of microsteps/s²
+    pub fn from_steps_per_sec2(steps_per_sec2: f32, units: &Units) -> Self {
+        Self {
+            d_max: units.acceleration_from_steps_per_sec2(steps_per_sec2),
+        }
+    }
+
+    /// DMAX of millimeters per second²
+    pub fn from_mm_per_sec2(mm_per_sec2: f32, units: &Units) -> Self {
+        Self {
+            d_max: units.acceleration_from_mm_per_sec2(mm_per_sec2),
+        }
+    }
+}
+
+impl<const M: u8> D1<M> {
+    /// D1 of microsteps/s²
+    pub fn from_steps_per_sec2(steps_per_sec2: f32, units: &Units) -> Self {
+        Self {
+            d1: units.acceleration_from_steps_per_sec2(steps_per_sec2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const UNITS: Units = Units {
+        f_clk: F_CLK_INTERNAL,
+        microsteps_per_rev: 200 * 256,
+        microsteps_per_mm: 1600.0,
+    };
+
+    #[test]
+    fn velocity() {
+        // 13.2MHz / 2^24 * 1271 = 1000.0 microsteps/s
+        assert_eq!(UNITS.velocity_from_steps_per_sec(1000.0), 1271);
+        assert!((UNITS.velocity_to_steps_per_sec(1271) - 1000.0).abs() < 0.01);
+        assert_eq!(UNITS.velocity_from_rpm(60.0), 65075);
+        assert!((UNITS.velocity_to_rpm(65075) - 60.0).abs() < 0.01);
+        assert_eq!(UNITS.velocity_from_mm_per_sec(10.0), 20336);
+        assert_eq!(UNITS.velocity_from_steps_per_sec(1e9), (1 << 23) - 512);
+        assert_eq!(UNITS.velocity_from_steps_per_sec(-5.0), 0);
+        assert_eq!(
+            VStart::<0>::from_steps_per_sec(1e9, &UNITS).v_start,
+            (1 << 18) - 1
+        );
+    }
+
+    #[test]
+    fn acceleration() {
+        // 13.2MHz² / 2^41 = 79.2 microsteps/s²
+        assert_eq!(UNITS.acceleration_from_steps_per_sec2(7924.0), 100);
+        assert!((UNITS.acceleration_to_steps_per_sec2(100) - 7924.0).abs() < 1.0);
+        assert_eq!(AMax::<1>::from_mm_per_sec2(1e6, &UNITS).a_max, u16::MAX);
+        assert_eq!(DMax::<1>::from_steps_per_sec2(792.4, &UNITS).d_max, 10);
+    }
+}
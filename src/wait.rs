@@ -0,0 +1,164 @@
+//! Blocking waits
+//!
+//! [`wait_position_reached`] and [`wait_standstill`] poll RAMP_STAT until the motor reached its target or stands
+//! still, sleeping between polls with an injected `DelayUs`. The sleep starts at [`MIN_POLL_INTERVAL_US`] and doubles
+//! up to [`MAX_POLL_INTERVAL_US`], keeping short waits responsive and long ones light on the bus. The wait fails with
+//! [`WaitError::TimeoutError`] once `timeout_us` microseconds elapsed.
+//!
+//! Reading RAMP_STAT clears its event flags. The delay is the embedded-hal 0.2 `DelayUs<u32>` the driver is built on.
+
+use embedded_hal::{
+    blocking::{delay::DelayUs, spi::Transfer},
+    digital::v2::OutputPin,
+};
+
+use crate::motor::Motor;
+use crate::registers::{ramp_generator_driver_feature_control_register::RampStat, Register};
+use crate::spi::SpiError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// First sleep between two polls (µs)
+pub const MIN_POLL_INTERVAL_US: u32 = 100;
+/// Longest sleep between two polls (µs)
+pub const MAX_POLL_INTERVAL_US: u32 = 10_000;
+
+/// Blocking wait error
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WaitError<SPI, CS> {
+    /// SPI bus error
+    SpiError(SpiError<SPI, CS>),
+    /// The condition was not met within the timeout
+    TimeoutError,
+}
+
+impl<SPI, CS> From<SpiError<SPI, CS>> for WaitError<SPI, CS> {
+    fn from(e: SpiError<SPI, CS>) -> Self {
+        WaitError::SpiError(e)
+    }
+}
+
+/// Polls RAMP_STAT with a growing interval until `done` holds
+fn wait_ramp_stat<CS: OutputPin, SPI: Transfer<u8>, D: DelayUs<u32>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    timeout_us: u32,
+    delay: &mut D,
+    done: impl Fn(&RampStat<M>) -> bool,
+) -> Result<(), WaitError<SPI::Error, CS::Error>>
+where
+    RampStat<M>: Register,
+{
+    let (tmc5072, spi) = motor.parts();
+    let mut elapsed = 0u32;
+    let mut interval = MIN_POLL_INTERVAL_US;
+    loop {
+        if done(&tmc5072.read_register::<RampStat<M>, _>(spi)?.data) {
+            return Ok(());
+        }
+        if elapsed >= timeout_us {
+            return Err(WaitError::TimeoutError);
+        }
+        let sleep = interval.min(timeout_us - elapsed);
+        delay.delay_us(sleep);
+        elapsed += sleep;
+        interval = (interval * 2).min(MAX_POLL_INTERVAL_US);
+    }
+}
+
+/// Waits until motor `M` reached its target position (RAMP_STAT position_reached)
+pub fn wait_position_reached<CS: OutputPin, SPI: Transfer<u8>, D: DelayUs<u32>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    timeout_us: u32,
+    delay: &mut D,
+) -> Result<(), WaitError<SPI::Error, CS::Error>>
+where
+    RampStat<M>: Register,
+{
+    wait_ramp_stat(motor, timeout_us, delay, |ramp_stat| {
+        ramp_stat.position_reached
+    })
+}
+
+/// Waits until motor `M` stands still (RAMP_STAT vzero)
+pub fn wait_standstill<CS: OutputPin, SPI: Transfer<u8>, D: DelayUs<u32>, const M: u8>(
+    motor: &mut Motor<'_, CS, SPI, M>,
+    timeout_us: u32,
+    delay: &mut D,
+) -> Result<(), WaitError<SPI::Error, CS::Error>>
+where
+    RampStat<M>: Register,
+{
+    wait_ramp_stat(motor, timeout_us, delay, |ramp_stat| ramp_stat.vzero)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{
+        ramp_generator_driver_feature_control_register::SwMode, ramp_generator_register::XTarget,
+    };
+    use crate::sim::{CsPin, Simulator};
+    use crate::Tmc5072;
+
+    /// Delay recording the sleeps
+    #[derive(Default)]
+    struct Sleeps {
+        first: [u32; 4],
+        count: usize,
+        total: u32,
+    }
+
+    impl DelayUs<u32> for Sleeps {
+        fn delay_us(&mut self, us: u32) {
+            if let Some(slot) = self.first.get_mut(self.count) {
+                *slot = us;
+            }
+            self.count += 1;
+            self.total += us;
+        }
+    }
+
+    #[test]
+    fn reached() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        tmc5072
+            .write_register(XTarget::<0> { x_target: 300 }, &mut sim)
+            .unwrap();
+        let mut delay = Sleeps::default();
+        let mut motor = tmc5072.motor::<0, _>(&mut sim);
+        assert_eq!(wait_position_reached(&mut motor, 1000, &mut delay), Ok(()));
+        assert_eq!(wait_standstill(&mut motor, 1000, &mut delay), Ok(()));
+        assert_eq!(delay.count, 0);
+    }
+
+    #[test]
+    fn timeout() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        // stopped by a switch before the target
+        sim.set_switches(1, None, Some(100));
+        tmc5072
+            .write_register(
+                SwMode::<1> {
+                    stop_r_enable: true,
+                    ..Default::default()
+                },
+                &mut sim,
+            )
+            .unwrap();
+        tmc5072
+            .write_register(XTarget::<1> { x_target: 300 }, &mut sim)
+            .unwrap();
+        let mut delay = Sleeps::default();
+        let mut motor = tmc5072.motor::<1, _>(&mut sim);
+        assert_eq!(
+            wait_position_reached(&mut motor, 1000, &mut delay),
+            Err(WaitError::TimeoutError)
+        );
+        assert_eq!(delay.first, [100, 200, 400, 300]);
+        assert_eq!(delay.total, 1000);
+    }
+}
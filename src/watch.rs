@@ -0,0 +1,176 @@
+//! Register change notifications
+//!
+//! A [`Watcher`] polls a set of watched (register, mask) pairs and only reports the ones whose masked bits changed
+//! since the previous poll, e.g. to be notified when `fsactive` or `otpw` toggles without diffing DRV_STATUS by hand.
+//!
+//! All watched registers are read with a single pipelined [`Tmc5072::read_many`].
+//! Watching a clear on read register (GSTAT, RAMP_STAT, ENC_STATUS) clears its flags.
+
+use embedded_hal::{blocking::spi::Transfer, digital::v2::OutputPin};
+
+use crate::registers::Register;
+use crate::spi::SpiError;
+use crate::Tmc5072;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Watched bits of a register
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Watch {
+    /// Register address
+    pub addr: u8,
+    /// Watched bits
+    pub mask: u32,
+}
+
+impl Watch {
+    /// Watches the bits of register `R` set in `mask`, e.g. `DrvStatus { otpw: true, ..DrvStatus::from(0) }`
+    ///
+    /// Build the mask on a zeroed value: `Default` gives the reset value, whose set bits would be watched too.
+    pub fn of<R>(mask: R) -> Self
+    where
+        R: Register,
+        u32: From<R>,
+    {
+        Self {
+            addr: R::addr(),
+            mask: u32::from(mask),
+        }
+    }
+}
+
+/// Change of watched bits
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WatchChange {
+    /// Register address
+    pub addr: u8,
+    /// Previous register value
+    pub previous: u32,
+    /// New register value
+    pub value: u32,
+    /// Watched bits that changed
+    pub changed: u32,
+}
+
+impl WatchChange {
+    /// Previous value as a typed register
+    pub fn previous<R: Register>(&self) -> R
+    where
+        u32: From<R>,
+    {
+        R::from(self.previous)
+    }
+
+    /// New value as a typed register
+    pub fn value<R: Register>(&self) -> R
+    where
+        u32: From<R>,
+    {
+        R::from(self.value)
+    }
+}
+
+/// Changes of `N` watches, by watch index
+pub type WatchChanges<const N: usize> = [Option<WatchChange>; N];
+
+/// Polls `N` watched registers
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Watcher<const N: usize> {
+    watches: [Watch; N],
+    last: [Option<u32>; N],
+}
+
+impl<const N: usize> Watcher<N> {
+    /// Creates a watcher, the first poll only records the initial values
+    pub fn new(watches: [Watch; N]) -> Self {
+        Self {
+            watches,
+            last: [None; N],
+        }
+    }
+
+    /// Watched registers
+    pub fn watches(&self) -> &[Watch; N] {
+        &self.watches
+    }
+
+    /// Feeds the value of watch `i` read by other means, returns its change if any
+    ///
+    /// Returns `None` when `i` is not a watch index.
+    pub fn observe(&mut self, i: usize, value: u32) -> Option<WatchChange> {
+        let watch = *self.watches.get(i)?;
+        let previous = self.last[i].replace(value)?;
+        let changed = (previous ^ value) & watch.mask;
+        (changed != 0).then_some(WatchChange {
+            addr: watch.addr,
+            previous,
+            value,
+            changed,
+        })
+    }
+
+    /// Reads all watched registers, returns the changes by watch index
+    pub fn poll<CS: OutputPin, SPI: Transfer<u8>>(
+        &mut self,
+        tmc5072: &mut Tmc5072<CS>,
+        spi: &mut SPI,
+    ) -> Result<WatchChanges<N>, SpiError<SPI::Error, CS::Error>> {
+        let addrs = self.watches.map(|watch| watch.addr);
+        let values = tmc5072.read_many(&addrs, spi)?.data;
+        let mut changes = [None; N];
+        for (i, &value) in values.iter().enumerate() {
+            changes[i] = self.observe(i, value);
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registers::{motor_driver_register::DrvStatus, ramp_generator_register::XActual};
+    use crate::sim::{CsPin, Simulator};
+
+    #[test]
+    fn changes() {
+        let mut sim = Simulator::new();
+        let mut tmc5072 = Tmc5072::new(&mut sim, CsPin).unwrap();
+        let mut watcher = Watcher::new([
+            Watch::of(DrvStatus::<1> {
+                fsactive: true,
+                otpw: true,
+                ..DrvStatus::from(0)
+            }),
+            Watch::of(XActual::<0> { x_actual: -1 }),
+        ]);
+        assert_eq!(watcher.poll(&mut tmc5072, &mut sim).unwrap(), [None; 2]);
+        // unwatched bits
+        sim.set(
+            DrvStatus::<1>::addr(),
+            u32::from(DrvStatus::<1> {
+                sg_result: 300,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(watcher.poll(&mut tmc5072, &mut sim).unwrap(), [None; 2]);
+        sim.set(
+            DrvStatus::<1>::addr(),
+            u32::from(DrvStatus::<1> {
+                sg_result: 300,
+                otpw: true,
+                ..Default::default()
+            }),
+        );
+        sim.set(XActual::<0>::addr(), 10);
+        let changes = watcher.poll(&mut tmc5072, &mut sim).unwrap();
+        let change = changes[0].unwrap();
+        assert_eq!(change.changed, 1 << 26);
+        assert!(!change.previous::<DrvStatus<1>>().otpw);
+        assert!(change.value::<DrvStatus<1>>().otpw);
+        assert_eq!(changes[1].unwrap().value::<XActual<0>>().x_actual, 10);
+        assert_eq!(watcher.poll(&mut tmc5072, &mut sim).unwrap(), [None; 2]);
+        assert_eq!(watcher.observe(2, 0), None);
+    }
+}